@@ -0,0 +1,221 @@
+use amm::{
+    client::{build_crank_ix, build_deposit_ix, build_initialize_ix, build_swap_ix, build_withdraw_ix, config_address, lp_mint_address, pool_address},
+    state::{AmmConfig, FeeDestinationMode, FeeTier, ACCOUNT_VERSION},
+};
+use anchor_lang::{
+    solana_program::{program_option::COption, program_pack::Pack},
+    AccountSerialize, Discriminator, Space,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, spl_associated_token_account::instruction::create_associated_token_account_idempotent},
+    token::spl_token,
+};
+use solana_program_test::{read_file, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::path::{Path, PathBuf};
+
+// ========================================
+// 计算单元（CU）基准
+// ========================================
+//
+// 用 solana-program-test 加载 anchor build 生成的 amm.so，
+// 依次执行 initialize / deposit / swap / crank / withdraw，打印每条指令消耗的 CU。
+// 超过 BUDGETS 中的预算时以非零状态码退出，CI 可以直接用来防止性能回退。
+//
+// 全局配置需要程序的升级权限才能创建，这里直接把 AmmConfig 账户写进测试环境。
+
+const FEE: u16 = 30;
+
+/// (指令名, CU 预算)
+const BUDGETS: &[(&str, u64)] = &[
+    ("swap", 60_000),
+];
+
+#[tokio::main]
+async fn main() {
+    let mint_authority = Keypair::new();
+    let mint_a = Pubkey::new_unique();
+    let mint_b = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    add_program(&mut program_test);
+
+    let user = Keypair::new();
+    program_test.add_account(user.pubkey(), Account {
+        lamports: 10_000_000_000,
+        ..Account::default()
+    });
+
+    for mint in [mint_a, mint_b] {
+        add_mint(&mut program_test, mint, &mint_authority.pubkey());
+        add_token_account(&mut program_test, get_associated_token_address(&user.pubkey(), &mint), mint, user.pubkey(), 1_000_000_000);
+    }
+
+    add_config(&mut program_test, user.pubkey());
+
+    let mut ctx = program_test.start_with_context().await;
+
+    let pool = pool_address(&mint_a, &mint_b, FEE);
+    let mint_lp = lp_mint_address(&pool);
+
+    let mut results = Vec::new();
+
+    results.push(("initialize", run(&mut ctx, &user, vec![
+        build_initialize_ix(&user.pubkey(), &user.pubkey(), &mint_a, &mint_b, FEE, 0, None, None, 0, false, false, FeeDestinationMode::Lp),
+    ]).await));
+
+    // 首次存入不计入 deposit 的 CU，第二次按比例存入才是常规路径
+    run(&mut ctx, &user, vec![
+        create_associated_token_account_idempotent(&user.pubkey(), &user.pubkey(), &mint_lp, &spl_token::ID),
+        build_deposit_ix(&user.pubkey(), &mint_a, &mint_b, FEE, 0, 100_000_000, 100_000_000),
+    ]).await;
+
+    results.push(("deposit", run(&mut ctx, &user, vec![
+        build_deposit_ix(&user.pubkey(), &mint_a, &mint_b, FEE, 1_000_000, u64::MAX, u64::MAX),
+    ]).await));
+
+    // 与上面的 deposit 参数相同，对比两者的 CU 即可看出精简存入省下的部分
+    #[cfg(feature = "lite-deposit")]
+    results.push(("deposit_lite", run(&mut ctx, &user, vec![
+        amm::client::build_deposit_lite_ix(&user.pubkey(), &mint_a, &mint_b, FEE, 1_000_000, u64::MAX, u64::MAX),
+    ]).await));
+
+    results.push(("swap", run(&mut ctx, &user, vec![
+        build_swap_ix(&user.pubkey(), &mint_a, &mint_b, FEE, 1_000_000, u64::MAX, true, false),
+    ]).await));
+
+    results.push(("crank", run(&mut ctx, &user, vec![
+        build_crank_ix(&user.pubkey(), &mint_a, &mint_b, FEE),
+    ]).await));
+
+    results.push(("withdraw", run(&mut ctx, &user, vec![
+        build_withdraw_ix(&user.pubkey(), &mint_a, &mint_b, FEE, 1_000_000_000_000_000, 0, 0),
+    ]).await));
+
+    let mut over_budget = false;
+    println!("{:<12} {:>10} {:>10}", "instruction", "CU", "budget");
+    for (name, units) in &results {
+        let budget = BUDGETS.iter().find(|(budget_name, _)| budget_name == name).map(|(_, budget)| *budget);
+        match budget {
+            Some(budget) if *units > budget => {
+                over_budget = true;
+                println!("{:<12} {:>10} {:>10}  OVER BUDGET", name, units, budget);
+            }
+            Some(budget) => println!("{:<12} {:>10} {:>10}", name, units, budget),
+            None => println!("{:<12} {:>10} {:>10}", name, units, "-"),
+        }
+    }
+
+    if over_budget {
+        std::process::exit(1);
+    }
+}
+
+/// 发送交易并返回消耗的 CU，交易失败时直接 panic 并打印日志
+async fn run(ctx: &mut ProgramTestContext, user: &Keypair, instructions: Vec<Instruction>) -> u64 {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&instructions, Some(&user.pubkey()), &[user], blockhash);
+
+    let result = ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    let metadata = result.metadata.expect("transaction metadata");
+    if let Err(err) = result.result {
+        panic!("transaction failed: {err:?}\n{}", metadata.log_messages.join("\n"));
+    }
+
+    // 整笔交易的 CU，被测指令都单独成一笔交易发送
+    metadata.compute_units_consumed
+}
+
+/// 读取 anchor build 生成的 amm.so（SBF_OUT_DIR 优先，否则是工作区的 target/deploy）写入测试环境，
+/// 与 tests/common 相同，不经过 ProgramTest::new 的查找
+fn add_program(program_test: &mut ProgramTest) {
+    let dir = std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"));
+    let data = read_file(dir.join("amm.so"));
+    program_test.add_account(amm::ID, Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: bpf_loader::id(),
+        executable: true,
+        rent_epoch: 0,
+    });
+}
+
+fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, authority: &Pubkey) {
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply: 1_000_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }, &mut data).unwrap();
+
+    program_test.add_account(mint, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::ID,
+        ..Account::default()
+    });
+}
+
+fn add_token_account(program_test: &mut ProgramTest, address: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }, &mut data).unwrap();
+
+    program_test.add_account(address, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::ID,
+        ..Account::default()
+    });
+}
+
+fn add_config(program_test: &mut ProgramTest, admin: Pubkey) {
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &amm::ID);
+    assert_eq!(config, config_address());
+
+    let mut data = Vec::with_capacity(AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE);
+    AmmConfig {
+        version: ACCOUNT_VERSION,
+        admin,
+        fee_tiers: vec![FeeTier { fee: FEE, protocol_fee_bps: 0 }],
+        max_creator_fee_bps: 0,
+        protocol_token_mint: Pubkey::default(),
+        max_buyback_amount_in: 0,
+        max_buyback_impact_bps: 0,
+        allow_freezable_mints: false,
+        require_token_badges: false,
+        membership_mint: Pubkey::default(),
+        fee_rebate_tiers: Vec::new(),
+        guardian: Pubkey::default(),
+        emergency_mode: false,
+        emergency_exit_ts: 0,
+        fee_recipients: Vec::new(),
+        fee_exemptions: Vec::new(),
+        bump,
+    }.try_serialize(&mut data).unwrap();
+    data.resize(AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE, 0);
+
+    program_test.add_account(config, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: amm::ID,
+        ..Account::default()
+    });
+}
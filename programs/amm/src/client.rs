@@ -0,0 +1,1054 @@
+use anchor_lang::{prelude::*, solana_program::{instruction::Instruction, sysvar}, system_program, InstructionData};
+use anchor_spl::{associated_token::{self, get_associated_token_address}, token};
+
+use crate::ID;
+
+// ========================================
+// 链下客户端辅助函数（需要开启 `client` feature）
+// ========================================
+//
+// 机器人和测试只需要提供 mint、fee 等普通公钥/参数，
+// PDA 和关联代币账户都在这里推导，避免手写账户列表出错。
+
+/// pool PDA：["pool", mint_a, mint_b, fee]
+pub fn pool_address(mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool", mint_a.as_ref(), mint_b.as_ref(), fee.to_le_bytes().as_ref()], &ID).0
+}
+
+/// LP mint PDA：["lp", pool]
+pub fn lp_mint_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp", pool.as_ref()], &ID).0
+}
+
+/// 多资产池 PDA：["multi_pool", sha256(mints), fee]，mints 需要按地址升序
+pub fn multi_pool_address(mints: &[Pubkey], fee: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"multi_pool", crate::context::multi_pool_seed(mints).as_ref(), fee.to_le_bytes().as_ref()], &ID).0
+}
+
+/// 多资产池 LP mint PDA：["multi_lp", multi_pool]
+pub fn multi_lp_mint_address(multi_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"multi_lp", multi_pool.as_ref()], &ID).0
+}
+
+/// 创建者手续费账户 PDA：["creator_fee", pool, mint]
+pub fn creator_fee_vault_address(pool: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator_fee", pool.as_ref(), mint.as_ref()], &ID).0
+}
+
+/// 全局配置 PDA：["config"]
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+/// 全局注册表 PDA：["registry"]
+pub fn registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"registry"], &ID).0
+}
+
+/// 注册表分页 PDA：["registry_page", page]
+pub fn registry_page_address(page: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"registry_page", page.to_le_bytes().as_ref()], &ID).0
+}
+
+/// 交易对反查记录 PDA：["pair", 较小的 mint, 较大的 mint]，两个 mint 可以按任意顺序传入
+pub fn pair_marker_address(mint_x: &Pubkey, mint_y: &Pubkey) -> Pubkey {
+    let (mint_0, mint_1) = crate::state::PairMarker::sorted_mints(*mint_x, *mint_y);
+    Pubkey::find_program_address(&[b"pair", mint_0.as_ref(), mint_1.as_ref()], &ID).0
+}
+
+/// registry_page 需要传入注册表当前的 current_page
+/// payer 支付租金，自己付租金时传入和 signer 相同的地址
+/// initial_price 为 (numerator, denominator)，None 表示不声明初始价格
+/// trading_window 为 (start_ts, end_ts)，None 表示不限制交易时间
+/// token_badges 为 true 时传入两个 mint 的 TokenBadge，协议开启了 require_token_badges 时需要
+/// soulbound_lp 为 true 时创建 LP 不可自由转让的池子，见 context/position.rs
+/// fee_destination_mode 为 Creator 时手续费中 LP 的那部分归创建者，需要协议开启 require_token_badges
+#[allow(clippy::too_many_arguments)]
+pub fn build_initialize_ix(signer: &Pubkey, payer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, creator_fee_bps: u16, initial_price: Option<(u64, u64)>, trading_window: Option<(i64, i64)>, registry_page: u32, token_badges: bool, soulbound_lp: bool, fee_destination_mode: crate::state::FeeDestinationMode) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let (initial_price_numerator, initial_price_denominator) = initial_price.unwrap_or_default();
+    let (start_ts, end_ts) = trading_window.unwrap_or_default();
+    let accounts = crate::accounts::Initialize {
+        signer: *signer,
+        payer: *payer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp: lp_mint_address(&pool),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        creator_fee_vault_a: creator_fee_vault_address(&pool, mint_a),
+        creator_fee_vault_b: creator_fee_vault_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        registry: registry_address(),
+        registry_page: registry_page_address(registry_page),
+        pair_marker: pair_marker_address(mint_a, mint_b),
+        token_badge_a: token_badges.then(|| token_badge_address(mint_a)),
+        token_badge_b: token_badges.then(|| token_badge_address(mint_b)),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Initialize { fee, creator_fee_bps, initial_price_numerator, initial_price_denominator, start_ts, end_ts, soulbound_lp, fee_destination_mode }.data(),
+    }
+}
+
+/// 协议手续费为 LP 模式的池子需要在账户中补上 treasury_lp，开启 JIT 保护的池子需要补上 liquidity_guard，
+/// 这里默认都传 None；想更新仓位列表时用 build_deposit_with_portfolio_ix
+pub fn build_deposit_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_token_a: u64, max_token_b: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: deposit_accounts(signer, mint_a, mint_b, fee).to_account_metas(None),
+        data: crate::instruction::Deposit { amount, max_token_a, max_token_b, tag: None }.data(),
+    }
+}
+
+pub fn build_deposit_by_amounts_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount_a_desired: u64, amount_b_desired: u64, min_lp: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: deposit_accounts(signer, mint_a, mint_b, fee).to_account_metas(None),
+        data: crate::instruction::DepositByAmounts { amount_a_desired, amount_b_desired, min_lp, tag: None }.data(),
+    }
+}
+
+pub fn build_deposit_imbalanced_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount_a: u64, amount_b: u64, min_lp: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: deposit_accounts(signer, mint_a, mint_b, fee).to_account_metas(None),
+        data: crate::instruction::DepositImbalanced { amount_a, amount_b, min_lp, tag: None }.data(),
+    }
+}
+
+/// delegate 需要事先被 owner approve 为 TokenA / TokenB 账户的委托人，owner 的 LP 账户必须已经存在
+#[allow(clippy::too_many_arguments)]
+pub fn build_deposit_with_delegate_ix(delegate: &Pubkey, owner: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_token_a: u64, max_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let accounts = crate::accounts::DelegatedDeposit {
+        delegate: *delegate,
+        owner: *owner,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        owner_ata_a: get_associated_token_address(owner, mint_a),
+        owner_ata_b: get_associated_token_address(owner, mint_b),
+        owner_ata_lp: get_associated_token_address(owner, &mint_lp),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        liquidity_guard: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::DepositWithDelegate { amount, max_token_a, max_token_b }.data(),
+    }
+}
+
+/// 代币账户取 signer 的关联代币账户，用别的代币账户时直接修改返回值中对应的 AccountMeta
+#[cfg(feature = "lite-deposit")]
+pub fn build_deposit_lite_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_token_a: u64, max_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let accounts = crate::accounts::DepositLite {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        signer_ata_lp: get_associated_token_address(signer, &mint_lp),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        token_program: token::ID,
+    };
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::DepositLite { amount, max_token_a, max_token_b }.data(),
+    }
+}
+
+pub fn build_withdraw_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, min_token_a: u64, min_token_b: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: withdraw_accounts(signer, mint_a, mint_b, fee).to_account_metas(None),
+        data: crate::instruction::Withdraw { amount, min_token_a, min_token_b, tag: None, unwrap: false }.data(),
+    }
+}
+
+pub fn build_withdraw_percent_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, bps: u16, min_token_a: u64, min_token_b: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: withdraw_accounts(signer, mint_a, mint_b, fee).to_account_metas(None),
+        data: crate::instruction::WithdrawPercent { bps, min_token_a, min_token_b }.data(),
+    }
+}
+
+/// 分期取出计划 PDA：["scheduled_withdraw", pool, owner, id]
+pub fn scheduled_withdraw_address(pool: &Pubkey, owner: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"scheduled_withdraw", pool.as_ref(), owner.as_ref(), id.to_le_bytes().as_ref()], &ID).0
+}
+
+pub fn build_schedule_withdraw_ix(owner: &Pubkey, pool: &Pubkey, id: u64, amount_lp: u64, duration: i64) -> Instruction {
+    let mint_lp = lp_mint_address(pool);
+    let scheduled_withdraw = scheduled_withdraw_address(pool, owner, id);
+    let accounts = crate::accounts::ScheduleWithdraw {
+        owner: *owner,
+        mint_lp,
+        pool: *pool,
+        scheduled_withdraw,
+        escrow: get_associated_token_address(&scheduled_withdraw, &mint_lp),
+        owner_ata_lp: get_associated_token_address(owner, &mint_lp),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::ScheduleWithdraw { id, amount_lp, duration }.data(),
+    }
+}
+
+/// 代币转到 owner 的关联代币账户；LP 模式的池子需要补上 treasury_lp，开启 JIT 保护的池子需要补上 liquidity_guard
+#[allow(clippy::too_many_arguments)]
+pub fn build_claim_scheduled_withdraw_ix(owner: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, id: u64, min_token_a: u64, min_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let scheduled_withdraw = scheduled_withdraw_address(&pool, owner, id);
+    let accounts = crate::accounts::ClaimScheduledWithdraw {
+        owner: *owner,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        scheduled_withdraw,
+        escrow: get_associated_token_address(&scheduled_withdraw, &mint_lp),
+        owner_ata_a: get_associated_token_address(owner, mint_a),
+        owner_ata_b: get_associated_token_address(owner, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        treasury_lp: None,
+        liquidity_guard: None,
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::ClaimScheduledWithdraw { min_token_a, min_token_b }.data(),
+    }
+}
+
+pub fn build_emergency_withdraw_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, min_token_a: u64, min_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let accounts = crate::accounts::EmergencyWithdraw {
+        signer: *signer,
+        mint_lp,
+        signer_ata_lp: get_associated_token_address(signer, &mint_lp),
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::EmergencyWithdraw { amount, min_token_a, min_token_b }.data(),
+    }
+}
+
+/// 钱包限额记录 PDA：["trader", pool, trader]
+pub fn trader_state_address(pool: &Pubkey, trader: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trader", pool.as_ref(), trader.as_ref()], &ID).0
+}
+
+pub fn build_init_trader_state_ix(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::InitTraderState {
+        signer: *signer,
+        pool: *pool,
+        trader_state: trader_state_address(pool, signer),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::InitTraderState {}.data(),
+    }
+}
+
+pub fn build_migrate_trader_state_ix(payer: &Pubkey, pool: &Pubkey, trader: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::MigrateTraderState {
+        payer: *payer,
+        pool: *pool,
+        trader: *trader,
+        trader_state: trader_state_address(pool, trader),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::MigrateTraderState {}.data(),
+    }
+}
+
+/// 模拟后从 return data 中解析 PoolVaults
+pub fn build_get_pool_vaults_ix(pool: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::GetPoolVaults { pool: *pool };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::GetPoolVaults {}.data(),
+    }
+}
+
+/// JIT 保护记录 PDA：["lp_guard", pool, owner]
+pub fn liquidity_guard_address(pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp_guard", pool.as_ref(), owner.as_ref()], &ID).0
+}
+
+pub fn build_init_liquidity_guard_ix(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::InitLiquidityGuard {
+        signer: *signer,
+        pool: *pool,
+        liquidity_guard: liquidity_guard_address(pool, signer),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::InitLiquidityGuard {}.data(),
+    }
+}
+
+/// 多池子仓位列表 PDA：["portfolio", owner]
+pub fn portfolio_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"portfolio", owner.as_ref()], &ID).0
+}
+
+pub fn build_init_portfolio_ix(signer: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::InitPortfolio {
+        signer: *signer,
+        portfolio: portfolio_address(signer),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::InitPortfolio {}.data(),
+    }
+}
+
+/// 与 build_deposit_ix 相同，另外传入 signer 的仓位列表，存入后把池子加入列表（需要事先 init_portfolio）
+pub fn build_deposit_with_portfolio_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_token_a: u64, max_token_b: u64) -> Instruction {
+    let mut accounts = deposit_accounts(signer, mint_a, mint_b, fee);
+    accounts.portfolio = Some(portfolio_address(signer));
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Deposit { amount, max_token_a, max_token_b, tag: None }.data(),
+    }
+}
+
+/// 与 build_withdraw_ix 相同，另外传入 signer 的仓位列表，全部取出后把池子从列表中移除
+pub fn build_withdraw_with_portfolio_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, min_token_a: u64, min_token_b: u64) -> Instruction {
+    let mut accounts = withdraw_accounts(signer, mint_a, mint_b, fee);
+    accounts.portfolio = Some(portfolio_address(signer));
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Withdraw { amount, min_token_a, min_token_b, tag: None, unwrap: false }.data(),
+    }
+}
+
+/// 仓位 PDA：["position", pool, owner]，只用于 soulbound LP 池子
+pub fn position_address(pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", pool.as_ref(), owner.as_ref()], &ID).0
+}
+
+/// 仓位转让白名单 PDA：["position_whitelist", pool, wallet]
+pub fn position_whitelist_address(pool: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position_whitelist", pool.as_ref(), wallet.as_ref()], &ID).0
+}
+
+pub fn build_init_position_ix(signer: &Pubkey, pool: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::InitPosition {
+        signer: *signer,
+        pool: *pool,
+        position: position_address(pool, signer),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::InitPosition {}.data(),
+    }
+}
+
+pub fn build_transfer_position_ix(signer: &Pubkey, pool: &Pubkey, to_owner: &Pubkey, amount: u64) -> Instruction {
+    let accounts = crate::accounts::TransferPosition {
+        signer: *signer,
+        pool: *pool,
+        from_position: position_address(pool, signer),
+        to_position: position_address(pool, to_owner),
+        whitelist: position_whitelist_address(pool, to_owner),
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::TransferPosition { amount }.data(),
+    }
+}
+
+/// proof: 接收方在默克尔根白名单中的默克尔证明，叶子见 position_whitelist_leaf
+pub fn build_transfer_position_with_proof_ix(signer: &Pubkey, pool: &Pubkey, to_owner: &Pubkey, amount: u64, proof: Vec<[u8; 32]>) -> Instruction {
+    let accounts = crate::accounts::TransferPositionWithProof {
+        signer: *signer,
+        pool: *pool,
+        from_position: position_address(pool, signer),
+        to_position: position_address(pool, to_owner),
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::TransferPositionWithProof { amount, proof }.data(),
+    }
+}
+
+/// 开启 JIT 保护的池子需要补上 liquidity_guard，这里默认传 None
+pub fn build_deposit_position_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_token_a: u64, max_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let accounts = crate::accounts::PositionDeposit {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        position_vault: get_associated_token_address(&pool, &mint_lp),
+        position: position_address(&pool, signer),
+        pool,
+        config: config_address(),
+        liquidity_guard: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::DepositPosition { amount, max_token_a, max_token_b }.data(),
+    }
+}
+
+pub fn build_withdraw_position_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, min_token_a: u64, min_token_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let accounts = crate::accounts::PositionWithdraw {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        position_vault: get_associated_token_address(&pool, &mint_lp),
+        position: position_address(&pool, signer),
+        pool,
+        liquidity_guard: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::WithdrawPosition { amount, min_token_a, min_token_b }.data(),
+    }
+}
+
+/// authority: 池子管理员，payer 支付白名单账户的租金
+pub fn build_add_position_whitelist_ix(payer: &Pubkey, authority: &Pubkey, pool: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::AddPositionWhitelist {
+        payer: *payer,
+        authority: *authority,
+        pool: *pool,
+        wallet: *wallet,
+        whitelist: position_whitelist_address(pool, wallet),
+        system_program: system_program::ID,
+    };
+
+    let mut accounts = accounts.to_account_metas(None);
+    // authority 是 UncheckedAccount，生成的 AccountMeta 不带签名标记
+    accounts[1].is_signer = true;
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: crate::instruction::AddPositionWhitelist {}.data(),
+    }
+}
+
+/// 成交统计 PDA：["pool_stats", pool]
+pub fn pool_stats_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_stats", pool.as_ref()], &ID).0
+}
+
+pub fn build_init_pool_stats_ix(payer: &Pubkey, pool: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::InitPoolStats {
+        payer: *payer,
+        pool: *pool,
+        pool_stats: pool_stats_address(pool),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::InitPoolStats {}.data(),
+    }
+}
+
+/// rate_limited: 池子开启了钱包限额或往返附加费时为 true，会带上 signer 的 TraderState
+#[allow(clippy::too_many_arguments)]
+pub fn build_swap_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_amount_in: u64, is_a: bool, rate_limited: bool) -> Instruction {
+    let accounts = swap_accounts(signer, mint_a, mint_b, fee, rate_limited);
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Swap { amount, max_amount_in, is_a, tag: None, unwrap: false }.data(),
+    }
+}
+
+/// 登记了 swap hook 的池子：传入 hook_program，hook_accounts 是 hook 需要的额外账户（pool.swap_hook_accounts 个）
+#[allow(clippy::too_many_arguments)]
+pub fn build_swap_with_hook_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_amount_in: u64, is_a: bool, hook_program: &Pubkey, hook_accounts: &[AccountMeta]) -> Instruction {
+    let mut accounts = swap_accounts(signer, mint_a, mint_b, fee, false);
+    accounts.hook_program = Some(*hook_program);
+
+    let mut accounts = accounts.to_account_metas(None);
+    accounts.extend_from_slice(hook_accounts);
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: crate::instruction::Swap { amount, max_amount_in, is_a, tag: None, unwrap: false }.data(),
+    }
+}
+
+/// relayer: 代付交易费的中继者钱包，补偿转入它在输出代币上的关联代币账户（需要事先创建）
+#[allow(clippy::too_many_arguments)]
+pub fn build_swap_with_relayer_fee_ix(signer: &Pubkey, relayer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount: u64, max_amount_in: u64, is_a: bool, relayer_fee: u64) -> Instruction {
+    let mut accounts = swap_accounts(signer, mint_a, mint_b, fee, false);
+    let mint_out = if is_a { mint_a } else { mint_b };
+    accounts.relayer_ata = Some(get_associated_token_address(relayer, mint_out));
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SwapWithRelayerFee { amount, max_amount_in, is_a, relayer_fee }.data(),
+    }
+}
+
+/// output_mint: 想要获得的代币，必须是 mint_a 或 mint_b
+#[allow(clippy::too_many_arguments)]
+pub fn build_swap_by_mint_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, output_mint: &Pubkey, amount: u64, max_amount_in: u64, rate_limited: bool) -> Instruction {
+    let accounts = swap_accounts(signer, mint_a, mint_b, fee, rate_limited);
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SwapByMint { output_mint: *output_mint, amount, max_amount_in }.data(),
+    }
+}
+
+/// fee_1 / fee_2: 两个池子的费率，allocation_bps 是 fee_1 池子成交的比例
+#[allow(clippy::too_many_arguments)]
+pub fn build_split_swap_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee_1: u16, fee_2: u16, amount: u64, allocation_bps: u16, max_amount_in: u64, is_a: bool) -> Instruction {
+    let pool_1 = pool_address(mint_a, mint_b, fee_1);
+    let pool_2 = pool_address(mint_a, mint_b, fee_2);
+    let accounts = crate::accounts::SplitSwap {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_1,
+        pool_1_ata_a: get_associated_token_address(&pool_1, mint_a),
+        pool_1_ata_b: get_associated_token_address(&pool_1, mint_b),
+        pool_1_fee_vault_a: creator_fee_vault_address(&pool_1, mint_a),
+        pool_1_fee_vault_b: creator_fee_vault_address(&pool_1, mint_b),
+        pool_2,
+        pool_2_ata_a: get_associated_token_address(&pool_2, mint_a),
+        pool_2_ata_b: get_associated_token_address(&pool_2, mint_b),
+        pool_2_fee_vault_a: creator_fee_vault_address(&pool_2, mint_a),
+        pool_2_fee_vault_b: creator_fee_vault_address(&pool_2, mint_b),
+        config: config_address(),
+        instructions: sysvar::instructions::ID,
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SplitSwap { amount, allocation_bps, max_amount_in, is_a }.data(),
+    }
+}
+
+pub fn build_rebalance_pair_ix(caller: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee_x: u16, fee_y: u16, amount_a: u64) -> Instruction {
+    let pool_x = pool_address(mint_a, mint_b, fee_x);
+    let pool_y = pool_address(mint_a, mint_b, fee_y);
+    let accounts = crate::accounts::RebalancePair {
+        caller: *caller,
+        caller_ata_b: get_associated_token_address(caller, mint_b),
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        pool_x,
+        pool_x_ata_a: get_associated_token_address(&pool_x, mint_a),
+        pool_x_ata_b: get_associated_token_address(&pool_x, mint_b),
+        pool_x_fee_vault_a: creator_fee_vault_address(&pool_x, mint_a),
+        pool_x_fee_vault_b: creator_fee_vault_address(&pool_x, mint_b),
+        pool_y,
+        pool_y_ata_a: get_associated_token_address(&pool_y, mint_a),
+        pool_y_ata_b: get_associated_token_address(&pool_y, mint_b),
+        pool_y_fee_vault_a: creator_fee_vault_address(&pool_y, mint_a),
+        pool_y_fee_vault_b: creator_fee_vault_address(&pool_y, mint_b),
+        config: config_address(),
+        instructions: sysvar::instructions::ID,
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::RebalancePair { amount_a }.data(),
+    }
+}
+
+/// LP 质押挖矿 PDA：["farm", pool]
+#[cfg(feature = "farming")]
+pub fn farm_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"farm", pool.as_ref()], &ID).0
+}
+
+/// 质押仓位 PDA：["stake", farm, owner, id]
+#[cfg(feature = "farming")]
+pub fn stake_position_address(farm: &Pubkey, owner: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"stake", farm.as_ref(), owner.as_ref(), id.to_le_bytes().as_ref()], &ID).0
+}
+
+#[cfg(feature = "farming")]
+pub fn build_stake_ix(owner: &Pubkey, pool: &Pubkey, id: u64, amount: u64, lock: crate::state::LockDuration) -> Instruction {
+    let farm = farm_address(pool);
+    let mint_lp = lp_mint_address(pool);
+    let accounts = crate::accounts::Stake {
+        owner: *owner,
+        farm,
+        mint_lp,
+        farm_ata_lp: get_associated_token_address(&farm, &mint_lp),
+        owner_ata_lp: get_associated_token_address(owner, &mint_lp),
+        stake_position: stake_position_address(&farm, owner, id),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Stake { id, amount, lock }.data(),
+    }
+}
+
+#[cfg(feature = "farming")]
+pub fn build_unstake_ix(owner: &Pubkey, pool: &Pubkey, reward_mint: &Pubkey, id: u64) -> Instruction {
+    let farm = farm_address(pool);
+    let mint_lp = lp_mint_address(pool);
+    let accounts = crate::accounts::ManageStake {
+        owner: *owner,
+        farm,
+        mint_lp,
+        reward_mint: *reward_mint,
+        stake_position: stake_position_address(&farm, owner, id),
+        farm_ata_lp: get_associated_token_address(&farm, &mint_lp),
+        farm_ata_reward: get_associated_token_address(&farm, reward_mint),
+        owner_ata_lp: get_associated_token_address(owner, &mint_lp),
+        owner_ata_reward: get_associated_token_address(owner, reward_mint),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Unstake {}.data(),
+    }
+}
+
+/// reward_mint 必须是池子的 TokenA 或 TokenB，池子需要先由协议管理员开启 protocol_fees_to_farm
+#[cfg(feature = "farming")]
+pub fn build_fund_farm_from_fees_ix(keeper: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, reward_mint: &Pubkey) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let farm = farm_address(&pool);
+    let accounts = crate::accounts::FundFarmFromFees {
+        keeper: *keeper,
+        pool,
+        farm,
+        reward_mint: *reward_mint,
+        farm_ata_reward: get_associated_token_address(&farm, reward_mint),
+        creator_fee_vault_a: creator_fee_vault_address(&pool, mint_a),
+        creator_fee_vault_b: creator_fee_vault_address(&pool, mint_b),
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::FundFarmFromFees {}.data(),
+    }
+}
+
+/// 代币徽章 PDA：["token_badge", mint]
+pub fn token_badge_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token_badge", mint.as_ref()], &ID).0
+}
+
+pub fn build_issue_token_badge_ix(payer: &Pubkey, authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::IssueTokenBadge {
+        payer: *payer,
+        authority: *authority,
+        config: config_address(),
+        mint: *mint,
+        token_badge: token_badge_address(mint),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::IssueTokenBadge {}.data(),
+    }
+}
+
+/// 金库是 config PDA 名下的关联代币账户，协议自有的 LP 也托管在 config 名下
+#[allow(clippy::too_many_arguments)]
+pub fn build_seed_pool_from_treasury_ix(payer: &Pubkey, authority: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount_a: u64, amount_b: u64, min_lp: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    let config = config_address();
+    let accounts = crate::accounts::SeedPoolFromTreasury {
+        payer: *payer,
+        authority: *authority,
+        config,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        pool,
+        treasury_ata_a: get_associated_token_address(&config, mint_a),
+        treasury_ata_b: get_associated_token_address(&config, mint_b),
+        treasury_ata_lp: get_associated_token_address(&config, &mint_lp),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        treasury_lp: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SeedPoolFromTreasury { amount_a, amount_b, min_lp }.data(),
+    }
+}
+
+/// 报价做市商 PDA：["market_maker", pool, signing_key]
+pub fn market_maker_address(pool: &Pubkey, signing_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"market_maker", pool.as_ref(), signing_key.as_ref()], &ID).0
+}
+
+/// owner: 做市商库存代币账户的 owner，需要和池子管理员一起签名
+pub fn build_register_market_maker_ix(payer: &Pubkey, authority: &Pubkey, owner: &Pubkey, pool: &Pubkey, signing_key: &Pubkey) -> Instruction {
+    let accounts = crate::accounts::RegisterMarketMaker {
+        payer: *payer,
+        authority: *authority,
+        owner: *owner,
+        pool: *pool,
+        market_maker: market_maker_address(pool, signing_key),
+        system_program: system_program::ID,
+    };
+
+    let mut accounts = accounts.to_account_metas(None);
+    // authority 是 UncheckedAccount，生成的 AccountMeta 不带签名标记
+    accounts[1].is_signer = true;
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: crate::instruction::RegisterMarketMaker { signing_key: *signing_key }.data(),
+    }
+}
+
+/// 按签名报价成交的 swap，调用方需要在它之前放入对 quote.message() 的 ed25519 验签指令
+///
+/// inventory: 做市商的输出代币账户；destination: 做市商接收输入代币的账户
+#[allow(clippy::too_many_arguments)]
+pub fn build_swap_with_quote_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, signing_key: &Pubkey, quote: crate::context::SignedQuote, max_amount_in: u64, inventory: &Pubkey, destination: &Pubkey, rate_limited: bool) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mut accounts = swap_accounts(signer, mint_a, mint_b, fee, rate_limited).to_account_metas(None);
+    accounts.push(AccountMeta::new(market_maker_address(&pool, signing_key), false));
+    accounts.push(AccountMeta::new(*inventory, false));
+    accounts.push(AccountMeta::new(*destination, false));
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: crate::instruction::SwapWithQuote { quote, max_amount_in }.data(),
+    }
+}
+
+fn swap_accounts(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, rate_limited: bool) -> crate::accounts::Swap {
+    let pool = pool_address(mint_a, mint_b, fee);
+    crate::accounts::Swap {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        creator_fee_vault_a: creator_fee_vault_address(&pool, mint_a),
+        creator_fee_vault_b: creator_fee_vault_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        trader_state: rate_limited.then(|| trader_state_address(&pool, signer)),
+        pool_stats: None,
+        instructions: sysvar::instructions::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        relayer_ata: None,
+        hook_program: None,
+    }
+}
+
+pub fn build_crank_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let accounts = crate::accounts::Crank {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        pool_stats: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Crank {}.data(),
+    }
+}
+
+pub fn build_buyback_and_burn_ix(keeper: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let accounts = crate::accounts::BuybackAndBurn {
+        keeper: *keeper,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        creator_fee_vault_a: creator_fee_vault_address(&pool, mint_a),
+        creator_fee_vault_b: creator_fee_vault_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        token_program: token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::BuybackAndBurn {}.data(),
+    }
+}
+
+/// pools: 每个池子的 (mint_a, mint_b, fee)，协议分成转到各个 treasury 在对应 mint 上的关联代币账户
+///
+/// treasuries: config.fee_recipients 为空时只传一个金库，否则按 fee_recipients 的顺序传入每个接收方的 owner
+pub fn build_collect_protocol_fees_batch_ix(authority: &Pubkey, treasuries: &[Pubkey], pools: &[(Pubkey, Pubkey, u16)]) -> Instruction {
+    let accounts = crate::accounts::CollectProtocolFeesBatch {
+        authority: *authority,
+        config: config_address(),
+        token_program: token::ID,
+    };
+
+    let mut accounts = accounts.to_account_metas(None);
+    // authority 是 UncheckedAccount，生成的 AccountMeta 不带签名标记
+    accounts[0].is_signer = true;
+    for (mint_a, mint_b, fee) in pools {
+        let pool = pool_address(mint_a, mint_b, *fee);
+        accounts.push(AccountMeta::new(pool, false));
+        accounts.push(AccountMeta::new(creator_fee_vault_address(&pool, mint_a), false));
+        accounts.push(AccountMeta::new(creator_fee_vault_address(&pool, mint_b), false));
+        for treasury in treasuries {
+            accounts.push(AccountMeta::new(get_associated_token_address(treasury, mint_a), false));
+            accounts.push(AccountMeta::new(get_associated_token_address(treasury, mint_b), false));
+        }
+    }
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: crate::instruction::CollectProtocolFeesBatch {}.data(),
+    }
+}
+
+pub fn build_verify_pool_ix(mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let accounts = crate::accounts::VerifyPool {
+        mint_lp: lp_mint_address(&pool),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        creator_fee_vault_a: creator_fee_vault_address(&pool, mint_a),
+        creator_fee_vault_b: creator_fee_vault_address(&pool, mint_b),
+        pool,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::VerifyPool {}.data(),
+    }
+}
+
+pub fn build_donate_ix(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16, amount_a: u64, amount_b: u64) -> Instruction {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let accounts = crate::accounts::Donate {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp: lp_mint_address(&pool),
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::Donate { amount_a, amount_b }.data(),
+    }
+}
+
+pub fn build_set_max_price_impact_ix(authority: &Pubkey, pool: &Pubkey, max_price_impact_bps: u16) -> Instruction {
+    let accounts = crate::accounts::PoolAdmin {
+        authority: *authority,
+        pool: *pool,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SetMaxPriceImpact { max_price_impact_bps }.data(),
+    }
+}
+
+pub fn build_set_min_trade_amount_ix(authority: &Pubkey, pool: &Pubkey, min_trade_amount: u64) -> Instruction {
+    let accounts = crate::accounts::PoolAdmin {
+        authority: *authority,
+        pool: *pool,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: crate::instruction::SetMinTradeAmount { min_trade_amount }.data(),
+    }
+}
+
+fn deposit_accounts(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> crate::accounts::Deposit {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    crate::accounts::Deposit {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        signer_ata_lp: get_associated_token_address(signer, &mint_lp),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        config: config_address(),
+        treasury_lp: None,
+        liquidity_guard: None,
+        portfolio: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+fn withdraw_accounts(signer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, fee: u16) -> crate::accounts::Withdraw {
+    let pool = pool_address(mint_a, mint_b, fee);
+    let mint_lp = lp_mint_address(&pool);
+    crate::accounts::Withdraw {
+        signer: *signer,
+        mint_a: *mint_a,
+        mint_b: *mint_b,
+        mint_lp,
+        signer_ata_a: get_associated_token_address(signer, mint_a),
+        signer_ata_b: get_associated_token_address(signer, mint_b),
+        signer_ata_lp: get_associated_token_address(signer, &mint_lp),
+        pool_ata_a: get_associated_token_address(&pool, mint_a),
+        pool_ata_b: get_associated_token_address(&pool, mint_b),
+        pool,
+        treasury_lp: None,
+        liquidity_guard: None,
+        portfolio: None,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
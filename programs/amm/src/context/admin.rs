@@ -0,0 +1,298 @@
+use anchor_lang::prelude::*;
+
+use crate::{context::{collect_protocol_fees::validate_fee_recipients, initialize_config::validate_fee_tiers}, swap_hook::MAX_SWAP_HOOK_ACCOUNTS, error::AmmError, fee_exemption::validate_fee_exemptions, fee_rebate::validate_fee_rebate_tiers, math::{BPS_DENOMINATOR, MAX_FEE_BPS}, state::{AmmConfig, CurveType, FeeExemption, FeeRebateTier, FeeRecipient, FeeTier, Pool, PoolStatus}};
+
+// ========================================
+// 管理员指令的公共约束
+// ========================================
+//
+// 管理员账户统一声明为 UncheckedAccount，而不是 Signer：
+// - Squads 等多签的 vault 是 PDA，通过 invoke_signed 以 CPI 方式签名
+// - 只比较 key 并检查 is_signer，就能同时支持普通钱包和多签 PDA
+//
+// 在 Accounts 结构体中的用法：
+//   #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+//   authority: UncheckedAccount<'info>,
+
+/// authority 的 key 与池子记录一致，并且已经签名
+pub fn is_pool_authority(authority: &AccountInfo, pool: &Pool) -> bool {
+    authority.key() == pool.authority && authority.is_signer
+}
+
+/// authority 的 key 与全局配置中的协议管理员一致，并且已经签名
+pub fn is_config_admin(authority: &AccountInfo, config: &AmmConfig) -> bool {
+    authority.key() == config.admin && authority.is_signer
+}
+
+/// 指令内部使用的版本，返回更具体的错误
+pub fn check_pool_authority(authority: &AccountInfo, pool: &Pool) -> Result<()> {
+    require_keys_eq!(authority.key(), pool.authority, AmmError::InvalidAuthority);
+    require!(authority.is_signer, AmmError::AuthorityNotSigner);
+    Ok(())
+}
+
+/// 池子管理员指令共用的账户结构
+#[derive(Accounts)]
+pub struct PoolAdmin<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> PoolAdmin<'info> {
+    /// 设置单笔 swap 的最大价格冲击，0 表示关闭限制
+    pub fn set_max_price_impact(&mut self, max_price_impact_bps: u16) -> Result<()> {
+        self.pool.max_price_impact_bps = max_price_impact_bps;
+        Ok(())
+    }
+
+    /// 设置单笔 swap 最多取走输出侧储备量的比例（基点），0 表示不限制
+    pub fn set_max_drain(&mut self, max_drain_bps: u16) -> Result<()> {
+        require!(max_drain_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidMaxDrain);
+        self.pool.max_drain_bps = max_drain_bps;
+        Ok(())
+    }
+
+    /// 设置单笔 swap 的最小输出数量，0 表示不限制
+    pub fn set_min_trade_amount(&mut self, min_trade_amount: u64) -> Result<()> {
+        self.pool.min_trade_amount = min_trade_amount;
+        Ok(())
+    }
+
+    /// 设置熔断阈值：swap 后现价偏离 TWAP 超过 circuit_breaker_bps 时拒绝，0 表示关闭
+    pub fn set_circuit_breaker(&mut self, circuit_breaker_bps: u16) -> Result<()> {
+        self.pool.circuit_breaker_bps = circuit_breaker_bps;
+        Ok(())
+    }
+
+    /// 结束 LBP，之后 swap 回到普通的恒定乘积曲线
+    ///
+    /// 权重还没有到 50/50 时提前结束，价格会跳到储备量之比，由池子创建者自行承担
+    pub fn end_lbp(&mut self) -> Result<()> {
+        require!(self.pool.curve_type == CurveType::Weighted, AmmError::LbpNotEnabled);
+        self.pool.curve_type = CurveType::ConstantProduct;
+        Ok(())
+    }
+
+    /// 设置每个钱包的 swap 限额，window_slots 为 0 表示关闭
+    pub fn set_rate_limit(&mut self, window_slots: u64, max_out_a: u64, max_out_b: u64) -> Result<()> {
+        self.pool.rate_limit_window_slots = window_slots;
+        self.pool.max_out_a_per_window = max_out_a;
+        self.pool.max_out_b_per_window = max_out_b;
+        Ok(())
+    }
+
+    /// 设置存款后储备量的上限，试运行结束后可以调高，设为 0 表示取消限制
+    ///
+    /// 只限制存款，swap 改变储备量不受影响
+    pub fn set_deposit_cap(&mut self, max_reserve_a: u64, max_reserve_b: u64) -> Result<()> {
+        self.pool.max_reserve_a = max_reserve_a;
+        self.pool.max_reserve_b = max_reserve_b;
+        Ok(())
+    }
+
+    /// 切换池子状态：Active / WithdrawOnly / Paused
+    pub fn set_pool_status(&mut self, status: PoolStatus) -> Result<()> {
+        self.pool.status = status;
+        Ok(())
+    }
+
+    /// 禁用某个方向的 swap，用于只卖 / 只买的发射阶段，两个都为 false 表示恢复双向交易
+    pub fn set_trade_directions(&mut self, disable_swap_a_to_b: bool, disable_swap_b_to_a: bool) -> Result<()> {
+        self.pool.disable_swap_a_to_b = disable_swap_a_to_b;
+        self.pool.disable_swap_b_to_a = disable_swap_b_to_a;
+        Ok(())
+    }
+
+    /// 开启 / 关闭紧急取出，记账出现问题时通常同时把池子设为 Paused
+    pub fn set_emergency_withdraw(&mut self, enabled: bool) -> Result<()> {
+        self.pool.emergency_withdraw_enabled = enabled;
+        Ok(())
+    }
+
+    /// 开启 / 关闭 JIT 保护，开启后 LP 需要先 init_liquidity_guard 才能存取流动性
+    pub fn set_jit_protection(&mut self, jit_protection: bool) -> Result<()> {
+        self.pool.jit_protection = jit_protection;
+        Ok(())
+    }
+
+    /// 登记 swap hook，hook_program 传默认值表示取消；hook_accounts 是 hook 需要的额外账户数量
+    pub fn set_swap_hook(&mut self, hook_program: Pubkey, hook_accounts: u8) -> Result<()> {
+        require!(hook_accounts <= MAX_SWAP_HOOK_ACCOUNTS, AmmError::TooManySwapHookAccounts);
+        // 回调会带上 pool 账户，hook 不能是本程序自己
+        require_keys_neq!(hook_program, crate::ID, AmmError::InvalidSwapHook);
+        self.pool.swap_hook_program = hook_program;
+        self.pool.swap_hook_accounts = if hook_program == Pubkey::default() { 0 } else { hook_accounts };
+        Ok(())
+    }
+
+    /// 登记外部预言机价格带，oracle 传默认值表示取消
+    pub fn set_external_oracle(&mut self, oracle: Pubkey, band_bps: u16, max_age: u32) -> Result<()> {
+        if oracle != Pubkey::default() {
+            require!(band_bps > 0 && band_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidExternalOracleConfig);
+            require_gt!(max_age, 0, AmmError::InvalidExternalOracleConfig);
+        }
+        self.pool.external_oracle = oracle;
+        self.pool.external_oracle_band_bps = band_bps;
+        self.pool.external_oracle_max_age = max_age;
+        Ok(())
+    }
+
+    /// 设置往返 swap 附加费，window_slots 为 0 表示关闭
+    pub fn set_round_trip_fee(&mut self, window_slots: u64, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, AmmError::InvalidFee);
+        self.pool.round_trip_window_slots = window_slots;
+        self.pool.round_trip_fee_bps = fee_bps;
+        Ok(())
+    }
+
+    /// 发布默克尔根白名单的默克尔根，传全 0 表示取消，已经发布的根被整体替换
+    pub fn set_position_whitelist_root(&mut self, root: [u8; 32]) -> Result<()> {
+        require!(self.pool.soulbound_lp, AmmError::PoolNotSoulbound);
+        self.pool.position_whitelist_root = root;
+        Ok(())
+    }
+
+    /// 登记会员制池子的 NFT 集合（collection mint），传默认值表示取消
+    pub fn set_trade_gate(&mut self, collection: Pubkey) -> Result<()> {
+        self.pool.trade_gate_collection = collection;
+        Ok(())
+    }
+
+    /// 开启 / 关闭 swap 的 CPI 限制
+    pub fn set_cpi_restricted(&mut self, cpi_restricted: bool) -> Result<()> {
+        self.pool.cpi_restricted = cpi_restricted;
+        Ok(())
+    }
+
+    /// 两步转交的第一步：提名新的创建者，对方调用 accept_pool_creator 后才生效
+    ///
+    /// 再次调用会覆盖之前的提名，传入默认值表示取消
+    pub fn transfer_pool_creator(&mut self, new_creator: Pubkey) -> Result<()> {
+        self.pool.pending_creator = new_creator;
+        Ok(())
+    }
+}
+
+/// 两步转交的第二步：被提名的新创建者签名接受
+#[derive(Accounts)]
+pub struct AcceptPoolCreator<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA
+    #[account(
+        constraint = pool.pending_creator != Pubkey::default() && new_creator.key() == pool.pending_creator @ AmmError::InvalidAuthority,
+        constraint = new_creator.is_signer @ AmmError::AuthorityNotSigner
+    )]
+    new_creator: UncheckedAccount<'info>,
+    #[account(mut)]
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> AcceptPoolCreator<'info> {
+    /// 创建者角色包括池子管理员和创建者手续费的领取人，两者一起转交
+    ///
+    /// 已经累积在手续费账户中的创建者手续费之后也由新创建者领取
+    pub fn accept_pool_creator(&mut self) -> Result<()> {
+        let new_creator = self.new_creator.key();
+        self.pool.authority = new_creator;
+        self.pool.creator = new_creator;
+        self.pool.pending_creator = Pubkey::default();
+        Ok(())
+    }
+}
+
+/// 协议管理员指令共用的账户结构
+#[derive(Accounts)]
+pub struct ConfigAdmin<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+}
+
+impl<'info> ConfigAdmin<'info> {
+    /// 替换允许创建池子的手续费档位，已经存在的池子不受影响
+    pub fn set_fee_tiers(&mut self, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        validate_fee_tiers(&fee_tiers)?;
+        self.config.fee_tiers = fee_tiers;
+        Ok(())
+    }
+
+    /// 新增一个手续费档位，fee 已经存在时报错
+    pub fn add_fee_tier(&mut self, fee: u16, protocol_fee_bps: u16) -> Result<()> {
+        let mut fee_tiers = self.config.fee_tiers.clone();
+        fee_tiers.push(FeeTier { fee, protocol_fee_bps });
+        validate_fee_tiers(&fee_tiers)?;
+        self.config.fee_tiers = fee_tiers;
+        Ok(())
+    }
+
+    /// 下线一个手续费档位：之后不能再用它创建池子，已经存在的池子照常运行
+    pub fn remove_fee_tier(&mut self, fee: u16) -> Result<()> {
+        let index = self.config.fee_tiers.iter().position(|tier| tier.fee == fee).ok_or(AmmError::FeeTierNotAllowed)?;
+        self.config.fee_tiers.remove(index);
+        Ok(())
+    }
+
+    /// 设置创建者手续费分成的上限，已经存在的池子不受影响
+    pub fn set_max_creator_fee(&mut self, max_creator_fee_bps: u16) -> Result<()> {
+        require!(max_creator_fee_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidBps);
+        self.config.max_creator_fee_bps = max_creator_fee_bps;
+        Ok(())
+    }
+
+    /// 设置回购销毁的协议代币和每次执行的限额，protocol_token_mint 设为默认值表示关闭
+    pub fn set_buyback_config(&mut self, protocol_token_mint: Pubkey, max_buyback_amount_in: u64, max_buyback_impact_bps: u16) -> Result<()> {
+        require!(max_buyback_impact_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidBps);
+        self.config.protocol_token_mint = protocol_token_mint;
+        self.config.max_buyback_amount_in = max_buyback_amount_in;
+        self.config.max_buyback_impact_bps = max_buyback_impact_bps;
+        Ok(())
+    }
+
+    /// 是否允许用有 freeze authority 的 mint 创建池子，只影响之后创建的池子
+    pub fn set_allow_freezable_mints(&mut self, allow_freezable_mints: bool) -> Result<()> {
+        self.config.allow_freezable_mints = allow_freezable_mints;
+        Ok(())
+    }
+
+    /// 是否只允许持有 TokenBadge 的 mint 创建池子，只影响之后创建的池子
+    pub fn set_require_token_badges(&mut self, require_token_badges: bool) -> Result<()> {
+        self.config.require_token_badges = require_token_badges;
+        Ok(())
+    }
+
+    /// 设置可以开启全局紧急模式的守护者，见 emergency_mode.rs
+    pub fn set_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        self.config.guardian = guardian;
+        Ok(())
+    }
+
+    /// 替换持有者手续费折扣的会员代币和档位，立即对所有池子生效
+    pub fn set_fee_rebate_tiers(&mut self, membership_mint: Pubkey, tiers: Vec<FeeRebateTier>) -> Result<()> {
+        validate_fee_rebate_tiers(&tiers)?;
+        self.config.membership_mint = membership_mint;
+        self.config.fee_rebate_tiers = tiers;
+        Ok(())
+    }
+
+    /// 替换协议手续费的接收方，传空列表表示恢复为转给调用方指定的一个金库
+    pub fn set_fee_recipients(&mut self, recipients: Vec<FeeRecipient>) -> Result<()> {
+        validate_fee_recipients(&recipients)?;
+        self.config.fee_recipients = recipients;
+        Ok(())
+    }
+
+    /// 替换集成方手续费豁免列表，立即对所有池子生效
+    pub fn set_fee_exemptions(&mut self, exemptions: Vec<FeeExemption>) -> Result<()> {
+        validate_fee_exemptions(&exemptions)?;
+        self.config.fee_exemptions = exemptions;
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{burn, mint_to, transfer, Burn, Mint, MintTo, Token, TokenAccount, Transfer}};
+
+use crate::{
+    error::AmmError,
+    events::LiquidityDeposited,
+    math::{vault_lp_for_shares, vault_shares_for_lp},
+    state::{AmmConfig, AutoVault, Pool, PoolStatus, ACCOUNT_VERSION},
+};
+
+// ========================================
+// 自动复投金库
+// ========================================
+//
+// swap 手续费直接留在池子的储备量里，LP 的净值本身就会增长，不需要领取。
+// 金库要复投的是额外发到金库 ATA 里的代币（例如外部的流动性挖矿奖励）：
+// - 用户存入 LP，按 金库 LP / 份额总量 的比例获得份额
+// - 任何人都可以调用 compound，把金库 ATA 里的 TokenA / TokenB 存入池子，
+//   换来的 LP 留在金库里，份额数量不变，每份额对应的 LP 就增加了
+// - 奖励通常只有一侧，compound 走 imbalanced_deposit_lp，
+//   偏离池子比例的部分按 swap 费率扣费，相当于先 swap 再按比例存入
+
+#[derive(Accounts)]
+pub struct InitializeAutoVault<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = signer,
+        space = AutoVault::DISCRIMINATOR.len() + AutoVault::INIT_SPACE,
+        seeds = [b"auto_vault", pool.key().as_ref()],
+        bump
+    )]
+    auto_vault: Account<'info, AutoVault>,
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"vault_share", auto_vault.key().as_ref()],
+        bump,
+        mint::decimals = mint_lp.decimals,
+        mint::authority = auto_vault
+    )]
+    share_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = signer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = signer,
+        associated_token::mint = mint_a,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = signer,
+        associated_token::mint = mint_b,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_b: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeAutoVault<'info> {
+    pub fn initialize_auto_vault(&mut self, bump: u8) -> Result<()> {
+        self.auto_vault.set_inner(AutoVault {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            mint_lp: self.mint_lp.key(),
+            share_mint: self.share_mint.key(),
+            last_compound_ts: Clock::get()?.unix_timestamp,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+/// 存入 / 赎回金库份额共用的账户结构
+#[derive(Accounts)]
+pub struct AutoVaultShares<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    #[account(
+        seeds = [b"auto_vault", auto_vault.pool.as_ref()],
+        bump = auto_vault.bump,
+        has_one = mint_lp,
+        has_one = share_mint
+    )]
+    auto_vault: Account<'info, AutoVault>,
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    share_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = signer
+    )]
+    signer_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = share_mint,
+        associated_token::authority = signer
+    )]
+    signer_ata_share: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> AutoVaultShares<'info> {
+    /// 存入 amount_lp 个 LP，获得金库份额
+    pub fn auto_vault_deposit(&mut self, amount_lp: u64, min_shares: u64) -> Result<()> {
+        let shares = vault_shares_for_lp(self.vault_ata_lp.amount, self.share_mint.supply, amount_lp)?;
+        require_gt!(shares, 0);
+
+        // Check slippage
+        require_gte!(shares, min_shares);
+
+        let accounts = Transfer {
+            from: self.signer_ata_lp.to_account_info(),
+            to: self.vault_ata_lp.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount_lp)?;
+
+        let accounts = MintTo {
+            mint: self.share_mint.to_account_info(),
+            to: self.signer_ata_share.to_account_info(),
+            authority: self.auto_vault.to_account_info(),
+        };
+
+        let vault_seeds = self.auto_vault.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&vault_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, shares)
+    }
+
+    /// 销毁 shares 个金库份额，取回对应的 LP（包含已经复投的部分）
+    pub fn auto_vault_withdraw(&mut self, shares: u64, min_lp: u64) -> Result<()> {
+        let amount_lp = vault_lp_for_shares(self.vault_ata_lp.amount, self.share_mint.supply, shares)?;
+        require_gt!(amount_lp, 0);
+
+        // Check slippage
+        require_gte!(amount_lp, min_lp);
+
+        let accounts = Burn {
+            mint: self.share_mint.to_account_info(),
+            from: self.signer_ata_share.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        burn(ctx, shares)?;
+
+        let accounts = Transfer {
+            from: self.vault_ata_lp.to_account_info(),
+            to: self.signer_ata_lp.to_account_info(),
+            authority: self.auto_vault.to_account_info(),
+        };
+
+        let vault_seeds = self.auto_vault.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&vault_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount_lp)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    // 任何人都可以调用 compound
+    signer: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    #[account(
+        mut,
+        seeds = [b"auto_vault", pool.key().as_ref()],
+        bump = auto_vault.bump
+    )]
+    auto_vault: Account<'info, AutoVault>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = auto_vault
+    )]
+    vault_ata_b: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Compound<'info> {
+    pub fn compound(&mut self) -> Result<()> {
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.pool.require_skim_protocol_fee()?;
+
+        // 复投是不按比例的存入，与 deposit_imbalanced 一样需要处在交易窗口内，并且 swap 的方向没有被禁用
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_trading_window(now)?;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+
+        let amount_a = self.vault_ata_a.amount;
+        let amount_b = self.vault_ata_b.amount;
+        self.pool.check_imbalanced_direction(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+
+        let amount_lp = self.pool.curve(now).imbalanced_deposit_lp(
+            self.pool_ata_a.amount,
+            self.pool_ata_b.amount,
+            self.mint_lp.supply,
+            amount_a,
+            amount_b,
+            self.pool.fee,
+        )?;
+
+        // 没有可以复投的代币，或者数量太少铸造不出 LP
+        require_gt!(amount_lp, 0);
+
+        self.pool.check_deposit_cap(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+
+        self.transfer_from_vault(self.vault_ata_a.to_account_info(), self.pool_ata_a.to_account_info(), amount_a)?;
+        self.transfer_from_vault(self.vault_ata_b.to_account_info(), self.pool_ata_b.to_account_info(), amount_b)?;
+
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.vault_ata_lp.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount_lp)?;
+
+        self.auto_vault.last_compound_ts = now;
+
+        // 复投的 LP 记在金库名下
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.auto_vault.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// 金库 PDA 签名，把金库 ATA 里的代币转入池子
+    fn transfer_from_vault(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.auto_vault.to_account_info(),
+        };
+
+        let vault_seeds = self.auto_vault.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&vault_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
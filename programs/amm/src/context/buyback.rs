@@ -0,0 +1,294 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{burn, transfer, Burn, Token, Transfer};
+
+use crate::{
+    context::{admin::is_config_admin, swap::SwapResult},
+    error::AmmError,
+    events::SwapEvent,
+    math::{price_impact_bps, BPS_DENOMINATOR},
+    state::{AmmConfig, CurveType, Pool, PoolStatus, ProtocolFeeMode},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// 协议手续费回购销毁
+// ========================================
+//
+// 协议管理员为池子设置 protocol_fee_bps 后，每笔 swap 手续费中的这部分与创建者分成一起
+// 转入池子的手续费账户，数量记录在 pool.protocol_fees_a / protocol_fees_b。
+//
+// 如果池子的一侧是 AmmConfig 中配置的协议代币，任何人都可以调用 buyback_and_burn：
+// 1. 协议代币一侧的协议手续费直接销毁
+// 2. 另一侧的协议手续费通过池子本身卖出换成协议代币，再销毁
+//
+// 卖出部分每次不超过 max_buyback_amount_in，价格冲击不超过 max_buyback_impact_bps，
+// 剩余的协议手续费留到下一次执行。这两个上限只限制单次回购的规模，本身挡不住夹子：
+// 攻击者可以在同一笔交易里先把协议代币的价格推高，调用回购，再卖回去。因此另外要求：
+// - 回购前的现价偏离 TWAP 不超过 max_buyback_impact_bps，还没有 TWAP 时拒绝执行。
+//   crank 和每笔 swap 都会推进累加器，被推高的现价只在当前时间戳之后才计入 TWAP
+// - 每个池子每 BUYBACK_INTERVAL 秒最多执行一次，攻击者不能在一个 slot 里反复触发回购
+// 回购不再从手续费中拆出创建者和协议分成，付给池子的手续费全部留给 LP。
+
+/// 同一个池子两次 buyback_and_burn 之间的最小间隔（秒）
+pub const BUYBACK_INTERVAL: i64 = 3_600;
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+    #[account(mut)]
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> SetProtocolFee<'info> {
+    /// 设置协议从 swap 手续费中分走的比例，与创建者分成之和不能超过 10000
+    pub fn set_protocol_fee(&mut self, protocol_fee_bps: u16) -> Result<()> {
+        require!(
+            self.pool.creator_fee_bps as u128 + protocol_fee_bps as u128 <= BPS_DENOMINATOR,
+            AmmError::ProtocolFeeTooHigh
+        );
+        self.pool.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
+    /// 切换协议分成的收取方式，LP 模式只支持恒定乘积曲线，见 protocol_fee.rs
+    ///
+    /// 切换时 k_last 清零：切到 LP 模式后从下一次存取流动性开始记录，
+    /// 切回 Skim 模式时上次结算以来还没有铸造的协议 LP 作废
+    pub fn set_protocol_fee_mode(&mut self, protocol_fee_mode: ProtocolFeeMode, treasury_lp: Pubkey) -> Result<()> {
+        if protocol_fee_mode == ProtocolFeeMode::Lp {
+            require!(self.pool.curve_type == CurveType::ConstantProduct, AmmError::UnsupportedCurve);
+            require_keys_neq!(treasury_lp, Pubkey::default(), AmmError::TreasuryLpRequired);
+        }
+
+        self.pool.protocol_fee_mode = protocol_fee_mode;
+        self.pool.treasury_lp = treasury_lp;
+        self.pool.k_last = 0;
+        Ok(())
+    }
+
+    /// 允许或禁止 fund_farm_from_fees 把池子的协议分成注入 Farm，见 farm.rs
+    pub fn set_protocol_fees_to_farm(&mut self, enabled: bool) -> Result<()> {
+        self.pool.protocol_fees_to_farm = enabled;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    // 任何人都可以调用，不需要与池子有关的权限
+    keeper: Signer<'info>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验，销毁协议代币时需要可写
+    #[account(mut)]
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验，销毁协议代币时需要可写
+    #[account(mut)]
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_a
+    #[account(mut, address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_b
+    #[account(mut, address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Account<'info, Pool>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Account<'info, AmmConfig>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> BuybackAndBurn<'info> {
+    pub fn buyback_and_burn(&mut self) -> Result<()> {
+        let protocol_token_mint = self.config.protocol_token_mint;
+        require_keys_neq!(protocol_token_mint, Pubkey::default(), AmmError::BuybackNotConfigured);
+
+        // 买入协议代币，is_a 与 swap 相同，true 表示买入 TokenA
+        let is_a = if protocol_token_mint == self.pool.mint_a {
+            true
+        } else if protocol_token_mint == self.pool.mint_b {
+            false
+        } else {
+            return err!(AmmError::MintNotInPool);
+        };
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            self.pool.last_buyback_ts == 0 || now >= self.pool.last_buyback_ts.saturating_add(BUYBACK_INTERVAL),
+            AmmError::BuybackTooSoon
+        );
+
+        // 在储备量变化之前推进价格累加器
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        // 回购前的现价必须贴近 TWAP，拒绝在被推高的价格上买入
+        let deviation_bps = self.pool.twap_deviation_bps(reserve_a, reserve_b)?.ok_or(AmmError::TwapUnavailable)?;
+        require!(deviation_bps <= self.config.max_buyback_impact_bps as u128, AmmError::CircuitBreakerTripped);
+
+        let (mint_out, pool_in, pool_out, vault_in, vault_out, reserve_in, reserve_out, fees_in, fees_out) = if is_a {
+            (
+                self.mint_a.to_account_info(),
+                self.pool_ata_b.to_account_info(),
+                self.pool_ata_a.to_account_info(),
+                self.creator_fee_vault_b.to_account_info(),
+                self.creator_fee_vault_a.to_account_info(),
+                reserve_b,
+                reserve_a,
+                self.pool.protocol_fees_b,
+                self.pool.protocol_fees_a,
+            )
+        } else {
+            (
+                self.mint_b.to_account_info(),
+                self.pool_ata_a.to_account_info(),
+                self.pool_ata_b.to_account_info(),
+                self.creator_fee_vault_a.to_account_info(),
+                self.creator_fee_vault_b.to_account_info(),
+                reserve_a,
+                reserve_b,
+                self.pool.protocol_fees_a,
+                self.pool.protocol_fees_b,
+            )
+        };
+
+        // ==========================================
+        // 1. 卖出另一侧的协议手续费，单次不超过上限
+        // ==========================================
+        let budget = fees_in.min(self.config.max_buyback_amount_in);
+        let amount_out = if budget > 0 {
+            self.pool.curve(now).swap_out_given_in(reserve_in, reserve_out, budget, is_a, self.pool.fee)?
+        } else {
+            0
+        };
+
+        let (amount_in, amount_in_with_fees) = if amount_out > 0 {
+            require!(
+                price_impact_bps(reserve_out, amount_out)? <= self.config.max_buyback_impact_bps as u128,
+                AmmError::PriceImpactTooHigh
+            );
+
+            let (amount_in, amount_in_with_fees) = self.pool.quote_exact_out(reserve_in, reserve_out, amount_out, is_a, now)?;
+            require_gte!(budget, amount_in_with_fees);
+
+            // 熔断：按交易后的储备量检查现价与 TWAP 的偏离
+            let (reserve_a_after, reserve_b_after) = if is_a {
+                (reserve_a - amount_out, reserve_b.checked_add(amount_in_with_fees).ok_or(ProgramError::ArithmeticOverflow)?)
+            } else {
+                (reserve_a.checked_add(amount_in_with_fees).ok_or(ProgramError::ArithmeticOverflow)?, reserve_b - amount_out)
+            };
+            self.pool.check_circuit_breaker(reserve_a_after, reserve_b_after)?;
+
+            (amount_in, amount_in_with_fees)
+        } else {
+            // 手续费还不够买到 1 个最小单位，留到下一次
+            (0, 0)
+        };
+
+        require!(fees_out > 0 || amount_out > 0, AmmError::ZeroAmount);
+
+        // ==========================================
+        // 2. 转账和销毁，全部由池子 PDA 签名
+        // ==========================================
+        self.transfer_from_pool(vault_in, pool_in, amount_in_with_fees)?;
+        self.burn_from_pool(mint_out.clone(), pool_out, amount_out)?;
+        self.burn_from_pool(mint_out, vault_out, fees_out)?;
+
+        if is_a {
+            self.pool.protocol_fees_b -= amount_in_with_fees;
+            self.pool.protocol_fees_a = 0;
+        } else {
+            self.pool.protocol_fees_a -= amount_in_with_fees;
+            self.pool.protocol_fees_b = 0;
+        }
+        self.pool.last_buyback_ts = now;
+
+        // 只有买入时储备量才会变化，trader 记为调用者
+        if amount_out > 0 {
+            let result = SwapResult {
+                amount_in: amount_in_with_fees,
+                amount_out,
+                fee_paid: amount_in_with_fees - amount_in,
+            };
+            emit!(SwapEvent::new(&mut self.pool, self.keeper.key(), is_a, &result, 0, None)?);
+        }
+        Ok(())
+    }
+
+    /// 池子 PDA 签名，从池子名下的账户转出
+    fn transfer_from_pool(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+
+    /// 池子 PDA 签名，销毁池子名下账户中的协议代币
+    fn burn_from_pool(&self, mint: AccountInfo<'info>, from: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Burn {
+            mint,
+            from,
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        burn(ctx, amount)
+    }
+}
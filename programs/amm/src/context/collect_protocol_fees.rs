@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{context::admin::is_config_admin, error::AmmError, math::{split_by_weights, BPS_DENOMINATOR}, state::{AmmConfig, FeeRecipient, Pool, MAX_FEE_RECIPIENTS}, token_account::read_token_mint_and_owner};
+
+// ========================================
+// 批量领取协议手续费
+// ========================================
+//
+// Skim 模式下协议分成与创建者分成一起存放在池子的手续费账户中，数量记录在
+// pool.protocol_fees_a / protocol_fees_b，除了 buyback_and_burn 之外没有别的出口。
+// 协议管理员可以用 collect_protocol_fees_batch 在一笔交易里把多个池子的协议分成转到金库：
+//
+// - remaining_accounts 每个池子一组：[pool, creator_fee_vault_a, creator_fee_vault_b, destination_a, destination_b, ...]
+// - destination 是接收方在对应 mint 上的代币账户，mint 由 SPL Token 程序在 transfer 中校验
+// - 只转出协议分成，创建者的部分留在手续费账户中；领取之后等待回购的协议分成清零
+//
+// config.fee_recipients 为空时每组只有一对 destination，全部转给调用方指定的金库；
+// 设置了接收方（例如金库 70%、保险基金 20%、开发基金 10%）时，每组按 fee_recipients 的顺序
+// 为每个接收方传一对 destination，代币账户的 owner 必须是对应的接收方，协议分成按权重拆分，
+// 舍入的余数归最后一个接收方。
+//
+// 每组每个接收方最多转账两次，一笔交易能处理的池子数量受账户数量和计算量限制，由调用方控制。
+
+/// remaining_accounts 中每个池子固定占用的账户数量，之后是每个接收方的一对 destination
+pub const PROTOCOL_FEE_BATCH_GROUP: usize = 3;
+
+#[derive(Accounts)]
+pub struct CollectProtocolFeesBatch<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> CollectProtocolFeesBatch<'info> {
+    pub fn collect_protocol_fees_batch(&self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let recipients = self.config.fee_recipients.len().max(1);
+        let group_len = PROTOCOL_FEE_BATCH_GROUP + 2 * recipients;
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(group_len),
+            AmmError::InvalidProtocolFeeBatch
+        );
+
+        for group in remaining_accounts.chunks(group_len) {
+            self.collect_from_pool(group)?;
+        }
+        Ok(())
+    }
+
+    /// 领取一个池子的协议分成，先清零再转账，同一个池子在批次中重复出现时第二次转出 0
+    fn collect_from_pool(&self, group: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [pool_info, creator_fee_vault_a, creator_fee_vault_b, destinations @ ..] = group else {
+            return err!(AmmError::InvalidProtocolFeeBatch);
+        };
+
+        let mut pool = Account::<Pool>::try_from(pool_info)?;
+        require!(pool_info.is_writable, AmmError::InvalidProtocolFeeBatch);
+        require!(!pool.locked, AmmError::PoolLocked);
+        require_keys_eq!(creator_fee_vault_a.key(), pool.creator_fee_vault_a, AmmError::InvalidProtocolFeeBatch);
+        require_keys_eq!(creator_fee_vault_b.key(), pool.creator_fee_vault_b, AmmError::InvalidProtocolFeeBatch);
+
+        let (amount_a, amount_b) = (pool.protocol_fees_a, pool.protocol_fees_b);
+        pool.protocol_fees_a = 0;
+        pool.protocol_fees_b = 0;
+
+        let shares_a = self.split_protocol_fee(amount_a)?;
+        let shares_b = self.split_protocol_fee(amount_b)?;
+
+        for (i, pair) in destinations.chunks(2).enumerate() {
+            let [destination_a, destination_b] = pair else {
+                return err!(AmmError::InvalidProtocolFeeBatch);
+            };
+            if let Some(recipient) = self.config.fee_recipients.get(i) {
+                require_keys_eq!(read_token_mint_and_owner(destination_a)?.1, recipient.owner, AmmError::InvalidProtocolFeeBatch);
+                require_keys_eq!(read_token_mint_and_owner(destination_b)?.1, recipient.owner, AmmError::InvalidProtocolFeeBatch);
+            }
+            self.transfer_from_pool(&pool, creator_fee_vault_a, destination_a, shares_a[i])?;
+            self.transfer_from_pool(&pool, creator_fee_vault_b, destination_b, shares_b[i])?;
+        }
+
+        // 池子不在 Accounts 结构中，Anchor 不会自动写回，必须手动 exit
+        pool.exit(&crate::ID)
+    }
+
+    /// 按 config.fee_recipients 的权重拆分，没有设置接收方时整笔转给唯一的金库
+    fn split_protocol_fee(&self, amount: u64) -> Result<Vec<u64>> {
+        if self.config.fee_recipients.is_empty() {
+            return Ok(vec![amount]);
+        }
+        let weights: Vec<u16> = self.config.fee_recipients.iter().map(|recipient| recipient.weight_bps).collect();
+        split_by_weights(amount, &weights)
+    }
+
+    /// 池子 PDA 签名，从手续费账户转出
+    fn transfer_from_pool(&self, pool: &Account<'info, Pool>, from: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            authority: pool.to_account_info(),
+        };
+
+        let pool_seeds = pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
+
+/// 检查接收方：最多 MAX_FEE_RECIPIENTS 个，owner 互不相同，权重都大于 0 且之和为 10000；空列表表示关闭
+pub fn validate_fee_recipients(recipients: &[FeeRecipient]) -> Result<()> {
+    if recipients.is_empty() {
+        return Ok(());
+    }
+    require!(recipients.len() <= MAX_FEE_RECIPIENTS, AmmError::InvalidFeeRecipients);
+    require!(recipients.iter().all(|recipient| recipient.weight_bps > 0), AmmError::InvalidFeeRecipients);
+    let total: u128 = recipients.iter().map(|recipient| recipient.weight_bps as u128).sum();
+    require!(total == BPS_DENOMINATOR, AmmError::InvalidFeeRecipients);
+    for (i, recipient) in recipients.iter().enumerate() {
+        require!(recipients[..i].iter().all(|other| other.owner != recipient.owner), AmmError::InvalidFeeRecipients);
+    }
+    Ok(())
+}
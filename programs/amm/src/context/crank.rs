@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
+
+use crate::{error::AmmError, state::{Pool, PoolStats}};
+
+#[derive(Accounts)]
+pub struct Crank<'info> {
+    // 任何人都可以调用 crank，不需要与池子有关的权限
+    signer: Signer<'info>,
+    mint_a: Account<'info, Mint>,
+    mint_b: Account<'info, Mint>,
+    #[account(
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Account<'info, TokenAccount>,
+    #[account(
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked
+    )]
+    pool: Account<'info, Pool>,
+    // 可选的成交统计账户，见 pool_stats.rs
+    #[account(
+        mut,
+        constraint = pool_stats.load()?.pool == pool.key() @ AmmError::InvalidPoolStats
+    )]
+    pool_stats: Option<AccountLoader<'info, PoolStats>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Crank<'info> {
+    pub fn crank(&mut self) -> Result<()> {
+        // 池子长时间没有交易时，累加器和观测缓冲区不会前进，
+        // TWAP 查询就只能拿到很久以前的观测。crank 用当前储备量补齐这段时间。
+        //
+        // 注意：crank 只负责预言机和成交统计，不向调用者支付 bounty。
+        // 协议分成虽然记在 pool.protocol_fees_a / protocol_fees_b（存放在创建者手续费账户中），
+        // 但只能由 collect_protocol_fees_batch、buyback_and_burn、fund_farm_from_fees 取出；
+        // crank 不需要任何权限，从中支付 bounty 会让任何人都能用空转的 crank 把协议分成领走，
+        // 需要 keeper 激励时由协议在链下发放。程序也没有动态手续费，不需要衰减 EWMA。
+        let now = Clock::get()?.unix_timestamp;
+
+        // 同时滚动成交统计的当前小时桶，见 pool_stats.rs
+        if let Some(pool_stats) = &self.pool_stats {
+            pool_stats.load_mut()?.roll(now);
+        }
+
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)
+    }
+}
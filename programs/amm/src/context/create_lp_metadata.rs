@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3, Metadata},
+    token::Mint,
+};
+
+use crate::{context::admin::is_pool_authority, error::AmmError, state::Pool};
+
+#[derive(Accounts)]
+pub struct CreateLpMetadata<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    mint_a: Account<'info, Mint>,
+    mint_b: Account<'info, Mint>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Account<'info, Mint>,
+    #[account(
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pool: Account<'info, Pool>,
+    /// CHECK: 由 Token Metadata 程序创建和校验，这里只约束地址
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_lp.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    metadata: UncheckedAccount<'info>,
+    token_metadata_program: Program<'info, Metadata>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateLpMetadata<'info> {
+    pub fn create_lp_metadata(&mut self, uri: String) -> Result<()> {
+        // 名称形如 "AMM LP 7xKX-EPjF 0.30%"：链上拿不到两种代币的 symbol，
+        // 用 mint 地址的前 4 个字符区分，fee 以百分比显示
+        let mint_a = self.mint_a.key().to_string();
+        let mint_b = self.mint_b.key().to_string();
+        let name = format!(
+            "AMM LP {}-{} {}.{:02}%",
+            &mint_a[..4],
+            &mint_b[..4],
+            self.pool.fee / 100,
+            self.pool.fee % 100
+        );
+
+        let data = DataV2 {
+            name,
+            symbol: String::from("AMM-LP"),
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+
+        // pool PDA 既是 LP mint 的 mint authority，也作为 metadata 的 update authority
+        let accounts = CreateMetadataAccountsV3 {
+            metadata: self.metadata.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            mint_authority: self.pool.to_account_info(),
+            payer: self.payer.to_account_info(),
+            update_authority: self.pool.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            rent: self.rent.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_metadata_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        create_metadata_accounts_v3(ctx, data, true, true, None)
+    }
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{error::AmmError, state::Pool, token_account::read_token_amount};
+
+// ========================================
+// 创建者手续费
+// ========================================
+//
+// 社区创建的池子可以在 initialize 时设置 creator_fee_bps（不超过 AmmConfig 的上限），
+// 每笔 swap 的手续费中有 creator_fee_bps / 10000 转入池子名下的 creator_fee_vault，
+// 不计入储备量，创建者随时可以通过 claim_creator_fees 领取到自己指定的代币账户。
+//
+// 协议分成（protocol_fee_bps）也存放在同一个账户中，数量记录在 pool.protocol_fees_a / protocol_fees_b，
+// 领取时只转出超过这部分的余额，协议分成留给 buyback_and_burn。
+//
+// fee_destination_mode = Creator 的池子（许可部署中的协议自有流动性），扣除协议分成之后的手续费全部按创建者手续费处理，
+// 同样通过 claim_creator_fees 领取，creator 通常是 DAO 金库。
+
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(address = pool.creator @ AmmError::InvalidAuthority)]
+    creator: Signer<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_a
+    #[account(mut, address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_b
+    #[account(mut, address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    /// CHECK: 创建者指定的 TokenA 账户，由 SPL Token 程序在 transfer 中校验 mint
+    #[account(mut)]
+    destination_a: UncheckedAccount<'info>,
+    /// CHECK: 创建者指定的 TokenB 账户，由 SPL Token 程序在 transfer 中校验 mint
+    #[account(mut)]
+    destination_b: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pool: Account<'info, Pool>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> ClaimCreatorFees<'info> {
+    pub fn claim_creator_fees(&mut self) -> Result<()> {
+        let amount_a = read_token_amount(&self.creator_fee_vault_a)?
+            .checked_sub(self.pool.protocol_fees_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let amount_b = read_token_amount(&self.creator_fee_vault_b)?
+            .checked_sub(self.pool.protocol_fees_b).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        self.transfer_from_pool(self.creator_fee_vault_a.to_account_info(), self.destination_a.to_account_info(), amount_a)?;
+        self.transfer_from_pool(self.creator_fee_vault_b.to_account_info(), self.destination_b.to_account_info(), amount_b)
+    }
+
+    /// 池子 PDA 签名，从创建者手续费账户转出
+    fn transfer_from_pool(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
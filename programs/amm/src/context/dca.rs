@@ -0,0 +1,346 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{close_account, transfer, CloseAccount, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{context::{pool_stats::record_swap_stats, swap::SwapResult}, error::AmmError, events::SwapEvent, state::{AmmConfig, DcaOrder, Pool, PoolStats, PoolStatus, ACCOUNT_VERSION}, token_account::read_token_amount};
+
+// ========================================
+// 定投（DCA）订单
+// ========================================
+//
+// 1. create_dca：用户把全部输入代币存入订单 PDA 的托管 ATA
+// 2. execute_dca：任何人（keeper）每隔 interval 秒可以执行一次，
+//    用 amount_per_interval 个输入代币在池子里 swap，输出直接转到 destination，
+//    keeper 从托管中拿到 keeper_fee 作为报酬
+// 3. cancel_dca：用户取回剩余的托管代币并关闭订单
+//
+// swap 是精确输出，定投是精确输入：先用池子曲线的 swap_out_given_in 估算能拿到的输出，
+// 再用 quote_exact_out 按与 swap 相同的路径（价格冲击限制等）报价，
+// 实际付出不超过本次预算，没用完的留在托管中。
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateDca<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    mint_in: Box<Account<'info, Mint>>,
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = owner,
+        space = DcaOrder::DISCRIMINATOR.len() + DcaOrder::INIT_SPACE,
+        seeds = [b"dca", pool.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    dca_order: Account<'info, DcaOrder>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = dca_order
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner
+    )]
+    owner_ata_in: Box<Account<'info, TokenAccount>>,
+    destination: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CreateDca<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dca(
+        &mut self,
+        id: u64,
+        total_amount: u64,
+        amount_per_interval: u64,
+        min_amount_out: u64,
+        interval: i64,
+        keeper_fee: u64,
+        is_a: bool,
+        bump: u8,
+    ) -> Result<()> {
+        let (mint_in, mint_out) = if is_a {
+            (self.pool.mint_b, self.pool.mint_a)
+        } else {
+            (self.pool.mint_a, self.pool.mint_b)
+        };
+        require_keys_eq!(self.mint_in.key(), mint_in, AmmError::InvalidDcaOrder);
+        require_keys_eq!(self.destination.mint, mint_out, AmmError::InvalidDcaOrder);
+
+        require!(amount_per_interval > 0 && interval > 0, AmmError::InvalidDcaOrder);
+        require!(keeper_fee < amount_per_interval, AmmError::InvalidDcaOrder);
+        require_gte!(total_amount, amount_per_interval);
+
+        let accounts = Transfer {
+            from: self.owner_ata_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, total_amount)?;
+
+        self.dca_order.set_inner(DcaOrder {
+            version: ACCOUNT_VERSION,
+            owner: self.owner.key(),
+            pool: self.pool.key(),
+            id,
+            is_a,
+            escrow: self.escrow.key(),
+            destination: self.destination.key(),
+            amount_per_interval,
+            min_amount_out,
+            interval,
+            next_execution_ts: Clock::get()?.unix_timestamp,
+            keeper_fee,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    // 任何人都可以执行到期的订单
+    keeper: Signer<'info>,
+    /// CHECK: 接收 keeper_fee，由 SPL Token 程序在 transfer 中校验 mint
+    #[account(mut)]
+    keeper_ata_in: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_a
+    #[account(mut, address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_b
+    #[account(mut, address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    // 钱包限额是按 signer 统计的，keeper 代为执行会绕过限额，所以限额池子不支持定投
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 可选的成交统计账户，见 pool_stats.rs
+    #[account(
+        mut,
+        constraint = pool_stats.load()?.pool == pool.key() @ AmmError::InvalidPoolStats
+    )]
+    pool_stats: Option<AccountLoader<'info, PoolStats>>,
+    #[account(
+        mut,
+        seeds = [b"dca", pool.key().as_ref(), dca_order.owner.as_ref(), dca_order.id.to_le_bytes().as_ref()],
+        bump = dca_order.bump,
+        has_one = pool,
+        has_one = escrow,
+        has_one = destination
+    )]
+    dca_order: Account<'info, DcaOrder>,
+    /// CHECK: 地址必须等于 dca_order.escrow
+    #[account(mut)]
+    escrow: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 dca_order.destination
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> ExecuteDca<'info> {
+    pub fn execute_dca(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require_gte!(now, self.dca_order.next_execution_ts, AmmError::DcaNotDue);
+
+        // 最后一次执行时托管余额可能不足一整份，按剩余数量执行
+        let balance = read_token_amount(&self.escrow)?;
+        let keeper_fee = self.dca_order.keeper_fee;
+        require_gt!(balance, keeper_fee);
+        let budget = self.dca_order.amount_per_interval.min(balance - keeper_fee);
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+
+        // 在储备量变化之前推进价格累加器
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let is_a = self.dca_order.is_a;
+        let (pool_in, pool_out, creator_fee_vault, reserve_in, reserve_out) = if is_a {
+            (self.pool_ata_b.to_account_info(), self.pool_ata_a.to_account_info(), self.creator_fee_vault_b.to_account_info(), reserve_b, reserve_a)
+        } else {
+            (self.pool_ata_a.to_account_info(), self.pool_ata_b.to_account_info(), self.creator_fee_vault_a.to_account_info(), reserve_a, reserve_b)
+        };
+
+        let amount_out = self.pool.curve(now).swap_out_given_in(reserve_in, reserve_out, budget, is_a, self.pool.fee)?;
+        require_gt!(amount_out, 0);
+
+        // Check slippage：不足一整份时按比例缩小最小输出
+        let min_amount_out = (self.dca_order.min_amount_out as u128)
+            .checked_mul(budget as u128).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(self.dca_order.amount_per_interval as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(amount_out as u128, min_amount_out);
+
+        let (amount_in, amount_in_with_fees) = self.pool.quote_exact_out(reserve_in, reserve_out, amount_out, is_a, now)?;
+        require_gte!(budget, amount_in_with_fees);
+
+        let (creator_fee, protocol_fee) = self.pool.fee_shares(amount_in_with_fees)?;
+        let vault_fee = creator_fee + protocol_fee;
+        let amount_to_pool = amount_in_with_fees - vault_fee;
+
+        let (reserve_a_after, reserve_b_after) = if is_a {
+            (reserve_a - amount_out, reserve_b.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?)
+        } else {
+            (reserve_a.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?, reserve_b - amount_out)
+        };
+        self.pool.check_circuit_breaker(reserve_a_after, reserve_b_after)?;
+
+        self.transfer_from_escrow(pool_in, amount_to_pool)?;
+        self.transfer_from_escrow(creator_fee_vault, vault_fee)?;
+        self.pool.accrue_protocol_fee(is_a, protocol_fee)?;
+        self.transfer_from_escrow(self.keeper_ata_in.to_account_info(), keeper_fee)?;
+
+        let accounts = Transfer {
+            from: pool_out,
+            to: self.destination.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount_out)?;
+
+        let result = SwapResult {
+            amount_in: amount_in_with_fees,
+            amount_out,
+            fee_paid: amount_in_with_fees - amount_in,
+        };
+        record_swap_stats(self.pool_stats.as_ref(), is_a, result.amount_in, result.amount_out, result.fee_paid, now)?;
+        // 成交记在订单所有者名下
+        emit!(SwapEvent::new(&mut self.pool, self.dca_order.owner, is_a, &result, 0, None)?);
+
+        self.dca_order.next_execution_ts = now.checked_add(self.dca_order.interval).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// 订单 PDA 签名，从托管 ATA 转出输入代币
+    fn transfer_from_escrow(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to,
+            authority: self.dca_order.to_account_info(),
+        };
+
+        let order_seeds = self.dca_order.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&order_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelDca<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"dca", dca_order.pool.as_ref(), owner.key().as_ref(), dca_order.id.to_le_bytes().as_ref()],
+        bump = dca_order.bump,
+        has_one = owner,
+        has_one = escrow
+    )]
+    dca_order: Account<'info, DcaOrder>,
+    #[account(mut)]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = owner
+    )]
+    owner_ata_in: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelDca<'info> {
+    pub fn cancel_dca(&mut self) -> Result<()> {
+        let order_seeds = self.dca_order.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&order_seeds.as_seeds()];
+
+        if self.escrow.amount > 0 {
+            let accounts = Transfer {
+                from: self.escrow.to_account_info(),
+                to: self.owner_ata_in.to_account_info(),
+                authority: self.dca_order.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, self.escrow.amount)?;
+        }
+
+        // 关闭托管 ATA，租金退还给用户；订单账户由 close = owner 关闭
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.dca_order.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        close_account(ctx)
+    }
+}
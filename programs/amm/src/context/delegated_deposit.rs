@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer}};
+
+use crate::{error::AmmError, events::LiquidityDeposited, math::max_amount_limit, state::{AmmConfig, LiquidityGuard, Pool, PoolStatus}};
+
+// ========================================
+// 通过 SPL Token 委托存入流动性
+// ========================================
+//
+// owner 事先用 spl-token approve 把自己 TokenA / TokenB 账户的额度委托给 delegate
+// （会话密钥、智能钱包的执行密钥等），之后由 delegate 签名存入：
+// - 转账的 authority 是 delegate，额度由 SPL Token 程序扣减和校验，owner 不需要签名
+// - LP 始终铸造到 owner 的 LP 关联代币账户，delegate 拿不到任何 LP
+// - 额度用完或 owner 调用 revoke 后，delegate 就无法再动用 owner 的代币
+
+#[derive(Accounts)]
+pub struct DelegatedDeposit<'info> {
+    delegate: Signer<'info>,
+    /// CHECK: 只用于推导关联代币账户，不需要签名
+    owner: UncheckedAccount<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::authority = owner,
+        associated_token::mint = mint_a,
+        constraint = owner_ata_a.delegate.contains(&delegate.key()) @ AmmError::DelegateNotApproved
+    )]
+    owner_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = owner,
+        associated_token::mint = mint_b,
+        constraint = owner_ata_b.delegate.contains(&delegate.key()) @ AmmError::DelegateNotApproved
+    )]
+    owner_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = owner,
+        associated_token::mint = mint_lp
+    )]
+    owner_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = !pool.soulbound_lp @ AmmError::LpSoulbound,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        mut,
+        seeds = [b"lp_guard", pool.key().as_ref(), owner.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> DelegatedDeposit<'info> {
+    /// 与 deposit 相同的按比例存入，代币由 delegate 代为转出
+    pub fn deposit_with_delegate(&mut self, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.pool.require_skim_protocol_fee()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_deposit_window(now)?;
+        LiquidityGuard::record_deposit(&self.pool, self.liquidity_guard.as_deref_mut(), Clock::get()?.slot)?;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            // 首次存入：账户里已有的代币一起计入，见 Pool::first_deposit_lp
+            (max_token_a, max_token_b, self.pool.first_deposit_lp(self.pool_ata_a.amount, self.pool_ata_b.amount, max_token_a, max_token_b)?)
+        } else {
+            require_gt!(amount, 0, AmmError::ZeroAmount);
+
+            // 按 LP 总供应量等比例存入，向上取整
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
+
+            // Check slippage A
+            require_gte!(max_amount_limit(max_token_a), amount_a);
+
+            // Check slippage B
+            require_gte!(max_amount_limit(max_token_b), amount_b);
+            (amount_a, amount_b, amount)
+        };
+
+        self.pool.check_deposit_cap(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+
+        // 委托额度不足时 SPL Token 程序会拒绝转账
+        self.transfer_as_delegate(self.owner_ata_a.to_account_info(), self.pool_ata_a.to_account_info(), amount_a)?;
+        self.transfer_as_delegate(self.owner_ata_b.to_account_info(), self.pool_ata_b.to_account_info(), amount_b)?;
+
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.owner_ata_lp.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount_lp)?;
+
+        // owner 是 LP 的接收者，delegate 只是代为签名
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.owner.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// delegate 签名，从 owner 的代币账户转入池子
+    fn transfer_as_delegate(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.delegate.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount)
+    }
+}
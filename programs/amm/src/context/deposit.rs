@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{associated_token::AssociatedToken, token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer}};
 
-use crate::state::Pool;
+use crate::{error::AmmError, events::LiquidityDeposited, guard::has_distinct_keys, protocol_fee::settle_protocol_fee, math::{initial_lp, max_amount_limit, max_proportional_lp, mul_div_floor, BPS_DENOMINATOR}, state::{AmmConfig, LiquidityGuard, Pool, PoolStatus, UserPortfolio}};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -12,11 +12,12 @@ pub struct Deposit<'info> {
     #[account(
         mut,
         seeds = [b"lp", pool.key().as_ref()],
-        bump
+        bump = pool.lp_bump
     )]
     mint_lp: Account<'info, Mint>,
     #[account(
         mut,
+        constraint = has_distinct_keys(&[signer_ata_a.key(), signer_ata_b.key(), signer_ata_lp.key(), pool_ata_a.key(), pool_ata_b.key()]) @ AmmError::DuplicateMutableAccount,
         associated_token::authority = signer,
         associated_token::mint = mint_a
     )]
@@ -46,150 +47,311 @@ pub struct Deposit<'info> {
     )]
     pool_ata_b: Account<'info, TokenAccount>,
     #[account(
+        mut,
         seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
-        bump = pool.bump
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = !pool.soulbound_lp @ AmmError::LpSoulbound,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive
     )]
     pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 协议手续费为 LP 模式的池子需要传入，地址必须等于 pool.treasury_lp，其他池子传 None
+    #[account(mut, address = pool.treasury_lp)]
+    treasury_lp: Option<Box<Account<'info, TokenAccount>>>,
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        mut,
+        seeds = [b"lp_guard", pool.key().as_ref(), signer.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    // 可选：传入时更新调用者的仓位列表，见 portfolio.rs
+    #[account(
+        mut,
+        seeds = [b"portfolio", signer.key().as_ref()],
+        bump = portfolio.bump
+    )]
+    portfolio: Option<Account<'info, UserPortfolio>>,
     token_program: Program<'info, Token>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> Deposit<'info> {
-    pub fn deposit(&mut self, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
-        let (amount_a, amount_b, amount_lp) = if self.pool_ata_a.amount == 0 && self.pool_ata_b.amount == 0 {
-            let k = max_token_a.checked_mul(max_token_b).ok_or(ProgramError::ArithmeticOverflow)?;
-            (max_token_a, max_token_b, k)
-        } else {
-            let k = (self.pool_ata_a.amount as u128).checked_mul(self.pool_ata_b.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?;
+    /// remaining_accounts: 开启外部预言机保护的池子传入预言机账户，见 external_oracle.rs
+    ///
+    /// tag: 调用者自定义的标记，在 LiquidityDeposited 事件中原样回显
+    pub fn deposit(&mut self, amount: u64, max_token_a: u64, max_token_b: u64, tag: Option<[u8; 16]>, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.before_proportional_deposit(now)?;
 
-            let k2 = k.checked_add(amount as u128).ok_or(ProgramError::ArithmeticOverflow)?;
-            let ratio = k2.checked_mul(1000000).ok_or(ProgramError::ArithmeticOverflow)?
-                .checked_div(k).ok_or(ProgramError::ArithmeticOverflow)?;
-
-            let amount_a: u64 = ratio.checked_mul(self.pool_ata_a.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .checked_div(1000000).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .checked_sub(self.pool_ata_a.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            self.initial_deposit(max_token_a, max_token_b)?
+        } else {
+            require_gt!(amount, 0, AmmError::ZeroAmount);
 
-            let amount_b: u64 = ratio.checked_mul(self.pool_ata_b.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .checked_div(1000000).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .checked_sub(self.pool_ata_b.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?
-                                     .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+            // 按 LP 总供应量等比例存入，向上取整
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
 
             // Check slippage A
-            require_gte!(max_token_a, amount_a);
+            require_gte!(max_amount_limit(max_token_a), amount_a);
 
             // Check slippage B
-            require_gte!(max_token_b, amount_b);
+            require_gte!(max_amount_limit(max_token_b), amount_b);
             (amount_a, amount_b, amount)
         };
 
-        // ==========================================
-        // CPI 调用 1: 转移 Token A 到池子 (用户签名)
-        // ==========================================
-        // 这是一个普通的 CPI 调用，用户签名授权转移自己的代币
-        let accounts = Transfer {
-            from: self.signer_ata_a.to_account_info(),  // 源账户：用户的 Token A 账户
-            to: self.pool_ata_a.to_account_info(),      // 目标账户：池子的 Token A 账户
-            authority: self.signer.to_account_info(),    // 权限：用户签名者
+        self.complete_deposit(amount_a, amount_b, amount_lp, tag, remaining_accounts, now)
+    }
+
+    /// 按愿意存入的代币数量按比例存入：lp = min(amount_a * supply / reserve_a, amount_b * supply / reserve_b)，
+    /// 实际存入 deposit_amounts(lp)，不会超过 amount_a_desired / amount_b_desired，多余的一侧留在用户账户中
+    ///
+    /// 空池子的首次存入与 deposit 相同，两侧全部存入
+    pub fn deposit_by_amounts(&mut self, amount_a_desired: u64, amount_b_desired: u64, min_lp: u64, tag: Option<[u8; 16]>, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.before_proportional_deposit(now)?;
+
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            self.initial_deposit(amount_a_desired, amount_b_desired)?
+        } else {
+            let amount_lp = max_proportional_lp(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount_a_desired, amount_b_desired)?;
+            require_gt!(amount_lp, 0, AmmError::ZeroAmount);
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount_lp)?;
+            (amount_a, amount_b, amount_lp)
         };
 
-        let ctx = CpiContext::new(
-            self.token_program.to_account_info(),   // 被调用程序：SPL Token 程序
-            accounts
-        );
-        
-        // 调用 SPL Token 程序的 transfer 指令
-        transfer(ctx, amount_a)?;
+        // Check slippage
+        require_gte!(amount_lp, min_lp);
+
+        self.complete_deposit(amount_a, amount_b, amount_lp, tag, remaining_accounts, now)
+    }
+
+    /// 按比例存入之前：检查存入窗口和冻结账户，记录 JIT 保护和仓位列表，推进预言机，结算 LP 模式的协议手续费
+    fn before_proportional_deposit(&mut self, now: i64) -> Result<()> {
+        self.pool.check_deposit_window(now)?;
+        self.require_not_frozen()?;
+        LiquidityGuard::record_deposit(&self.pool, self.liquidity_guard.as_deref_mut(), Clock::get()?.slot)?;
+        UserPortfolio::record_deposit(self.portfolio.as_deref_mut(), self.pool.key())?;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+        settle_protocol_fee(&self.pool, &mut self.mint_lp, self.treasury_lp.as_deref(), &self.token_program, self.pool_ata_a.amount, self.pool_ata_b.amount)
+    }
+
+    /// 空池子的首次存入，返回 (amount_a, amount_b, amount_lp)
+    fn initial_deposit(&self, amount_a: u64, amount_b: u64) -> Result<(u64, u64, u64)> {
+        let amount_lp = self.pool.first_deposit_lp(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+        Ok((amount_a, amount_b, amount_lp))
+    }
+
+    /// 存入数量确定之后：检查存款上限和外部预言机，转入代币、铸造 LP、记录新的 k，发出 LiquidityDeposited
+    fn complete_deposit(&mut self, amount_a: u64, amount_b: u64, amount_lp: u64, tag: Option<[u8; 16]>, remaining_accounts: &[AccountInfo], now: i64) -> Result<()> {
+        self.pool.check_deposit_cap(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+        self.check_external_oracle(remaining_accounts, amount_a, amount_b, now)?;
 
         // ==========================================
-        // CPI 调用 2: 转移 Token B 到池子 (用户签名)
+        // CPI 调用 1、2: 转移 Token A / Token B 到池子 (用户签名)
         // ==========================================
-        // 同样是普通 CPI 调用，转移用户的 Token B
-        let accounts = Transfer {
-            from: self.signer_ata_b.to_account_info(),
-            to: self.pool_ata_b.to_account_info(),
-            authority: self.signer.to_account_info(),
-        };
-
-        let ctx = CpiContext::new(
-            self.token_program.to_account_info(), 
-            accounts
-        );
-        
-        transfer(ctx, amount_b)?;
+        // 普通 CPI 调用，用户签名授权转移自己的代币；两次转账共用同一份 token_program / signer 的 AccountInfo
+        self.transfer_pair_to_pool(amount_a, amount_b)?;
 
         // ==========================================
         // CPI 调用 3: 铸造 LP 代币 (PDA 签名)
         // ==========================================
-        // 这是一个 PDA CPI 调用，池子作为 LP token 的 mint authority
+        // 池子作为 LP token 的 mint authority，用 pool 的种子签名，signer_seeds 的三重引用见 seeds.rs
+        //
+        // pool.fee 是 pool PDA 的种子之一：同一对代币可以创建多个不同费率的池子，
+        // 不同 fee 生成不同的 PDA 地址。它不是 deposit 的手续费，deposit/withdraw 不收手续费，只有 swap 收取
+        self.mint_lp_to_signer(amount_lp)?;
+
+        self.update_k_last(amount_a, amount_b)?;
+
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag,
+        });
+        Ok(())
+    }
+
+    pub fn deposit_imbalanced(&mut self, amount_a: u64, amount_b: u64, min_lp: u64, tag: Option<[u8; 16]>, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        require!(amount_a > 0 || amount_b > 0, AmmError::ZeroAmount);
+
+        // 不按比例的存入相当于先 swap 再存入，需要处在交易窗口内，并且 swap 的方向没有被禁用
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_trading_window(now)?;
+        self.require_not_frozen()?;
+        self.pool.check_imbalanced_direction(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+        // 不按比例的存入包含一笔隐含的 swap，登记了 swap hook 的池子不能绕过 hook，只接受按比例存入
+        require!(!self.pool.has_swap_hook(), AmmError::SwapHookRequired);
+        // 同理，会员制池子的隐含 swap 也不能绕过 NFT 持有检查，见 trade_gate.rs
+        require!(!self.pool.is_trade_gated(), AmmError::TradeGateRequired);
+        // 隐含的 swap 不经过 trader_state：开启钱包限额的池子里可以用它绕过限额买入，
+        // 开启往返附加费的池子里可以用它做夹子的第二条腿而不付附加费
+        require!(!self.pool.requires_trader_state(), AmmError::TraderStateRequired);
+        LiquidityGuard::record_deposit(&self.pool, self.liquidity_guard.as_deref_mut(), Clock::get()?.slot)?;
+        UserPortfolio::record_deposit(self.portfolio.as_deref_mut(), self.pool.key())?;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+        settle_protocol_fee(&self.pool, &mut self.mint_lp, self.treasury_lp.as_deref(), &self.token_program, self.pool_ata_a.amount, self.pool_ata_b.amount)?;
+
+        // 偏离池子比例的部分按 swap 费率扣费后，再按不变量的增长铸造 LP，曲线不支持时报错
+        let amount_lp = self.pool.curve(now).imbalanced_deposit_lp(
+            self.pool_ata_a.amount,
+            self.pool_ata_b.amount,
+            self.mint_lp.supply,
+            amount_a,
+            amount_b,
+            self.pool.fee,
+        )?;
+
+        require_gt!(amount_lp, 0);
+
+        // Check slippage
+        require_gte!(amount_lp, min_lp);
+
+        self.complete_deposit(amount_a, amount_b, amount_lp, tag, remaining_accounts, now)
+    }
+
+    /// 按存入之后的储备量检查外部预言机价格带，pool_ata 的余额是存入前读取的
+    fn check_external_oracle(&self, remaining_accounts: &[AccountInfo], amount_a: u64, amount_b: u64, now: i64) -> Result<()> {
+        let (oracle, _) = self.pool.split_external_oracle(remaining_accounts)?;
+        let reserve_a = self.pool_ata_a.amount.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_b = self.pool_ata_b.amount.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.pool.check_external_oracle(oracle, reserve_a, reserve_b, now)
+    }
+
+    /// 转账之前检查两侧的代币账户都没有被冻结，两个 mint 都没有 freeze authority 时不检查
+    fn require_not_frozen(&self) -> Result<()> {
+        if !self.pool.has_freezable_mint {
+            return Ok(());
+        }
+        let frozen = self.signer_ata_a.is_frozen() || self.signer_ata_b.is_frozen() || self.pool_ata_a.is_frozen() || self.pool_ata_b.is_frozen();
+        require!(!frozen, AmmError::AccountFrozen);
+        Ok(())
+    }
+
+    /// 存入之后记录新的 k，pool_ata 的余额是存入前读取的
+    fn update_k_last(&mut self, amount_a: u64, amount_b: u64) -> Result<()> {
+        let reserve_a = self.pool_ata_a.amount.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_b = self.pool_ata_b.amount.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.pool.update_k_last(reserve_a, reserve_b);
+        Ok(())
+    }
+
+    /// 用户签名，把两种代币转入池子，token_program 和 signer 的 AccountInfo 只构造一次，数量为 0 的一侧跳过
+    fn transfer_pair_to_pool(&self, amount_a: u64, amount_b: u64) -> Result<()> {
+        let token_program = self.token_program.to_account_info();
+        let authority = self.signer.to_account_info();
+
+        for (from, to, amount) in [
+            (&self.signer_ata_a, &self.pool_ata_a, amount_a),
+            (&self.signer_ata_b, &self.pool_ata_b, amount_b),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.clone(),
+            };
+
+            let ctx = CpiContext::new(token_program.clone(), accounts);
+
+            transfer(ctx, amount)?;
+        }
+        Ok(())
+    }
+
+    /// 池子 PDA 签名，给用户铸造 LP 代币
+    fn mint_lp_to_signer(&self, amount: u64) -> Result<()> {
         let accounts = MintTo {
-            mint: self.mint_lp.to_account_info(),       // LP token mint 账户
-            to: self.signer_ata_lp.to_account_info(),   // 目标：用户的 LP token 账户
-            authority: self.pool.to_account_info(),     // 权限：池子 PDA（mint authority）
+            mint: self.mint_lp.to_account_info(),
+            to: self.signer_ata_lp.to_account_info(),
+            authority: self.pool.to_account_info(),
         };
 
-        // ==========================================
-        // Pool Fee 详解
-        // ==========================================
-        // 
-        // pool.fee 有两个重要作用：
-        // 
-        // 1. **PDA 种子区分器**：
-        //    - fee 是生成 pool PDA 的种子之一
-        //    - 同一对代币 (mint_a, mint_b) 可以创建多个不同手续费率的池子
-        //    - 例如：USDC/USDT 可以有 0.01%, 0.3%, 1% 等不同费率的池子
-        //    - 不同 fee 生成不同的 PDA 地址，实现池子隔离
-        //
-        // 2. **交易手续费率**：
-        //    - 在 swap 操作中，fee 用于计算实际手续费
-        //    - 公式：amount_in_with_fees = amount_in * (10000 + fee) / 10000
-        //    - fee 以基点为单位：100 = 1%, 30 = 0.3%, 1 = 0.01%
-        //    - deposit/withdraw 操作不收手续费，只有 swap 收取
-        // 总结：pool.fee 不是 deposit 时的手续费，而是用于区分不同费率池子的标识符，实际的手续费只在 swap 交易时收取！
-        
-        let binding = self.pool.fee.to_le_bytes();
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
 
-        // ==========================================
-        // 三重引用的 signer_seeds 类型解析
-        // ==========================================
-        // 
-        // 类型签名：[&[&[u8]]; 1]
-        // 
-        // 层次结构解析：
-        // 1. 最内层 `&[u8]`     -> 单个种子的字节切片引用
-        // 2. 中间层 `&[&[u8]]`  -> 种子组的引用（生成一个 PDA 所需的所有种子）
-        // 3. 最外层 `[&[&[u8]]; 1]` -> 种子组数组（支持多个 PDA 同时签名）
-        //
-        // 为什么需要三重引用？
-        // - Solana 指令可能需要多个 PDA 同时签名
-        // - 每个 PDA 需要一组种子来生成
-        // - 每组种子包含多个字节切片
-        // - 因此形成了三层嵌套的引用结构
-        //
-        // 实际使用中：
-        // - 我们只需要一个 PDA (pool) 签名，所以数组长度为 1
-        // - 这个 PDA 需要 5 个种子：["pool", mint_a, mint_b, fee, bump]
-        // - 每个种子都是 &[u8] 类型
-        
-        let signer_seeds: [&[&[u8]]; 1] = [&[
-            &b"pool"[..],                                    // 种子 1: "pool" 字面量
-            self.mint_a.to_account_info().key.as_ref(),     // 种子 2: mint_a 公钥
-            self.mint_b.to_account_info().key.as_ref(),     // 种子 3: mint_b 公钥  
-            binding.as_ref(),                               // 种子 4: fee 参数
-            &[self.pool.bump]                               // 种子 5: canonical bump
-        ]];
-
-        // 使用 PDA 签名创建 CPI Context
-        // new_with_signer 允许程序代表 PDA 进行签名
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),   // SPL Token 程序
-            accounts, 
-            &signer_seeds                           // PDA 签名种子：&[&[&[u8]]]
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
         );
 
-        // 调用 SPL Token 程序的 mint_to 指令，铸造 LP 代币给用户
-        mint_to(ctx, amount_lp)
+        mint_to(ctx, amount)
+    }
+}
+
+/// 空池子存入比例与声明的初始价格之间允许的偏差（基点）
+pub const INITIAL_PRICE_TOLERANCE_BPS: u128 = 100;
+
+impl Pool {
+    /// LP 供应量为 0 时的首次存入，返回铸造的 LP
+    ///
+    /// 池子代币账户的地址可以提前算出，任何人都能在首次存入之前往里面转入代币。
+    /// 判断空池子看 LP 供应量而不是储备量，已经在账户里的代币和本次存入一起计入：
+    /// 按存入之后的储备量检查初始价格、计算 LP，抢先转入的代币归首个存入者
+    pub fn first_deposit_lp(&self, vault_a: u64, vault_b: u64, amount_a: u64, amount_b: u64) -> Result<u64> {
+        // 首次存入两侧都必须有代币，否则价格无从确定
+        require!(amount_a > 0 && amount_b > 0, AmmError::ZeroAmount);
+        let reserve_a = vault_a.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_b = vault_b.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.check_initial_price(reserve_a, reserve_b)?;
+        initial_lp(reserve_a, reserve_b)
+    }
+
+    /// 空池子存入时，amount_b / amount_a 与声明的初始价格偏差不能超过 INITIAL_PRICE_TOLERANCE_BPS
+    ///
+    /// 任何人都可以抢先创建某个交易对、某个费率的池子，如果首次存入的价格可以随意指定，
+    /// 抢注者就能按偏离市场的价格注入流动性，等着后来的人按错误的价格交易。
+    /// 创建时声明初始价格后，池子在没有流动性时只能按这个价格开始，集成方看到声明的价格即可判断池子是否可信。
+    pub fn check_initial_price(&self, amount_a: u64, amount_b: u64) -> Result<()> {
+        if self.initial_price_denominator == 0 {
+            return Ok(());
+        }
+
+        // |amount_b * den - amount_a * num| / (amount_a * num)，交叉相乘避免除法
+        let declared = amount_a as u128 * self.initial_price_numerator as u128;
+        let actual = amount_b as u128 * self.initial_price_denominator as u128;
+        let deviation_bps = mul_div_floor(actual.abs_diff(declared), BPS_DENOMINATOR, declared).unwrap_or(u128::MAX);
+
+        require!(deviation_bps <= INITIAL_PRICE_TOLERANCE_BPS, AmmError::InitialPriceMismatch);
+        Ok(())
+    }
+
+    /// end_ts 之后池子只能取出；start_ts 之前允许存入，方便限时池子开盘前准备流动性
+    pub fn check_deposit_window(&self, now: i64) -> Result<()> {
+        require!(self.end_ts == 0 || now < self.end_ts, AmmError::TradingEnded);
+        Ok(())
+    }
+
+    /// 存入后的储备量不能超过 max_reserve_a / max_reserve_b，0 表示不限制
+    pub fn check_deposit_cap(&self, reserve_a: u64, reserve_b: u64, amount_a: u64, amount_b: u64) -> Result<()> {
+        let reserve_a_after = reserve_a.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_b_after = reserve_b.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if self.max_reserve_a > 0 {
+            require_gte!(self.max_reserve_a, reserve_a_after, AmmError::DepositCapExceeded);
+        }
+        if self.max_reserve_b > 0 {
+            require_gte!(self.max_reserve_b, reserve_b_after, AmmError::DepositCapExceeded);
+        }
+        Ok(())
     }
 }
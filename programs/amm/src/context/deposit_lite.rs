@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{mint_to, transfer, Mint, MintTo, Token, Transfer};
+
+use crate::{error::AmmError, events::LiquidityDeposited, guard::has_distinct_keys, math::max_amount_limit, state::{AmmConfig, LiquidityGuard, Pool, PoolStatus}, token_account::{read_token_amount, require_not_frozen}};
+
+// ========================================
+// 精简存入（feature = "lite-deposit"）
+// ========================================
+//
+// 给做市机器人用的按比例存入，按 swap 的做法省掉 deposit 中与机器人无关的账户校验：
+// - signer_ata_a / signer_ata_b / signer_ata_lp 不要求是关联代币账户，也不反序列化，
+//   由 SPL Token 程序在 transfer / mint_to 中校验 owner 和 mint；LP 代币账户必须事先创建好
+// - pool_ata_a / pool_ata_b 只按 Pool 中记录的地址校验，余额通过 read_token_amount 直接读取
+// - 不需要 associated_token_program / system_program
+// 只支持 Skim 模式的协议手续费和没有开启 jit_protection 的池子，不支持 tag，其余检查与 deposit 相同。
+
+#[derive(Accounts)]
+pub struct DepositLite<'info> {
+    signer: Signer<'info>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验
+    mint_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Account<'info, Mint>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验，不能与其他可写代币账户相同
+    #[account(
+        mut,
+        constraint = has_distinct_keys(&[signer_ata_a.key(), signer_ata_b.key(), signer_ata_lp.key(), pool_ata_a.key(), pool_ata_b.key()]) @ AmmError::DuplicateMutableAccount
+    )]
+    signer_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验
+    #[account(mut)]
+    signer_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 mint_to 中校验 mint 与 mint_lp 一致
+    #[account(mut)]
+    signer_ata_lp: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = !pool.soulbound_lp @ AmmError::LpSoulbound,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive
+    )]
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> DepositLite<'info> {
+    /// 参数含义与 deposit 相同，remaining_accounts: 开启外部预言机保护的池子传入预言机账户
+    pub fn deposit_lite(&mut self, amount: u64, max_token_a: u64, max_token_b: u64, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.pool.require_skim_protocol_fee()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_deposit_window(now)?;
+        if self.pool.has_freezable_mint {
+            require_not_frozen(&[&self.signer_ata_a, &self.signer_ata_b, &self.pool_ata_a, &self.pool_ata_b])?;
+        }
+        // 没有 liquidity_guard 账户，开启 jit_protection 的池子在这里返回 LiquidityGuardRequired
+        LiquidityGuard::record_deposit(&self.pool, None, Clock::get()?.slot)?;
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            // 首次存入：账户里已有的代币一起计入，见 Pool::first_deposit_lp
+            (max_token_a, max_token_b, self.pool.first_deposit_lp(reserve_a, reserve_b, max_token_a, max_token_b)?)
+        } else {
+            require_gt!(amount, 0, AmmError::ZeroAmount);
+
+            // 按 LP 总供应量等比例存入，向上取整
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(reserve_a, reserve_b, self.mint_lp.supply, amount)?;
+
+            // Check slippage A
+            require_gte!(max_amount_limit(max_token_a), amount_a);
+
+            // Check slippage B
+            require_gte!(max_amount_limit(max_token_b), amount_b);
+            (amount_a, amount_b, amount)
+        };
+
+        self.pool.check_deposit_cap(reserve_a, reserve_b, amount_a, amount_b)?;
+
+        let reserve_a_after = reserve_a.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_b_after = reserve_b.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        let (oracle, _) = self.pool.split_external_oracle(remaining_accounts)?;
+        self.pool.check_external_oracle(oracle, reserve_a_after, reserve_b_after, now)?;
+
+        self.transfer_pair_to_pool(amount_a, amount_b)?;
+        self.mint_lp_to_signer(amount_lp)?;
+
+        self.pool.update_k_last(reserve_a_after, reserve_b_after);
+
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// 用户签名，把两种代币转入池子，token_program 和 signer 的 AccountInfo 只构造一次
+    fn transfer_pair_to_pool(&self, amount_a: u64, amount_b: u64) -> Result<()> {
+        let token_program = self.token_program.to_account_info();
+        let authority = self.signer.to_account_info();
+
+        for (from, to, amount) in [
+            (&self.signer_ata_a, &self.pool_ata_a, amount_a),
+            (&self.signer_ata_b, &self.pool_ata_b, amount_b),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.clone(),
+            };
+
+            let ctx = CpiContext::new(token_program.clone(), accounts);
+
+            transfer(ctx, amount)?;
+        }
+        Ok(())
+    }
+
+    /// 池子 PDA 签名，给用户铸造 LP 代币
+    fn mint_lp_to_signer(&self, amount: u64) -> Result<()> {
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.signer_ata_lp.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount)
+    }
+}
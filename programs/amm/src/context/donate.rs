@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{transfer, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{error::AmmError, events::Donated, state::Pool};
+
+#[derive(Accounts)]
+pub struct Donate<'info> {
+    signer: Signer<'info>,
+    mint_a: Account<'info, Mint>,
+    mint_b: Account<'info, Mint>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::authority = signer,
+        associated_token::mint = mint_a
+    )]
+    signer_ata_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::authority = signer,
+        associated_token::mint = mint_b
+    )]
+    signer_ata_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Account<'info, Pool>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Donate<'info> {
+    pub fn donate(&mut self, amount_a: u64, amount_b: u64) -> Result<()> {
+        // 池子的储备量就是两个 pool ATA 的余额，不单独记账，
+        // 所以捐赠 = 把代币转进 pool ATA，不铸造 LP，价值按比例归现有全体 LP。
+        //
+        // 与直接往 pool ATA 转账相比，这条指令会：
+        // 1. 要求池子里已经有 LP，避免捐赠被第一个存入者全部拿走
+        // 2. 在余额变化前推进价格累加器，单边捐赠导致的价格变化也能正确进入 TWAP
+        require_gt!(self.mint_lp.supply, 0);
+        require_gt!(amount_a as u128 + amount_b as u128, 0);
+
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, Clock::get()?.unix_timestamp)?;
+
+        if amount_a > 0 {
+            let accounts = Transfer {
+                from: self.signer_ata_a.to_account_info(),
+                to: self.pool_ata_a.to_account_info(),
+                authority: self.signer.to_account_info(),
+            };
+
+            let ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, amount_a)?;
+        }
+
+        if amount_b > 0 {
+            let accounts = Transfer {
+                from: self.signer_ata_b.to_account_info(),
+                to: self.pool_ata_b.to_account_info(),
+                authority: self.signer.to_account_info(),
+            };
+
+            let ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, amount_b)?;
+        }
+
+        emit!(Donated {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            donor: self.signer.key(),
+            amount_a,
+            amount_b,
+        });
+        Ok(())
+    }
+}
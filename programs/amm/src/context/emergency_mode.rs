@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+
+use crate::{context::admin::is_config_admin, error::AmmError, events::EmergencyModeChanged, state::AmmConfig};
+
+// ========================================
+// 全局紧急模式（程序级 kill switch）
+// ========================================
+//
+// 发现漏洞时的最后手段：guardian（或 admin）调用 enable_emergency_mode 后，所有池子立即等同于 WithdrawOnly：
+// - swap、存入、闪电贷、限价单 / DCA / OTC 成交、回购、复投、创建池子等指令都检查 AmmConfig，
+//   紧急模式下返回 EmergencyModeActive
+// - withdraw / withdraw_percent / emergency_withdraw、领取手续费和挖矿奖励照常可用，LP 随时可以撤出
+// - 池子自己的 status 不变，退出紧急模式后恢复原来的状态
+//
+// guardian 与 admin 分开保管，只能开启、不能退出。退出需要 admin 先 request_emergency_exit，
+// 等待 EMERGENCY_EXIT_DELAY 秒后再 disable_emergency_mode；等待期间 guardian 再次开启会取消申请，
+// admin 密钥泄露时攻击者无法立即恢复交易。
+
+/// 申请退出紧急模式之后需要等待的时间（秒）
+pub const EMERGENCY_EXIT_DELAY: i64 = 2 * 24 * 60 * 60;
+
+#[derive(Accounts)]
+pub struct EnableEmergencyMode<'info> {
+    /// CHECK: 只比较 key 并要求签名，guardian 或 admin 都可以开启
+    #[account(
+        constraint = authority.is_signer @ AmmError::AuthorityNotSigner,
+        constraint = authority.key() == config.guardian || authority.key() == config.admin @ AmmError::InvalidAuthority
+    )]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+}
+
+impl<'info> EnableEmergencyMode<'info> {
+    /// 开启紧急模式，已经开启时取消进行中的退出申请
+    pub fn enable_emergency_mode(&mut self) -> Result<()> {
+        self.config.emergency_mode = true;
+        self.config.emergency_exit_ts = 0;
+
+        emit!(EmergencyModeChanged {
+            authority: self.authority.key(),
+            emergency_mode: true,
+            exit_ts: 0,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct EmergencyExit<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.emergency_mode @ AmmError::EmergencyModeNotActive
+    )]
+    config: Account<'info, AmmConfig>,
+}
+
+impl<'info> EmergencyExit<'info> {
+    /// 申请退出紧急模式，EMERGENCY_EXIT_DELAY 秒之后才能执行；重复申请会重新计时
+    pub fn request_emergency_exit(&mut self) -> Result<()> {
+        let exit_ts = Clock::get()?.unix_timestamp.checked_add(EMERGENCY_EXIT_DELAY).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.config.emergency_exit_ts = exit_ts;
+
+        emit!(EmergencyModeChanged {
+            authority: self.authority.key(),
+            emergency_mode: true,
+            exit_ts,
+        });
+        Ok(())
+    }
+
+    /// 等待期满后退出紧急模式，所有池子恢复各自原来的状态
+    pub fn disable_emergency_mode(&mut self) -> Result<()> {
+        let exit_ts = self.config.emergency_exit_ts;
+        require!(exit_ts > 0 && Clock::get()?.unix_timestamp >= exit_ts, AmmError::EmergencyExitNotReady);
+
+        self.config.emergency_mode = false;
+        self.config.emergency_exit_ts = 0;
+
+        emit!(EmergencyModeChanged {
+            authority: self.authority.key(),
+            emergency_mode: false,
+            exit_ts: 0,
+        });
+        Ok(())
+    }
+}
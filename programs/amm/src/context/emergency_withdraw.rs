@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::{
+    error::AmmError,
+    events::EmergencyWithdrawn,
+    math::mul_div_floor,
+    state::Pool,
+    token_account::{is_token_account_frozen, read_token_amount},
+};
+
+// ========================================
+// 紧急取出（绕过曲线和协议手续费结算）
+// ========================================
+//
+// 正常的 withdraw 依赖曲线的 withdraw_amounts、协议手续费结算（LP 模式会先增发 LP）和预言机更新，
+// 如果这些记账出现不一致（程序 bug）或者某一侧代币账户被发行方冻结，LP 可能无法取回资金。
+//
+// 池子管理员打开 emergency_withdraw_enabled 之后，emergency_withdraw 只做最简单的事情：
+// 按 amount / LP 总供应量，从池子代币账户的实际余额中向下取整取出两种代币，然后销毁 LP。
+// 不检查池子状态（Paused 时同样可用），不读取 Pool 里的任何记账字段。
+//
+// 被冻结的一侧无法转出，直接跳过，调用者按比例放弃这一侧的份额，
+// 所以 min_token_a / min_token_b 的滑点保护仍然保留，调用者可以拒绝这种结果。
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::authority = signer,
+        token::mint = mint_lp
+    )]
+    signer_ata_lp: Account<'info, TokenAccount>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验 mint，冻结的一侧不会被转出
+    #[account(mut)]
+    signer_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验 mint，冻结的一侧不会被转出
+    #[account(mut)]
+    signer_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.emergency_withdraw_enabled @ AmmError::EmergencyWithdrawDisabled
+    )]
+    pool: Account<'info, Pool>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> EmergencyWithdraw<'info> {
+    pub fn emergency_withdraw(&mut self, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        let supply = self.mint_lp.supply;
+        let amount_a = self.pro_rata(&self.pool_ata_a, amount, supply)?;
+        let amount_b = self.pro_rata(&self.pool_ata_b, amount, supply)?;
+
+        // Check slippage A
+        require_gte!(amount_a, min_token_a);
+
+        // Check slippage B
+        require_gte!(amount_b, min_token_b);
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        for (from, to, amount) in [(&self.pool_ata_a, &self.signer_ata_a, amount_a), (&self.pool_ata_b, &self.signer_ata_b, amount_b)] {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.pool.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, amount)?;
+        }
+
+        // Burn LP Token
+        let accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.signer_ata_lp.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        burn(ctx, amount)?;
+
+        emit!(EmergencyWithdrawn {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp: amount,
+            amount_a,
+            amount_b,
+        });
+        Ok(())
+    }
+
+    /// 池子代币账户余额中属于 amount 个 LP 的部分，向下取整；账户被冻结时为 0
+    fn pro_rata(&self, vault: &AccountInfo, amount: u64, supply: u64) -> Result<u64> {
+        if is_token_account_frozen(vault)? {
+            return Ok(0);
+        }
+        let balance = read_token_amount(vault)?;
+        let share = mul_div_floor(balance as u128, amount as u128, supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(share as u64)
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::AmmError, state::{Pool, POOL_V3_SPACES, POOL_VERSION}};
+
+// ========================================
+// 扩容池子账户（为尾部新增字段预留空间）
+// ========================================
+//
+// 从 v3 开始 Pool 只在末尾追加字段（见 POOL_VERSION），已经存在的池子账户比新布局短，
+// 按新布局反序列化会失败。extend_pool_account 把账户扩容到 new_space，payer 补足租金差额，
+// 新增的字节为 0，对应字段的默认值（0 / false / 枚举第一个变体），与 initialize 的默认值一致。
+//
+// 只有 version 字节等于 POOL_VERSION 的池子按这种方式扩容，当前长度和 new_space 都必须是
+// POOL_V3_SPACES 中登记过的长度：在中间插入过字段的旧布局补 0 之后字段会错位，
+// 这些池子（v1、v2）需要用 migrate_pool_v2 整体重写。
+//
+// 短账户无法反序列化成 Account<Pool>，也就用不了 Anchor 的 realloc 约束，
+// 这里与 migrate_pool_v2 一样按 owner、discriminator 和 version 校验后手动 resize。
+// 无需权限，任何人付租金都可以扩容。
+// 单条指令最多扩容 MAX_PERMITTED_DATA_INCREASE 字节，超出时由运行时拒绝，可以分几次扩到中间的长度。
+
+#[derive(Accounts)]
+pub struct ExtendPoolAccount<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 可能比当前布局短，无法用 Account<Pool> 反序列化，通过 owner、discriminator 和 version 校验
+    #[account(mut, owner = crate::ID)]
+    pool: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> ExtendPoolAccount<'info> {
+    pub fn extend_pool_account(&mut self, new_space: u32) -> Result<()> {
+        let pool_info = self.pool.to_account_info();
+        let new_space = new_space as usize;
+
+        {
+            let data = pool_info.try_borrow_data()?;
+            require!(data.starts_with(Pool::DISCRIMINATOR), AmmError::InvalidPoolVersion);
+            require!(data.get(Pool::DISCRIMINATOR.len()) == Some(&POOL_VERSION), AmmError::InvalidPoolVersion);
+            require!(POOL_V3_SPACES.contains(&data.len()), AmmError::InvalidPoolVersion);
+            // 只能变长，扩到之后某个登记过的布局长度
+            require!(new_space > data.len() && POOL_V3_SPACES.contains(&new_space), AmmError::InvalidPoolSpace);
+        }
+
+        let rent = Rent::get()?.minimum_balance(new_space).saturating_sub(pool_info.lamports());
+        if rent > 0 {
+            let accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: pool_info.clone(),
+            };
+
+            let ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, rent)?;
+        }
+
+        // resize 会把新增的字节清零
+        pool_info.resize(new_space)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,582 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{transfer, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{
+    context::admin::is_pool_authority,
+    error::AmmError,
+    math::{mul_div_floor, BPS_DENOMINATOR},
+    state::{Farm, FarmAccrualMode, LockDuration, Pool, StakePosition, ACCOUNT_VERSION, LOCK_MONTH_SECONDS, MAX_EARLY_EXIT_PENALTY_BPS},
+};
+
+// ========================================
+// LP 质押挖矿（锁仓加成）
+// ========================================
+//
+// 池子管理员为池子创建 Farm（PDA：["farm", pool]），任何人都可以向奖励 ATA 注入奖励代币，
+// 奖励按 reward_rate 每秒释放，按质押权重分配（MasterChef 式的 acc_reward_per_weight 累加器）。
+// 没有人质押的时间段不释放，奖励留给之后的质押者。
+//
+// 质押时选择锁仓时长，权重 = LP 数量 × 加成：不锁 1x，1 个月 1.25x，3 个月 1.5x，6 个月 2x。
+// 每笔质押是一个 StakePosition（PDA：["stake", farm, owner, id]），解锁前也可以退出，
+// 但要罚没一部分 LP：比例从 MAX_EARLY_EXIT_PENALTY_BPS 开始随剩余锁仓时间线性减少，
+// 罚没的 LP 留在 farm 的 LP ATA 中，通过 acc_penalty_per_weight 按权重分给剩下的质押者，
+// 与奖励一起领取。没有剩下的质押者时不罚没。
+//
+// 创建时可以选择 LpSeconds 分配方式：每段时间释放的奖励不再按当前权重分，
+// 而是按各仓位从质押开始累计的 LP 秒数（权重 × 已质押秒数）分，
+// 同样的权重持有 10 天的仓位分得的奖励是持有 1 天的 10 倍，短期流动性分得的比例更少。
+// 在 t 时刻仓位 i 的 LP 秒数是 w_i × (t - s_i)，总数是 W × t - Σ w_i × s_i，
+// 所以只需要两个累加器（Σ E / D 和 Σ E × t / D，D 是释放时的总 LP 秒数），
+// 在每次 stake / unstake / claim / fund 时按当时的 LP 秒数结算上一段时间，不需要遍历仓位。
+// 罚没 LP 仍然按权重分配。
+//
+// 奖励代币是池子的 TokenA 或 TokenB 时，协议管理员可以打开 pool.protocol_fees_to_farm，
+// 之后任何人都可以调用 fund_farm_from_fees，把手续费账户中这一侧的协议分成（Skim 模式，见 buyback.rs）
+// 全部注入 Farm，按 reward_rate 释放给质押者，质押 LP 就有了来自真实交易量的收益；
+// 另一侧的协议分成不受影响，仍然通过 collect_protocol_fees_batch 或 buyback_and_burn 取出。
+
+/// 累加器的定点数精度（Q64.64）
+const Q64: u128 = 1 << 64;
+
+#[derive(Accounts)]
+pub struct CreateFarm<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    // 奖励和质押本金分开托管，不能是同一种代币
+    #[account(constraint = reward_mint.key() != mint_lp.key() @ AmmError::InvalidFarm)]
+    reward_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = payer,
+        space = Farm::DISCRIMINATOR.len() + Farm::INIT_SPACE,
+        seeds = [b"farm", pool.key().as_ref()],
+        bump
+    )]
+    farm: Box<Account<'info, Farm>>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm
+    )]
+    farm_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    farm_ata_reward: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CreateFarm<'info> {
+    pub fn create_farm(&mut self, reward_rate: u64, accrual_mode: FarmAccrualMode, bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.farm.set_inner(Farm {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            mint_lp: self.mint_lp.key(),
+            reward_mint: self.reward_mint.key(),
+            reward_rate,
+            reward_remaining: 0,
+            total_staked: 0,
+            total_weight: 0,
+            acc_reward_per_weight: 0,
+            acc_penalty_per_weight: 0,
+            last_update_ts: now,
+            bump,
+            accrual_mode,
+            origin_ts: now,
+            total_weight_start: 0,
+            acc_reward_per_lp_second: 0,
+            acc_reward_time_per_lp_second: 0,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FarmAdmin<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool)]
+    farm: Account<'info, Farm>,
+}
+
+impl<'info> FarmAdmin<'info> {
+    /// 修改之前先按旧速率释放到现在，新速率只影响之后的时间段
+    pub fn set_farm_reward_rate(&mut self, reward_rate: u64) -> Result<()> {
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.farm.reward_rate = reward_rate;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FundFarm<'info> {
+    funder: Signer<'info>,
+    #[account(mut, has_one = reward_mint)]
+    farm: Account<'info, Farm>,
+    reward_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    farm_ata_reward: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = funder
+    )]
+    funder_ata_reward: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> FundFarm<'info> {
+    pub fn fund_farm(&mut self, amount: u64) -> Result<()> {
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        // 先结算之前的时间段，注入的奖励不会补发给过去没有奖励可发的时间
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+
+        let accounts = Transfer {
+            from: self.funder_ata_reward.to_account_info(),
+            to: self.farm_ata_reward.to_account_info(),
+            authority: self.funder.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount)?;
+
+        self.farm.reward_remaining = self.farm.reward_remaining.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FundFarmFromFees<'info> {
+    // 任何人都可以调用，不需要与池子有关的权限
+    keeper: Signer<'info>,
+    #[account(
+        mut,
+        has_one = creator_fee_vault_a,
+        has_one = creator_fee_vault_b,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.protocol_fees_to_farm @ AmmError::ProtocolFeesToFarmDisabled
+    )]
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        mut,
+        seeds = [b"farm", pool.key().as_ref()],
+        bump = farm.bump,
+        has_one = pool,
+        has_one = reward_mint
+    )]
+    farm: Box<Account<'info, Farm>>,
+    reward_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    farm_ata_reward: Box<Account<'info, TokenAccount>>,
+    /// CHECK: 通过 pool 的 has_one 校验
+    #[account(mut)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 校验
+    #[account(mut)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> FundFarmFromFees<'info> {
+    pub fn fund_farm_from_fees(&mut self) -> Result<()> {
+        let reward_mint = self.reward_mint.key();
+        let (amount, vault) = if reward_mint == self.pool.mint_a {
+            (std::mem::take(&mut self.pool.protocol_fees_a), self.creator_fee_vault_a.to_account_info())
+        } else if reward_mint == self.pool.mint_b {
+            (std::mem::take(&mut self.pool.protocol_fees_b), self.creator_fee_vault_b.to_account_info())
+        } else {
+            return err!(AmmError::FarmRewardNotPoolToken);
+        };
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        // 与 fund_farm 相同，先结算之前的时间段
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+
+        let accounts = Transfer {
+            from: vault,
+            to: self.farm_ata_reward.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)?;
+
+        self.farm.reward_remaining = self.farm.reward_remaining.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        has_one = mint_lp
+    )]
+    farm: Box<Account<'info, Farm>>,
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm
+    )]
+    farm_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint_lp,
+        token::authority = owner
+    )]
+    owner_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = owner,
+        space = StakePosition::DISCRIMINATOR.len() + StakePosition::INIT_SPACE,
+        seeds = [b"stake", farm.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    stake_position: Box<Account<'info, StakePosition>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> Stake<'info> {
+    pub fn stake(&mut self, id: u64, amount: u64, lock: LockDuration, bump: u8) -> Result<()> {
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.farm.update(now)?;
+
+        let accounts = Transfer {
+            from: self.owner_ata_lp.to_account_info(),
+            to: self.farm_ata_lp.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount)?;
+
+        let weight = amount as u128 * lock.boost_bps() / BPS_DENOMINATOR;
+        self.farm.total_staked = self.farm.total_staked.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.farm.total_weight = self.farm.total_weight.checked_add(weight).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // 新仓位只分之后释放的奖励和罚没
+        self.stake_position.set_inner(StakePosition {
+            version: ACCOUNT_VERSION,
+            farm: self.farm.key(),
+            owner: self.owner.key(),
+            id,
+            amount,
+            lock,
+            weight,
+            unlock_ts: now.checked_add(lock.seconds()).ok_or(ProgramError::ArithmeticOverflow)?,
+            reward_debt: 0,
+            penalty_debt: accrued(weight, self.farm.acc_penalty_per_weight)?,
+            bump,
+            lp_seconds_debt: 0,
+        });
+
+        let weight_start = self.farm.weight_start(&self.stake_position)?;
+        self.farm.total_weight_start = self.farm.total_weight_start.checked_add(weight_start).ok_or(ProgramError::ArithmeticOverflow)?;
+        // 把 debt 设为当前的累计值
+        self.farm.settle(&mut self.stake_position)?;
+        Ok(())
+    }
+}
+
+/// 领取奖励和退出质押共用的账户结构
+#[derive(Accounts)]
+pub struct ManageStake<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        has_one = mint_lp,
+        has_one = reward_mint
+    )]
+    farm: Box<Account<'info, Farm>>,
+    mint_lp: Box<Account<'info, Mint>>,
+    reward_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"stake", farm.key().as_ref(), owner.key().as_ref(), stake_position.id.to_le_bytes().as_ref()],
+        bump = stake_position.bump,
+        has_one = owner,
+        has_one = farm
+    )]
+    stake_position: Box<Account<'info, StakePosition>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm
+    )]
+    farm_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    farm_ata_reward: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint_lp,
+        token::authority = owner
+    )]
+    owner_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner
+    )]
+    owner_ata_reward: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> ManageStake<'info> {
+    /// 领取奖励和分到的罚没 LP，仓位保持不变
+    pub fn claim_stake_rewards(&mut self) -> Result<()> {
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        let (reward, penalty_lp) = self.farm.settle(&mut self.stake_position)?;
+        self.pay_out(reward, penalty_lp)
+    }
+
+    /// 退出质押：取回本金（解锁前扣除罚没部分）、奖励和分到的罚没 LP，关闭仓位
+    pub fn unstake(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.farm.update(now)?;
+        let (reward, penalty_lp) = self.farm.settle(&mut self.stake_position)?;
+
+        let amount = self.stake_position.amount;
+        self.farm.total_staked -= amount;
+        self.farm.total_weight -= self.stake_position.weight;
+        self.farm.total_weight_start -= self.farm.weight_start(&self.stake_position)?;
+
+        // 罚没的 LP 按权重分给剩下的质押者，没有人可分时不罚没
+        let penalty = if self.farm.total_weight > 0 {
+            early_exit_penalty(&self.stake_position, now)?
+        } else {
+            0
+        };
+        if penalty > 0 {
+            let per_weight = mul_div_floor(penalty as u128, Q64, self.farm.total_weight).ok_or(ProgramError::ArithmeticOverflow)?;
+            self.farm.acc_penalty_per_weight = self.farm.acc_penalty_per_weight.checked_add(per_weight).ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        self.pay_out(reward, amount - penalty + penalty_lp)?;
+
+        self.stake_position.close(self.owner.to_account_info())
+    }
+
+    fn pay_out(&self, reward: u64, amount_lp: u64) -> Result<()> {
+        let farm_seeds = self.farm.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&farm_seeds.as_seeds()];
+
+        for (from, to, amount) in [
+            (self.farm_ata_reward.to_account_info(), self.owner_ata_reward.to_account_info(), reward),
+            (self.farm_ata_lp.to_account_info(), self.owner_ata_lp.to_account_info(), amount_lp),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from,
+                to,
+                authority: self.farm.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, amount)?;
+        }
+        Ok(())
+    }
+}
+
+impl LockDuration {
+    /// 锁仓秒数
+    pub fn seconds(self) -> i64 {
+        match self {
+            LockDuration::None => 0,
+            LockDuration::OneMonth => LOCK_MONTH_SECONDS,
+            LockDuration::ThreeMonths => 3 * LOCK_MONTH_SECONDS,
+            LockDuration::SixMonths => 6 * LOCK_MONTH_SECONDS,
+        }
+    }
+
+    /// 奖励权重加成（基点），10000 表示 1x
+    pub fn boost_bps(self) -> u128 {
+        match self {
+            LockDuration::None => 10_000,
+            LockDuration::OneMonth => 12_500,
+            LockDuration::ThreeMonths => 15_000,
+            LockDuration::SixMonths => 20_000,
+        }
+    }
+}
+
+impl Farm {
+    /// 释放 last_update_ts 到 now 之间的奖励，不超过已经注入的数量；没有人质押时不释放
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        if now <= self.last_update_ts {
+            return Ok(());
+        }
+
+        if self.total_weight > 0 {
+            let elapsed = (now - self.last_update_ts) as u128;
+            let emitted = (self.reward_rate as u128).saturating_mul(elapsed).min(self.reward_remaining as u128);
+            let distributed = match self.accrual_mode {
+                FarmAccrualMode::PerShare => {
+                    let per_weight = mul_div_floor(emitted, Q64, self.total_weight).ok_or(ProgramError::ArithmeticOverflow)?;
+                    self.acc_reward_per_weight = self.acc_reward_per_weight.checked_add(per_weight).ok_or(ProgramError::ArithmeticOverflow)?;
+                    true
+                }
+                FarmAccrualMode::LpSeconds => self.accrue_lp_seconds(emitted, now)?,
+            };
+            // 累加器向下取整，分不完的零头留在奖励 ATA 中
+            if distributed {
+                self.reward_remaining -= emitted as u64;
+            }
+        }
+
+        self.last_update_ts = now;
+        Ok(())
+    }
+
+    /// 按 now 时刻的总 LP 秒数分配 emitted，返回是否分配出去（所有仓位都刚刚质押时总数为 0，不释放）
+    fn accrue_lp_seconds(&mut self, emitted: u128, now: i64) -> Result<bool> {
+        let t = (now - self.origin_ts) as u128;
+        let total_lp_seconds = self.total_weight.checked_mul(t).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_sub(self.total_weight_start).ok_or(ProgramError::ArithmeticOverflow)?;
+        if total_lp_seconds == 0 {
+            return Ok(false);
+        }
+
+        let per_lp_second = mul_div_floor(emitted, Q64, total_lp_seconds).ok_or(ProgramError::ArithmeticOverflow)?;
+        let time_per_lp_second = mul_div_floor(emitted.checked_mul(t).ok_or(ProgramError::ArithmeticOverflow)?, Q64, total_lp_seconds)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.acc_reward_per_lp_second = self.acc_reward_per_lp_second.checked_add(per_lp_second).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.acc_reward_time_per_lp_second = self.acc_reward_time_per_lp_second.checked_add(time_per_lp_second).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(true)
+    }
+
+    /// 仓位的 权重 × 质押时间（相对 origin_ts），PerShare 模式下为 0
+    pub fn weight_start(&self, position: &StakePosition) -> Result<u128> {
+        if self.accrual_mode != FarmAccrualMode::LpSeconds {
+            return Ok(0);
+        }
+        let staked_at = position.unlock_ts - position.lock.seconds() - self.origin_ts;
+        position.weight.checked_mul(staked_at as u128).ok_or(ProgramError::ArithmeticOverflow.into())
+    }
+
+    /// 仓位自上次结算以来的 (奖励, 罚没 LP)，并把 debt 更新到当前的累计值
+    pub fn settle(&self, position: &mut StakePosition) -> Result<(u64, u64)> {
+        let pending_reward = match self.accrual_mode {
+            FarmAccrualMode::PerShare => {
+                let reward = accrued(position.weight, self.acc_reward_per_weight)?;
+                // 累加器只增不减，权重不变，所以不会小于 debt
+                let pending = reward.checked_sub(position.reward_debt).ok_or(ProgramError::ArithmeticOverflow)?;
+                position.reward_debt = reward;
+                pending
+            }
+            FarmAccrualMode::LpSeconds => {
+                // 每段的奖励 = w × (t - s) × E / D = w × (E × t / D) - w × s × (E / D)
+                let reward_time = accrued(position.weight, self.acc_reward_time_per_lp_second)?;
+                let reward_start = accrued(self.weight_start(position)?, self.acc_reward_per_lp_second)?;
+                let pending_time = reward_time.checked_sub(position.reward_debt).ok_or(ProgramError::ArithmeticOverflow)?;
+                let pending_start = reward_start.checked_sub(position.lp_seconds_debt).ok_or(ProgramError::ArithmeticOverflow)?;
+                position.reward_debt = reward_time;
+                position.lp_seconds_debt = reward_start;
+                // 两项分别向下取整，差值可能比精确值多出 1
+                pending_time.saturating_sub(pending_start).saturating_sub(1)
+            }
+        };
+
+        let penalty = accrued(position.weight, self.acc_penalty_per_weight)?;
+        let pending_penalty = penalty.checked_sub(position.penalty_debt).ok_or(ProgramError::ArithmeticOverflow)?;
+        position.penalty_debt = penalty;
+        Ok((
+            pending_reward.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+            pending_penalty.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?,
+        ))
+    }
+}
+
+/// weight 按累加器 acc（Q64.64）累计到的数量，向下取整
+fn accrued(weight: u128, acc: u128) -> Result<u128> {
+    Ok(mul_div_floor(weight, acc, Q64).ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+/// 解锁前退出罚没的 LP：amount × MAX_EARLY_EXIT_PENALTY_BPS × 剩余锁仓时间 / 锁仓时长
+fn early_exit_penalty(position: &StakePosition, now: i64) -> Result<u64> {
+    let lock_seconds = position.lock.seconds();
+    if lock_seconds == 0 || now >= position.unlock_ts {
+        return Ok(0);
+    }
+
+    let remaining = (position.unlock_ts - now) as u128;
+    let penalty = mul_div_floor(
+        position.amount as u128 * MAX_EARLY_EXIT_PENALTY_BPS as u128,
+        remaining,
+        lock_seconds as u128 * BPS_DENOMINATOR,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(penalty as u64)
+}
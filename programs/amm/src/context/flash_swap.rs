@@ -0,0 +1,188 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::{AccountMeta, Instruction}, program::invoke, sysvar::instructions as sysvar_instructions},
+};
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{context::{pool_stats::record_swap_stats, swap::SwapResult}, error::AmmError, events::SwapEvent, guard::require_top_level, state::{AmmConfig, Pool, PoolStats, PoolStatus}, token_account::read_token_amount};
+
+// ========================================
+// Flash swap（乐观转账）
+// ========================================
+//
+// 1. 池子先把 amount_out 个输出代币转给 signer
+// 2. CPI 调用者指定的 callback_program，remaining_accounts 原样传给它
+// 3. 回调结束后检查池子输入侧余额至少增加了「含手续费的输入」
+//
+// 回调期间 pool.locked = true 并立即写回账户数据，
+// 任何读取储备量的指令（包括再次 swap / deposit / withdraw）都会被拒绝，防止重入。
+#[derive(Accounts)]
+pub struct FlashSwap<'info> {
+    signer: Signer<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 接收输出代币，由 SPL Token 程序在 transfer 中校验
+    #[account(mut)]
+    signer_ata_out: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_a
+    #[account(mut, address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_b
+    #[account(mut, address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = pool.round_trip_window_slots == 0 @ AmmError::TraderStateRequired,
+        constraint = !pool.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 可选的成交统计账户，见 pool_stats.rs
+    #[account(
+        mut,
+        constraint = pool_stats.load()?.pool == pool.key() @ AmmError::InvalidPoolStats
+    )]
+    pool_stats: Option<AccountLoader<'info, PoolStats>>,
+    /// CHECK: 任意可执行程序，回调中需要把输入代币还给池子
+    #[account(executable, constraint = callback_program.key() != crate::ID @ AmmError::PoolLocked)]
+    callback_program: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar，地址由 address 约束校验
+    #[account(address = sysvar_instructions::ID)]
+    instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> FlashSwap<'info> {
+    pub fn flash_swap(&mut self, amount: u64, is_a: bool, data: Vec<u8>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // flash swap 本身就是为可组合性设计的，限制 CPI 的池子一并拒绝
+        if self.pool.cpi_restricted {
+            require_top_level(&self.instructions)?;
+        }
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let (pool_in, pool_out, creator_fee_vault, reserve_in, reserve_out) = if is_a {
+            (self.pool_ata_b.to_account_info(), self.pool_ata_a.to_account_info(), self.creator_fee_vault_b.to_account_info(), reserve_b, reserve_a)
+        } else {
+            (self.pool_ata_a.to_account_info(), self.pool_ata_b.to_account_info(), self.creator_fee_vault_a.to_account_info(), reserve_a, reserve_b)
+        };
+
+        let (amount_in, amount_in_with_fees) = self.pool.quote_exact_out(reserve_in, reserve_out, amount, is_a, now)?;
+        let (creator_fee, protocol_fee) = self.pool.fee_shares(amount_in_with_fees)?;
+        let vault_fee = creator_fee + protocol_fee;
+
+        // ==========================================
+        // 1. 先把输出代币转给用户
+        // ==========================================
+        let accounts = Transfer {
+            from: pool_out,
+            to: self.signer_ata_out.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)?;
+
+        // ==========================================
+        // 2. 加锁并回调
+        // ==========================================
+        // Anchor 默认在指令结束时才写回账户数据，这里必须手动 exit，
+        // 回调里重入的指令才能读到 locked = true
+        self.pool.locked = true;
+        self.pool.exit(&crate::ID)?;
+
+        let callback = Instruction {
+            program_id: self.callback_program.key(),
+            accounts: remaining_accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data,
+        };
+        let mut account_infos = remaining_accounts.to_vec();
+        account_infos.push(self.callback_program.to_account_info());
+        invoke(&callback, &account_infos)?;
+
+        self.pool.locked = false;
+
+        // ==========================================
+        // 3. 检查还款（含手续费）
+        // ==========================================
+        let repaid = read_token_amount(&pool_in)?
+            .checked_sub(reserve_in).ok_or(AmmError::FlashSwapNotRepaid)?;
+        require_gte!(repaid, amount_in_with_fees, AmmError::FlashSwapNotRepaid);
+
+        // 还款已经进入池子，创建者和协议分成由池子签名转到手续费账户
+        if vault_fee > 0 {
+            let accounts = Transfer {
+                from: pool_in,
+                to: creator_fee_vault,
+                authority: self.pool.to_account_info(),
+            };
+
+            let pool_seeds = self.pool.signer_seeds();
+
+            let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, vault_fee)?;
+        }
+
+        self.pool.accrue_protocol_fee(is_a, protocol_fee)?;
+
+        // 熔断：按还款后的实际余额检查现价与 TWAP 的偏离
+        self.pool.check_circuit_breaker(read_token_amount(&self.pool_ata_a)?, read_token_amount(&self.pool_ata_b)?)?;
+
+        // 多还的部分同样留在池子里，按实际到账数量统计
+        let result = SwapResult {
+            amount_in: repaid,
+            amount_out: amount,
+            fee_paid: repaid.checked_sub(amount_in).ok_or(ProgramError::ArithmeticOverflow)?,
+        };
+        record_swap_stats(self.pool_stats.as_ref(), is_a, result.amount_in, result.amount_out, result.fee_paid, now)?;
+        emit!(SwapEvent::new(&mut self.pool, self.signer.key(), is_a, &result, 0, None)?);
+        result.set_return_data()
+    }
+}
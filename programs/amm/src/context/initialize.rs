@@ -1,53 +1,181 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
 use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
 
-use crate::state::Pool;
+use crate::{error::AmmError, math::{BPS_DENOMINATOR, MAX_FEE_BPS}, state::{AmmConfig, CurveType, FeeDestinationMode, PairMarker, PairPool, Pool, PoolRegistry, PoolStatus, ProtocolFeeMode, RegistryEntry, RegistryPage, TokenBadge, ACCOUNT_VERSION, MAX_PAIR_POOLS, POOL_VERSION, REGISTRY_PAGE_CAPACITY}};
 
 #[derive(Accounts)]
-#[instruction(fee: u16)]
+#[instruction(fee: u16, creator_fee_bps: u16)]
 pub struct Initialize<'info> {
-    #[account(mut)]
+    // 池子创建者：成为池子管理员并享有创建者手续费
     signer: Signer<'info>,
+    // 支付所有账户租金，可以和 signer 相同，也可以是替用户代付的协议钱包
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(
+        constraint = !is_lp_mint(&mint_a) @ AmmError::LpMintAsPoolSide,
+        constraint = mint_a.freeze_authority.is_none() || config.allow_freezable_mints @ AmmError::MintHasFreezeAuthority,
+        constraint = mint_a.supply > 0 @ AmmError::ZeroSupplyMint
+    )]
     mint_a: Account<'info, Mint>,
+    #[account(
+        constraint = mint_b.key() != mint_a.key() @ AmmError::IdenticalMints,
+        constraint = !is_lp_mint(&mint_b) @ AmmError::LpMintAsPoolSide,
+        constraint = mint_b.freeze_authority.is_none() || config.allow_freezable_mints @ AmmError::MintHasFreezeAuthority,
+        constraint = mint_b.supply > 0 @ AmmError::ZeroSupplyMint
+    )]
     mint_b: Account<'info, Mint>,
+    // 不设置 mint::freeze_authority，LP mint 创建时 freeze authority 为 None，任何人都无法冻结 LP
     #[account(
         init,
-        payer = signer,
+        payer = payer,
         mint::decimals = 0,
         mint::authority = pool,
         seeds = [b"lp", pool.key().as_ref()],
         bump
     )]
     mint_lp: Account<'info, Mint>,
+    // 池子的关联代币账户地址可以提前算出，任何人都能抢先创建，用 init 的话池子就再也建不起来了。
+    // 改用 init_if_needed：已经存在时 Anchor 校验它是 pool 在该 mint 上的关联代币账户，
+    // 再要求没有 delegate 和 close authority，见 is_untouched_vault；
+    // 抢先转入的代币会在首次存入时和存入的代币一起计入储备，归首个存入者，见 Pool::first_deposit_lp
     #[account(
-        init,
-        payer = signer,
+        init_if_needed,
+        payer = payer,
         associated_token::authority = pool,
-        associated_token::mint = mint_a
+        associated_token::mint = mint_a,
+        constraint = is_untouched_vault(&pool_ata_a) @ AmmError::PoolVaultCompromised
     )]
     pool_ata_a: Account<'info, TokenAccount>,
     #[account(
-        init,
-        payer = signer,
+        init_if_needed,
+        payer = payer,
         associated_token::authority = pool,
-        associated_token::mint = mint_b
+        associated_token::mint = mint_b,
+        constraint = is_untouched_vault(&pool_ata_b) @ AmmError::PoolVaultCompromised
     )]
     pool_ata_b: Account<'info, TokenAccount>,
+    // 创建者手续费单独存放，不计入储备量
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_a,
+        token::authority = pool,
+        seeds = [b"creator_fee", pool.key().as_ref(), mint_a.key().as_ref()],
+        bump
+    )]
+    creator_fee_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_b,
+        token::authority = pool,
+        seeds = [b"creator_fee", pool.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    creator_fee_vault_b: Box<Account<'info, TokenAccount>>,
     #[account(
         init,
-        payer = signer,
+        payer = payer,
         space = Pool::DISCRIMINATOR.len() + Pool::INIT_SPACE,
         seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), fee.to_le_bytes().as_ref()],
         bump
     )]
     pool: Account<'info, Pool>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = fee <= MAX_FEE_BPS @ AmmError::InvalidFee,
+        constraint = config.fee_tier(fee).is_some() @ AmmError::FeeTierNotAllowed,
+        constraint = creator_fee_bps <= config.max_creator_fee_bps @ AmmError::CreatorFeeTooHigh,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Account<'info, AmmConfig>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PoolRegistry::DISCRIMINATOR.len() + PoolRegistry::INIT_SPACE,
+        seeds = [b"registry"],
+        bump
+    )]
+    registry: Account<'info, PoolRegistry>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = RegistryPage::DISCRIMINATOR.len() + RegistryPage::INIT_SPACE,
+        seeds = [b"registry_page", registry.current_page.to_le_bytes().as_ref()],
+        bump
+    )]
+    registry_page: Box<Account<'info, RegistryPage>>,
+    // 交易对反查记录，种子中的两个 mint 按字节序排列，与 mint_a / mint_b 的顺序无关
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PairMarker::DISCRIMINATOR.len() + PairMarker::INIT_SPACE,
+        seeds = [b"pair", PairMarker::sorted_mints(mint_a.key(), mint_b.key()).0.as_ref(), PairMarker::sorted_mints(mint_a.key(), mint_b.key()).1.as_ref()],
+        bump
+    )]
+    pair_marker: Box<Account<'info, PairMarker>>,
+    // 只有 config.require_token_badges 开启时需要传入，见 token_badge.rs
+    #[account(
+        seeds = [b"token_badge", mint_a.key().as_ref()],
+        bump = token_badge_a.bump
+    )]
+    token_badge_a: Option<Box<Account<'info, TokenBadge>>>,
+    #[account(
+        seeds = [b"token_badge", mint_b.key().as_ref()],
+        bump = token_badge_b.bump
+    )]
+    token_badge_b: Option<Box<Account<'info, TokenBadge>>>,
     token_program: Program<'info, Token>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
+/// 池子代币账户没有 delegate 和 close authority
+///
+/// 两者都要 owner（pool PDA）签名才能设置，正常情况下一定为空，这里防御性地再检查一次，
+/// 保证抢先创建的关联代币账户不会留下可以转走或关闭池子储备的权限
+pub fn is_untouched_vault(vault: &TokenAccount) -> bool {
+    vault.delegate.is_none() && vault.close_authority.is_none()
+}
+
+/// 判断一个 mint 是否是本程序某个池子的 LP mint
+///
+/// LP mint 的地址是 PDA ["lp", pool]，mint authority 又正好是 pool，
+/// 所以用 mint authority 重新推导一次 PDA，和 mint 地址相同就说明是 LP mint。
+/// 用 LP 代币组池子会让池子之间互相嵌套，价格和清算都很难推理，直接拒绝。
+pub fn is_lp_mint(mint: &Account<Mint>) -> bool {
+    match mint.mint_authority {
+        COption::Some(authority) => {
+            let (lp_mint, _) = Pubkey::find_program_address(&[b"lp", authority.as_ref()], &crate::ID);
+            lp_mint == mint.key()
+        }
+        COption::None => false,
+    }
+}
+
 impl<'info> Initialize<'info> {
-    pub fn initialize(&mut self, fee: u16, bump: u8, lp_bump: u8) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(&mut self, fee: u16, creator_fee_bps: u16, initial_price_numerator: u64, initial_price_denominator: u64, start_ts: i64, end_ts: i64, soulbound_lp: bool, fee_destination_mode: FeeDestinationMode, bump: u8, lp_bump: u8, registry_bump: u8, registry_page_bump: u8, pair_marker_bump: u8) -> Result<()> {
+        // 策展部署模式：两个 mint 都必须有徽章，徽章地址已经由种子约束校验
+        if self.config.require_token_badges {
+            require!(self.token_badge_a.is_some() && self.token_badge_b.is_some(), AmmError::TokenBadgeRequired);
+        }
+
+        // 协议分成取档位的默认值，与创建者分成之和不能超过 10000（与 set_protocol_fee 相同）
+        // 手续费全部归创建者的池子只能在许可部署中创建，公开部署里 LP 存入之前未必会检查这个字段
+        require!(fee_destination_mode == FeeDestinationMode::Lp || self.config.require_token_badges, AmmError::FeeDestinationNotAllowed);
+
+        let protocol_fee_bps = self.config.fee_tier(fee).map_or(0, |tier| tier.protocol_fee_bps);
+        require!(creator_fee_bps as u128 + protocol_fee_bps as u128 <= BPS_DENOMINATOR, AmmError::ProtocolFeeTooHigh);
+
+        // 初始价格要么不声明（都为 0），要么分子分母都不为 0
+        require!((initial_price_numerator == 0) == (initial_price_denominator == 0), AmmError::InvalidInitialPrice);
+
+        // 交易窗口：截止时间必须晚于起始时间和当前时间，否则池子创建出来就无法交易
+        require!(start_ts >= 0 && end_ts >= 0, AmmError::InvalidTradingWindow);
+        require!(end_ts == 0 || (end_ts > start_ts && end_ts > Clock::get()?.unix_timestamp), AmmError::InvalidTradingWindow);
+
         // 这里的 set_inner 是将数据写入到已经初始化的 Pool 账户中
         // bump 和 lp_bump 不是传入给账户初始化的参数，而是：
         // 1. 在账户验证阶段，Anchor 已经为 pool 和 mint_lp 这两个 PDA 计算了 canonical bump
@@ -55,11 +183,118 @@ impl<'info> Initialize<'info> {
         // 3. 现在我们将这些预计算的 bump 值存储到 Pool 数据结构中，作为状态的一部分
         // 4. 存储 bump 的目的是为了后续操作（如签名）时能够重新生成正确的 PDA 地址
         self.pool.set_inner(Pool {
+            version: POOL_VERSION,
+            authority: self.signer.key(), // 创建者即管理员，之后可以转交给多签
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),   
+            vault_a: self.pool_ata_a.key(),
+            vault_b: self.pool_ata_b.key(),
+            decimals_a: self.mint_a.decimals,
+            decimals_b: self.mint_b.decimals,
+            has_freezable_mint: self.mint_a.freeze_authority.is_some() || self.mint_b.freeze_authority.is_some(),
             fee,
+            initial_price_numerator,
+            initial_price_denominator,
+            start_ts,
+            end_ts,
+            creator: self.signer.key(),
+            pending_creator: Pubkey::default(),
+            creator_fee_bps,
+            creator_fee_vault_a: self.creator_fee_vault_a.key(),
+            creator_fee_vault_b: self.creator_fee_vault_b.key(),
+            protocol_fee_bps,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            protocol_fee_mode: ProtocolFeeMode::Skim,
+            k_last: 0,
+            treasury_lp: Pubkey::default(),
             bump,      // pool PDA 的 canonical bump，用于后续重新生成 pool 地址
             lp_bump,   // LP mint PDA 的 canonical bump，用于后续 LP token 相关操作
+            max_price_impact_bps: 0,
+            min_trade_amount: 0,
+            circuit_breaker_bps: 0,
+            cpi_restricted: false,
+            disable_swap_a_to_b: false,
+            disable_swap_b_to_a: false,
+            locked: false,
+            status: PoolStatus::Active,
+            emergency_withdraw_enabled: false,
+            event_seq: 0,
+            max_reserve_a: 0,
+            max_reserve_b: 0,
+            rate_limit_window_slots: 0,
+            max_out_a_per_window: 0,
+            max_out_b_per_window: 0,
+            curve_type: CurveType::ConstantProduct,
+            amp: 0,
+            lbp_start_weight_a: 0,
+            lbp_end_weight_a: 0,
+            lbp_start_ts: 0,
+            lbp_end_ts: 0,
+            last_update_ts: Clock::get()?.unix_timestamp,
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+            observation_index: 0,
+            observations: Default::default(),
+            jit_protection: false,
+            swap_hook_program: Pubkey::default(),
+            swap_hook_accounts: 0,
+            soulbound_lp,
+            external_oracle: Pubkey::default(),
+            external_oracle_band_bps: 0,
+            external_oracle_max_age: 0,
+            fee_destination_mode,
+            trade_gate_collection: Pubkey::default(),
+            round_trip_window_slots: 0,
+            round_trip_fee_bps: 0,
+            position_whitelist_root: [0; 32],
+            max_drain_bps: 0,
+            protocol_fees_to_farm: false,
+            last_buyback_ts: 0,
+        });
+
+        self.register_pool(fee, registry_bump, registry_page_bump)?;
+        self.register_pair(fee, pair_marker_bump)
+    }
+
+    /// 把新池子追加到注册表当前分页，分页写满后滚动到下一页
+    fn register_pool(&mut self, fee: u16, registry_bump: u8, registry_page_bump: u8) -> Result<()> {
+        // init_if_needed 首次创建时账户数据为默认值，这里补上版本、bump 和页码
+        self.registry.version = ACCOUNT_VERSION;
+        self.registry.bump = registry_bump;
+        self.registry_page.version = ACCOUNT_VERSION;
+        self.registry_page.bump = registry_page_bump;
+        self.registry_page.page = self.registry.current_page;
+
+        self.registry_page.entries.push(RegistryEntry {
+            pool: self.pool.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            fee,
+        });
+        self.registry.total_pools = self.registry.total_pools.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // 当前页已满：下一次 initialize 会通过新的 current_page 种子创建下一页
+        if self.registry_page.entries.len() >= REGISTRY_PAGE_CAPACITY {
+            self.registry.current_page = self.registry.current_page.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// 把新池子追加到交易对反查记录
+    fn register_pair(&mut self, fee: u16, pair_marker_bump: u8) -> Result<()> {
+        let (mint_0, mint_1) = PairMarker::sorted_mints(self.mint_a.key(), self.mint_b.key());
+
+        // init_if_needed 首次创建时账户数据为默认值，这里补上版本、bump 和两个 mint
+        self.pair_marker.version = ACCOUNT_VERSION;
+        self.pair_marker.mint_0 = mint_0;
+        self.pair_marker.mint_1 = mint_1;
+        self.pair_marker.bump = pair_marker_bump;
+
+        require!(self.pair_marker.pools.len() < MAX_PAIR_POOLS, AmmError::PairMarkerFull);
+        self.pair_marker.pools.push(PairPool {
+            fee,
+            reversed: self.mint_a.key() != mint_0,
         });
         Ok(())
     }
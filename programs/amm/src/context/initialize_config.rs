@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, math::{BPS_DENOMINATOR, MAX_FEE_BPS}, program::Amm, state::{AmmConfig, FeeTier, ACCOUNT_VERSION, MAX_FEE_TIERS}};
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    #[account(
+        init,
+        payer = signer,
+        space = AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    config: Account<'info, AmmConfig>,
+    // 只有程序的升级权限持有者可以创建全局配置，防止被别人抢先初始化
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    program: Program<'info, Amm>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()))]
+    program_data: Account<'info, ProgramData>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    /// 初始档位的协议分成都为 0，之后可以用 add_fee_tier / set_fee_tiers 调整
+    pub fn initialize_config(&mut self, admin: Pubkey, fee_tiers: Vec<u16>, bump: u8) -> Result<()> {
+        let fee_tiers: Vec<FeeTier> = fee_tiers.into_iter().map(|fee| FeeTier { fee, protocol_fee_bps: 0 }).collect();
+        validate_fee_tiers(&fee_tiers)?;
+
+        self.config.set_inner(AmmConfig {
+            version: ACCOUNT_VERSION,
+            admin,
+            fee_tiers,
+            max_creator_fee_bps: 0, // 默认不允许创建者分成，由协议管理员开启
+            protocol_token_mint: Pubkey::default(), // 默认不回购，由协议管理员开启
+            max_buyback_amount_in: 0,
+            max_buyback_impact_bps: 0,
+            allow_freezable_mints: false,
+            require_token_badges: false,
+            membership_mint: Pubkey::default(),
+            fee_rebate_tiers: Vec::new(),
+            guardian: Pubkey::default(),
+            emergency_mode: false,
+            emergency_exit_ts: 0,
+            fee_recipients: Vec::new(),
+            fee_exemptions: Vec::new(),
+            bump,
+        });
+        Ok(())
+    }
+}
+
+/// 档位数量不超过 MAX_FEE_TIERS，fee 互不相同且不超过 MAX_FEE_BPS，协议分成不超过 10000
+pub fn validate_fee_tiers(fee_tiers: &[FeeTier]) -> Result<()> {
+    require!(fee_tiers.len() <= MAX_FEE_TIERS, AmmError::TooManyFeeTiers);
+    for (i, tier) in fee_tiers.iter().enumerate() {
+        require!(tier.fee <= MAX_FEE_BPS, AmmError::InvalidFee);
+        require!(tier.protocol_fee_bps as u128 <= BPS_DENOMINATOR, AmmError::ProtocolFeeTooHigh);
+        require!(fee_tiers[..i].iter().all(|other| other.fee != tier.fee), AmmError::DuplicateFeeTier);
+    }
+    Ok(())
+}
+
+impl AmmConfig {
+    /// fee 对应的手续费档位，没有配置时返回 None
+    pub fn fee_tier(&self, fee: u16) -> Option<&FeeTier> {
+        self.fee_tiers.iter().find(|tier| tier.fee == fee)
+    }
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    context::admin::is_pool_authority,
+    error::AmmError,
+    math::interpolate_weight,
+    state::{CurveType, Pool, MAX_LBP_WEIGHT_BPS, MIN_LBP_WEIGHT_BPS},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// LBP（Liquidity Bootstrapping Pool）
+// ========================================
+//
+// 项目方以很高的自身代币权重（例如 95/5）开池，权重随时间线性移动到 50/50，
+// 价格在没有买盘时会逐渐下降，抢跑和巨鲸早期扫货都不划算，实现相对公平的发行。
+//
+// 权重改变的是同样储备量下的价格：price_a = (reserve_b / w_b) / (reserve_a / w_a)
+
+#[derive(Accounts)]
+pub struct ConfigureLbp<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(mut, constraint = !pool.locked @ AmmError::PoolLocked)]
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> ConfigureLbp<'info> {
+    pub fn configure_lbp(&mut self, start_weight_a: u16, end_weight_a: u16, start_ts: i64, end_ts: i64) -> Result<()> {
+        // 已经有流动性的池子切换成加权曲线会让价格瞬间跳变，只允许在空池子上配置
+        require!(
+            read_token_amount(&self.pool_ata_a)? == 0 && read_token_amount(&self.pool_ata_b)? == 0,
+            AmmError::PoolNotEmpty
+        );
+
+        let valid_weight = |w: u16| (MIN_LBP_WEIGHT_BPS..=MAX_LBP_WEIGHT_BPS).contains(&w);
+        require!(valid_weight(start_weight_a) && valid_weight(end_weight_a), AmmError::InvalidLbpSchedule);
+        require!(end_ts > start_ts, AmmError::InvalidLbpSchedule);
+
+        self.pool.curve_type = CurveType::Weighted;
+        self.pool.lbp_start_weight_a = start_weight_a;
+        self.pool.lbp_end_weight_a = end_weight_a;
+        self.pool.lbp_start_ts = start_ts;
+        self.pool.lbp_end_ts = end_ts;
+        Ok(())
+    }
+}
+
+impl Pool {
+    /// 当前时刻的 (weight_a, weight_b)，以基点表示
+    pub fn lbp_weights(&self, now: i64) -> (u16, u16) {
+        let weight_a = interpolate_weight(
+            self.lbp_start_weight_a,
+            self.lbp_end_weight_a,
+            self.lbp_start_ts,
+            self.lbp_end_ts,
+            now,
+        );
+        (weight_a, 10_000 - weight_a)
+    }
+}
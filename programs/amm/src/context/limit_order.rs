@@ -0,0 +1,339 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{close_account, transfer, CloseAccount, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{context::pool_stats::record_swap_stats, error::AmmError, events::LimitOrderFilled, math::mul_div_floor, state::{AmmConfig, LimitOrder, Pool, PoolStats, PoolStatus, ACCOUNT_VERSION, PRICE_FEED_EXPONENT}, token_account::read_token_amount};
+
+// ========================================
+// 限价单（可部分成交）
+// ========================================
+//
+// 1. create_limit_order：用户把 amount_in 个输入代币存入订单 PDA 的托管 ATA，
+//    限价 = min_amount_out / amount_in
+// 2. fill_limit_order：任何人都可以在池子价格优于限价时成交其中一部分，
+//    成交的输入从托管转入池子，输出直接转到 destination。
+//    池子价格来回波动时可以多次成交，每次都单独检查限价，直到 filled_amount 达到 amount_in
+// 3. cancel_limit_order：用户取回没有成交的托管代币并关闭订单
+//
+// 成交量越大价格冲击越大，单次能成交多少由调用方按当前储备量在链下计算，
+// 链上只检查这一次的成交价不低于限价。与定投相同，先用 swap_out_given_in 估算输出，
+// 再用 quote_exact_out 按与 swap 相同的路径报价，实际付出不超过本次的 amount。
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateLimitOrder<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    mint_in: Box<Account<'info, Mint>>,
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::DISCRIMINATOR.len() + LimitOrder::INIT_SPACE,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    limit_order: Account<'info, LimitOrder>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = limit_order
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner
+    )]
+    owner_ata_in: Box<Account<'info, TokenAccount>>,
+    destination: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CreateLimitOrder<'info> {
+    pub fn create_limit_order(&mut self, id: u64, amount_in: u64, min_amount_out: u64, is_a: bool, bump: u8) -> Result<()> {
+        let (mint_in, mint_out) = if is_a {
+            (self.pool.mint_b, self.pool.mint_a)
+        } else {
+            (self.pool.mint_a, self.pool.mint_b)
+        };
+        require_keys_eq!(self.mint_in.key(), mint_in, AmmError::InvalidLimitOrder);
+        require_keys_eq!(self.destination.mint, mint_out, AmmError::InvalidLimitOrder);
+
+        require!(amount_in > 0 && min_amount_out > 0, AmmError::InvalidLimitOrder);
+
+        let accounts = Transfer {
+            from: self.owner_ata_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount_in)?;
+
+        self.limit_order.set_inner(LimitOrder {
+            version: ACCOUNT_VERSION,
+            owner: self.owner.key(),
+            pool: self.pool.key(),
+            id,
+            is_a,
+            escrow: self.escrow.key(),
+            destination: self.destination.key(),
+            amount_in,
+            min_amount_out,
+            filled_amount: 0,
+            received_amount: 0,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    // 任何人都可以成交达到限价的订单
+    filler: Signer<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_a
+    #[account(mut, address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.creator_fee_vault_b
+    #[account(mut, address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
+    // 与定投相同，代为成交会绕过按 signer 统计的钱包限额，所以限额池子不支持限价单
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 可选的成交统计账户，见 pool_stats.rs
+    #[account(
+        mut,
+        constraint = pool_stats.load()?.pool == pool.key() @ AmmError::InvalidPoolStats
+    )]
+    pool_stats: Option<AccountLoader<'info, PoolStats>>,
+    #[account(
+        mut,
+        seeds = [b"limit_order", pool.key().as_ref(), limit_order.owner.as_ref(), limit_order.id.to_le_bytes().as_ref()],
+        bump = limit_order.bump,
+        has_one = pool,
+        has_one = escrow,
+        has_one = destination
+    )]
+    limit_order: Account<'info, LimitOrder>,
+    /// CHECK: 地址必须等于 limit_order.escrow
+    #[account(mut)]
+    escrow: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 limit_order.destination
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> FillLimitOrder<'info> {
+    /// amount: 本次最多成交的输入代币（含手续费），超过剩余数量时按剩余数量成交
+    pub fn fill_limit_order(&mut self, amount: u64) -> Result<()> {
+        let remaining = self.limit_order.amount_in - self.limit_order.filled_amount;
+        let budget = amount.min(remaining);
+        require_gt!(budget, 0, AmmError::ZeroAmount);
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        // 在储备量变化之前推进价格累加器
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let is_a = self.limit_order.is_a;
+        let (pool_in, pool_out, creator_fee_vault, reserve_in, reserve_out) = if is_a {
+            (self.pool_ata_b.to_account_info(), self.pool_ata_a.to_account_info(), self.creator_fee_vault_b.to_account_info(), reserve_b, reserve_a)
+        } else {
+            (self.pool_ata_a.to_account_info(), self.pool_ata_b.to_account_info(), self.creator_fee_vault_a.to_account_info(), reserve_a, reserve_b)
+        };
+
+        let amount_out = self.pool.curve(now).swap_out_given_in(reserve_in, reserve_out, budget, is_a, self.pool.fee)?;
+        require_gt!(amount_out, 0);
+
+        let (amount_in, amount_in_with_fees) = self.pool.quote_exact_out(reserve_in, reserve_out, amount_out, is_a, now)?;
+        require_gte!(budget, amount_in_with_fees);
+
+        // 限价：amount_out / amount_in_with_fees >= min_amount_out / amount_in，交叉相乘避免除法
+        require!(
+            amount_out as u128 * self.limit_order.amount_in as u128 >= amount_in_with_fees as u128 * self.limit_order.min_amount_out as u128,
+            AmmError::LimitPriceNotMet
+        );
+
+        let (creator_fee, protocol_fee) = self.pool.fee_shares(amount_in_with_fees)?;
+        let vault_fee = creator_fee + protocol_fee;
+        let amount_to_pool = amount_in_with_fees - vault_fee;
+
+        // 熔断：按交易后的储备量检查现价与 TWAP 的偏离
+        let (reserve_a_after, reserve_b_after) = if is_a {
+            (reserve_a - amount_out, reserve_b.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?)
+        } else {
+            (reserve_a.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?, reserve_b - amount_out)
+        };
+        self.pool.check_circuit_breaker(reserve_a_after, reserve_b_after)?;
+
+        self.transfer_from_escrow(pool_in, amount_to_pool)?;
+        self.transfer_from_escrow(creator_fee_vault, vault_fee)?;
+        self.pool.accrue_protocol_fee(is_a, protocol_fee)?;
+
+        let accounts = Transfer {
+            from: pool_out,
+            to: self.destination.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount_out)?;
+
+        record_swap_stats(self.pool_stats.as_ref(), is_a, amount_in_with_fees, amount_out, amount_in_with_fees - amount_in, now)?;
+
+        let event_seq = self.pool.next_event_seq()?;
+        let order = &mut self.limit_order;
+        order.filled_amount += amount_in_with_fees;
+        order.received_amount = order.received_amount.checked_add(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let scale = 10u128.pow(PRICE_FEED_EXPONENT.unsigned_abs());
+        let average_price = mul_div_floor(order.received_amount as u128, scale, order.filled_amount as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        emit!(LimitOrderFilled {
+            pool: order.pool,
+            event_seq,
+            order: order.key(),
+            amount_in: amount_in_with_fees,
+            amount_out,
+            filled_amount: order.filled_amount,
+            received_amount: order.received_amount,
+            average_price: u64::try_from(average_price).map_err(|_| ProgramError::ArithmeticOverflow)?,
+        });
+        Ok(())
+    }
+
+    /// 订单 PDA 签名，从托管 ATA 转出输入代币
+    fn transfer_from_escrow(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to,
+            authority: self.limit_order.to_account_info(),
+        };
+
+        let order_seeds = self.limit_order.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&order_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"limit_order", limit_order.pool.as_ref(), owner.key().as_ref(), limit_order.id.to_le_bytes().as_ref()],
+        bump = limit_order.bump,
+        has_one = owner,
+        has_one = escrow
+    )]
+    limit_order: Account<'info, LimitOrder>,
+    #[account(mut)]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = owner
+    )]
+    owner_ata_in: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelLimitOrder<'info> {
+    /// 取回没有成交的部分并关闭订单，全部成交后同样用它回收租金
+    pub fn cancel_limit_order(&mut self) -> Result<()> {
+        let order_seeds = self.limit_order.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&order_seeds.as_seeds()];
+
+        if self.escrow.amount > 0 {
+            let accounts = Transfer {
+                from: self.escrow.to_account_info(),
+                to: self.owner_ata_in.to_account_info(),
+                authority: self.limit_order.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, self.escrow.amount)?;
+        }
+
+        // 关闭托管 ATA，租金退还给用户；订单账户由 close = owner 关闭
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.limit_order.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        close_account(ctx)
+    }
+}
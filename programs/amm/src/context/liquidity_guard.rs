@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, state::{LiquidityGuard, Pool, ACCOUNT_VERSION}};
+
+// ========================================
+// JIT 流动性保护
+// ========================================
+//
+// JIT（just-in-time）流动性：看到一笔大额 swap 后，在同一个区块里先存入大量流动性、
+// 吃掉这笔 swap 的大部分手续费，再立刻取出，长期 LP 的手续费收入被稀释。
+//
+// 池子管理员打开 jit_protection 之后，deposit / deposit_imbalanced / delegated_deposit
+// 和 withdraw / withdraw_percent 都需要传入 LP 自己的 LiquidityGuard：
+// - 存入时把当前 slot 记为 last_deposit_slot
+// - 取出时如果当前 slot 等于 last_deposit_slot 就拒绝
+// 每个钱包需要先调用一次 init_liquidity_guard 创建自己的记录。
+// 把 LP 转到另一个钱包可以绕过这项检查，所以它只是提高 JIT 的成本，而不是彻底禁止。
+
+#[derive(Accounts)]
+pub struct InitLiquidityGuard<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = signer,
+        space = LiquidityGuard::DISCRIMINATOR.len() + LiquidityGuard::INIT_SPACE,
+        seeds = [b"lp_guard", pool.key().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    liquidity_guard: Account<'info, LiquidityGuard>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitLiquidityGuard<'info> {
+    pub fn init_liquidity_guard(&mut self, bump: u8) -> Result<()> {
+        self.liquidity_guard.set_inner(LiquidityGuard {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            owner: self.signer.key(),
+            last_deposit_slot: 0,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+impl LiquidityGuard {
+    /// 存入流动性时调用：开启 jit_protection 的池子必须传入 guard，记录当前 slot
+    pub fn record_deposit(pool: &Pool, guard: Option<&mut Self>, slot: u64) -> Result<()> {
+        match guard {
+            Some(guard) => guard.last_deposit_slot = slot,
+            None => require!(!pool.jit_protection, AmmError::LiquidityGuardRequired),
+        }
+        Ok(())
+    }
+
+    /// 取出流动性时调用：开启 jit_protection 的池子必须传入 guard，拒绝与存入同一 slot 的取出
+    pub fn check_withdraw(pool: &Pool, guard: Option<&Self>, slot: u64) -> Result<()> {
+        if !pool.jit_protection {
+            return Ok(());
+        }
+        let guard = guard.ok_or(AmmError::LiquidityGuardRequired)?;
+        require_neq!(guard.last_deposit_slot, slot, AmmError::SameSlotWithdraw);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::AmmError, state::{AmmConfig, AmmConfigV1, FeeTier, ACCOUNT_VERSION}};
+
+// ========================================
+// 全局配置布局迁移（v1 -> 当前版本）
+// ========================================
+//
+// v1 的 AmmConfig 只有 admin / fee_tiers（Vec<u16>）/ max_creator_fee_bps / bump，
+// 按当前布局反序列化会把 fee_tiers 读错位。migrate_config 无需权限，任何人付租金都可以迁移：
+// admin、档位和创建者分成上限原样保留，档位的协议分成为 0，其他新字段取与 initialize_config 相同的默认值。
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 旧布局无法用 Account<AmmConfig> 反序列化，通过种子和 owner 校验，数据在指令中手动解析
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"config"],
+        bump
+    )]
+    config: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateConfig<'info> {
+    pub fn migrate_config(&mut self) -> Result<()> {
+        let config_info = self.config.to_account_info();
+
+        // v1 的 fee_tiers 按 max_len 分配空间，账户长度固定，version 字节和长度同时匹配才迁移
+        let v1 = {
+            let data = config_info.try_borrow_data()?;
+            require!(data.starts_with(AmmConfig::DISCRIMINATOR), AmmError::InvalidAccountVersion);
+            let mut body = &data[AmmConfig::DISCRIMINATOR.len()..];
+            require!(body.len() == AmmConfigV1::INIT_SPACE && body.first() == Some(&1), AmmError::InvalidAccountVersion);
+            AmmConfigV1::deserialize(&mut body)?
+        };
+
+        let new_len = AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(config_info.lamports());
+        if rent > 0 {
+            let accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: config_info.clone(),
+            };
+
+            let ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, rent)?;
+        }
+
+        config_info.resize(new_len)?;
+
+        let config = AmmConfig {
+            version: ACCOUNT_VERSION,
+            admin: v1.admin,
+            fee_tiers: v1.fee_tiers.into_iter().map(|fee| FeeTier { fee, protocol_fee_bps: 0 }).collect(),
+            max_creator_fee_bps: v1.max_creator_fee_bps,
+            protocol_token_mint: Pubkey::default(),
+            max_buyback_amount_in: 0,
+            max_buyback_impact_bps: 0,
+            allow_freezable_mints: false,
+            require_token_badges: false,
+            membership_mint: Pubkey::default(),
+            fee_rebate_tiers: Vec::new(),
+            guardian: Pubkey::default(),
+            emergency_mode: false,
+            emergency_exit_ts: 0,
+            fee_recipients: Vec::new(),
+            fee_exemptions: Vec::new(),
+            bump: v1.bump,
+        };
+
+        // try_serialize 会连同 discriminator 一起写入
+        let mut data = config_info.try_borrow_mut_data()?;
+        config.try_serialize(&mut &mut data[..])
+    }
+}
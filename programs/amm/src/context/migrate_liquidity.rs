@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{burn, mint_to, transfer, Burn, Mint, MintTo, Token, Transfer};
+
+use crate::{
+    error::AmmError,
+    events::{LiquidityDeposited, LiquidityWithdrawn},
+    math::max_proportional_lp,
+    state::{AmmConfig, Pool, PoolStatus},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// 流动性迁移
+// ========================================
+//
+// 管理员把旧池子标记为 WithdrawOnly 之后，LP 可以用一条指令把流动性搬到同一交易对的新池子：
+// 1. 销毁旧池子的 LP，按比例计算可以取回的 (amount_a, amount_b)
+// 2. 按新池子的比例计算最多能铸造的新 LP，对应的代币由旧池子直接转入新池子
+// 3. 比例不一致多出来的零头退回给用户
+//
+// 整个过程在同一笔交易里完成，不需要先取出再存入，中间也不会被夹。
+
+#[derive(Accounts)]
+pub struct MigrateLiquidity<'info> {
+    signer: Signer<'info>,
+    /// CHECK: 通过两个 pool 的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过两个 pool 的种子校验
+    mint_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), old_pool.fee.to_le_bytes().as_ref()],
+        bump = old_pool.bump,
+        constraint = !old_pool.locked @ AmmError::PoolLocked,
+        constraint = old_pool.status == PoolStatus::WithdrawOnly @ AmmError::PoolNotActive,
+        constraint = !old_pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    old_pool: Box<Account<'info, Pool>>,
+    #[account(
+        mut,
+        seeds = [b"lp", old_pool.key().as_ref()],
+        bump = old_pool.lp_bump
+    )]
+    old_mint_lp: Box<Account<'info, Mint>>,
+    /// CHECK: 地址必须等于 old_pool.vault_a
+    #[account(mut, address = old_pool.vault_a)]
+    old_pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 old_pool.vault_b
+    #[account(mut, address = old_pool.vault_b)]
+    old_pool_ata_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), new_pool.fee.to_le_bytes().as_ref()],
+        bump = new_pool.bump,
+        constraint = new_pool.key() != old_pool.key() @ AmmError::InvalidMigrationTarget,
+        constraint = !new_pool.locked @ AmmError::PoolLocked,
+        constraint = new_pool.status == PoolStatus::Active @ AmmError::InvalidMigrationTarget,
+        constraint = !new_pool.soulbound_lp @ AmmError::LpSoulbound,
+        constraint = !new_pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    new_pool: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    #[account(
+        mut,
+        seeds = [b"lp", new_pool.key().as_ref()],
+        bump = new_pool.lp_bump
+    )]
+    new_mint_lp: Box<Account<'info, Mint>>,
+    /// CHECK: 地址必须等于 new_pool.vault_a
+    #[account(mut, address = new_pool.vault_a)]
+    new_pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 new_pool.vault_b
+    #[account(mut, address = new_pool.vault_b)]
+    new_pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 burn 中校验 owner 和 mint
+    #[account(mut)]
+    signer_ata_old_lp: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 mint_to 中校验 mint
+    #[account(mut)]
+    signer_ata_new_lp: UncheckedAccount<'info>,
+    /// CHECK: 接收零头，由 SPL Token 程序在 transfer 中校验 mint
+    #[account(mut)]
+    signer_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 接收零头，由 SPL Token 程序在 transfer 中校验 mint
+    #[account(mut)]
+    signer_ata_b: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> MigrateLiquidity<'info> {
+    pub fn migrate_liquidity(&mut self, amount: u64, min_new_lp: u64) -> Result<()> {
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.old_pool.require_skim_protocol_fee()?;
+        self.new_pool.require_skim_protocol_fee()?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let old_reserve_a = read_token_amount(&self.old_pool_ata_a)?;
+        let old_reserve_b = read_token_amount(&self.old_pool_ata_b)?;
+        let new_reserve_a = read_token_amount(&self.new_pool_ata_a)?;
+        let new_reserve_b = read_token_amount(&self.new_pool_ata_b)?;
+
+        // 两个池子的储备量都要变化，先推进各自的价格累加器
+        self.old_pool.update_oracle(old_reserve_a, old_reserve_b, now)?;
+        self.new_pool.update_oracle(new_reserve_a, new_reserve_b, now)?;
+        self.new_pool.check_deposit_window(now)?;
+
+        // 1. 旧池子按比例取出
+        let (amount_a, amount_b) = self.old_pool.curve(now).withdraw_amounts(old_reserve_a, old_reserve_b, self.old_mint_lp.supply, amount)?;
+
+        // 2. 新池子按比例存入，空池子时全部存入
+        let (deposit_a, deposit_b, new_lp) = if self.new_mint_lp.supply == 0 {
+            (amount_a, amount_b, self.new_pool.first_deposit_lp(new_reserve_a, new_reserve_b, amount_a, amount_b)?)
+        } else {
+            let new_lp = max_proportional_lp(new_reserve_a, new_reserve_b, self.new_mint_lp.supply, amount_a, amount_b)?;
+            let (deposit_a, deposit_b) = self.new_pool.curve(now).deposit_amounts(new_reserve_a, new_reserve_b, self.new_mint_lp.supply, new_lp)?;
+            (deposit_a, deposit_b, new_lp)
+        };
+
+        require_gt!(new_lp, 0);
+
+        // Check slippage
+        require_gte!(new_lp, min_new_lp);
+
+        self.new_pool.check_deposit_cap(new_reserve_a, new_reserve_b, deposit_a, deposit_b)?;
+
+        let accounts = Burn {
+            mint: self.old_mint_lp.to_account_info(),
+            from: self.signer_ata_old_lp.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        burn(ctx, amount)?;
+
+        // 3. 旧池子签名：存入部分直接转入新池子，零头退回用户
+        self.transfer_from_old_pool(self.old_pool_ata_a.to_account_info(), self.new_pool_ata_a.to_account_info(), deposit_a)?;
+        self.transfer_from_old_pool(self.old_pool_ata_b.to_account_info(), self.new_pool_ata_b.to_account_info(), deposit_b)?;
+        self.transfer_from_old_pool(self.old_pool_ata_a.to_account_info(), self.signer_ata_a.to_account_info(), amount_a - deposit_a)?;
+        self.transfer_from_old_pool(self.old_pool_ata_b.to_account_info(), self.signer_ata_b.to_account_info(), amount_b - deposit_b)?;
+
+        // 4. 新池子签名：铸造新 LP
+        let accounts = MintTo {
+            mint: self.new_mint_lp.to_account_info(),
+            to: self.signer_ata_new_lp.to_account_info(),
+            authority: self.new_pool.to_account_info(),
+        };
+
+        let pool_seeds = self.new_pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, new_lp)?;
+
+        // 两个池子各自发出事件：旧池子记一次取出，新池子记一次存入
+        emit!(LiquidityWithdrawn {
+            pool: self.old_pool.key(),
+            event_seq: self.old_pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp: amount,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        emit!(LiquidityDeposited {
+            pool: self.new_pool.key(),
+            event_seq: self.new_pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp: new_lp,
+            amount_a: deposit_a,
+            amount_b: deposit_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// 旧池子 PDA 签名转出代币
+    fn transfer_from_old_pool(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.old_pool.to_account_info(),
+        };
+
+        let pool_seeds = self.old_pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
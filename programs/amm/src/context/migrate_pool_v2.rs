@@ -0,0 +1,241 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
+
+use crate::{error::AmmError, state::{AmmConfig, CurveType, FeeDestinationMode, Pool, PoolStatus, PoolV1, PoolV2, ProtocolFeeMode, POOL_VERSION}};
+
+// ========================================
+// Pool 账户布局迁移（v1 / v2 -> 当前版本）
+// ========================================
+//
+// 旧布局的池子直接按当前布局反序列化会失败，或者读出错位的数据。
+// migrate_pool_v2 无需权限，任何人付租金都可以迁移：
+// 1. 按（版本, 数据长度）识别旧布局，见 LegacyPool；识别不了的账户一律拒绝，不做猜测
+// 2. payer 补足租金，resize 到当前布局的大小
+// 3. 创建旧布局没有的创建者手续费账户（v2 已有时只校验）
+// 4. 按当前布局写回数据，旧字段原样保留，新字段取与 initialize 相同的默认值
+//
+// v1 池子没有记录创建者，管理员和创建者都设置为协议管理员，之后可以再转交。
+// 指令名沿用最早的 v1 -> v2 迁移，目标始终是 POOL_VERSION。
+
+/// migrate_pool_v2 能够读取的旧布局，新增版本时在这里加一个变体，并在 LegacyPool::read 中登记
+///
+/// 带观测数组的布局很大，放在堆上，避免占用 BPF 4KB 的栈帧
+enum LegacyPool {
+    V1(PoolV1),
+    V2(Box<PoolV2>),
+}
+
+impl LegacyPool {
+    /// v1 没有 version 字段，只能按长度识别；之后的版本要求 version 字节和长度同时匹配
+    fn read(data: &[u8]) -> Result<Self> {
+        require!(data.starts_with(Pool::DISCRIMINATOR), AmmError::InvalidPoolVersion);
+        let mut body = &data[Pool::DISCRIMINATOR.len()..];
+        match (body.len(), body.first()) {
+            (len, _) if len == PoolV1::INIT_SPACE => Ok(Self::V1(PoolV1::deserialize(&mut body)?)),
+            (len, Some(2)) if len == PoolV2::INIT_SPACE => Ok(Self::V2(Box::new(PoolV2::deserialize(&mut body)?))),
+            _ => err!(AmmError::InvalidPoolVersion),
+        }
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(fee: u16)]
+pub struct MigratePoolV2<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    /// CHECK: 旧布局无法用 Account<Pool> 反序列化，通过种子和 owner 校验，数据在指令中手动解析
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), fee.to_le_bytes().as_ref()],
+        bump
+    )]
+    pool: UncheckedAccount<'info>,
+    #[account(
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = mint_a,
+        token::authority = pool,
+        seeds = [b"creator_fee", pool.key().as_ref(), mint_a.key().as_ref()],
+        bump
+    )]
+    creator_fee_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = mint_b,
+        token::authority = pool,
+        seeds = [b"creator_fee", pool.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    creator_fee_vault_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePoolV2<'info> {
+    pub fn migrate_pool_v2(&mut self, fee: u16) -> Result<()> {
+        let pool_info = self.pool.to_account_info();
+
+        // ==========================================
+        // 1. 识别并读取旧数据
+        // ==========================================
+        let legacy = LegacyPool::read(&pool_info.try_borrow_data()?)?;
+        let (mint_a, mint_b, legacy_fee, bump, lp_bump) = match &legacy {
+            LegacyPool::V1(v1) => (v1.mint_a, v1.mint_b, v1.fee, v1.bump, v1.lp_bump),
+            LegacyPool::V2(v2) => (v2.mint_a, v2.mint_b, v2.fee, v2.bump, v2.lp_bump),
+        };
+
+        // 种子已经保证了地址，这里再确认旧数据与种子一致
+        require_keys_eq!(mint_a, self.mint_a.key(), AmmError::MintAMismatch);
+        require_keys_eq!(mint_b, self.mint_b.key(), AmmError::MintBMismatch);
+        require_eq!(legacy_fee, fee, AmmError::InvalidFee);
+
+        // ==========================================
+        // 2. 补足租金并扩容
+        // ==========================================
+        let new_len = Pool::DISCRIMINATOR.len() + Pool::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(pool_info.lamports());
+        if rent > 0 {
+            let accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: pool_info.clone(),
+            };
+
+            let ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, rent)?;
+        }
+
+        pool_info.resize(new_len)?;
+
+        // ==========================================
+        // 3. 按当前布局写回
+        // ==========================================
+        let mut pool = Pool {
+            version: POOL_VERSION,
+            authority: self.config.admin,
+            mint_a,
+            mint_b,
+            vault_a: self.pool_ata_a.key(),
+            vault_b: self.pool_ata_b.key(),
+            decimals_a: self.mint_a.decimals,
+            decimals_b: self.mint_b.decimals,
+            has_freezable_mint: self.mint_a.freeze_authority.is_some() || self.mint_b.freeze_authority.is_some(),
+            fee,
+            initial_price_numerator: 0,
+            initial_price_denominator: 0,
+            start_ts: 0,
+            end_ts: 0,
+            creator: self.config.admin,
+            pending_creator: Pubkey::default(),
+            creator_fee_bps: 0,
+            creator_fee_vault_a: self.creator_fee_vault_a.key(),
+            creator_fee_vault_b: self.creator_fee_vault_b.key(),
+            protocol_fee_bps: 0,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            protocol_fee_mode: ProtocolFeeMode::Skim,
+            k_last: 0,
+            treasury_lp: Pubkey::default(),
+            bump,
+            lp_bump,
+            max_price_impact_bps: 0,
+            min_trade_amount: 0,
+            circuit_breaker_bps: 0,
+            cpi_restricted: false,
+            disable_swap_a_to_b: false,
+            disable_swap_b_to_a: false,
+            locked: false,
+            status: PoolStatus::Active,
+            emergency_withdraw_enabled: false,
+            event_seq: 0,
+            max_reserve_a: 0,
+            max_reserve_b: 0,
+            rate_limit_window_slots: 0,
+            max_out_a_per_window: 0,
+            max_out_b_per_window: 0,
+            curve_type: CurveType::ConstantProduct,
+            amp: 0,
+            lbp_start_weight_a: 0,
+            lbp_end_weight_a: 0,
+            lbp_start_ts: 0,
+            lbp_end_ts: 0,
+            last_update_ts: Clock::get()?.unix_timestamp, // 累加器从迁移时开始计时
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+            observation_index: 0,
+            observations: Default::default(),
+            jit_protection: false,
+            swap_hook_program: Pubkey::default(),
+            swap_hook_accounts: 0,
+            soulbound_lp: false,
+            external_oracle: Pubkey::default(),
+            external_oracle_band_bps: 0,
+            external_oracle_max_age: 0,
+            fee_destination_mode: FeeDestinationMode::Lp,
+            trade_gate_collection: Pubkey::default(),
+            round_trip_window_slots: 0,
+            round_trip_fee_bps: 0,
+            position_whitelist_root: [0; 32],
+            max_drain_bps: 0,
+            protocol_fees_to_farm: false,
+            last_buyback_ts: 0,
+        };
+
+        if let LegacyPool::V2(v2) = legacy {
+            require_keys_eq!(v2.vault_a, self.pool_ata_a.key(), AmmError::InvalidPoolVersion);
+            require_keys_eq!(v2.vault_b, self.pool_ata_b.key(), AmmError::InvalidPoolVersion);
+            require_keys_eq!(v2.creator_fee_vault_a, self.creator_fee_vault_a.key(), AmmError::InvalidPoolVersion);
+            require_keys_eq!(v2.creator_fee_vault_b, self.creator_fee_vault_b.key(), AmmError::InvalidPoolVersion);
+
+            pool.authority = v2.authority;
+            pool.creator = v2.creator;
+            pool.creator_fee_bps = v2.creator_fee_bps;
+            pool.max_price_impact_bps = v2.max_price_impact_bps;
+            pool.circuit_breaker_bps = v2.circuit_breaker_bps;
+            pool.cpi_restricted = v2.cpi_restricted;
+            pool.status = v2.status;
+            pool.rate_limit_window_slots = v2.rate_limit_window_slots;
+            pool.max_out_a_per_window = v2.max_out_a_per_window;
+            pool.max_out_b_per_window = v2.max_out_b_per_window;
+            if v2.lbp_enabled {
+                pool.curve_type = CurveType::Weighted;
+            }
+            pool.lbp_start_weight_a = v2.lbp_start_weight_a;
+            pool.lbp_end_weight_a = v2.lbp_end_weight_a;
+            pool.lbp_start_ts = v2.lbp_start_ts;
+            pool.lbp_end_ts = v2.lbp_end_ts;
+            pool.last_update_ts = v2.last_update_ts;
+            pool.price_a_cumulative = v2.price_a_cumulative;
+            pool.price_b_cumulative = v2.price_b_cumulative;
+            pool.observation_index = v2.observation_index;
+            pool.observations = v2.observations;
+        }
+
+        // try_serialize 会连同 discriminator 一起写入
+        let mut data = pool_info.try_borrow_mut_data()?;
+        pool.try_serialize(&mut &mut data[..])
+    }
+}
@@ -8,4 +8,134 @@ pub mod withdraw;
 pub use withdraw::*;
 
 pub mod swap;
-pub use swap::*;
\ No newline at end of file
+pub use swap::*;
+
+pub mod crank;
+pub use crank::*;
+
+pub mod admin;
+pub use admin::*;
+
+pub mod donate;
+pub use donate::*;
+
+pub mod create_lp_metadata;
+pub use create_lp_metadata::*;
+
+pub mod initialize_config;
+pub use initialize_config::*;
+
+pub mod lbp;
+pub use lbp::*;
+
+pub mod stable_swap;
+pub use stable_swap::*;
+
+pub mod update_price_feed;
+pub use update_price_feed::*;
+
+pub mod flash_swap;
+pub use flash_swap::*;
+
+pub mod trader_state;
+pub use trader_state::*;
+
+pub mod auto_vault;
+pub use auto_vault::*;
+
+#[cfg(feature = "orders")]
+pub mod dca;
+#[cfg(feature = "orders")]
+pub use dca::*;
+
+pub mod creator_fee;
+pub use creator_fee::*;
+
+pub mod migrate_liquidity;
+pub use migrate_liquidity::*;
+
+pub mod migrate_pool_v2;
+pub use migrate_pool_v2::*;
+
+pub mod migrate_config;
+pub use migrate_config::*;
+
+pub mod delegated_deposit;
+pub use delegated_deposit::*;
+
+pub mod buyback;
+pub use buyback::*;
+
+pub mod snapshot;
+pub use snapshot::*;
+
+#[cfg(feature = "orders")]
+pub mod limit_order;
+#[cfg(feature = "orders")]
+pub use limit_order::*;
+
+pub mod verify_pool;
+pub use verify_pool::*;
+
+#[cfg(feature = "orders")]
+pub mod otc;
+#[cfg(feature = "orders")]
+pub use otc::*;
+
+pub mod multi_pool;
+pub use multi_pool::*;
+
+pub mod pool_stats;
+pub use pool_stats::*;
+
+pub mod rfq;
+pub use rfq::*;
+
+pub mod split_swap;
+pub use split_swap::*;
+
+pub mod token_badge;
+pub use token_badge::*;
+
+pub mod emergency_withdraw;
+pub use emergency_withdraw::*;
+
+pub mod extend_pool;
+pub use extend_pool::*;
+
+pub mod liquidity_guard;
+pub use liquidity_guard::*;
+
+pub mod pool_vaults;
+pub use pool_vaults::*;
+
+pub mod position;
+pub use position::*;
+
+pub mod rebalance;
+pub use rebalance::*;
+
+pub mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+pub mod emergency_mode;
+pub use emergency_mode::*;
+
+pub mod scheduled_withdraw;
+pub use scheduled_withdraw::*;
+
+pub mod portfolio;
+pub use portfolio::*;
+
+pub mod protocol_liquidity;
+pub use protocol_liquidity::*;
+
+#[cfg(feature = "farming")]
+pub mod farm;
+#[cfg(feature = "farming")]
+pub use farm::*;
+
+#[cfg(feature = "lite-deposit")]
+pub mod deposit_lite;
+#[cfg(feature = "lite-deposit")]
+pub use deposit_lite::*;
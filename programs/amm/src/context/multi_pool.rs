@@ -0,0 +1,397 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use anchor_spl::{associated_token::get_associated_token_address, token::{burn, mint_to, transfer, Burn, Mint, MintTo, Token, TokenAccount, Transfer}};
+
+use crate::{
+    context::initialize::is_lp_mint,
+    curve::{CurveCalculator, StableSwap},
+    error::AmmError,
+    math::{deposit_amounts_n, max_amount_limit, stable_swap_n_amount_in, stable_swap_n_d, withdraw_amounts_n, MAX_FEE_BPS},
+    state::{AmmConfig, MultiPool, PoolStatus, TokenBadge, ACCOUNT_VERSION, MAX_MULTI_POOL_TOKENS, MIN_MULTI_POOL_TOKENS},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// 多资产池（3~8 种代币）
+// ========================================
+//
+// 稳定币三池等场景下，多种代币共用一份流动性比两两组池子的资金效率高得多。
+// 多资产池使用 StableSwap N 币种不变量（math.rs），与两币种池子是完全独立的账户：
+// - 池子 PDA：["multi_pool", sha256(mints), fee]，mints 必须按地址严格升序，同一组代币和手续费只能有一个池子
+// - LP mint PDA：["multi_lp", multi_pool]
+// - 代币数量不固定，各代币账户通过 remaining_accounts 按 mints 的顺序传入
+//
+// 与两币种池子相比只保留核心功能：按比例存取流动性和精确输出 swap，
+// 手续费全部留在池子里归 LP，没有创建者/协议分成、预言机和限额。
+// 账户中也没有 event_seq，multi_deposit / multi_withdraw / multi_swap 不发出事件，索引器需要按交易回放。
+// 首次存入铸造的 LP 数量等于存入后的不变量 D。
+
+/// 池子 PDA 的种子：所有 mint 依次拼接后的 sha256
+pub fn multi_pool_seed(mints: &[Pubkey]) -> [u8; 32] {
+    let seeds: Vec<&[u8]> = mints.iter().map(|mint| mint.as_ref()).collect();
+    hashv(&seeds).to_bytes()
+}
+
+impl MultiPool {
+    pub fn tokens(&self) -> usize {
+        self.num_tokens as usize
+    }
+
+    /// 按顺序校验传入的池子代币账户并读取余额
+    fn vault_balances(&self, vaults: &[AccountInfo]) -> Result<Vec<u64>> {
+        require_eq!(vaults.len(), self.tokens(), AmmError::InvalidMultiPoolAccounts);
+
+        vaults
+            .iter()
+            .zip(&self.vaults)
+            .map(|(vault, expected)| {
+                require_keys_eq!(vault.key(), *expected, AmmError::InvalidMultiPoolAccounts);
+                read_token_amount(vault)
+            })
+            .collect()
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(fee: u16, amp: u64, mints: Vec<Pubkey>)]
+pub struct InitializeMultiPool<'info> {
+    // 池子创建者：成为池子管理员
+    signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = MultiPool::DISCRIMINATOR.len() + MultiPool::INIT_SPACE,
+        seeds = [b"multi_pool", multi_pool_seed(&mints).as_ref(), fee.to_le_bytes().as_ref()],
+        bump
+    )]
+    multi_pool: Box<Account<'info, MultiPool>>,
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = 0,
+        mint::authority = multi_pool,
+        seeds = [b"multi_lp", multi_pool.key().as_ref()],
+        bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = fee <= MAX_FEE_BPS @ AmmError::InvalidFee,
+        constraint = config.fee_tier(fee).is_some() @ AmmError::FeeTierNotAllowed,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Account<'info, AmmConfig>,
+    token_program: Program<'info, Token>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeMultiPool<'info> {
+    /// remaining_accounts：先是 N 个 mint，再是池子在每个 mint 上的关联代币账户（需要事先创建），顺序与 mints 相同；
+    /// config.require_token_badges 开启时最后再传 N 个 TokenBadge
+    pub fn initialize_multi_pool(&mut self, fee: u16, amp: u64, mints: Vec<Pubkey>, remaining_accounts: &'info [AccountInfo<'info>], bump: u8, lp_bump: u8) -> Result<()> {
+        let n = mints.len();
+        require!((MIN_MULTI_POOL_TOKENS..=MAX_MULTI_POOL_TOKENS).contains(&n), AmmError::InvalidMultiPoolTokens);
+        // 严格升序同时保证了没有重复的 mint，也让同一组代币只有一种排列
+        require!(mints.windows(2).all(|pair| pair[0] < pair[1]), AmmError::InvalidMultiPoolTokens);
+        let groups = if self.config.require_token_badges { 3 } else { 2 };
+        require_eq!(remaining_accounts.len(), n * groups, AmmError::InvalidMultiPoolAccounts);
+
+        StableSwap { amp }.validate()?;
+
+        let pool_key = self.multi_pool.key();
+        let (mint_infos, rest) = remaining_accounts.split_at(n);
+        let (vault_infos, badge_infos) = rest.split_at(n);
+        for (badge_info, mint) in badge_infos.iter().zip(&mints) {
+            TokenBadge::verify(badge_info, mint)?;
+        }
+
+        let mut pool_mints = [Pubkey::default(); MAX_MULTI_POOL_TOKENS];
+        let mut pool_vaults = [Pubkey::default(); MAX_MULTI_POOL_TOKENS];
+        let mut decimals = None;
+        for (i, (mint_info, vault_info)) in mint_infos.iter().zip(vault_infos).enumerate() {
+            require_keys_eq!(mint_info.key(), mints[i], AmmError::InvalidMultiPoolAccounts);
+
+            // 与两币种池子相同的 mint 检查
+            let mint = Account::<Mint>::try_from(mint_info)?;
+            require!(!is_lp_mint(&mint), AmmError::LpMintAsPoolSide);
+            require!(mint.freeze_authority.is_none() || self.config.allow_freezable_mints, AmmError::MintHasFreezeAuthority);
+            require_gt!(mint.supply, 0, AmmError::ZeroSupplyMint);
+            require_eq!(*decimals.get_or_insert(mint.decimals), mint.decimals, AmmError::MultiPoolDecimalsMismatch);
+
+            require_keys_eq!(vault_info.key(), get_associated_token_address(&pool_key, &mints[i]), AmmError::InvalidMultiPoolAccounts);
+            Account::<TokenAccount>::try_from(vault_info)?;
+
+            pool_mints[i] = mints[i];
+            pool_vaults[i] = vault_info.key();
+        }
+
+        self.multi_pool.set_inner(MultiPool {
+            version: ACCOUNT_VERSION,
+            authority: self.signer.key(),
+            mints_hash: multi_pool_seed(&mints),
+            num_tokens: n as u8,
+            mints: pool_mints,
+            vaults: pool_vaults,
+            decimals: decimals.unwrap_or_default(),
+            fee,
+            amp,
+            status: PoolStatus::Active,
+            bump,
+            lp_bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct MultiDeposit<'info> {
+    signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"multi_lp", multi_pool.key().as_ref()],
+        bump = multi_pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = mint_lp,
+        token::authority = signer
+    )]
+    signer_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"multi_pool", multi_pool.mints_hash.as_ref(), multi_pool.fee.to_le_bytes().as_ref()],
+        bump = multi_pool.bump,
+        constraint = multi_pool.status == PoolStatus::Active @ AmmError::PoolNotActive
+    )]
+    multi_pool: Box<Account<'info, MultiPool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> MultiDeposit<'info> {
+    /// remaining_accounts：先是用户的 N 个代币账户，再是池子的 N 个代币账户，顺序与 mints 相同
+    ///
+    /// 空池子首次存入时按 max_amounts 全额存入，每种代币都必须大于 0，铸造 D 个 LP
+    pub fn multi_deposit(&mut self, amount_lp: u64, max_amounts: Vec<u64>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let n = self.multi_pool.tokens();
+        require_eq!(remaining_accounts.len(), n * 2, AmmError::InvalidMultiPoolAccounts);
+        require_eq!(max_amounts.len(), n, AmmError::InvalidMultiPoolAccounts);
+
+        let (user_atas, vaults) = remaining_accounts.split_at(n);
+        let balances = self.multi_pool.vault_balances(vaults)?;
+
+        let (amounts, amount_lp) = if self.mint_lp.supply == 0 {
+            require!(max_amounts.iter().all(|&amount| amount > 0), AmmError::ZeroAmount);
+            let d = stable_swap_n_d(self.multi_pool.amp, &max_amounts)?;
+            (max_amounts, u64::try_from(d).map_err(|_| ProgramError::ArithmeticOverflow)?)
+        } else {
+            require_gt!(amount_lp, 0, AmmError::ZeroAmount);
+
+            // 按 LP 总供应量等比例存入，向上取整
+            let amounts = deposit_amounts_n(&balances, self.mint_lp.supply, amount_lp)?;
+            for (amount, max_amount) in amounts.iter().zip(&max_amounts) {
+                require_gte!(max_amount_limit(*max_amount), *amount);
+            }
+            (amounts, amount_lp)
+        };
+
+        // 用户账户的 mint 由 SPL Token 程序检查，必须与池子账户相同
+        for ((user_ata, vault), amount) in user_atas.iter().zip(vaults).zip(amounts) {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from: user_ata.clone(),
+                to: vault.clone(),
+                authority: self.signer.to_account_info(),
+            };
+
+            let ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, amount)?;
+        }
+
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.signer_ata_lp.to_account_info(),
+            authority: self.multi_pool.to_account_info(),
+        };
+
+        let multi_pool_seeds = self.multi_pool.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&multi_pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount_lp)
+    }
+}
+
+#[derive(Accounts)]
+pub struct MultiWithdraw<'info> {
+    signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"multi_lp", multi_pool.key().as_ref()],
+        bump = multi_pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = mint_lp,
+        token::authority = signer
+    )]
+    signer_ata_lp: Box<Account<'info, TokenAccount>>,
+    // 与两币种池子相同，WithdrawOnly 状态下仍然可以取出
+    #[account(
+        seeds = [b"multi_pool", multi_pool.mints_hash.as_ref(), multi_pool.fee.to_le_bytes().as_ref()],
+        bump = multi_pool.bump,
+        constraint = multi_pool.status != PoolStatus::Paused @ AmmError::PoolNotActive
+    )]
+    multi_pool: Box<Account<'info, MultiPool>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> MultiWithdraw<'info> {
+    /// remaining_accounts：先是用户的 N 个代币账户，再是池子的 N 个代币账户，顺序与 mints 相同
+    pub fn multi_withdraw(&mut self, amount_lp: u64, min_amounts: Vec<u64>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let n = self.multi_pool.tokens();
+        require_eq!(remaining_accounts.len(), n * 2, AmmError::InvalidMultiPoolAccounts);
+        require_eq!(min_amounts.len(), n, AmmError::InvalidMultiPoolAccounts);
+
+        let (user_atas, vaults) = remaining_accounts.split_at(n);
+        let balances = self.multi_pool.vault_balances(vaults)?;
+
+        // 按比例取出，向下取整
+        let amounts = withdraw_amounts_n(&balances, self.mint_lp.supply, amount_lp)?;
+        for (amount, min_amount) in amounts.iter().zip(&min_amounts) {
+            require_gte!(*amount, *min_amount);
+        }
+
+        let accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.signer_ata_lp.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        burn(ctx, amount_lp)?;
+
+        let multi_pool_seeds = self.multi_pool.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&multi_pool_seeds.as_seeds()];
+
+        for ((user_ata, vault), amount) in user_atas.iter().zip(vaults).zip(amounts) {
+            if amount == 0 {
+                continue;
+            }
+
+            let accounts = Transfer {
+                from: vault.clone(),
+                to: user_ata.clone(),
+                authority: self.multi_pool.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, amount)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct MultiSwap<'info> {
+    signer: Signer<'info>,
+    /// CHECK: 用户付出代币的账户，mint 由 SPL Token 程序检查，必须与池子的输入账户相同
+    #[account(mut)]
+    signer_ata_in: UncheckedAccount<'info>,
+    /// CHECK: 用户接收代币的账户，mint 由 SPL Token 程序检查，必须与池子的输出账户相同
+    #[account(mut)]
+    signer_ata_out: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"multi_pool", multi_pool.mints_hash.as_ref(), multi_pool.fee.to_le_bytes().as_ref()],
+        bump = multi_pool.bump,
+        constraint = multi_pool.status == PoolStatus::Active @ AmmError::PoolNotActive
+    )]
+    multi_pool: Box<Account<'info, MultiPool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> MultiSwap<'info> {
+    /// remaining_accounts：池子的 N 个代币账户，顺序与 mints 相同，计算不变量需要全部余额
+    ///
+    /// index_in / index_out 是代币在 mints 中的下标；与两币种 swap 相同，精确输出，付出不超过 max_amount_in（含手续费）
+    pub fn multi_swap(&mut self, index_in: u8, index_out: u8, amount_out: u64, max_amount_in: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (index_in, index_out) = (index_in as usize, index_out as usize);
+        let n = self.multi_pool.tokens();
+        require!(index_in < n && index_out < n && index_in != index_out, AmmError::InvalidMultiPoolAccounts);
+        require_gt!(amount_out, 0, AmmError::ZeroAmount);
+
+        let balances = self.multi_pool.vault_balances(remaining_accounts)?;
+
+        let (_, amount_in_with_fees) = stable_swap_n_amount_in(self.multi_pool.amp, &balances, index_in, index_out, amount_out, self.multi_pool.fee)?;
+
+        // Check slippage
+        require_gte!(max_amount_limit(max_amount_in), amount_in_with_fees);
+
+        let accounts = Transfer {
+            from: self.signer_ata_in.to_account_info(),
+            to: remaining_accounts[index_in].clone(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount_in_with_fees)?;
+
+        let accounts = Transfer {
+            from: remaining_accounts[index_out].clone(),
+            to: self.signer_ata_out.to_account_info(),
+            authority: self.multi_pool.to_account_info(),
+        };
+
+        let multi_pool_seeds = self.multi_pool.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&multi_pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount_out)
+    }
+}
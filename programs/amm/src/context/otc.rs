@@ -0,0 +1,323 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{close_account, transfer, CloseAccount, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{
+    error::AmmError,
+    events::OtcOfferAccepted,
+    math::{mul_div_ceil, BPS_DENOMINATOR},
+    state::{AmmConfig, OtcOffer, Pool, PoolStatus, ACCOUNT_VERSION},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// OTC 报价（以池子 TWAP 为参考价格）
+// ========================================
+//
+// 大额交易直接走曲线价格冲击太大，可以改成点对点成交：
+// 1. create_otc_offer：maker 把要卖出的代币存入报价 PDA 的托管 ATA，
+//    约定成交价 = 池子 TWAP × (10000 + spread_bps) / 10000
+// 2. accept_otc_offer：taker 按成交时的 TWAP 付款给 maker，取走全部托管代币
+// 3. cancel_otc_offer：maker 取回托管代币并关闭报价
+//
+// 成交不经过曲线，不改变储备量，也不收取池子手续费。
+// TWAP 覆盖最近若干个观测（约 OBSERVATION_CAPACITY × OBSERVATION_INTERVAL 秒），
+// 单笔交易推动现价不会明显改变成交价；maker 另外用 min_amount_out 限定最低收款，
+// taker 用 max_payment 限定最高付款。付款向上取整，舍入误差归 maker。
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateOtcOffer<'info> {
+    #[account(mut)]
+    maker: Signer<'info>,
+    mint_in: Box<Account<'info, Mint>>,
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = maker,
+        space = OtcOffer::DISCRIMINATOR.len() + OtcOffer::INIT_SPACE,
+        seeds = [b"otc_offer", pool.key().as_ref(), maker.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    otc_offer: Account<'info, OtcOffer>,
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_in,
+        associated_token::authority = otc_offer
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = maker
+    )]
+    maker_ata_in: Box<Account<'info, TokenAccount>>,
+    destination: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CreateOtcOffer<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_otc_offer(&mut self, id: u64, amount: u64, spread_bps: i16, min_amount_out: u64, taker: Pubkey, expires_at: i64, is_a: bool, bump: u8) -> Result<()> {
+        // maker 托管的是卖出的一侧
+        let (mint_in, mint_out) = if is_a {
+            (self.pool.mint_b, self.pool.mint_a)
+        } else {
+            (self.pool.mint_a, self.pool.mint_b)
+        };
+        require_keys_eq!(self.mint_in.key(), mint_in, AmmError::InvalidOtcOffer);
+        require_keys_eq!(self.destination.mint, mint_out, AmmError::InvalidOtcOffer);
+
+        require!(amount > 0 && min_amount_out > 0, AmmError::InvalidOtcOffer);
+        require!((spread_bps.unsigned_abs() as u128) < BPS_DENOMINATOR, AmmError::InvalidOtcOffer);
+        require!(expires_at == 0 || expires_at > Clock::get()?.unix_timestamp, AmmError::InvalidOtcOffer);
+
+        let accounts = Transfer {
+            from: self.maker_ata_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount)?;
+
+        self.otc_offer.set_inner(OtcOffer {
+            version: ACCOUNT_VERSION,
+            maker: self.maker.key(),
+            pool: self.pool.key(),
+            id,
+            is_a,
+            escrow: self.escrow.key(),
+            destination: self.destination.key(),
+            amount,
+            spread_bps,
+            min_amount_out,
+            taker,
+            expires_at,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptOtcOffer<'info> {
+    #[account(
+        constraint = otc_offer.taker == Pubkey::default() || otc_offer.taker == taker.key() @ AmmError::InvalidAuthority
+    )]
+    taker: Signer<'info>,
+    /// CHECK: 地址必须等于 otc_offer.maker，接收报价账户和托管 ATA 的租金
+    #[account(mut)]
+    maker: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    // 可写是为了推进价格累加器，成交价使用包含当前时刻的 TWAP
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive
+    )]
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    #[account(
+        mut,
+        close = maker,
+        seeds = [b"otc_offer", pool.key().as_ref(), otc_offer.maker.as_ref(), otc_offer.id.to_le_bytes().as_ref()],
+        bump = otc_offer.bump,
+        has_one = maker,
+        has_one = pool,
+        has_one = escrow,
+        has_one = destination
+    )]
+    otc_offer: Account<'info, OtcOffer>,
+    #[account(mut)]
+    escrow: Box<Account<'info, TokenAccount>>,
+    /// CHECK: 地址必须等于 otc_offer.destination
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::authority = taker
+    )]
+    taker_ata_in: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = escrow.mint
+    )]
+    taker_ata_out: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> AcceptOtcOffer<'info> {
+    /// max_payment: taker 最多付给 maker 的代币
+    pub fn accept_otc_offer(&mut self, max_payment: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.otc_offer.expires_at == 0 || now <= self.otc_offer.expires_at, AmmError::OtcOfferExpired);
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let twap = match self.pool.twap_a() {
+            Some(twap) if twap > 0 => twap,
+            _ => return err!(AmmError::TwapUnavailable),
+        };
+
+        let amount = self.otc_offer.amount;
+        let spread = (BPS_DENOMINATOR as i128 + self.otc_offer.spread_bps as i128) as u128;
+
+        // twap 是每单位 TokenA 值多少 TokenB（Q64.64）
+        let payment = if self.otc_offer.is_a {
+            // maker 卖出 TokenB，收取 TokenA：amount / twap
+            let denominator = twap.checked_mul(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?;
+            mul_div_ceil((amount as u128) << 64, spread, denominator)
+        } else {
+            // maker 卖出 TokenA，收取 TokenB：amount × twap
+            let numerator = twap.checked_mul(spread).ok_or(ProgramError::ArithmeticOverflow)?;
+            mul_div_ceil(amount as u128, numerator, BPS_DENOMINATOR << 64)
+        }.ok_or(ProgramError::ArithmeticOverflow)?;
+        let payment = u64::try_from(payment).map_err(|_| ProgramError::ArithmeticOverflow)?;
+
+        require_gt!(payment, 0, AmmError::ZeroAmount);
+        require_gte!(payment, self.otc_offer.min_amount_out, AmmError::LimitPriceNotMet);
+        require_gte!(max_payment, payment);
+
+        let accounts = Transfer {
+            from: self.taker_ata_in.to_account_info(),
+            to: self.destination.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, payment)?;
+
+        let offer_seeds = self.otc_offer.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&offer_seeds.as_seeds()];
+
+        // 托管中多出的代币（有人直接转入）一并给 taker，之后才能关闭托管 ATA
+        let accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.taker_ata_out.to_account_info(),
+            authority: self.otc_offer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, self.escrow.amount)?;
+
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.otc_offer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        close_account(ctx)?;
+
+        emit!(OtcOfferAccepted {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            offer: self.otc_offer.key(),
+            maker: self.otc_offer.maker,
+            taker: self.taker.key(),
+            amount,
+            payment,
+            twap,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelOtcOffer<'info> {
+    #[account(mut)]
+    maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        seeds = [b"otc_offer", otc_offer.pool.as_ref(), maker.key().as_ref(), otc_offer.id.to_le_bytes().as_ref()],
+        bump = otc_offer.bump,
+        has_one = maker,
+        has_one = escrow
+    )]
+    otc_offer: Account<'info, OtcOffer>,
+    #[account(mut)]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = escrow.mint,
+        token::authority = maker
+    )]
+    maker_ata_in: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelOtcOffer<'info> {
+    /// 取回托管代币并关闭报价，过期的报价同样用它回收
+    pub fn cancel_otc_offer(&mut self) -> Result<()> {
+        let offer_seeds = self.otc_offer.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&offer_seeds.as_seeds()];
+
+        if self.escrow.amount > 0 {
+            let accounts = Transfer {
+                from: self.escrow.to_account_info(),
+                to: self.maker_ata_in.to_account_info(),
+                authority: self.otc_offer.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, self.escrow.amount)?;
+        }
+
+        // 关闭托管 ATA，租金退还给 maker；报价账户由 close = maker 关闭
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.otc_offer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        close_account(ctx)
+    }
+}
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Pool, PoolStats, VolumeBucket, ACCOUNT_VERSION, STATS_BUCKET_COUNT, STATS_BUCKET_SECONDS};
+
+// ========================================
+// 池子成交统计（24 小时滚动窗口）
+// ========================================
+//
+// 任何人都可以为池子调用一次 init_pool_stats 创建 PoolStats 账户（PDA：["pool_stats", pool]）。
+// 之后 swap / swap_by_mint / flash_swap / execute_dca / fill_limit_order 传入这个账户时，
+// 成交量和手续费记入当前小时的桶；crank 传入时只滚动桶，长时间没有交易的池子账户里也不会留着过期的桶。
+//
+// 桶按 hour % STATS_BUCKET_COUNT 复用，每次写入前清空已经滚出窗口的桶（roll）。
+// 读取时 rolling_window 仍然按 hour 过滤，两次 roll 之间读取也不会计入过期数据，
+// UI、其他程序（以及以后的动态手续费）不需要索引器就能拿到 24 小时成交量和手续费 APR 的分子。
+//
+// 成交量按池子两侧的实际流入、流出计：买入方向付出的含手续费输入和拿走的输出都计入，
+// 手续费是含手续费输入与不含手续费输入之差，包括创建者和协议分成。
+// 账户是可选的，没有创建或没有传入时不影响交易本身。
+
+#[derive(Accounts)]
+pub struct InitPoolStats<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<PoolStats>(),
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump
+    )]
+    pool_stats: AccountLoader<'info, PoolStats>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitPoolStats<'info> {
+    pub fn init_pool_stats(&mut self, bump: u8) -> Result<()> {
+        let mut pool_stats = self.pool_stats.load_init()?;
+        pool_stats.version = ACCOUNT_VERSION;
+        pool_stats.bump = bump;
+        pool_stats.pool = self.pool.key();
+        Ok(())
+    }
+}
+
+/// now 所在的小时编号
+fn hour_of(now: i64) -> i64 {
+    now.div_euclid(STATS_BUCKET_SECONDS)
+}
+
+impl PoolStats {
+    /// 清空滚出窗口的桶，返回当前小时的桶
+    ///
+    /// 池子可能几个小时没有交易，所以每次都检查全部桶，而不只是当前小时对应的那一个
+    pub fn roll(&mut self, now: i64) -> &mut VolumeBucket {
+        let hour = hour_of(now);
+        let oldest = hour - STATS_BUCKET_COUNT as i64 + 1;
+        for bucket in self.buckets.iter_mut().filter(|bucket| bucket.hour < oldest) {
+            *bucket = VolumeBucket::default();
+        }
+
+        let bucket = &mut self.buckets[hour.rem_euclid(STATS_BUCKET_COUNT as i64) as usize];
+        if bucket.hour != hour {
+            *bucket = VolumeBucket { hour, ..Default::default() };
+        }
+        bucket
+    }
+
+    /// 记录一笔 swap：is_a 表示买入 TokenA（付出 TokenB）
+    pub fn record_swap(&mut self, is_a: bool, amount_in_with_fees: u64, amount_out: u64, fee: u64, now: i64) -> Result<()> {
+        let (volume_a, volume_b, fees_a, fees_b) = if is_a {
+            (amount_out, amount_in_with_fees, 0, fee)
+        } else {
+            (amount_in_with_fees, amount_out, fee, 0)
+        };
+
+        // 统计只用于展示和参考，累计值溢出时饱和，不让统计阻塞交易
+        let bucket = self.roll(now);
+        bucket.volume_a = bucket.volume_a.saturating_add(volume_a);
+        bucket.volume_b = bucket.volume_b.saturating_add(volume_b);
+        bucket.fees_a = bucket.fees_a.saturating_add(fees_a);
+        bucket.fees_b = bucket.fees_b.saturating_add(fees_b);
+
+        self.swap_count = self.swap_count.saturating_add(1);
+        self.total_volume_a = self.total_volume_a.saturating_add(volume_a);
+        self.total_volume_b = self.total_volume_b.saturating_add(volume_b);
+        self.total_fees_a = self.total_fees_a.saturating_add(fees_a);
+        self.total_fees_b = self.total_fees_b.saturating_add(fees_b);
+        Ok(())
+    }
+
+    /// 截至 now 的最近 STATS_BUCKET_COUNT 个小时（含当前小时）的合计，过期的桶不计入
+    pub fn rolling_window(&self, now: i64) -> VolumeBucket {
+        let hour = hour_of(now);
+        let oldest = hour - STATS_BUCKET_COUNT as i64 + 1;
+        self.buckets
+            .iter()
+            .filter(|bucket| bucket.hour >= oldest && bucket.hour <= hour)
+            .fold(VolumeBucket { hour, ..Default::default() }, |mut sum, bucket| {
+                sum.volume_a = sum.volume_a.saturating_add(bucket.volume_a);
+                sum.volume_b = sum.volume_b.saturating_add(bucket.volume_b);
+                sum.fees_a = sum.fees_a.saturating_add(bucket.fees_a);
+                sum.fees_b = sum.fees_b.saturating_add(bucket.fees_b);
+                sum
+            })
+    }
+}
+
+/// 可选的 PoolStats 账户存在时记录一笔 swap，各个 swap 类指令共用
+pub fn record_swap_stats(pool_stats: Option<&AccountLoader<PoolStats>>, is_a: bool, amount_in_with_fees: u64, amount_out: u64, fee: u64, now: i64) -> Result<()> {
+    match pool_stats {
+        Some(pool_stats) => pool_stats.load_mut()?.record_swap(is_a, amount_in_with_fees, amount_out, fee, now),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Pool;
+
+// ========================================
+// 账户解析：只凭 (pool, user) 拼出 swap 的账户列表
+// ========================================
+//
+// 钱包和路由器拿到的通常只有池子地址和用户地址，swap 的其余账户按下面的规则得到：
+// - mint_a / mint_b、creator_fee_vault_a / creator_fee_vault_b：读取 pool 账户的同名字段，
+//   swap 对它们使用 has_one 约束，IDL 中带有 relations: ["pool"]，Anchor 客户端会自动填充
+// - pool_ata_a / pool_ata_b：pool.vault_a / pool.vault_b，
+//   即 pool 在 mint_a / mint_b 上的关联代币账户（verify_pool 会检查这一点）
+// - signer_ata_a / signer_ata_b：用户在 mint_a / mint_b 上的代币账户，通常是关联代币账户
+// - instructions / token_program / associated_token_program / system_program：IDL 中是固定地址
+//
+// swap 为了省计算量只按 Pool 中记录的地址校验 pool_ata_a / pool_ata_b，不在链上推导 PDA，
+// 所以这两个账户在 IDL 里没有 seeds。不想自己推导的客户端可以先模拟 get_pool_vaults，
+// 从 return data 中一次拿到池子相关的全部地址。
+
+/// get_pool_vaults 的返回值，账户名与 swap 的账户一一对应
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PoolVaults {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub mint_lp: Pubkey,
+    pub pool_ata_a: Pubkey,
+    pub pool_ata_b: Pubkey,
+    pub creator_fee_vault_a: Pubkey,
+    pub creator_fee_vault_b: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolVaults<'info> {
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> GetPoolVaults<'info> {
+    pub fn get_pool_vaults(&self) -> Result<PoolVaults> {
+        let pool_key = self.pool.key();
+        let mint_lp = Pubkey::create_program_address(&self.pool.lp_mint_signer_seeds(&pool_key).as_seeds(), &crate::ID)
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        Ok(PoolVaults {
+            mint_a: self.pool.mint_a,
+            mint_b: self.pool.mint_b,
+            mint_lp,
+            pool_ata_a: self.pool.vault_a,
+            pool_ata_b: self.pool.vault_b,
+            creator_fee_vault_a: self.pool.creator_fee_vault_a,
+            creator_fee_vault_b: self.pool.creator_fee_vault_b,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, state::{UserPortfolio, ACCOUNT_VERSION, MAX_PORTFOLIO_POOLS}};
+
+// ========================================
+// 多池子 LP 仓位汇总
+// ========================================
+//
+// 钱包想展示「你的流动性仓位」时，原本需要扫描所有池子的 LP mint 查找用户的余额。
+// 用户可以调用一次 init_portfolio 创建自己的 UserPortfolio，
+// 之后在 deposit / withdraw 时把它作为 portfolio 传入：
+// - 存入时，池子还不在列表中就追加
+// - 取出之后 LP 余额为 0 时，把池子从列表中移除
+// 不传 portfolio 的存取不会更新列表。LP 转账、delegated_deposit、分期取出等其他路径也不会更新，
+// 所以列表只是展示用的索引，仓位的实际数量仍然以 LP 余额为准。
+
+#[derive(Accounts)]
+pub struct InitPortfolio<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    #[account(
+        init,
+        payer = signer,
+        space = UserPortfolio::DISCRIMINATOR.len() + UserPortfolio::INIT_SPACE,
+        seeds = [b"portfolio", signer.key().as_ref()],
+        bump
+    )]
+    portfolio: Account<'info, UserPortfolio>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitPortfolio<'info> {
+    pub fn init_portfolio(&mut self, bump: u8) -> Result<()> {
+        self.portfolio.set_inner(UserPortfolio {
+            version: ACCOUNT_VERSION,
+            owner: self.signer.key(),
+            pools: Vec::new(),
+            bump,
+        });
+        Ok(())
+    }
+}
+
+impl UserPortfolio {
+    /// 存入流动性时调用：池子还不在列表中就追加，列表已满时报错
+    pub fn record_deposit(portfolio: Option<&mut Self>, pool: Pubkey) -> Result<()> {
+        let Some(portfolio) = portfolio else {
+            return Ok(());
+        };
+        if portfolio.pools.contains(&pool) {
+            return Ok(());
+        }
+        require_gt!(MAX_PORTFOLIO_POOLS, portfolio.pools.len(), AmmError::PortfolioFull);
+        portfolio.pools.push(pool);
+        Ok(())
+    }
+
+    /// 取出流动性时调用：取出之后 LP 余额为 0 就把池子从列表中移除
+    pub fn record_withdraw(portfolio: Option<&mut Self>, pool: Pubkey, lp_remaining: u64) {
+        if let Some(portfolio) = portfolio {
+            if lp_remaining == 0 {
+                portfolio.pools.retain(|p| *p != pool);
+            }
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use anchor_spl::{associated_token::AssociatedToken, token::{burn, mint_to, transfer, Burn, Mint, MintTo, Token, TokenAccount, Transfer}};
+
+use crate::{
+    context::{admin::is_pool_authority, snapshot::verify_merkle_proof},
+    error::AmmError,
+    events::{LiquidityDeposited, LiquidityWithdrawn},
+    math::max_amount_limit,
+    state::{AmmConfig, LiquidityGuard, Pool, PoolStatus, Position, PositionWhitelist, ACCOUNT_VERSION},
+};
+
+// ========================================
+// 不可自由转让的 LP（soulbound LP）
+// ========================================
+//
+// 合规受限的池子在 initialize 时传入 soulbound_lp = true：
+// - LP 代币仍然照常铸造和销毁（所有按 LP 供应量计算的逻辑都不变），
+//   但只铸造到 position_vault（pool 在 LP mint 上的关联代币账户），由池子托管
+// - 每个钱包的份额记在自己的 Position PDA 中，只能通过 deposit_position / withdraw_position 存取
+// - 仓位只能通过 transfer_position 转给池子管理员加入白名单的钱包
+// - 成员很多的池子可以改用默克尔根白名单：管理员用 set_position_whitelist_root 发布所有白名单钱包的默克尔根，
+//   transfer_position_with_proof 附上接收方的默克尔证明，不需要为每个钱包创建 PositionWhitelist PDA。
+//   两种白名单同时有效
+//
+// 默克尔根白名单是静态的：链上只保存一个 32 字节的根，不 CPI spl-account-compression，
+// 也没有并发默克尔树和叶子变更记录。增删成员时管理员在链下重建整棵树并重新发布根，
+// 旧根签出的证明随即失效；成员名单由管理员链下保存，索引器无法从链上重建。
+// 需要逐个增删、保留链上记录的场景继续用 PositionWhitelist PDA。
+// - deposit / deposit_imbalanced / delegated_deposit / migrate_liquidity 会拒绝这类池子，
+//   用户手里始终没有可以自由转让的 LP 代币
+//
+// 全局紧急模式下 deposit_position 被拒绝，withdraw_position 照常可用，与普通 LP 的 deposit / withdraw 一致。
+//
+// 与 delegated_deposit 相同，这里没有 treasury_lp 账户，只支持 Skim 模式的协议分成。
+// emergency_withdraw 按调用者持有的 LP 代币取出，对托管在 position_vault 中的仓位不可用。
+
+#[derive(Accounts)]
+pub struct InitPosition<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    #[account(constraint = pool.soulbound_lp @ AmmError::PoolNotSoulbound)]
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = signer,
+        space = Position::DISCRIMINATOR.len() + Position::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    position: Account<'info, Position>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitPosition<'info> {
+    pub fn init_position(&mut self, bump: u8) -> Result<()> {
+        self.position.set_inner(Position {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            owner: self.signer.key(),
+            liquidity: 0,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PositionDeposit<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::authority = signer,
+        associated_token::mint = mint_a
+    )]
+    signer_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = signer,
+        associated_token::mint = mint_b
+    )]
+    signer_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    // 托管所有仓位的 LP 代币，第一次存入时创建
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::authority = pool,
+        associated_token::mint = mint_lp
+    )]
+    position_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump = position.bump
+    )]
+    position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = pool.soulbound_lp @ AmmError::PoolNotSoulbound,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        mut,
+        seeds = [b"lp_guard", pool.key().as_ref(), signer.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> PositionDeposit<'info> {
+    /// 与 deposit 相同的按比例存入，LP 记入 signer 的 Position
+    pub fn deposit_position(&mut self, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.pool.require_skim_protocol_fee()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_deposit_window(now)?;
+        LiquidityGuard::record_deposit(&self.pool, self.liquidity_guard.as_deref_mut(), Clock::get()?.slot)?;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            // 首次存入：账户里已有的代币一起计入，见 Pool::first_deposit_lp
+            (max_token_a, max_token_b, self.pool.first_deposit_lp(self.pool_ata_a.amount, self.pool_ata_b.amount, max_token_a, max_token_b)?)
+        } else {
+            require_gt!(amount, 0, AmmError::ZeroAmount);
+
+            // 按 LP 总供应量等比例存入，向上取整
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
+
+            // Check slippage A
+            require_gte!(max_amount_limit(max_token_a), amount_a);
+
+            // Check slippage B
+            require_gte!(max_amount_limit(max_token_b), amount_b);
+            (amount_a, amount_b, amount)
+        };
+
+        self.pool.check_deposit_cap(self.pool_ata_a.amount, self.pool_ata_b.amount, amount_a, amount_b)?;
+
+        self.transfer_to_pool(self.signer_ata_a.to_account_info(), self.pool_ata_a.to_account_info(), amount_a)?;
+        self.transfer_to_pool(self.signer_ata_b.to_account_info(), self.pool_ata_b.to_account_info(), amount_b)?;
+
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.position_vault.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount_lp)?;
+
+        self.position.liquidity = self.position.liquidity.checked_add(amount_lp).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// 用户签名，把代币转入池子
+    fn transfer_to_pool(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct PositionWithdraw<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::authority = signer,
+        associated_token::mint = mint_a
+    )]
+    signer_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::authority = signer,
+        associated_token::mint = mint_b
+    )]
+    signer_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_a
+    )]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_b
+    )]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::authority = pool,
+        associated_token::mint = mint_lp
+    )]
+    position_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump = position.bump
+    )]
+    position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = pool.soulbound_lp @ AmmError::PoolNotSoulbound,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status != PoolStatus::Paused @ AmmError::PoolPaused,
+        constraint = !pool.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool: Box<Account<'info, Pool>>,
+    // 不传 AmmConfig：与 withdraw 相同，全局紧急模式下仓位照常可以取出，见 emergency_mode.rs
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        seeds = [b"lp_guard", pool.key().as_ref(), signer.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> PositionWithdraw<'info> {
+    /// 与 withdraw 相同的按比例取出，从 signer 的 Position 中扣除 amount 个 LP
+    pub fn withdraw_position(&mut self, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+        require_gte!(self.position.liquidity, amount, AmmError::InsufficientPositionLiquidity);
+        LiquidityGuard::check_withdraw(&self.pool, self.liquidity_guard.as_deref(), Clock::get()?.slot)?;
+
+        // 没有 treasury_lp 账户，无法结算 LP 模式的协议手续费
+        self.pool.require_skim_protocol_fee()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+
+        // 按 amount / lp_total_supply 的比例取出两种代币，向下取整
+        let (amount_a, amount_b) = self.pool.curve(now).withdraw_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
+
+        // Check slippage A
+        require_gte!(amount_a, min_token_a);
+
+        // Check slippage B
+        require_gte!(amount_b, min_token_b);
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        for (from, to, amount) in [
+            (self.pool_ata_a.to_account_info(), self.signer_ata_a.to_account_info(), amount_a),
+            (self.pool_ata_b.to_account_info(), self.signer_ata_b.to_account_info(), amount_b),
+        ] {
+            let accounts = Transfer {
+                from,
+                to,
+                authority: self.pool.to_account_info(),
+            };
+
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds
+            );
+
+            transfer(ctx, amount)?;
+        }
+
+        // 托管的 LP 由池子签名销毁
+        let accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.position_vault.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        burn(ctx, amount)?;
+
+        self.position.liquidity -= amount;
+
+        emit!(LiquidityWithdrawn {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp: amount,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct TransferPosition<'info> {
+    signer: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump = from_position.bump
+    )]
+    from_position: Account<'info, Position>,
+    // 接收方需要事先 init_position
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), to_position.owner.as_ref()],
+        bump = to_position.bump
+    )]
+    to_position: Account<'info, Position>,
+    #[account(
+        seeds = [b"position_whitelist", pool.key().as_ref(), to_position.owner.as_ref()],
+        bump = whitelist.bump
+    )]
+    whitelist: Account<'info, PositionWhitelist>,
+}
+
+impl<'info> TransferPosition<'info> {
+    /// 把 amount 个 LP 从 signer 的仓位转给白名单中的钱包
+    pub fn transfer_position(&mut self, amount: u64) -> Result<()> {
+        move_position_liquidity(&mut self.from_position, &mut self.to_position, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct TransferPositionWithProof<'info> {
+    signer: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), signer.key().as_ref()],
+        bump = from_position.bump
+    )]
+    from_position: Account<'info, Position>,
+    // 接收方需要事先 init_position
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), to_position.owner.as_ref()],
+        bump = to_position.bump
+    )]
+    to_position: Account<'info, Position>,
+}
+
+impl<'info> TransferPositionWithProof<'info> {
+    /// 与 transfer_position 相同，接收方用默克尔根白名单的默克尔证明代替 PositionWhitelist 账户
+    pub fn transfer_position_with_proof(&mut self, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        let root = self.pool.position_whitelist_root;
+        require!(root != [0; 32], AmmError::PositionWhitelistRootNotSet);
+
+        let leaf = position_whitelist_leaf(&self.to_position.owner);
+        require!(verify_merkle_proof(&proof, root, leaf), AmmError::InvalidMerkleProof);
+
+        move_position_liquidity(&mut self.from_position, &mut self.to_position, amount)
+    }
+}
+
+/// 默克尔根白名单的叶子：sha256(0x00 || wallet)，内部节点与快照相同，见 snapshot.rs
+pub fn position_whitelist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[&[0], wallet.as_ref()]).to_bytes()
+}
+
+/// 两种转让共用：在两个仓位之间移动 amount 个 LP
+fn move_position_liquidity(from: &mut Position, to: &mut Position, amount: u64) -> Result<()> {
+    require_gt!(amount, 0, AmmError::ZeroAmount);
+    require_gte!(from.liquidity, amount, AmmError::InsufficientPositionLiquidity);
+    require_keys_neq!(from.owner, to.owner, AmmError::InvalidPositionTransfer);
+
+    from.liquidity -= amount;
+    to.liquidity = to.liquidity.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AddPositionWhitelist<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(constraint = pool.soulbound_lp @ AmmError::PoolNotSoulbound)]
+    pool: Account<'info, Pool>,
+    /// CHECK: 只作为种子和记录的地址
+    wallet: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = PositionWhitelist::DISCRIMINATOR.len() + PositionWhitelist::INIT_SPACE,
+        seeds = [b"position_whitelist", pool.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    whitelist: Account<'info, PositionWhitelist>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> AddPositionWhitelist<'info> {
+    pub fn add_position_whitelist(&mut self, bump: u8) -> Result<()> {
+        self.whitelist.set_inner(PositionWhitelist {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            wallet: self.wallet.key(),
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RemovePositionWhitelist<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(mut, constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    pool: Account<'info, Pool>,
+    // 租金退还给池子管理员；已经持有的仓位不受影响，只是不能再接收转让
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"position_whitelist", pool.key().as_ref(), whitelist.wallet.as_ref()],
+        bump = whitelist.bump
+    )]
+    whitelist: Account<'info, PositionWhitelist>,
+}
+
+impl<'info> RemovePositionWhitelist<'info> {
+    pub fn remove_position_whitelist(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer}};
+
+use crate::{
+    context::admin::is_config_admin,
+    error::AmmError,
+    events::LiquidityDeposited,
+    math::max_proportional_lp,
+    protocol_fee::settle_protocol_fee,
+    state::{AmmConfig, Pool, PoolStatus},
+};
+
+// ========================================
+// 协议自有流动性（POL）
+// ========================================
+//
+// 协议金库就是 config PDA 名下的关联代币账户：把某个协议手续费接收方的 owner 设为 config 地址
+// （见 collect_protocol_fees.rs），或者直接把代币转到 config 的 ATA，都会进入金库。
+//
+// 协议管理员调用 seed_pool_from_treasury，把金库里的 TokenA / TokenB 按池子比例存入，
+// 铸造的 LP 留在 config 名下的 LP 关联代币账户里，治理执行一条指令即可部署协议自有流动性，
+// 不需要先把代币转给某个钱包再由钱包存入。
+// 存入的检查与 deposit_by_amounts 相同（存款上限、初始价格、外部预言机、LP 模式的协议手续费结算），
+// 没有对应的取出指令，LP 会一直留在 config 名下。
+
+#[derive(Accounts)]
+pub struct SeedPoolFromTreasury<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = !pool.soulbound_lp @ AmmError::LpSoulbound,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive
+    )]
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = config
+    )]
+    treasury_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = config
+    )]
+    treasury_ata_b: Box<Account<'info, TokenAccount>>,
+    // 协议自有的 LP 托管在 config 名下，第一次为这个池子注入时创建
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config
+    )]
+    treasury_ata_lp: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    // 协议手续费为 LP 模式的池子需要传入，地址必须等于 pool.treasury_lp，其他池子传 None
+    #[account(mut, address = pool.treasury_lp)]
+    treasury_lp: Option<Box<Account<'info, TokenAccount>>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> SeedPoolFromTreasury<'info> {
+    /// 按 deposit_by_amounts 的规则存入金库中的代币，实际存入不超过 amount_a / amount_b，
+    /// remaining_accounts: 开启外部预言机保护的池子传入预言机账户
+    pub fn seed_pool_from_treasury(&mut self, amount_a: u64, amount_b: u64, min_lp: u64, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+        settle_protocol_fee(&self.pool, &mut self.mint_lp, self.treasury_lp.as_deref(), &self.token_program, self.pool_ata_a.amount, self.pool_ata_b.amount)?;
+
+        let (reserve_a, reserve_b) = (self.pool_ata_a.amount, self.pool_ata_b.amount);
+        let (amount_a, amount_b, amount_lp) = if self.mint_lp.supply == 0 {
+            (amount_a, amount_b, self.pool.first_deposit_lp(reserve_a, reserve_b, amount_a, amount_b)?)
+        } else {
+            let amount_lp = max_proportional_lp(reserve_a, reserve_b, self.mint_lp.supply, amount_a, amount_b)?;
+            require_gt!(amount_lp, 0, AmmError::ZeroAmount);
+            let (amount_a, amount_b) = self.pool.curve(now).deposit_amounts(reserve_a, reserve_b, self.mint_lp.supply, amount_lp)?;
+            (amount_a, amount_b, amount_lp)
+        };
+
+        // Check slippage
+        require_gte!(amount_lp, min_lp);
+
+        self.pool.check_deposit_cap(reserve_a, reserve_b, amount_a, amount_b)?;
+        let new_reserve_a = reserve_a.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let new_reserve_b = reserve_b.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        let (oracle, _) = self.pool.split_external_oracle(remaining_accounts)?;
+        self.pool.check_external_oracle(oracle, new_reserve_a, new_reserve_b, now)?;
+
+        self.transfer_from_treasury(self.treasury_ata_a.to_account_info(), self.pool_ata_a.to_account_info(), amount_a)?;
+        self.transfer_from_treasury(self.treasury_ata_b.to_account_info(), self.pool_ata_b.to_account_info(), amount_b)?;
+
+        let accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.treasury_ata_lp.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        mint_to(ctx, amount_lp)?;
+
+        self.pool.update_k_last(new_reserve_a, new_reserve_b);
+
+        // LP 记在 config（协议金库）名下
+        emit!(LiquidityDeposited {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.config.key(),
+            amount_lp,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+        Ok(())
+    }
+
+    /// config PDA 签名，把金库里的代币转入池子
+    fn transfer_from_treasury(&self, from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from,
+            to,
+            authority: self.config.to_account_info(),
+        };
+
+        let config_seeds = self.config.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&config_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+}
@@ -0,0 +1,241 @@
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions as sysvar_instructions};
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{
+    context::swap::SwapResult,
+    error::AmmError,
+    events::SwapEvent,
+    guard::require_top_level,
+    math::{mul_div_floor, BPS_DENOMINATOR},
+    state::{AmmConfig, CurveType, Pool, PoolStatus},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// 同一交易对两个费率池子之间的价格对齐
+// ========================================
+//
+// 同一交易对的不同费率池子各自成交，价格会慢慢分开。rebalance_pair 无需权限，
+// 让两个池子直接互相成交，把 TokenA 从便宜的池子搬到贵的池子：
+// 1. 在便宜的池子按精确输出买入 amount_a 个 TokenA，用 TokenB 支付（含手续费）
+// 2. 把这些 TokenA 卖给贵的池子，换回更多的 TokenB
+// 3. 两者之差是利润（TokenB），其中 REBALANCE_CALLER_SHARE_BPS 付给调用者，其余留在贵的池子里
+//
+// 调用者不需要垫付任何代币，所有转账都由两个池子签名。每一侧的检查与 swap 相同
+// （预言机、交易窗口、交易方向、价格冲击、熔断、创建者和协议分成），另外要求：
+// - 利润必须大于 0，否则说明两个池子的价差还不够覆盖两边的手续费
+// - 成交后便宜的池子价格不能超过贵的池子，amount_a 过大、把价差反向拉开时失败
+// amount_a 由调用者在链下计算，链上只验证结果。
+//
+// 只支持恒定乘积池子（现价就是储备量之比），开启钱包限额或登记了 swap hook 的池子不支持。
+
+/// 利润中付给调用者的比例（基点）
+pub const REBALANCE_CALLER_SHARE_BPS: u16 = 2_000;
+
+#[derive(Accounts)]
+pub struct RebalancePair<'info> {
+    caller: Signer<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验 mint 为 TokenB
+    #[account(mut)]
+    caller_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 通过两个池子的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过两个池子的种子校验
+    mint_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool_x.fee.to_le_bytes().as_ref()],
+        bump = pool_x.bump,
+        constraint = !pool_x.locked @ AmmError::PoolLocked,
+        constraint = pool_x.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = pool_x.curve_type == CurveType::ConstantProduct @ AmmError::UnsupportedCurve,
+        constraint = !pool_x.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool_x.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool_x.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool_x.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool_x: Box<Account<'info, Pool>>,
+    /// CHECK: 地址必须等于 pool_x.vault_a
+    #[account(mut, address = pool_x.vault_a)]
+    pool_x_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_x.vault_b
+    #[account(mut, address = pool_x.vault_b)]
+    pool_x_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_x.creator_fee_vault_a
+    #[account(mut, address = pool_x.creator_fee_vault_a)]
+    pool_x_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_x.creator_fee_vault_b
+    #[account(mut, address = pool_x.creator_fee_vault_b)]
+    pool_x_fee_vault_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool_y.fee.to_le_bytes().as_ref()],
+        bump = pool_y.bump,
+        constraint = pool_y.key() != pool_x.key() @ AmmError::InvalidAllocation,
+        constraint = !pool_y.locked @ AmmError::PoolLocked,
+        constraint = pool_y.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = pool_y.curve_type == CurveType::ConstantProduct @ AmmError::UnsupportedCurve,
+        constraint = !pool_y.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool_y.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool_y.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool_y.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool_y: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    /// CHECK: 地址必须等于 pool_y.vault_a
+    #[account(mut, address = pool_y.vault_a)]
+    pool_y_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_y.vault_b
+    #[account(mut, address = pool_y.vault_b)]
+    pool_y_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_y.creator_fee_vault_a
+    #[account(mut, address = pool_y.creator_fee_vault_a)]
+    pool_y_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_y.creator_fee_vault_b
+    #[account(mut, address = pool_y.creator_fee_vault_b)]
+    pool_y_fee_vault_b: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar，地址由 address 约束校验
+    #[account(address = sysvar_instructions::ID)]
+    instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+/// 一侧池子的账户
+struct Side<'a, 'info> {
+    pool: &'a mut Account<'info, Pool>,
+    pool_ata_a: AccountInfo<'info>,
+    pool_ata_b: AccountInfo<'info>,
+    fee_vault_a: AccountInfo<'info>,
+    fee_vault_b: AccountInfo<'info>,
+}
+
+impl<'info> RebalancePair<'info> {
+    /// amount_a: 从便宜的池子搬到贵的池子的 TokenA 数量，方向按两个池子的现价自动判断
+    pub fn rebalance_pair(&mut self, amount_a: u64) -> Result<()> {
+        require_gt!(amount_a, 0, AmmError::ZeroAmount);
+
+        if self.pool_x.cpi_restricted || self.pool_y.cpi_restricted {
+            require_top_level(&self.instructions)?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (reserve_x_a, reserve_x_b) = (read_token_amount(&self.pool_x_ata_a)?, read_token_amount(&self.pool_x_ata_b)?);
+        let (reserve_y_a, reserve_y_b) = (read_token_amount(&self.pool_y_ata_a)?, read_token_amount(&self.pool_y_ata_b)?);
+
+        // 在储备量变化之前推进价格累加器
+        self.pool_x.update_oracle(reserve_x_a, reserve_x_b, now)?;
+        self.pool_y.update_oracle(reserve_y_a, reserve_y_b, now)?;
+
+        // TokenA 的价格 = reserve_b / reserve_a，交叉相乘比较
+        let x_is_cheap = reserve_x_b as u128 * (reserve_y_a as u128) < reserve_y_b as u128 * (reserve_x_a as u128);
+        let side_x = Side {
+            pool: &mut self.pool_x,
+            pool_ata_a: self.pool_x_ata_a.to_account_info(),
+            pool_ata_b: self.pool_x_ata_b.to_account_info(),
+            fee_vault_a: self.pool_x_fee_vault_a.to_account_info(),
+            fee_vault_b: self.pool_x_fee_vault_b.to_account_info(),
+        };
+        let side_y = Side {
+            pool: &mut self.pool_y,
+            pool_ata_a: self.pool_y_ata_a.to_account_info(),
+            pool_ata_b: self.pool_y_ata_b.to_account_info(),
+            fee_vault_a: self.pool_y_fee_vault_a.to_account_info(),
+            fee_vault_b: self.pool_y_fee_vault_b.to_account_info(),
+        };
+        let (cheap, rich, (cheap_a, cheap_b), (rich_a, rich_b)) = if x_is_cheap {
+            (side_x, side_y, (reserve_x_a, reserve_x_b), (reserve_y_a, reserve_y_b))
+        } else {
+            (side_y, side_x, (reserve_y_a, reserve_y_b), (reserve_x_a, reserve_x_b))
+        };
+
+        // 第一步：在便宜的池子买入 amount_a 个 TokenA（is_a = true，输入 TokenB）
+        let (cheap_in, cheap_in_with_fees) = cheap.pool.quote_exact_out(cheap_b, cheap_a, amount_a, true, now)?;
+        let (cheap_creator_fee, cheap_protocol_fee) = cheap.pool.fee_shares(cheap_in_with_fees)?;
+        let cheap_vault_fee = cheap_creator_fee + cheap_protocol_fee;
+
+        // 第二步：把 amount_a 卖给贵的池子（is_a = false，输出 TokenB）
+        // 先按精确输入算出能换回的 TokenB，再按精确输出报价拆出手续费，同时做 swap 的各项检查
+        let rich_out = rich.pool.curve(now).swap_out_given_in(rich_a, rich_b, amount_a, false, rich.pool.fee)?;
+        let (rich_in, rich_in_with_fees) = rich.pool.quote_exact_out(rich_a, rich_b, rich_out, false, now)?;
+        require_gte!(amount_a, rich_in_with_fees);
+        let (rich_creator_fee, rich_protocol_fee) = rich.pool.fee_shares(rich_in_with_fees)?;
+        let rich_vault_fee = rich_creator_fee + rich_protocol_fee;
+
+        // 利润以 TokenB 计，一部分付给调用者，其余留在贵的池子里
+        let profit = rich_out.checked_sub(cheap_in_with_fees).filter(|&profit| profit > 0).ok_or(AmmError::RebalanceNotProfitable)?;
+        let caller_reward = mul_div_floor(profit as u128, REBALANCE_CALLER_SHARE_BPS as u128, BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)? as u64;
+
+        // 熔断和方向检查都按成交后的储备量
+        let cheap_after = (cheap_a - amount_a, cheap_b.checked_add(cheap_in_with_fees - cheap_vault_fee).ok_or(ProgramError::ArithmeticOverflow)?);
+        let rich_after = (
+            rich_a.checked_add(amount_a - rich_vault_fee).ok_or(ProgramError::ArithmeticOverflow)?,
+            rich_b - cheap_in_with_fees - caller_reward,
+        );
+        cheap.pool.check_circuit_breaker(cheap_after.0, cheap_after.1)?;
+        rich.pool.check_circuit_breaker(rich_after.0, rich_after.1)?;
+        require!(
+            cheap_after.1 as u128 * (rich_after.0 as u128) <= rich_after.1 as u128 * (cheap_after.0 as u128),
+            AmmError::RebalanceOvershoot
+        );
+
+        let token_program = self.token_program.to_account_info();
+
+        // TokenA：便宜的池子 → 贵的池子，贵的池子的手续费分成转入它的手续费账户
+        pool_transfer(cheap.pool, &token_program, &cheap.pool_ata_a, &rich.pool_ata_a, amount_a - rich_vault_fee)?;
+        pool_transfer(cheap.pool, &token_program, &cheap.pool_ata_a, &rich.fee_vault_a, rich_vault_fee)?;
+
+        // TokenB：贵的池子 → 便宜的池子（含手续费），便宜的池子的手续费分成转入它的手续费账户
+        pool_transfer(rich.pool, &token_program, &rich.pool_ata_b, &cheap.pool_ata_b, cheap_in_with_fees - cheap_vault_fee)?;
+        pool_transfer(rich.pool, &token_program, &rich.pool_ata_b, &cheap.fee_vault_b, cheap_vault_fee)?;
+        pool_transfer(rich.pool, &token_program, &rich.pool_ata_b, &self.caller_ata_b.to_account_info(), caller_reward)?;
+
+        cheap.pool.accrue_protocol_fee(true, cheap_protocol_fee)?;
+        rich.pool.accrue_protocol_fee(false, rich_protocol_fee)?;
+
+        // 两个池子各自发出一条 SwapEvent，trader 记为调用者；贵的池子留下的利润不计入它的 amount_out
+        let cheap_result = SwapResult {
+            amount_in: cheap_in_with_fees,
+            amount_out: amount_a,
+            fee_paid: cheap_in_with_fees - cheap_in,
+        };
+        let rich_result = SwapResult {
+            amount_in: amount_a,
+            amount_out: cheap_in_with_fees + caller_reward,
+            fee_paid: rich_in_with_fees - rich_in,
+        };
+        emit!(SwapEvent::new(cheap.pool, self.caller.key(), true, &cheap_result, 0, None)?);
+        emit!(SwapEvent::new(rich.pool, self.caller.key(), false, &rich_result, 0, None)?);
+        Ok(())
+    }
+}
+
+/// 由池子签名转出，amount 为 0 时跳过
+fn pool_transfer<'info>(pool: &Account<'info, Pool>, token_program: &AccountInfo<'info>, from: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let accounts = Transfer {
+        from: from.clone(),
+        to: to.clone(),
+        authority: pool.to_account_info(),
+    };
+
+    let pool_seeds = pool.signer_seeds();
+
+    let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+    let ctx = CpiContext::new_with_signer(
+        token_program.clone(),
+        accounts,
+        &signer_seeds
+    );
+
+    transfer(ctx, amount)
+}
@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    context::admin::is_pool_authority,
+    error::AmmError,
+    state::{MarketMaker, Pool, ACCOUNT_VERSION},
+    token_account::read_token_mint_and_owner,
+};
+
+// ========================================
+// 做市商签名报价（RFQ）
+// ========================================
+//
+// 池子管理员可以为池子登记报价做市商（PDA：["market_maker", pool, signing_key]）。
+// 做市商在链下用 signing_key 对 SignedQuote 签名，交易者在同一笔交易中依次放入：
+// 1. ed25519 程序的验签指令（公钥、签名、消息都放在这条指令自己的数据里）
+// 2. swap_with_quote，账户与 swap 相同，remaining_accounts 为 [market_maker, 库存代币账户, 收款代币账户]，两个代币账户的 owner 都必须是做市商
+//
+// 报价有效并且比曲线价格更好时，交易者的输入代币直接转给做市商，输出代币从做市商的库存账户转出，
+// 不经过储备量，也不收取池子手续费。做市商需要事先把库存账户的 delegate 设为池子 PDA，
+// delegated_amount 就是它愿意通过报价卖出的上限。
+//
+// 池子同时是兜底的结算层：报价过期、nonce 已经用过或者不如曲线价格时，
+// 按报价的数量和方向退回到普通 swap，交易者总是拿到两者中更好的价格。
+// 签名不对或者账户不匹配仍然直接报错，避免把错误的输入当成退回。
+
+/// 做市商签名的报价，签名的消息就是它的 Borsh 序列化结果
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SignedQuote {
+    pub pool: Pubkey,
+    pub taker: Pubkey,                // 只允许该地址成交，默认值表示任何人
+    pub is_a: bool,                   // 与 swap 相同：true 表示 taker 买入 TokenA
+    pub amount_out: u64,              // taker 获得的输出代币
+    pub amount_in: u64,               // taker 付出的输入代币
+    pub nonce: u64,                   // 必须大于 MarketMaker.last_nonce
+    pub expires_at: i64,              // 过期时间戳，过期后退回曲线成交
+}
+
+impl SignedQuote {
+    /// ed25519 指令中被签名的消息
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data).map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+        Ok(data)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(signing_key: Pubkey)]
+pub struct RegisterMarketMaker<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    // 库存代币账户的 owner 必须签名同意：池子 PDA 是所有库存账户的 delegate，
+    // 否则池子管理员可以把别人的钱包登记成 owner，用自己的 signing_key 签报价取走库存
+    owner: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        space = MarketMaker::DISCRIMINATOR.len() + MarketMaker::INIT_SPACE,
+        seeds = [b"market_maker", pool.key().as_ref(), signing_key.as_ref()],
+        bump
+    )]
+    market_maker: Account<'info, MarketMaker>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterMarketMaker<'info> {
+    /// signing_key: 报价签名公钥，库存代币账户的 owner 就是签名的 owner 账户
+    pub fn register_market_maker(&mut self, signing_key: Pubkey, bump: u8) -> Result<()> {
+        self.market_maker.set_inner(MarketMaker {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            signing_key,
+            owner: self.owner.key(),
+            last_nonce: 0,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DeregisterMarketMaker<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(mut, constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    pool: Account<'info, Pool>,
+    // 注销后已经签出的报价立即失效，租金退还给池子管理员
+    #[account(mut, close = authority, has_one = pool)]
+    market_maker: Account<'info, MarketMaker>,
+}
+
+impl<'info> DeregisterMarketMaker<'info> {
+    pub fn deregister_market_maker(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// swap_with_quote 的 remaining_accounts
+pub struct QuoteAccounts<'info> {
+    pub market_maker: Account<'info, MarketMaker>,
+    pub inventory: &'info AccountInfo<'info>,     // 做市商的输出代币账户，池子 PDA 是 delegate
+    pub destination: &'info AccountInfo<'info>,   // 做市商接收输入代币的账户，owner 必须是做市商
+}
+
+/// 解析并校验 [market_maker, 库存代币账户, 收款代币账户]
+pub fn load_quote_accounts<'info>(pool: &Pubkey, mint_in: &Pubkey, mint_out: &Pubkey, accounts: &'info [AccountInfo<'info>]) -> Result<QuoteAccounts<'info>> {
+    let [market_maker, inventory, destination] = accounts else {
+        return err!(AmmError::InvalidQuoteAccounts);
+    };
+
+    let market_maker = Account::<MarketMaker>::try_from(market_maker)?;
+    require_keys_eq!(market_maker.pool, *pool, AmmError::InvalidQuoteAccounts);
+
+    // 池子 PDA 是所有做市商库存账户的 delegate，必须确认转出的是这个做市商自己的账户
+    let (mint, owner) = read_token_mint_and_owner(inventory)?;
+    require_keys_eq!(mint, *mint_out, AmmError::InvalidQuoteAccounts);
+    require_keys_eq!(owner, market_maker.owner, AmmError::InvalidQuoteAccounts);
+
+    // 收款账户也必须属于这个做市商，否则调用者可以传入自己的账户，付给自己之后照样取走库存
+    let (mint, owner) = read_token_mint_and_owner(destination)?;
+    require_keys_eq!(mint, *mint_in, AmmError::InvalidQuoteAccounts);
+    require_keys_eq!(owner, market_maker.owner, AmmError::InvalidQuoteAccounts);
+
+    Ok(QuoteAccounts { market_maker, inventory, destination })
+}
@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::{burn, close_account, transfer, Burn, CloseAccount, Mint, Token, TokenAccount, Transfer}};
+
+use crate::{error::AmmError, events::LiquidityWithdrawn, math::linear_vested_amount, protocol_fee::settle_protocol_fee, state::{LiquidityGuard, Pool, PoolStatus, ScheduledWithdraw, ACCOUNT_VERSION}};
+
+// ========================================
+// 分期取出流动性（线性释放）
+// ========================================
+//
+// 大额 LP（例如 DAO 的协议自有流动性）一次性取出会让池子深度骤降，
+// 承诺逐步退出的 LP 可以改用分期取出：
+// 1. schedule_withdraw：把 amount_lp 个 LP 转入计划 PDA 的托管 ATA，在 duration 秒内线性释放
+// 2. claim_scheduled_withdraw：owner 随时取出已经释放、还没取出的部分，
+//    按取出时的储备量换成 TokenA / TokenB，检查与 withdraw 相同（滑点、JIT 保护、外部预言机、冻结账户）
+// 3. 最后一次 claim 取完全部 LP 后自动关闭托管 ATA 和计划账户，租金退还 owner。
+//    任何人都能往托管 ATA 转入 LP，关闭之前先销毁计划之外多出来的 LP（相当于捐给其他 LP），
+//    否则余额不为 0 的托管 ATA 无法关闭，最后一次 claim 会一直失败
+//
+// 计划是对外的承诺，创建之后不能取消，也不能提前取出；托管中的 LP 照常分享手续费。
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct ScheduleWithdraw<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = owner,
+        space = ScheduledWithdraw::DISCRIMINATOR.len() + ScheduledWithdraw::INIT_SPACE,
+        seeds = [b"scheduled_withdraw", pool.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    scheduled_withdraw: Account<'info, ScheduledWithdraw>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_lp,
+        associated_token::authority = scheduled_withdraw
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = owner
+    )]
+    owner_ata_lp: Box<Account<'info, TokenAccount>>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> ScheduleWithdraw<'info> {
+    pub fn schedule_withdraw(&mut self, id: u64, amount_lp: u64, duration: i64, bump: u8) -> Result<()> {
+        require_gt!(amount_lp, 0, AmmError::ZeroAmount);
+        require_gt!(duration, 0, AmmError::InvalidScheduledWithdraw);
+
+        let start_ts = Clock::get()?.unix_timestamp;
+        let end_ts = start_ts.checked_add(duration).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let accounts = Transfer {
+            from: self.owner_ata_lp.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, amount_lp)?;
+
+        self.scheduled_withdraw.set_inner(ScheduledWithdraw {
+            version: ACCOUNT_VERSION,
+            owner: self.owner.key(),
+            pool: self.pool.key(),
+            id,
+            escrow: self.escrow.key(),
+            total_lp: amount_lp,
+            claimed_lp: 0,
+            start_ts,
+            end_ts,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimScheduledWithdraw<'info> {
+    #[account(mut)]
+    owner: Signer<'info>,
+    mint_a: Box<Account<'info, Mint>>,
+    mint_b: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"scheduled_withdraw", pool.key().as_ref(), owner.key().as_ref(), scheduled_withdraw.id.to_le_bytes().as_ref()],
+        bump = scheduled_withdraw.bump,
+        has_one = owner,
+        has_one = pool,
+        has_one = escrow
+    )]
+    scheduled_withdraw: Account<'info, ScheduledWithdraw>,
+    #[account(mut)]
+    escrow: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = owner
+    )]
+    owner_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = owner
+    )]
+    owner_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status != PoolStatus::Paused @ AmmError::PoolPaused
+    )]
+    pool: Account<'info, Pool>,
+    // 协议手续费为 LP 模式的池子需要传入，地址必须等于 pool.treasury_lp，其他池子传 None
+    #[account(mut, address = pool.treasury_lp)]
+    treasury_lp: Option<Box<Account<'info, TokenAccount>>>,
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        seeds = [b"lp_guard", pool.key().as_ref(), owner.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    token_program: Program<'info, Token>,
+}
+
+impl<'info> ClaimScheduledWithdraw<'info> {
+    /// 取出已经释放的全部 LP 对应的代币，remaining_accounts: 开启外部预言机保护的池子传入预言机账户
+    pub fn claim_scheduled_withdraw(&mut self, min_token_a: u64, min_token_b: u64, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let schedule = &self.scheduled_withdraw;
+        let vested = linear_vested_amount(schedule.total_lp, schedule.start_ts, schedule.end_ts, now)?;
+        let amount = vested - schedule.claimed_lp;
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        self.require_not_frozen()?;
+        LiquidityGuard::check_withdraw(&self.pool, self.liquidity_guard.as_deref(), Clock::get()?.slot)?;
+
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+        settle_protocol_fee(&self.pool, &mut self.mint_lp, self.treasury_lp.as_deref(), &self.token_program, self.pool_ata_a.amount, self.pool_ata_b.amount)?;
+
+        let (amount_a, amount_b) = self.pool.curve(now).withdraw_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
+
+        // Check slippage A
+        require_gte!(amount_a, min_token_a);
+
+        // Check slippage B
+        require_gte!(amount_b, min_token_b);
+
+        // 按取出之后的储备量检查外部预言机价格带
+        let (oracle, _) = self.pool.split_external_oracle(remaining_accounts)?;
+        self.pool.check_external_oracle(oracle, self.pool_ata_a.amount - amount_a, self.pool_ata_b.amount - amount_b, now)?;
+
+        self.scheduled_withdraw.claimed_lp = vested;
+
+        self.burn_from_escrow(amount)?;
+        self.transfer_from_pool(&self.pool_ata_a, &self.owner_ata_a, amount_a)?;
+        self.transfer_from_pool(&self.pool_ata_b, &self.owner_ata_b, amount_b)?;
+
+        // 取出之后记录新的 k，pool_ata 的余额是取出前读取的
+        self.pool.update_k_last(self.pool_ata_a.amount - amount_a, self.pool_ata_b.amount - amount_b);
+
+        emit!(LiquidityWithdrawn {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.owner.key(),
+            amount_lp: amount,
+            amount_a,
+            amount_b,
+            tag: None,
+        });
+
+        if self.scheduled_withdraw.claimed_lp == self.scheduled_withdraw.total_lp {
+            self.close_schedule()?;
+        }
+        Ok(())
+    }
+
+    /// 转账之前检查两侧的代币账户都没有被冻结，两个 mint 都没有 freeze authority 时不检查
+    fn require_not_frozen(&self) -> Result<()> {
+        if !self.pool.has_freezable_mint {
+            return Ok(());
+        }
+        let frozen = self.owner_ata_a.is_frozen() || self.owner_ata_b.is_frozen() || self.pool_ata_a.is_frozen() || self.pool_ata_b.is_frozen();
+        require!(!frozen, AmmError::AccountFrozen);
+        Ok(())
+    }
+
+    /// 计划 PDA 签名，销毁托管中的 LP
+    fn burn_from_escrow(&self, amount: u64) -> Result<()> {
+        let schedule_seeds = self.scheduled_withdraw.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&schedule_seeds.as_seeds()];
+
+        let accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.escrow.to_account_info(),
+            authority: self.scheduled_withdraw.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        burn(ctx, amount)
+    }
+
+    /// 池子 PDA 签名，把代币转给 owner
+    fn transfer_from_pool(&self, from: &Account<'info, TokenAccount>, to: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let accounts = Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        transfer(ctx, amount)
+    }
+
+    /// 全部取完之后关闭托管 ATA 和计划账户，租金退还 owner，托管中多出来的 LP 先销毁
+    fn close_schedule(&mut self) -> Result<()> {
+        // 计划内的 LP 已经全部销毁，余额只可能来自其他人转入
+        self.escrow.reload()?;
+        if self.escrow.amount > 0 {
+            self.burn_from_escrow(self.escrow.amount)?;
+        }
+
+        let schedule_seeds = self.scheduled_withdraw.signer_seeds();
+        let signer_seeds: [&[&[u8]];1] = [&schedule_seeds.as_seeds()];
+
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.scheduled_withdraw.to_account_info(),
+        };
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds
+        );
+
+        close_account(ctx)?;
+
+        self.scheduled_withdraw.close(self.owner.to_account_info())
+    }
+}
@@ -0,0 +1,165 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use anchor_spl::token::Mint;
+
+use crate::{
+    context::admin::is_pool_authority,
+    error::AmmError,
+    state::{LpSnapshot, Pool, VotingPower, ACCOUNT_VERSION},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// LP 快照（治理投票权）
+// ========================================
+//
+// LP 是普通的 SPL Token，转账时程序拿不到回调，无法在链上逐笔记录每个持有者的余额。
+// 这里把快照拆成三步，链上只保存可以校验的部分：
+// 1. snapshot_lp_balances：池子管理员在某个 slot 创建 LpSnapshot，记录 LP 总供应量和储备量
+// 2. set_snapshot_root：管理员发布该 slot 所有持有者 (owner, LP 余额) 的默克尔根，只能发布一次
+// 3. checkpoint_voting_power：任何人提交默克尔证明，为 owner 创建 VotingPower，
+//    已证明的总量不能超过快照时的 LP 总供应量，错误的根无法凭空多出投票权
+//
+// 治理合约只需要读取 VotingPower（种子 ["voting_power", snapshot, owner]）和 LpSnapshot。
+//
+// 默克尔树的约定：
+// - 叶子 = sha256(0x00 || owner || amount 小端序)
+// - 内部节点 = sha256(0x01 || min(left, right) || max(left, right))，两个子节点排序后再哈希，证明中不需要方向位
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct SnapshotLpBalances<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.lp_bump
+    )]
+    mint_lp: Box<Account<'info, Mint>>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(constraint = !pool.locked @ AmmError::PoolLocked)]
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        space = LpSnapshot::DISCRIMINATOR.len() + LpSnapshot::INIT_SPACE,
+        seeds = [b"snapshot", pool.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    snapshot: Account<'info, LpSnapshot>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> SnapshotLpBalances<'info> {
+    pub fn snapshot_lp_balances(&mut self, id: u64, bump: u8) -> Result<()> {
+        self.snapshot.set_inner(LpSnapshot {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            id,
+            slot: Clock::get()?.slot,
+            lp_supply: self.mint_lp.supply,
+            reserve_a: read_token_amount(&self.pool_ata_a)?,
+            reserve_b: read_token_amount(&self.pool_ata_b)?,
+            merkle_root: [0; 32],
+            total_checkpointed: 0,
+            checkpoint_count: 0,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetSnapshotRoot<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"snapshot", pool.key().as_ref(), snapshot.id.to_le_bytes().as_ref()],
+        bump = snapshot.bump,
+        has_one = pool
+    )]
+    snapshot: Account<'info, LpSnapshot>,
+}
+
+impl<'info> SetSnapshotRoot<'info> {
+    /// 发布默克尔根，发布后不能修改，已经创建的 VotingPower 不会因为换根而失效
+    pub fn set_snapshot_root(&mut self, merkle_root: [u8; 32]) -> Result<()> {
+        require!(self.snapshot.merkle_root == [0; 32], AmmError::SnapshotRootAlreadySet);
+        require!(merkle_root != [0; 32], AmmError::SnapshotRootNotSet);
+
+        self.snapshot.merkle_root = merkle_root;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CheckpointVotingPower<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 叶子中包含 owner，证明只能为 owner 本人创建投票权，不需要签名
+    owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"snapshot", snapshot.pool.as_ref(), snapshot.id.to_le_bytes().as_ref()],
+        bump = snapshot.bump
+    )]
+    snapshot: Account<'info, LpSnapshot>,
+    #[account(
+        init,
+        payer = payer,
+        space = VotingPower::DISCRIMINATOR.len() + VotingPower::INIT_SPACE,
+        seeds = [b"voting_power", snapshot.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    voting_power: Account<'info, VotingPower>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CheckpointVotingPower<'info> {
+    pub fn checkpoint_voting_power(&mut self, amount: u64, proof: Vec<[u8; 32]>, bump: u8) -> Result<()> {
+        require!(self.snapshot.merkle_root != [0; 32], AmmError::SnapshotRootNotSet);
+
+        let leaf = voting_power_leaf(&self.owner.key(), amount);
+        require!(verify_merkle_proof(&proof, self.snapshot.merkle_root, leaf), AmmError::InvalidMerkleProof);
+
+        // 每个 owner 只能创建一次（init），总量不超过快照时的 LP 总供应量
+        self.snapshot.total_checkpointed = self.snapshot.total_checkpointed.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(self.snapshot.lp_supply, self.snapshot.total_checkpointed, AmmError::SnapshotSupplyExceeded);
+        self.snapshot.checkpoint_count = self.snapshot.checkpoint_count.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        self.voting_power.set_inner(VotingPower {
+            version: ACCOUNT_VERSION,
+            snapshot: self.snapshot.key(),
+            owner: self.owner.key(),
+            amount,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+/// 默克尔树的叶子：sha256(0x00 || owner || amount 小端序)
+pub fn voting_power_leaf(owner: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[0], owner.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// 内部节点：两个子节点排序后哈希，sha256(0x01 || min || max)
+pub fn merkle_parent(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    let (first, second) = if left <= right { (left, right) } else { (right, left) };
+    hashv(&[&[1], &first, &second]).to_bytes()
+}
+
+/// 从叶子沿证明逐层哈希，结果等于 root 时证明有效
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| merkle_parent(node, *sibling)) == root
+}
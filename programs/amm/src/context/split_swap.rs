@@ -0,0 +1,253 @@
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions as sysvar_instructions};
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{
+    context::swap::SwapResult,
+    error::AmmError,
+    events::SwapEvent,
+    guard::require_top_level,
+    math::{max_amount_limit, mul_div_floor, BPS_DENOMINATOR},
+    state::{AmmConfig, Pool, PoolStatus},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// 在同一交易对的两个费率池子之间拆分 swap
+// ========================================
+//
+// 同一交易对可以有多个费率的池子（fee 是 pool PDA 的种子之一）。大额交易全部走一个池子时价格冲击大，
+// split_swap 按 allocation_bps 把输出数量分给两个池子，在同一条指令里原子地完成，
+// 两边含手续费的输入合计不超过 max_amount_in，不需要外部路由合约。
+//
+// 每一侧的检查与 swap 相同（预言机、交易窗口、价格冲击、熔断、创建者和协议分成），
+// 分配到 0 的一侧直接跳过。开启钱包限额的池子需要 trader_state，这里不支持。
+
+#[derive(Accounts)]
+pub struct SplitSwap<'info> {
+    signer: Signer<'info>,
+    /// CHECK: 通过两个池子的种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过两个池子的种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验
+    #[account(mut)]
+    signer_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验
+    #[account(mut)]
+    signer_ata_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool_1.fee.to_le_bytes().as_ref()],
+        bump = pool_1.bump,
+        constraint = !pool_1.locked @ AmmError::PoolLocked,
+        constraint = pool_1.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool_1.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool_1.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool_1.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool_1.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool_1: Box<Account<'info, Pool>>,
+    /// CHECK: 地址必须等于 pool_1.vault_a
+    #[account(mut, address = pool_1.vault_a)]
+    pool_1_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_1.vault_b
+    #[account(mut, address = pool_1.vault_b)]
+    pool_1_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_1.creator_fee_vault_a
+    #[account(mut, address = pool_1.creator_fee_vault_a)]
+    pool_1_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_1.creator_fee_vault_b
+    #[account(mut, address = pool_1.creator_fee_vault_b)]
+    pool_1_fee_vault_b: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool_2.fee.to_le_bytes().as_ref()],
+        bump = pool_2.bump,
+        constraint = pool_2.key() != pool_1.key() @ AmmError::InvalidAllocation,
+        constraint = !pool_2.locked @ AmmError::PoolLocked,
+        constraint = pool_2.status == PoolStatus::Active @ AmmError::PoolNotActive,
+        constraint = !pool_2.requires_trader_state() @ AmmError::TraderStateRequired,
+        constraint = !pool_2.is_trade_gated() @ AmmError::TradeGateRequired,
+        constraint = !pool_2.has_swap_hook() @ AmmError::SwapHookRequired,
+        constraint = !pool_2.is_oracle_guarded() @ AmmError::ExternalOracleRequired
+    )]
+    pool_2: Box<Account<'info, Pool>>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
+    )]
+    config: Box<Account<'info, AmmConfig>>,
+    /// CHECK: 地址必须等于 pool_2.vault_a
+    #[account(mut, address = pool_2.vault_a)]
+    pool_2_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_2.vault_b
+    #[account(mut, address = pool_2.vault_b)]
+    pool_2_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_2.creator_fee_vault_a
+    #[account(mut, address = pool_2.creator_fee_vault_a)]
+    pool_2_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool_2.creator_fee_vault_b
+    #[account(mut, address = pool_2.creator_fee_vault_b)]
+    pool_2_fee_vault_b: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar，地址由 address 约束校验
+    #[account(address = sysvar_instructions::ID)]
+    instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+}
+
+/// 一侧池子的代币账户
+struct LegVaults<'info> {
+    pool_ata_a: AccountInfo<'info>,
+    pool_ata_b: AccountInfo<'info>,
+    creator_fee_vault_a: AccountInfo<'info>,
+    creator_fee_vault_b: AccountInfo<'info>,
+}
+
+/// 交易者一侧的账户，两个池子共用
+struct Trader<'info> {
+    signer: AccountInfo<'info>,
+    signer_ata_a: AccountInfo<'info>,
+    signer_ata_b: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+}
+
+impl<'info> SplitSwap<'info> {
+    /// amount: 两个池子合计的输出数量；allocation_bps: 其中由 pool_1 成交的比例，其余由 pool_2 成交
+    pub fn split_swap(&mut self, amount: u64, allocation_bps: u16, max_amount_in: u64, is_a: bool) -> Result<()> {
+        require!(allocation_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidAllocation);
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+
+        if self.pool_1.cpi_restricted || self.pool_2.cpi_restricted {
+            require_top_level(&self.instructions)?;
+        }
+
+        let amount_1 = mul_div_floor(amount as u128, allocation_bps as u128, BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)? as u64;
+        let amount_2 = amount - amount_1;
+        let now = Clock::get()?.unix_timestamp;
+
+        let trader = Trader {
+            signer: self.signer.to_account_info(),
+            signer_ata_a: self.signer_ata_a.to_account_info(),
+            signer_ata_b: self.signer_ata_b.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+
+        let vaults_1 = LegVaults {
+            pool_ata_a: self.pool_1_ata_a.to_account_info(),
+            pool_ata_b: self.pool_1_ata_b.to_account_info(),
+            creator_fee_vault_a: self.pool_1_fee_vault_a.to_account_info(),
+            creator_fee_vault_b: self.pool_1_fee_vault_b.to_account_info(),
+        };
+        let (amount_in_1, fee_paid_1) = swap_leg(&mut self.pool_1, &vaults_1, &trader, amount_1, is_a, now)?;
+
+        let vaults_2 = LegVaults {
+            pool_ata_a: self.pool_2_ata_a.to_account_info(),
+            pool_ata_b: self.pool_2_ata_b.to_account_info(),
+            creator_fee_vault_a: self.pool_2_fee_vault_a.to_account_info(),
+            creator_fee_vault_b: self.pool_2_fee_vault_b.to_account_info(),
+        };
+        let (amount_in_2, fee_paid_2) = swap_leg(&mut self.pool_2, &vaults_2, &trader, amount_2, is_a, now)?;
+
+        // Check slippage：两个池子合计
+        let amount_in = amount_in_1.checked_add(amount_in_2).ok_or(ProgramError::ArithmeticOverflow)?;
+        require_gte!(max_amount_limit(max_amount_in), amount_in);
+
+        SwapResult {
+            amount_in,
+            amount_out: amount,
+            fee_paid: fee_paid_1 + fee_paid_2,
+        }.set_return_data()
+    }
+}
+
+/// 在一个池子上按精确输出成交，返回 (含手续费的输入, 手续费)，amount 为 0 时跳过
+fn swap_leg<'info>(pool: &mut Account<'info, Pool>, vaults: &LegVaults<'info>, trader: &Trader<'info>, amount: u64, is_a: bool, now: i64) -> Result<(u64, u64)> {
+    if amount == 0 {
+        return Ok((0, 0));
+    }
+
+    let reserve_a = read_token_amount(&vaults.pool_ata_a)?;
+    let reserve_b = read_token_amount(&vaults.pool_ata_b)?;
+
+    // 在储备量变化之前推进价格累加器
+    pool.update_oracle(reserve_a, reserve_b, now)?;
+
+    let (signer_in, signer_out, pool_in, pool_out, creator_fee_vault, reserve_in, reserve_out) = if is_a {
+        (&trader.signer_ata_a, &trader.signer_ata_b, &vaults.pool_ata_b, &vaults.pool_ata_a, &vaults.creator_fee_vault_b, reserve_b, reserve_a)
+    } else {
+        (&trader.signer_ata_b, &trader.signer_ata_a, &vaults.pool_ata_a, &vaults.pool_ata_b, &vaults.creator_fee_vault_a, reserve_a, reserve_b)
+    };
+
+    let (amount_in, amount_in_with_fees) = pool.quote_exact_out(reserve_in, reserve_out, amount, is_a, now)?;
+    let (creator_fee, protocol_fee) = pool.fee_shares(amount_in_with_fees)?;
+    let vault_fee = creator_fee + protocol_fee;
+    let amount_to_pool = amount_in_with_fees - vault_fee;
+
+    // 熔断：按交易后的储备量检查现价与 TWAP 的偏离
+    let (reserve_a_after, reserve_b_after) = if is_a {
+        (reserve_a - amount, reserve_b.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?)
+    } else {
+        (reserve_a.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?, reserve_b - amount)
+    };
+    pool.check_circuit_breaker(reserve_a_after, reserve_b_after)?;
+
+    let accounts = Transfer {
+        from: signer_out.clone(),
+        to: pool_in.clone(),
+        authority: trader.signer.clone(),
+    };
+
+    let ctx = CpiContext::new(
+        trader.token_program.clone(),
+        accounts
+    );
+
+    transfer(ctx, amount_to_pool)?;
+
+    if vault_fee > 0 {
+        let accounts = Transfer {
+            from: signer_out.clone(),
+            to: creator_fee_vault.clone(),
+            authority: trader.signer.clone(),
+        };
+
+        let ctx = CpiContext::new(
+            trader.token_program.clone(),
+            accounts
+        );
+
+        transfer(ctx, vault_fee)?;
+    }
+
+    pool.accrue_protocol_fee(is_a, protocol_fee)?;
+
+    let accounts = Transfer {
+        from: pool_out.clone(),
+        to: signer_in.clone(),
+        authority: pool.to_account_info(),
+    };
+
+    let pool_seeds = pool.signer_seeds();
+
+    let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+    let ctx = CpiContext::new_with_signer(
+        trader.token_program.clone(),
+        accounts,
+        &signer_seeds
+    );
+
+    transfer(ctx, amount)?;
+
+    // 每个池子各自发出一条 SwapEvent，使用各自的 event_seq
+    let result = SwapResult {
+        amount_in: amount_in_with_fees,
+        amount_out: amount,
+        fee_paid: amount_in_with_fees - amount_in,
+    };
+    emit!(SwapEvent::new(pool, trader.signer.key(), is_a, &result, 0, None)?);
+
+    Ok((result.amount_in, result.fee_paid))
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    context::admin::is_pool_authority,
+    curve::{CurveCalculator, StableSwap},
+    error::AmmError,
+    state::{CurveType, Pool},
+    token_account::read_token_amount,
+};
+
+// ========================================
+// StableSwap 池子
+// ========================================
+//
+// 稳定币、LST 等价格锚定的交易对用恒定乘积会在 1:1 附近产生不必要的滑点，
+// 管理员可以在空池子上切换到 StableSwap 曲线，amp 越大 1:1 附近越平坦。
+
+#[derive(Accounts)]
+pub struct ConfigureStableSwap<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_pool_authority
+    #[account(constraint = is_pool_authority(&authority, &pool) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(mut, constraint = !pool.locked @ AmmError::PoolLocked)]
+    pool: Account<'info, Pool>,
+}
+
+impl<'info> ConfigureStableSwap<'info> {
+    pub fn configure_stable_swap(&mut self, amp: u64) -> Result<()> {
+        // 与 LBP 相同，已经有流动性的池子切换曲线会让价格瞬间跳变
+        require!(
+            read_token_amount(&self.pool_ata_a)? == 0 && read_token_amount(&self.pool_ata_b)? == 0,
+            AmmError::PoolNotEmpty
+        );
+
+        StableSwap { amp }.validate()?;
+
+        self.pool.curve_type = CurveType::StableSwap;
+        self.pool.amp = amp;
+        Ok(())
+    }
+}
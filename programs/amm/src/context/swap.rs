@@ -1,131 +1,187 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{transfer, Mint, Token, TokenAccount, Transfer}};
+use anchor_lang::{prelude::*, solana_program::{program::set_return_data, sysvar::instructions as sysvar_instructions}};
+use anchor_spl::{associated_token::AssociatedToken, token::{transfer, Token, Transfer}};
 
-use crate::state::Pool;
+use crate::{context::{pool_stats::record_swap_stats, rfq::{load_quote_accounts, SignedQuote}}, error::AmmError, events::QuoteFilled, fee_exemption::exempted_fee, fee_rebate::rebated_fee, guard::{has_distinct_keys, require_ed25519_signature, require_top_level}, swap_hook::invoke_swap_hook, math::{creator_fee_amount, max_amount_limit, price_impact_bps, BPS_DENOMINATOR}, state::{AmmConfig, FeeDestinationMode, Pool, PoolStats, PoolStatus, TraderState}, token_account::{read_token_amount, require_not_frozen, unwrap_wsol}};
 
+// ==========================================
+// 计算量优化
+// ==========================================
+// swap 是调用最频繁的指令，这里刻意不反序列化代币账户：
+// - mint_a / mint_b 只用作 pool 的种子，种子校验通过就说明 mint 正确；
+//   has_one 在种子之前检查，传错 mint 时返回 MintAMismatch / MintBMismatch，
+//   而不是难以理解的 ConstraintSeeds
+// - pool_ata_a / pool_ata_b 只按 initialize 时记录在 Pool 中的地址校验，
+//   余额通过 read_token_amount 直接读取
+// - signer_ata_a / signer_ata_b 由 SPL Token 程序在 transfer 时校验
+//   （from 的 owner 必须是签名者，from / to 的 mint 必须一致）
+//   另外要求它们与池子的代币账户、手续费账户互不相同（只比较地址，不读取账户数据）
+// 客户端如何只凭 (pool, user) 解析这些账户见 pool_vaults.rs
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
     signer: Signer<'info>,
-    mint_a: Account<'info, Mint>,
-    mint_b: Account<'info, Mint>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验
+    mint_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 和种子校验
+    mint_b: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验，不能与其他可写代币账户相同
     #[account(
         mut,
-        associated_token::authority = signer,
-        associated_token::mint = mint_a
+        constraint = has_distinct_keys(&[
+            signer_ata_a.key(), signer_ata_b.key(), pool_ata_a.key(), pool_ata_b.key(), creator_fee_vault_a.key(), creator_fee_vault_b.key()
+        ]) @ AmmError::DuplicateMutableAccount
     )]
-    signer_ata_a: Account<'info, TokenAccount>,
+    signer_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验
+    #[account(mut)]
+    signer_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(mut, address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(mut, address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 校验，IDL 中可以从 pool 自动解析
+    #[account(mut)]
+    creator_fee_vault_a: UncheckedAccount<'info>,
+    /// CHECK: 通过 pool 的 has_one 校验，IDL 中可以从 pool 自动解析
+    #[account(mut)]
+    creator_fee_vault_b: UncheckedAccount<'info>,
     #[account(
         mut,
-        associated_token::authority = signer,
-        associated_token::mint = mint_b
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        has_one = creator_fee_vault_a,
+        has_one = creator_fee_vault_b,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status == PoolStatus::Active @ AmmError::PoolNotActive
     )]
-    signer_ata_b: Account<'info, TokenAccount>,
+    pool: Account<'info, Pool>,
+    // 全局紧急模式下所有池子只允许取出，见 emergency_mode.rs
     #[account(
-        mut,
-        associated_token::authority = pool,
-        associated_token::mint = mint_a
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.emergency_mode @ AmmError::EmergencyModeActive
     )]
-    pool_ata_a: Account<'info, TokenAccount>,
+    config: Box<Account<'info, AmmConfig>>,
+    // 可选的成交统计账户，见 pool_stats.rs
     #[account(
         mut,
-        associated_token::authority = pool,
-        associated_token::mint = mint_b
+        constraint = pool_stats.load()?.pool == pool.key() @ AmmError::InvalidPoolStats
     )]
-    pool_ata_b: Account<'info, TokenAccount>,
+    pool_stats: Option<AccountLoader<'info, PoolStats>>,
+    // 只有开启了钱包限额的池子需要传入，其他池子传 None
     #[account(
-        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
-        bump = pool.bump
+        mut,
+        seeds = [b"trader", pool.key().as_ref(), signer.key().as_ref()],
+        bump = trader_state.bump
     )]
-    pool: Account<'info, Pool>,
+    trader_state: Option<Account<'info, TraderState>>,
+    /// CHECK: Instructions sysvar，地址由 address 约束校验
+    #[account(address = sysvar_instructions::ID)]
+    instructions: UncheckedAccount<'info>,
     token_program: Program<'info, Token>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
+    // 只有 swap_with_relayer_fee 使用，接收中继者的补偿
+    /// CHECK: 由 SPL Token 程序在 transfer 中校验 mint 与输出代币一致
+    #[account(mut)]
+    relayer_ata: Option<UncheckedAccount<'info>>,
+    // 池子登记了 swap hook 时必须传入，见 swap_hook.rs
+    /// CHECK: 地址必须等于 pool.swap_hook_program
+    #[account(address = pool.swap_hook_program)]
+    hook_program: Option<UncheckedAccount<'info>>,
+}
+
+/// swap 的实际成交结果，通过 set_return_data 返回
+///
+/// CPI 调用方可以用 get_return_data 读取，客户端也能从模拟交易结果中解析：
+/// - amount_in: 用户实际付出的输入代币数量（含手续费）
+/// - amount_out: 用户实际获得的输出代币数量（swap_with_relayer_fee 已扣除中继者补偿）
+/// - fee_paid: 其中的手续费部分
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_paid: u64,
+}
+
+/// swap 所有检查通过之后的成交数量，swap 按它转账，dry_run_swap 只返回结果
+struct SwapQuote {
+    amount_in: u64,                   // 不含手续费的输入（仅用于统计手续费）
+    amount_in_with_fees: u64,         // 用户实际付出的数量
+    creator_fee: u64,                 // 其中转入手续费账户的创建者分成
+    protocol_fee: u64,                // 其中转入手续费账户的协议分成
+    reserves: (u64, u64),             // 报价时的 (reserve_a, reserve_b)，转账之后检查不变量使用
+}
+
+impl SwapQuote {
+    fn result(&self, amount_out: u64) -> Result<SwapResult> {
+        // 手续费 = 含手续费的输入 - 不含手续费的输入
+        let fee_paid = self.amount_in_with_fees.checked_sub(self.amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok(SwapResult {
+            amount_in: self.amount_in_with_fees,
+            amount_out,
+            fee_paid,
+        })
+    }
 }
 
 impl<'info> Swap<'info> {
-    pub fn swap(&mut self, amount: u64, max_amount_in: u64, is_a: bool) -> Result<()> {
-        /*
-            k = ab
-            a2 = a - amount 
-            b2 = k / a2
-        */
-        let k = (self.pool_ata_a.amount as u128)
-            .checked_mul(self.pool_ata_b.amount.into()).ok_or(ProgramError::ArithmeticOverflow)?;
+    /// remaining_accounts: 开启外部预言机保护的池子先传预言机账户（见 external_oracle.rs），
+    /// 开启会员制的池子接着传 [NFT 代币账户, NFT Metadata]（见 trade_gate.rs），
+    /// 然后是可选的 [config, 会员代币账户]（见 fee_rebate.rs），最后是 swap hook 的额外账户（见 swap_hook.rs）
+    ///
+    /// tag: 调用者自定义的标记，在 SwapEvent 中原样回显
+    /// unwrap: 输出代币是 WSOL 时，成交之后关闭 signer 的输出代币账户，SOL 直接退回 signer
+    pub fn swap(&mut self, amount: u64, max_amount_in: u64, is_a: bool, tag: Option<[u8; 16]>, unwrap: bool, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        self.execute_swap(amount, max_amount_in, is_a, tag, unwrap, 0, remaining_accounts)
+    }
+
+    /// swap 的实现：relayer_fee > 0 时，在发出 SwapEvent 和写入 return data 之前
+    /// 从用户收到的 amount 个输出代币中转出中继者补偿，两者记录的都是用户的净输出
+    #[allow(clippy::too_many_arguments)]
+    fn execute_swap(&mut self, amount: u64, max_amount_in: u64, is_a: bool, tag: Option<[u8; 16]>, unwrap: bool, relayer_fee: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (oracle, remaining_accounts) = self.pool.split_external_oracle(remaining_accounts)?;
+        let (gate_accounts, remaining_accounts) = self.pool.split_trade_gate(remaining_accounts)?;
+        let (fee_rebate_accounts, hook_accounts) = self.pool.split_swap_hook_accounts(remaining_accounts)?;
+        self.pool.check_trade_gate(&self.signer.key(), gate_accounts)?;
+        let quote = self.check_and_quote(amount, max_amount_in, is_a, oracle, fee_rebate_accounts)?;
+        let vault_fee = quote.creator_fee + quote.protocol_fee;
+        let amount_to_pool = quote.amount_in_with_fees - vault_fee;
 
         // 我理解了，这里 is_a 确实是 signer 想要 a , 付出 b
         // amount_in 是 signer 想要付出的 b 数量基础数量, 
-        // 后面会乘以 10000 + fee 再除以 10000 得到实际付出的 b 数量
+        // 后面会乘以 10000 再除以 10000 - fee 得到实际付出的 b 数量
         // 所以 max_amount_in 也是 pool 的进入 b 的最大数量，也就是用户付出的最大滑点。
         // 下面的from和to的cpi确实证明上面的signer_in 和 pool_in 是对应的，
         // 但是看起来很难看懂，所以还是改一下试试
-        let (signer_in, signer_out, pool_in, pool_out, amount_in) = if is_a {
+        let (signer_in, signer_out, pool_in, pool_out, creator_fee_vault) = if is_a {
             // 用户想要获得 amount 个 TokenA，需要付出 TokenB
-            let a2 = self.pool_ata_a.amount.checked_sub(amount).ok_or(ProgramError::ArithmeticOverflow)?;
-            
-            // 🔧 修复：精确计算，避免过早的向上取整
-            // 直接计算精确的 amount_in，而不是先计算 b2
-            // amount_in = (k / a2) - current_b = k / a2 - pool_b
-            // 为了避免精度损失，我们计算: amount_in = (k - a2 * pool_b) / a2
-            let numerator = k.checked_sub((a2 as u128).checked_mul(self.pool_ata_b.amount as u128)
-                .ok_or(ProgramError::ArithmeticOverflow)?)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            
-            let amount_in_exact = numerator.checked_div(a2 as u128)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            
             (
                 self.signer_ata_a.to_account_info(),
                 self.signer_ata_b.to_account_info(),
                 self.pool_ata_b.to_account_info(),
                 self.pool_ata_a.to_account_info(),
-                // 按理来说，k=ab是池子的恒定值，所以不应该是signer的k，所以池子是b2，signer才应该账户出账b2-pool.b.amount
-                amount_in_exact
+                self.creator_fee_vault_b.to_account_info(),
             )
         } else {
             // 用户想要获得 amount 个 TokenB，需要付出 TokenA
-            let b2 = self.pool_ata_b.amount.checked_sub(amount).ok_or(ProgramError::ArithmeticOverflow)?;
-            
-            // 🔧 修复：精确计算，避免过早的向上取整
-            // amount_in = (k / b2) - current_a = k / b2 - pool_a
-            // 为了避免精度损失，我们计算: amount_in = (k - b2 * pool_a) / b2
-            let numerator = k.checked_sub((b2 as u128).checked_mul(self.pool_ata_a.amount as u128)
-                .ok_or(ProgramError::ArithmeticOverflow)?)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            
-            let amount_in_exact = numerator.checked_div(b2 as u128)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-            
             (
                 self.signer_ata_b.to_account_info(),
                 self.signer_ata_a.to_account_info(),
                 self.pool_ata_a.to_account_info(),
                 self.pool_ata_b.to_account_info(),
-                amount_in_exact
+                self.creator_fee_vault_a.to_account_info(),
             )
         };
 
-        // 🔧 修复：只在最终手续费计算时向上取整，确保手续费被正确收取
-        // amount_in_with_fees = ceiling(amount_in * (10000 + fee) / 10000)
-        let fee_multiplier = 10_000u128 + self.pool.fee as u128;
-        let amount_with_fees_exact = amount_in
-            .checked_mul(fee_multiplier)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 向上取整确保手续费不会因为整数除法而丢失
-        let amount_in_with_fees: u64 = amount_with_fees_exact
-            .checked_add(10_000u128 - 1)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(10_000u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
-
-        // Check slippage
-        require_gte!(max_amount_in, amount_in_with_fees);
-
         // is_a: signer out B to pool B
         let accounts = Transfer {
-            from: signer_out,
+            from: signer_out.clone(),
             to: pool_in,
             authority: self.signer.to_account_info()
         };
@@ -135,7 +191,24 @@ impl<'info> Swap<'info> {
             accounts
         );
         
-        transfer(ctx, amount_in_with_fees)?;
+        transfer(ctx, amount_to_pool)?;
+
+        if vault_fee > 0 {
+            let accounts = Transfer {
+                from: signer_out,
+                to: creator_fee_vault,
+                authority: self.signer.to_account_info()
+            };
+
+            let ctx = CpiContext::new(
+                self.token_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, vault_fee)?;
+        }
+
+        self.pool.accrue_protocol_fee(is_a, quote.protocol_fee)?;
 
         // is_a: pool out A to signer A
         let accounts = Transfer {
@@ -144,9 +217,9 @@ impl<'info> Swap<'info> {
             authority: self.pool.to_account_info(),
         };
 
-        let binding = self.pool.fee.to_le_bytes();
+        let pool_seeds = self.pool.signer_seeds();
 
-        let signer_seeds: [&[&[u8]];1] = [&[&b"pool"[..], self.mint_a.to_account_info().key.as_ref(), self.mint_b.to_account_info().key.as_ref(), binding.as_ref(), &[self.pool.bump]]];
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
 
         let ctx = CpiContext::new_with_signer(
             self.token_program.to_account_info(), 
@@ -154,6 +227,394 @@ impl<'info> Swap<'info> {
             &signer_seeds
         );
         
-        transfer(ctx, amount)
+        transfer(ctx, amount)?;
+
+        if relayer_fee > 0 {
+            self.pay_relayer(is_a, relayer_fee)?;
+        }
+
+        // 重新读取转账之后的实际余额，不变量不能变小
+        let now = Clock::get()?.unix_timestamp;
+        let reserves_after = (read_token_amount(&self.pool_ata_a)?, read_token_amount(&self.pool_ata_b)?);
+        self.pool.curve(now).check_invariant(quote.reserves, reserves_after)?;
+
+        // 成交统计按池子实际流出的数量，事件和 return data 按用户到手的数量
+        let gross = quote.result(amount)?;
+        record_swap_stats(self.pool_stats.as_ref(), is_a, gross.amount_in, gross.amount_out, gross.fee_paid, now)?;
+        let result = SwapResult { amount_out: amount - relayer_fee, ..gross };
+        invoke_swap_hook(&mut self.pool, self.hook_program.as_ref(), &self.signer.to_account_info(), hook_accounts, is_a, &result, relayer_fee, tag)?;
+
+        if unwrap {
+            let (mint_out, signer_ata_out) = if is_a { (self.pool.mint_a, &self.signer_ata_a) } else { (self.pool.mint_b, &self.signer_ata_b) };
+            unwrap_wsol(&self.token_program, &mint_out, signer_ata_out.to_account_info(), self.signer.to_account_info())?;
+        }
+        result.set_return_data()
+    }
+
+    /// 只预览不成交：执行与 swap 完全相同的检查和计算，把 SwapResult 写入 return data，
+    /// 然后把 pool 和 trader_state 恢复原样，不转账、不改变任何状态。
+    ///
+    /// 账户列表与 swap 相同，客户端或者通过 CPI 包装 swap 的路由合约
+    /// 可以用同一组账户先模拟，拿到精确的成交结果和错误。
+    /// 不调用 swap hook，remaining_accounts 中 hook 的额外账户被忽略
+    pub fn dry_run_swap(&mut self, amount: u64, max_amount_in: u64, is_a: bool, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        let (oracle, remaining_accounts) = self.pool.split_external_oracle(remaining_accounts)?;
+        let (gate_accounts, remaining_accounts) = self.pool.split_trade_gate(remaining_accounts)?;
+        let (fee_rebate_accounts, _) = self.pool.split_swap_hook_accounts(remaining_accounts)?;
+        self.pool.check_trade_gate(&self.signer.key(), gate_accounts)?;
+        let pool = (*self.pool).clone();
+        let trader_state = self.trader_state.as_deref().cloned();
+
+        let quote = self.check_and_quote(amount, max_amount_in, is_a, oracle, fee_rebate_accounts)?;
+
+        *self.pool = pool;
+        if let (Some(account), Some(trader_state)) = (self.trader_state.as_mut(), trader_state) {
+            **account = trader_state;
+        }
+
+        quote.result(amount)?.set_return_data()
+    }
+
+    /// swap 和 dry_run_swap 共用的检查和报价：会推进预言机、记录钱包限额，不做转账
+    fn check_and_quote(&mut self, amount: u64, max_amount_in: u64, is_a: bool, oracle: Option<&AccountInfo>, fee_rebate_accounts: &[AccountInfo]) -> Result<SwapQuote> {
+        // 保守的池子可以关闭可组合性，只接受用户直接发起的 swap
+        if self.pool.cpi_restricted {
+            require_top_level(&self.instructions)?;
+        }
+
+        // 发行方可以冻结任何一方的代币账户，在转账之前检查，输出账户被冻结时不会等到转入之后才失败；
+        // 两个 mint 都没有 freeze authority 时账户不可能被冻结，不花这部分计算量
+        if self.pool.has_freezable_mint {
+            require_not_frozen(&[&self.signer_ata_a, &self.signer_ata_b, &self.pool_ata_a, &self.pool_ata_b])?;
+        }
+
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        // 在储备量变化之前推进价格累加器
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        let (reserve_in, reserve_out) = if is_a { (reserve_b, reserve_a) } else { (reserve_a, reserve_b) };
+
+        // 发射保护：每个钱包每个窗口的买入限额；防夹子：同一钱包短时间内往返 swap 的附加费
+        let round_trip_fee = if self.pool.requires_trader_state() {
+            let trader_state = self.trader_state.as_mut().ok_or(AmmError::TraderStateRequired)?;
+            let slot = Clock::get()?.slot;
+            if self.pool.rate_limit_window_slots > 0 {
+                trader_state.record_swap(&self.pool, amount, is_a, slot)?;
+            }
+            trader_state.record_direction(&self.pool, is_a, slot)
+        } else {
+            0
+        };
+
+        // 持有会员代币的钱包按打折后的手续费报价，合作集成方的豁免与折扣不叠加、取较低者，往返附加费加在减免之后
+        let fee = rebated_fee(&self.pool, &self.signer.key(), fee_rebate_accounts)?
+            .min(exempted_fee(&self.config, self.pool.fee, &self.signer.key(), &self.instructions)?);
+        let quote_fee = fee.checked_add(round_trip_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        let (amount_in, amount_in_with_fees) = self.pool.quote_exact_out_at_fee(reserve_in, reserve_out, amount, is_a, now, quote_fee)?;
+
+        // Check slippage
+        require_gte!(max_amount_limit(max_amount_in), amount_in_with_fees);
+
+        // 创建者和协议分成从手续费中扣出，直接转入手续费账户，不进入储备量；
+        // 只按 fee 拆分，附加费全部留在储备量中
+        let (creator_fee, protocol_fee) = self.pool.fee_shares_at_fee(amount_in_with_fees, fee)?;
+        let amount_to_pool = amount_in_with_fees - creator_fee - protocol_fee;
+
+        // 熔断：按交易后的储备量检查现价与 TWAP 的偏离
+        let (reserve_a_after, reserve_b_after) = if is_a {
+            (reserve_a - amount, reserve_b.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?)
+        } else {
+            (reserve_a.checked_add(amount_to_pool).ok_or(ProgramError::ArithmeticOverflow)?, reserve_b - amount)
+        };
+        self.pool.check_circuit_breaker(reserve_a_after, reserve_b_after)?;
+        self.pool.check_external_oracle(oracle, reserve_a_after, reserve_b_after, now)?;
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_in_with_fees,
+            creator_fee,
+            protocol_fee,
+            reserves: (reserve_a, reserve_b),
+        })
+    }
+}
+
+impl<'info> Swap<'info> {
+    /// 按输出代币的 mint 指定方向，等价于 swap(amount, max_amount_in, output_mint == mint_a)
+    ///
+    /// 集成方只需要知道自己想要哪个代币，不用关心池子里 mint_a / mint_b 的排列顺序
+    pub fn swap_by_mint(&mut self, output_mint: Pubkey, amount: u64, max_amount_in: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let is_a = if output_mint == self.pool.mint_a {
+            true
+        } else if output_mint == self.pool.mint_b {
+            false
+        } else {
+            return err!(AmmError::MintNotInPool);
+        };
+
+        self.swap(amount, max_amount_in, is_a, None, false, remaining_accounts)
+    }
+
+    /// 由中继者代付交易费的 swap：用户只签名，中继者作为 fee payer 提交交易，
+    /// 成交之后从用户收到的 amount 个输出代币中转出 relayer_fee 个到 relayer_ata 作为补偿。
+    /// relayer_fee 是用户签名的指令参数，中继者无法单方面修改；用户实际到手 amount - relayer_fee，
+    /// SwapResult 和 SwapEvent 的 amount_out 都是这个净数量，补偿记在 SwapEvent 的 relayer_fee 中
+    pub fn swap_with_relayer_fee(&mut self, amount: u64, max_amount_in: u64, is_a: bool, relayer_fee: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require_gte!(amount, relayer_fee, AmmError::RelayerFeeTooHigh);
+
+        self.execute_swap(amount, max_amount_in, is_a, None, false, relayer_fee, remaining_accounts)
+    }
+
+    /// 从 signer 的输出代币账户转出中继者补偿
+    fn pay_relayer(&self, is_a: bool, relayer_fee: u64) -> Result<()> {
+        let relayer_ata = self.relayer_ata.as_ref().ok_or(AmmError::RelayerAccountRequired)?;
+        let signer_ata_out = if is_a { &self.signer_ata_a } else { &self.signer_ata_b };
+
+        let accounts = Transfer {
+            from: signer_ata_out.to_account_info(),
+            to: relayer_ata.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts
+        );
+
+        transfer(ctx, relayer_fee)
+    }
+}
+
+impl<'info> Swap<'info> {
+    /// 按做市商签名的报价成交，报价不可用或不如曲线价格时退回普通 swap，见 rfq.rs
+    ///
+    /// max_amount_in 同时约束报价成交和退回后的曲线成交
+    pub fn swap_with_quote(&mut self, quote: SignedQuote, max_amount_in: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require_keys_eq!(quote.pool, self.pool.key(), AmmError::InvalidQuote);
+        require!(quote.taker == Pubkey::default() || quote.taker == self.signer.key(), AmmError::InvalidQuote);
+
+        // 与曲线成交相同：保守的池子只接受用户直接发起的 swap
+        if self.pool.cpi_restricted {
+            require_top_level(&self.instructions)?;
+        }
+
+        let is_a = quote.is_a;
+        let (mint_in, mint_out, signer_ata_in, signer_ata_out) = if is_a {
+            (self.pool.mint_b, self.pool.mint_a, self.signer_ata_b.to_account_info(), self.signer_ata_a.to_account_info())
+        } else {
+            (self.pool.mint_a, self.pool.mint_b, self.signer_ata_a.to_account_info(), self.signer_ata_b.to_account_info())
+        };
+
+        // 报价成交不改变池子储备量，只有退回曲线成交时才需要预言机账户
+        let (oracle, remaining_accounts) = self.pool.split_external_oracle(remaining_accounts)?;
+        let (gate_accounts, remaining_accounts) = self.pool.split_trade_gate(remaining_accounts)?;
+        let (quote_accounts, hook_accounts) = self.pool.split_swap_hook_accounts(remaining_accounts)?;
+        // 会员制池子的报价成交同样只对持有 NFT 的钱包开放
+        self.pool.check_trade_gate(&self.signer.key(), gate_accounts)?;
+        let mut accounts = load_quote_accounts(&self.pool.key(), &mint_in, &mint_out, quote_accounts)?;
+        require_ed25519_signature(&self.instructions, &accounts.market_maker.signing_key, &quote.message()?)?;
+
+        // 报价成交不经过曲线报价，单独检查交易窗口和方向
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.check_trading_window(now)?;
+        self.pool.check_trade_direction(is_a)?;
+
+        let usable = now <= quote.expires_at && quote.nonce > accounts.market_maker.last_nonce;
+        if !usable || !self.quote_beats_curve(&quote, now)? {
+            let fallback_accounts: Vec<AccountInfo<'info>> = oracle.into_iter().chain(gate_accounts).chain(hook_accounts).cloned().collect();
+            return self.swap(quote.amount_out, max_amount_in, is_a, None, false, &fallback_accounts);
+        }
+
+        // Check slippage
+        require_gt!(quote.amount_out, 0, AmmError::ZeroAmount);
+        require_gte!(max_amount_limit(max_amount_in), quote.amount_in);
+
+        // 报价成交的四个代币账户都可能被发行方冻结，转账之前检查
+        if self.pool.has_freezable_mint {
+            require_not_frozen(&[&signer_ata_in, &signer_ata_out, accounts.inventory, accounts.destination])?;
+        }
+
+        let accounts_in = Transfer {
+            from: signer_ata_in,
+            to: accounts.destination.clone(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            accounts_in
+        );
+
+        transfer(ctx, quote.amount_in)?;
+
+        // 池子 PDA 作为库存账户的 delegate 签名
+        let accounts_out = Transfer {
+            from: accounts.inventory.clone(),
+            to: signer_ata_out,
+            authority: self.pool.to_account_info(),
+        };
+
+        let pool_seeds = self.pool.signer_seeds();
+
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts_out,
+            &signer_seeds
+        );
+
+        transfer(ctx, quote.amount_out)?;
+
+        accounts.market_maker.last_nonce = quote.nonce;
+        accounts.market_maker.exit(&crate::ID)?;
+
+        emit!(QuoteFilled {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            market_maker: accounts.market_maker.key(),
+            taker: self.signer.key(),
+            is_a,
+            amount_in: quote.amount_in,
+            amount_out: quote.amount_out,
+            nonce: quote.nonce,
+        });
+
+        let result = SwapResult {
+            amount_in: quote.amount_in,
+            amount_out: quote.amount_out,
+            fee_paid: 0,
+        };
+        invoke_swap_hook(&mut self.pool, self.hook_program.as_ref(), &self.signer.to_account_info(), hook_accounts, is_a, &result, 0, None)?;
+        result.set_return_data()
+    }
+
+    /// 报价的输入是否少于曲线成交同样数量所需的含手续费输入
+    fn quote_beats_curve(&self, quote: &SignedQuote, now: i64) -> Result<bool> {
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+        let (reserve_in, reserve_out) = if quote.is_a { (reserve_b, reserve_a) } else { (reserve_a, reserve_b) };
+
+        // 曲线无法成交（超过价格冲击限制、储备量不足等）时只能按报价成交
+        Ok(match self.pool.quote_exact_out(reserve_in, reserve_out, quote.amount_out, quote.is_a, now) {
+            Ok((_, curve_amount_in)) => quote.amount_in < curve_amount_in,
+            Err(_) => true,
+        })
+    }
+}
+
+impl SwapResult {
+    /// 序列化后写入 return data
+    pub fn set_return_data(&self) -> Result<()> {
+        let mut data = Vec::with_capacity(24);
+        self.serialize(&mut data).map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+        set_return_data(&data);
+        Ok(())
+    }
+}
+
+impl Pool {
+    /// 精确输出报价：想拿走 amount_out 个输出代币需要付出的 (不含手续费的输入, 含手续费的输入)
+    ///
+    /// swap、flash_swap 和 DCA 共用，统一处理最小数量、价格冲击限制、取走储备量的比例和曲线选择
+    pub fn quote_exact_out(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, is_a: bool, now: i64) -> Result<(u64, u64)> {
+        self.quote_exact_out_at_fee(reserve_in, reserve_out, amount_out, is_a, now, self.fee)
+    }
+
+    /// 按指定手续费（基点）报价，swap 的持有者折扣使用
+    pub fn quote_exact_out_at_fee(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, is_a: bool, now: i64, fee: u16) -> Result<(u64, u64)> {
+        self.check_trading_window(now)?;
+        self.check_trade_direction(is_a)?;
+
+        // 0 数量的 swap 只会白白消耗手续费和 CPI；过小的 swap 每次都按有利于池子的方向取整，
+        // 反复交易会放大舍入误差，可以用 min_trade_amount 拒绝
+        require_gt!(amount_out, 0, AmmError::ZeroAmount);
+        require_gte!(amount_out, self.min_trade_amount, AmmError::TradeTooSmall);
+
+        // ==========================================
+        // 价格冲击保护（防夹子 / 防巨鲸）
+        // ==========================================
+        // 不含手续费，max_price_impact_bps 为 0 时不检查
+        if self.max_price_impact_bps > 0 {
+            let impact_bps = price_impact_bps(reserve_out, amount_out)?;
+            require!(impact_bps <= self.max_price_impact_bps as u128, AmmError::PriceImpactTooHigh);
+        }
+
+        // 小池子防误操作：一笔 swap 不能取走输出侧储备量的太大比例，max_drain_bps 为 0 时不检查
+        if self.max_drain_bps > 0 {
+            require!(
+                amount_out as u128 * BPS_DENOMINATOR <= reserve_out as u128 * self.max_drain_bps as u128,
+                AmmError::ReserveDrainTooHigh
+            );
+        }
+
+        // 按 curve_type 选择不变量，LBP 期间的权重按时间插值
+        self.curve(now).swap_in_given_out(reserve_in, reserve_out, amount_out, is_a, fee)
+    }
+
+    /// 限时池子只在 [start_ts, end_ts) 内允许 swap，各个 swap 类指令都通过报价函数检查
+    pub fn check_trading_window(&self, now: i64) -> Result<()> {
+        require!(now >= self.start_ts, AmmError::TradingNotStarted);
+        require!(self.end_ts == 0 || now < self.end_ts, AmmError::TradingEnded);
+        Ok(())
+    }
+
+    /// is_a 与 swap 相同：true 表示买入 TokenA（B→A），false 表示卖出 TokenA（A→B）
+    pub fn check_trade_direction(&self, is_a: bool) -> Result<()> {
+        if is_a {
+            require!(!self.disable_swap_b_to_a, AmmError::SwapBToADisabled);
+        } else {
+            require!(!self.disable_swap_a_to_b, AmmError::SwapAToBDisabled);
+        }
+        Ok(())
+    }
+
+    /// 不按比例的存入相当于把多出的一侧 swap 成另一侧，按多出的一侧检查方向
+    pub fn check_imbalanced_direction(&self, reserve_a: u64, reserve_b: u64, amount_a: u64, amount_b: u64) -> Result<()> {
+        let excess_a = amount_a as u128 * reserve_b as u128;
+        let excess_b = amount_b as u128 * reserve_a as u128;
+        if excess_a > excess_b {
+            self.check_trade_direction(false)
+        } else if excess_b > excess_a {
+            self.check_trade_direction(true)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 按含手续费的输入（用户实际付出的数量）拆出 (创建者分成, 协议分成)，两者都转入手续费账户，不计入储备量
+    ///
+    /// set_protocol_fee 保证 creator_fee_bps + protocol_fee_bps 不超过 10000，两者之和不会超过手续费，
+    /// 其余的手续费随输入一起转入池子，由 LP 共享
+    pub fn fee_shares(&self, amount_in_with_fees: u64) -> Result<(u64, u64)> {
+        self.fee_shares_at_fee(amount_in_with_fees, self.fee)
+    }
+
+    /// 按实际收取的手续费（基点）拆分，打折后的手续费分成同比例减少
+    pub fn fee_shares_at_fee(&self, amount_in_with_fees: u64, fee: u16) -> Result<(u64, u64)> {
+        // Creator 模式下 LP 的那部分也归创建者，LP 模式的协议分成仍然留在储备量中按 k 的增长结算
+        let creator_fee_bps = match self.fee_destination_mode {
+            FeeDestinationMode::Lp => self.creator_fee_bps,
+            FeeDestinationMode::Creator => (BPS_DENOMINATOR as u16).saturating_sub(self.protocol_fee_bps),
+        };
+        let creator_fee = creator_fee_amount(amount_in_with_fees, fee, creator_fee_bps)?;
+        // LP 模式下协议分成留在储备量中，存取流动性时以 LP 结算，见 protocol_fee.rs
+        let protocol_fee = if self.skims_protocol_fee() {
+            creator_fee_amount(amount_in_with_fees, fee, self.protocol_fee_bps)?
+        } else {
+            0
+        };
+        Ok((creator_fee, protocol_fee))
+    }
+
+    /// 记录手续费账户中属于协议的部分，is_a 与 swap 相同，true 表示输入是 TokenB
+    pub fn accrue_protocol_fee(&mut self, is_a: bool, protocol_fee: u64) -> Result<()> {
+        let protocol_fees = if is_a { &mut self.protocol_fees_b } else { &mut self.protocol_fees_a };
+        *protocol_fees = protocol_fees.checked_add(protocol_fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
     }
 }
\ No newline at end of file
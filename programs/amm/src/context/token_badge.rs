@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    context::admin::is_config_admin,
+    error::AmmError,
+    state::{AmmConfig, TokenBadge, ACCOUNT_VERSION},
+};
+
+// ========================================
+// 代币徽章（策展部署模式）
+// ========================================
+//
+// 需要限制上架资产的部署（例如 RWA 交易场所）可以打开 config.require_token_badges：
+// 之后 initialize / initialize_multi_pool 的每个 mint 都必须有协议管理员签发的 TokenBadge
+// （PDA：["token_badge", mint]）。开关和徽章都只影响之后创建的池子，撤销徽章不影响已有的池子。
+
+#[derive(Accounts)]
+pub struct IssueTokenBadge<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+    mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        space = TokenBadge::DISCRIMINATOR.len() + TokenBadge::INIT_SPACE,
+        seeds = [b"token_badge", mint.key().as_ref()],
+        bump
+    )]
+    token_badge: Account<'info, TokenBadge>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> IssueTokenBadge<'info> {
+    pub fn issue_token_badge(&mut self, bump: u8) -> Result<()> {
+        self.token_badge.set_inner(TokenBadge {
+            version: ACCOUNT_VERSION,
+            mint: self.mint.key(),
+            bump,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeTokenBadge<'info> {
+    /// CHECK: 只比较 key 并要求签名，兼容多签 PDA，见 is_config_admin
+    #[account(mut, constraint = is_config_admin(&authority, &config) @ AmmError::InvalidAuthority)]
+    authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config: Account<'info, AmmConfig>,
+    // 租金退还给协议管理员
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"token_badge", token_badge.mint.as_ref()],
+        bump = token_badge.bump
+    )]
+    token_badge: Account<'info, TokenBadge>,
+}
+
+impl<'info> RevokeTokenBadge<'info> {
+    pub fn revoke_token_badge(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl TokenBadge {
+    /// 校验通过 remaining_accounts 传入的徽章账户属于 mint
+    pub fn verify<'info>(account: &'info AccountInfo<'info>, mint: &Pubkey) -> Result<()> {
+        let badge = Account::<TokenBadge>::try_from(account).map_err(|_| AmmError::TokenBadgeRequired)?;
+        let address = Pubkey::create_program_address(&[b"token_badge", mint.as_ref(), &[badge.bump]], &crate::ID)
+            .map_err(|_| AmmError::TokenBadgeRequired)?;
+        require_keys_eq!(account.key(), address, AmmError::TokenBadgeRequired);
+        require_keys_eq!(badge.mint, *mint, AmmError::TokenBadgeRequired);
+        Ok(())
+    }
+}
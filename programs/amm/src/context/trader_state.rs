@@ -0,0 +1,165 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::AmmError, state::{Pool, TraderState, TraderStateV1, ACCOUNT_VERSION}};
+
+// ========================================
+// 每个钱包的 swap 限额 / 往返 swap 附加费
+// ========================================
+//
+// 发射新币的池子可以限制每个钱包在一段 slot 窗口内最多买走多少代币。
+//
+// 池子也可以对同一钱包短时间内的往返 swap 加收附加费：上一笔是 A→B，
+// round_trip_window_slots 个 slot 内又做了 B→A（或者反过来），这一笔按 fee + round_trip_fee_bps 报价。
+// 附加费不参与创建者和协议分成，全部留在储备量中归 LP，让用同一个钱包执行的夹子两条腿无利可图。
+// 换一个钱包执行第二条腿可以绕过，这只是增加摩擦，不能替代滑点保护。
+// flash_swap 不记录 swap 方向，开启附加费的池子里直接拒绝；deposit_imbalanced 和自动复投金库的 compound
+// 包含一笔隐含的 swap，既不计入限额也不记录方向，开启了限额或附加费的池子里都直接拒绝。
+//
+// 每个钱包需要先调用一次 init_trader_state 创建自己的记录，
+// 之后在开启了限额或附加费的池子里 swap 时把这个账户作为 trader_state 传入。
+// 往返附加费之前创建的 v1 记录比当前布局短，需要先调用一次 migrate_trader_state 扩容。
+
+#[derive(Accounts)]
+pub struct InitTraderState<'info> {
+    #[account(mut)]
+    signer: Signer<'info>,
+    pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = signer,
+        space = TraderState::DISCRIMINATOR.len() + TraderState::INIT_SPACE,
+        seeds = [b"trader", pool.key().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    trader_state: Account<'info, TraderState>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> InitTraderState<'info> {
+    pub fn init_trader_state(&mut self, bump: u8) -> Result<()> {
+        self.trader_state.set_inner(TraderState {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            trader: self.signer.key(),
+            window_start_slot: Clock::get()?.slot,
+            out_a: 0,
+            out_b: 0,
+            bump,
+            last_swap_slot: 0,
+            last_swap_is_a: None,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigrateTraderState<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: 只用作种子
+    pool: UncheckedAccount<'info>,
+    /// CHECK: 只用作种子，记录所属的钱包
+    trader: UncheckedAccount<'info>,
+    /// CHECK: v1 布局无法用 Account<TraderState> 反序列化，通过种子和 owner 校验，数据在指令中手动解析
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"trader", pool.key().as_ref(), trader.key().as_ref()],
+        bump
+    )]
+    trader_state: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateTraderState<'info> {
+    /// 无需权限：把 v1 记录扩容到当前布局，窗口计数原样保留，往返附加费从没有 swap 过开始记录
+    pub fn migrate_trader_state(&mut self) -> Result<()> {
+        let info = self.trader_state.to_account_info();
+
+        let v1 = {
+            let data = info.try_borrow_data()?;
+            require!(data.starts_with(TraderState::DISCRIMINATOR), AmmError::InvalidAccountVersion);
+            let mut body = &data[TraderState::DISCRIMINATOR.len()..];
+            require!(body.len() == TraderStateV1::INIT_SPACE && body.first() == Some(&1), AmmError::InvalidAccountVersion);
+            TraderStateV1::deserialize(&mut body)?
+        };
+
+        let new_len = TraderState::DISCRIMINATOR.len() + TraderState::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+        if rent > 0 {
+            let accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: info.clone(),
+            };
+
+            let ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                accounts
+            );
+
+            transfer(ctx, rent)?;
+        }
+
+        info.resize(new_len)?;
+
+        let trader_state = TraderState {
+            version: ACCOUNT_VERSION,
+            pool: v1.pool,
+            trader: v1.trader,
+            window_start_slot: v1.window_start_slot,
+            out_a: v1.out_a,
+            out_b: v1.out_b,
+            bump: v1.bump,
+            last_swap_slot: 0,
+            last_swap_is_a: None,
+        };
+
+        // try_serialize 会连同 discriminator 一起写入
+        let mut data = info.try_borrow_mut_data()?;
+        trader_state.try_serialize(&mut &mut data[..])
+    }
+}
+
+impl TraderState {
+    /// 记录一笔 swap 的输出数量，超过当前窗口的限额时报错
+    pub fn record_swap(&mut self, pool: &Pool, amount_out: u64, is_a: bool, slot: u64) -> Result<()> {
+        // 固定窗口：超过窗口长度就从当前 slot 重新开始计数
+        if slot >= self.window_start_slot.saturating_add(pool.rate_limit_window_slots) {
+            self.window_start_slot = slot;
+            self.out_a = 0;
+            self.out_b = 0;
+        }
+
+        let (used, limit) = if is_a {
+            (&mut self.out_a, pool.max_out_a_per_window)
+        } else {
+            (&mut self.out_b, pool.max_out_b_per_window)
+        };
+
+        *used = used.checked_add(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+        if limit > 0 {
+            require_gte!(limit, *used, AmmError::RateLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// 记录这笔 swap 的方向，返回需要加收的往返附加费（基点），没有开启或者不是往返时为 0
+    pub fn record_direction(&mut self, pool: &Pool, is_a: bool, slot: u64) -> u16 {
+        let round_trip = pool.round_trip_window_slots > 0
+            && self.last_swap_is_a == Some(!is_a)
+            && slot <= self.last_swap_slot.saturating_add(pool.round_trip_window_slots);
+
+        self.last_swap_slot = slot;
+        self.last_swap_is_a = Some(is_a);
+
+        if round_trip { pool.round_trip_fee_bps } else { 0 }
+    }
+}
+
+impl Pool {
+    /// 开启了钱包限额或往返附加费的池子，swap 必须传入 trader_state；
+    /// 其他不经过 swap 的成交路径无法传入，对这些池子直接拒绝
+    pub fn requires_trader_state(&self) -> bool {
+        self.rate_limit_window_slots > 0 || self.round_trip_window_slots > 0
+    }
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AmmError,
+    state::{Pool, PriceFeed, ACCOUNT_VERSION, PRICE_FEED_EXPONENT},
+    token_account::read_token_amount,
+};
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    // 任何人都可以刷新价格 feed
+    #[account(mut)]
+    signer: Signer<'info>,
+    /// CHECK: 地址必须等于 pool.vault_a
+    #[account(address = pool.vault_a)]
+    pool_ata_a: UncheckedAccount<'info>,
+    /// CHECK: 地址必须等于 pool.vault_b
+    #[account(address = pool.vault_b)]
+    pool_ata_b: UncheckedAccount<'info>,
+    #[account(mut, constraint = !pool.locked @ AmmError::PoolLocked)]
+    pool: Account<'info, Pool>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = PriceFeed::DISCRIMINATOR.len() + PriceFeed::INIT_SPACE,
+        seeds = [b"price_feed", pool.key().as_ref()],
+        bump
+    )]
+    price_feed: Account<'info, PriceFeed>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> UpdatePriceFeed<'info> {
+    pub fn update_price_feed(&mut self, bump: u8) -> Result<()> {
+        let reserve_a = read_token_amount(&self.pool_ata_a)?;
+        let reserve_b = read_token_amount(&self.pool_ata_b)?;
+        require!(reserve_a > 0 && reserve_b > 0, AmmError::EmptyPool);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.update_oracle(reserve_a, reserve_b, now)?;
+
+        // 现价 Q64.64 = reserve_b / reserve_a
+        let spot = ((reserve_b as u128) << 64)
+            .checked_div(reserve_a as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+        // 观测不足时 TWAP 退化为现价
+        let twap = self.pool.twap_a().unwrap_or(spot);
+
+        // 按两种代币的小数位数换算成「每枚 TokenA 值多少枚 TokenB」
+        let price = q64_to_feed_price(self.pool.normalize_price_a(spot)?)?;
+        let twap = q64_to_feed_price(self.pool.normalize_price_a(twap)?)?;
+
+        self.price_feed.set_inner(PriceFeed {
+            version: ACCOUNT_VERSION,
+            pool: self.pool.key(),
+            price,
+            conf: price.abs_diff(twap),
+            expo: PRICE_FEED_EXPONENT,
+            publish_time: now,
+            twap,
+            bump,
+        });
+        Ok(())
+    }
+}
+
+/// Q64.64 价格转换成 price * 10^-expo 的整数
+fn q64_to_feed_price(value: u128) -> Result<i64> {
+    let scale = 10u128.pow(PRICE_FEED_EXPONENT.unsigned_abs());
+    let scaled = (value >> 32)
+        .checked_mul(scale).ok_or(ProgramError::ArithmeticOverflow)?
+        >> 32;
+    Ok(scaled.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?)
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::get_associated_token_address, token::{Mint, TokenAccount}};
+
+use crate::{
+    error::AmmError,
+    events::PoolHealth,
+    state::{CurveType, Pool, ProtocolFeeMode},
+};
+
+// ========================================
+// 池子自检（监控用）
+// ========================================
+//
+// 任何人都可以调用 verify_pool，检查池子账户之间的一致性，结果写入 PoolHealth 事件：
+// 监控机器人定期模拟这条指令，从日志中读取事件，issues 不为 0 时报警。
+// 有任何一项不通过时，发出事件之后指令返回 PoolUnhealthy，其他程序也可以在同一笔交易中用它做断言。
+//
+// 储备量始终直接读取池子关联代币账户的余额，没有单独记账，所以不存在“记录的储备量与余额不一致”。
+// 有人直接转入池子账户的代币（没有经过 donate）会计入储备量，归全体 LP 所有，不算异常。
+//
+// 检查项（issues 的各个位）：
+// - POOL_SEEDS：用保存的 bump 重新推导 pool PDA，结果必须等于池子地址
+// - LP_MINT：用保存的 lp_bump 重新推导 LP mint，mint authority 必须是池子
+// - VAULTS：vault_a / vault_b 必须是池子的关联代币账户
+// - FEE_VAULTS：手续费账户的 mint 与 owner 必须与池子一致
+// - PROTOCOL_FEES：手续费账户的余额必须覆盖记录的协议手续费
+// - LP_SUPPLY：LP 供应量不为 0 时两侧储备量都不能为 0
+// - K_LAST：LP 模式下 k 只会因为 swap 和 donate 增长，不能低于 k_last
+
+pub const HEALTH_POOL_SEEDS: u32 = 1 << 0;
+pub const HEALTH_LP_MINT: u32 = 1 << 1;
+pub const HEALTH_VAULTS: u32 = 1 << 2;
+pub const HEALTH_FEE_VAULTS: u32 = 1 << 3;
+pub const HEALTH_PROTOCOL_FEES: u32 = 1 << 4;
+pub const HEALTH_LP_SUPPLY: u32 = 1 << 5;
+pub const HEALTH_K_LAST: u32 = 1 << 6;
+
+#[derive(Accounts)]
+pub struct VerifyPool<'info> {
+    // 种子和 lp_bump 由指令自己检查并报告，这里不加 seeds 约束，否则异常时只能得到一个约束错误
+    mint_lp: Box<Account<'info, Mint>>,
+    #[account(address = pool.vault_a)]
+    pool_ata_a: Box<Account<'info, TokenAccount>>,
+    #[account(address = pool.vault_b)]
+    pool_ata_b: Box<Account<'info, TokenAccount>>,
+    #[account(address = pool.creator_fee_vault_a)]
+    creator_fee_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(address = pool.creator_fee_vault_b)]
+    creator_fee_vault_b: Box<Account<'info, TokenAccount>>,
+    // 可写只是为了递增 event_seq；flash_swap 回调期间储备量处于中间状态，不做检查
+    #[account(mut, constraint = !pool.locked @ AmmError::PoolLocked)]
+    pool: Box<Account<'info, Pool>>,
+}
+
+impl<'info> VerifyPool<'info> {
+    pub fn verify_pool(&mut self) -> Result<()> {
+        let pool_key = self.pool.key();
+        let reserve_a = self.pool_ata_a.amount;
+        let reserve_b = self.pool_ata_b.amount;
+        let lp_supply = self.mint_lp.supply;
+        let mut issues = 0u32;
+
+        let fee = self.pool.fee.to_le_bytes();
+        let pool_seeds: &[&[u8]] = &[b"pool", self.pool.mint_a.as_ref(), self.pool.mint_b.as_ref(), fee.as_ref(), &[self.pool.bump]];
+        if Pubkey::create_program_address(pool_seeds, &crate::ID) != Ok(pool_key) {
+            issues |= HEALTH_POOL_SEEDS;
+        }
+
+        let lp_seeds: &[&[u8]] = &[b"lp", pool_key.as_ref(), &[self.pool.lp_bump]];
+        if Pubkey::create_program_address(lp_seeds, &crate::ID) != Ok(self.mint_lp.key())
+            || !self.mint_lp.mint_authority.contains(&pool_key)
+        {
+            issues |= HEALTH_LP_MINT;
+        }
+
+        if self.pool.vault_a != get_associated_token_address(&pool_key, &self.pool.mint_a)
+            || self.pool.vault_b != get_associated_token_address(&pool_key, &self.pool.mint_b)
+        {
+            issues |= HEALTH_VAULTS;
+        }
+
+        if self.creator_fee_vault_a.mint != self.pool.mint_a
+            || self.creator_fee_vault_a.owner != pool_key
+            || self.creator_fee_vault_b.mint != self.pool.mint_b
+            || self.creator_fee_vault_b.owner != pool_key
+        {
+            issues |= HEALTH_FEE_VAULTS;
+        }
+
+        if self.creator_fee_vault_a.amount < self.pool.protocol_fees_a
+            || self.creator_fee_vault_b.amount < self.pool.protocol_fees_b
+        {
+            issues |= HEALTH_PROTOCOL_FEES;
+        }
+
+        if lp_supply > 0 && (reserve_a == 0 || reserve_b == 0) {
+            issues |= HEALTH_LP_SUPPLY;
+        }
+
+        let k = reserve_a as u128 * reserve_b as u128;
+        if self.pool.protocol_fee_mode == ProtocolFeeMode::Lp
+            && self.pool.curve_type == CurveType::ConstantProduct
+            && k < self.pool.k_last
+        {
+            issues |= HEALTH_K_LAST;
+        }
+
+        emit!(PoolHealth {
+            pool: pool_key,
+            event_seq: self.pool.next_event_seq()?,
+            reserve_a,
+            reserve_b,
+            lp_supply,
+            k,
+            k_last: self.pool.k_last,
+            protocol_fees_a: self.pool.protocol_fees_a,
+            protocol_fees_b: self.pool.protocol_fees_b,
+            issues,
+        });
+
+        require!(issues == 0, AmmError::PoolUnhealthy);
+        Ok(())
+    }
+}
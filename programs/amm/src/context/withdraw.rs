@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token::{burn, spl_token::native_mint, transfer, Burn, Mint, Token, TokenAccount, Transfer}};
 
-use crate::state::Pool;
+use crate::{error::AmmError, events::LiquidityWithdrawn, guard::has_distinct_keys, protocol_fee::settle_protocol_fee, math::BPS_DENOMINATOR, state::{LiquidityGuard, Pool, PoolStatus, UserPortfolio}, token_account::unwrap_wsol};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -12,11 +12,12 @@ pub struct Withdraw<'info> {
     #[account(
         mut,
         seeds = [b"lp", pool.key().as_ref()],
-        bump
+        bump = pool.lp_bump
     )]
     mint_lp: Account<'info, Mint>,
     #[account(
         mut,
+        constraint = has_distinct_keys(&[signer_ata_a.key(), signer_ata_b.key(), signer_ata_lp.key(), pool_ata_a.key(), pool_ata_b.key()]) @ AmmError::DuplicateMutableAccount,
         associated_token::authority = signer,
         associated_token::mint = mint_a
     )]
@@ -46,10 +47,32 @@ pub struct Withdraw<'info> {
     )]
     pool_ata_b: Account<'info, TokenAccount>,
     #[account(
+        mut,
         seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), pool.fee.to_le_bytes().as_ref()],
-        bump = pool.bump
+        bump = pool.bump,
+        has_one = mint_a @ AmmError::MintAMismatch,
+        has_one = mint_b @ AmmError::MintBMismatch,
+        constraint = !pool.locked @ AmmError::PoolLocked,
+        constraint = pool.status != PoolStatus::Paused @ AmmError::PoolPaused
     )]
     pool: Account<'info, Pool>,
+    // 协议手续费为 LP 模式的池子需要传入，地址必须等于 pool.treasury_lp，其他池子传 None
+    #[account(mut, address = pool.treasury_lp)]
+    treasury_lp: Option<Box<Account<'info, TokenAccount>>>,
+    // 开启 jit_protection 的池子需要传入，其他池子传 None，见 liquidity_guard.rs
+    #[account(
+        mut,
+        seeds = [b"lp_guard", pool.key().as_ref(), signer.key().as_ref()],
+        bump = liquidity_guard.bump
+    )]
+    liquidity_guard: Option<Account<'info, LiquidityGuard>>,
+    // 可选：传入时更新调用者的仓位列表，见 portfolio.rs
+    #[account(
+        mut,
+        seeds = [b"portfolio", signer.key().as_ref()],
+        bump = portfolio.bump
+    )]
+    portfolio: Option<Account<'info, UserPortfolio>>,
     token_program: Program<'info, Token>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
@@ -89,39 +112,24 @@ let k2 = k.checked_sub(amount as u128).ok_or(...)?; // ❌ 错误逻辑
 整个AMM系统现在运行完美，代币守恒得到保证，数学计算精确无误！ 🚀
 */
 impl<'info> Withdraw<'info> {
-    pub fn withdraw(&mut self, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
+    /// remaining_accounts: 开启外部预言机保护的池子传入预言机账户，见 external_oracle.rs
+    ///
+    /// tag: 调用者自定义的标记，在 LiquidityWithdrawn 事件中原样回显
+    /// unwrap: 池子有一侧是 WSOL 时，取出之后关闭 signer 的 WSOL 账户，SOL 直接退回 signer
+    pub fn withdraw(&mut self, amount: u64, min_token_a: u64, min_token_b: u64, tag: Option<[u8; 16]>, unwrap: bool, remaining_accounts: &[AccountInfo]) -> Result<()> {
         // ========================================
         // 正确的流动性提取计算逻辑
         // ========================================
-        
-        // 获取当前LP代币总供应量
-        let lp_total_supply = self.mint_lp.supply;
-        
-        // 防止除零错误
-        require_gt!(lp_total_supply, 0);
-        require_gt!(amount, 0);
-        require_gte!(lp_total_supply, amount);
-
-        // 计算提取比例：要销毁的LP代币数量 / LP代币总供应量
-        // 使用高精度计算避免溢出：比例 = amount / lp_total_supply
-        // 为了保持精度，我们使用 1e6 作为精度倍数
-        let withdraw_ratio = (amount as u128)
-            .checked_mul(1_000_000u128).ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(lp_total_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
-
-        // 根据提取比例计算应该获得的TokenA数量
-        // amount_a = pool_a_balance * withdraw_ratio / 1_000_000
-        let amount_a: u64 = (self.pool_ata_a.amount as u128)
-            .checked_mul(withdraw_ratio).ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(1_000_000u128).ok_or(ProgramError::ArithmeticOverflow)?
-            .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        require_gt!(amount, 0, AmmError::ZeroAmount);
+        self.require_not_frozen()?;
+        LiquidityGuard::check_withdraw(&self.pool, self.liquidity_guard.as_deref(), Clock::get()?.slot)?;
 
-        // 根据提取比例计算应该获得的TokenB数量  
-        // amount_b = pool_b_balance * withdraw_ratio / 1_000_000
-        let amount_b: u64 = (self.pool_ata_b.amount as u128)
-            .checked_mul(withdraw_ratio).ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(1_000_000u128).ok_or(ProgramError::ArithmeticOverflow)?
-            .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let now = Clock::get()?.unix_timestamp;
+        self.pool.update_oracle(self.pool_ata_a.amount, self.pool_ata_b.amount, now)?;
+        settle_protocol_fee(&self.pool, &mut self.mint_lp, self.treasury_lp.as_deref(), &self.token_program, self.pool_ata_a.amount, self.pool_ata_b.amount)?;
+
+        // 按 amount / lp_total_supply 的比例取出两种代币，向下取整
+        let (amount_a, amount_b) = self.pool.curve(now).withdraw_amounts(self.pool_ata_a.amount, self.pool_ata_b.amount, self.mint_lp.supply, amount)?;
 
         // Check slippage A
         require_gte!(amount_a, min_token_a);
@@ -129,9 +137,13 @@ impl<'info> Withdraw<'info> {
         // Check slippage B
         require_gte!(amount_b, min_token_b);
 
-        let binding = self.pool.fee.to_le_bytes();
+        // 按取出之后的储备量检查外部预言机价格带
+        let (oracle, _) = self.pool.split_external_oracle(remaining_accounts)?;
+        self.pool.check_external_oracle(oracle, self.pool_ata_a.amount - amount_a, self.pool_ata_b.amount - amount_b, now)?;
+
+        let pool_seeds = self.pool.signer_seeds();
 
-        let signer_seeds: [&[&[u8]];1] = [&[&b"pool"[..], self.mint_a.to_account_info().key.as_ref(), self.mint_b.to_account_info().key.as_ref(), binding.as_ref(), &[self.pool.bump]]];
+        let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
 
         // Withdraw Token A Amount
         let accounts = Transfer {
@@ -175,6 +187,55 @@ impl<'info> Withdraw<'info> {
             accounts
         );
 
-        burn(ctx, amount)
+        burn(ctx, amount)?;
+
+        // 全部取出之后把池子从仓位列表中移除，signer_ata_lp 的余额是销毁前读取的
+        UserPortfolio::record_withdraw(self.portfolio.as_deref_mut(), self.pool.key(), self.signer_ata_lp.amount - amount);
+
+        // 取出之后记录新的 k，pool_ata 的余额是取出前读取的
+        self.pool.update_k_last(self.pool_ata_a.amount - amount_a, self.pool_ata_b.amount - amount_b);
+
+        emit!(LiquidityWithdrawn {
+            pool: self.pool.key(),
+            event_seq: self.pool.next_event_seq()?,
+            owner: self.signer.key(),
+            amount_lp: amount,
+            amount_a,
+            amount_b,
+            tag,
+        });
+
+        if unwrap {
+            let (mint, signer_ata) = if self.mint_a.key() == native_mint::ID {
+                (self.mint_a.key(), &self.signer_ata_a)
+            } else {
+                (self.mint_b.key(), &self.signer_ata_b)
+            };
+            unwrap_wsol(&self.token_program, &mint, signer_ata.to_account_info(), self.signer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// 转账之前检查两侧的代币账户都没有被冻结，两个 mint 都没有 freeze authority 时不检查
+    fn require_not_frozen(&self) -> Result<()> {
+        if !self.pool.has_freezable_mint {
+            return Ok(());
+        }
+        let frozen = self.signer_ata_a.is_frozen() || self.signer_ata_b.is_frozen() || self.pool_ata_a.is_frozen() || self.pool_ata_b.is_frozen();
+        require!(!frozen, AmmError::AccountFrozen);
+        Ok(())
+    }
+
+    /// 按用户当前 LP 余额的百分比（基点）取出流动性
+    /// LP 数量在链上读取余额后计算，UI 提供的 25%/50%/100% 不会因为余额变化而失效
+    pub fn withdraw_percent(&mut self, bps: u16, min_token_a: u64, min_token_b: u64, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        require!(bps > 0 && bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidBps);
+
+        let amount: u64 = (self.signer_ata_lp.amount as u128)
+            .checked_mul(bps as u128).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?
+            .try_into().map_err(|_| ProgramError::ArithmeticOverflow)?;
+
+        self.withdraw(amount, min_token_a, min_token_b, None, false, remaining_accounts)
     }
 }
@@ -0,0 +1,171 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AmmError,
+    math::{
+        deposit_amounts, imbalanced_deposit_lp, stable_swap_amount_in, stable_swap_amount_out, stable_swap_d, swap_amount_in,
+        swap_amount_out, weighted_invariant_log2, weighted_swap_amount_in, weighted_swap_amount_out, withdraw_amounts, BPS_DENOMINATOR,
+    },
+    state::{CurveType, Pool},
+};
+
+// ========================================
+// 可插拔的不变量（曲线）
+// ========================================
+//
+// 每种曲线实现 CurveCalculator，指令只通过 Pool::curve 拿到当前曲线再调用，
+// 不关心具体是哪一种。新增曲线只需要：
+// 1. 在 CurveType 中加一个变体
+// 2. 实现 CurveCalculator
+// 3. 在 Pool::curve 中加一个分支
+//
+// 所有实现都遵循 math.rs 的舍入约定：用户付给池子的向上取整，池子付给用户的向下取整。
+// 参数 is_a 与 swap 相同，true 表示买入 TokenA，只有加权曲线需要用它区分两侧的权重。
+
+/// StableSwap 放大系数 A 的范围
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 10_000;
+
+pub trait CurveCalculator {
+    /// 精确输出：拿走 amount_out 需要的 (不含手续费的输入, 含手续费的输入)
+    fn swap_in_given_out(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, is_a: bool, fee: u16) -> Result<(u64, u64)>;
+
+    /// 精确输入：付出 amount_in_with_fees 最多可以拿走的输出
+    fn swap_out_given_in(&self, reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, is_a: bool, fee: u16) -> Result<u64>;
+
+    /// 按比例存入：铸造 amount_lp 需要的 (amount_a, amount_b)
+    ///
+    /// 同比例放大两侧储备量不会改变任何一种曲线的价格，默认实现对所有曲线都成立
+    fn deposit_amounts(&self, reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_lp: u64) -> Result<(u64, u64)> {
+        deposit_amounts(reserve_a, reserve_b, lp_supply, amount_lp)
+    }
+
+    /// 按比例取出：销毁 amount_lp 可以拿回的 (amount_a, amount_b)
+    fn withdraw_amounts(&self, reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_lp: u64) -> Result<(u64, u64)> {
+        withdraw_amounts(reserve_a, reserve_b, lp_supply, amount_lp)
+    }
+
+    /// 任意比例存入可以铸造的 LP，需要按曲线自己的不变量计算，默认不支持
+    fn imbalanced_deposit_lp(&self, _reserve_a: u64, _reserve_b: u64, _lp_supply: u64, _amount_a: u64, _amount_b: u64, _fee: u16) -> Result<u64> {
+        err!(AmmError::UnsupportedCurve)
+    }
+
+    /// swap 转账之后按实际余额检查不变量没有变小，before / after 都是 (reserve_a, reserve_b)
+    ///
+    /// 手续费留在池子里，正确的报价只会让不变量变大；将来的数学回归会在这里直接失败，
+    /// 而不是悄悄把 LP 的价值流失出去。没有默认实现，新增的曲线必须给出自己的检查
+    fn check_invariant(&self, before: (u64, u64), after: (u64, u64)) -> Result<()>;
+
+    /// 检查曲线参数
+    fn validate(&self) -> Result<()>;
+}
+
+/// 恒定乘积 x * y = k
+pub struct ConstantProduct;
+
+impl CurveCalculator for ConstantProduct {
+    fn swap_in_given_out(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, _is_a: bool, fee: u16) -> Result<(u64, u64)> {
+        swap_amount_in(reserve_in, reserve_out, amount_out, fee)
+    }
+
+    fn swap_out_given_in(&self, reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, _is_a: bool, fee: u16) -> Result<u64> {
+        swap_amount_out(reserve_in, reserve_out, amount_in_with_fees, fee)
+    }
+
+    fn imbalanced_deposit_lp(&self, reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_a: u64, amount_b: u64, fee: u16) -> Result<u64> {
+        imbalanced_deposit_lp(reserve_a, reserve_b, lp_supply, amount_a, amount_b, fee)
+    }
+
+    /// new_a * new_b >= k，两个 u64 的乘积不会溢出 u128
+    fn check_invariant(&self, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        let k_before = before.0 as u128 * before.1 as u128;
+        let k_after = after.0 as u128 * after.1 as u128;
+        require_gte!(k_after, k_before, AmmError::InvariantViolated);
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// StableSwap，amp 为放大系数 A
+pub struct StableSwap {
+    pub amp: u64,
+}
+
+impl CurveCalculator for StableSwap {
+    fn swap_in_given_out(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, _is_a: bool, fee: u16) -> Result<(u64, u64)> {
+        stable_swap_amount_in(self.amp, reserve_in, reserve_out, amount_out, fee)
+    }
+
+    fn swap_out_given_in(&self, reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, _is_a: bool, fee: u16) -> Result<u64> {
+        stable_swap_amount_out(self.amp, reserve_in, reserve_out, amount_in_with_fees, fee)
+    }
+
+    /// D(after) >= D(before)，两次牛顿迭代都向上取整，报价时 y 同样向上取整，舍入不会让正确的报价失败
+    fn check_invariant(&self, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        let d_before = stable_swap_d(self.amp, before.0, before.1)?;
+        let d_after = stable_swap_d(self.amp, after.0, after.1)?;
+        require_gte!(d_after, d_before, AmmError::InvariantViolated);
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        require!((MIN_AMP..=MAX_AMP).contains(&self.amp), AmmError::InvalidCurveParameters);
+        Ok(())
+    }
+}
+
+/// 加权恒定乘积 x^w_a * y^w_b = k，权重以基点表示（LBP 使用）
+pub struct Weighted {
+    pub weight_a: u16,
+    pub weight_b: u16,
+}
+
+impl Weighted {
+    /// (weight_in, weight_out)
+    fn oriented(&self, is_a: bool) -> (u16, u16) {
+        if is_a { (self.weight_b, self.weight_a) } else { (self.weight_a, self.weight_b) }
+    }
+}
+
+impl CurveCalculator for Weighted {
+    fn swap_in_given_out(&self, reserve_in: u64, reserve_out: u64, amount_out: u64, is_a: bool, fee: u16) -> Result<(u64, u64)> {
+        let (weight_in, weight_out) = self.oriented(is_a);
+        weighted_swap_amount_in(reserve_in, reserve_out, amount_out, weight_in, weight_out, fee)
+    }
+
+    fn swap_out_given_in(&self, reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, is_a: bool, fee: u16) -> Result<u64> {
+        let (weight_in, weight_out) = self.oriented(is_a);
+        weighted_swap_amount_out(reserve_in, reserve_out, amount_in_with_fees, weight_in, weight_out, fee)
+    }
+
+    /// 在对数空间比较 a^w_a * b^w_b，log2 的截断误差远小于报价在幂上加的 2^-40 安全余量
+    fn check_invariant(&self, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        let log_before = weighted_invariant_log2(before.0, before.1, self.weight_a, self.weight_b)?;
+        let log_after = weighted_invariant_log2(after.0, after.1, self.weight_a, self.weight_b)?;
+        require_gte!(log_after, log_before, AmmError::InvariantViolated);
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        require!(self.weight_a > 0 && self.weight_b > 0, AmmError::InvalidCurveParameters);
+        require!(self.weight_a as u128 + self.weight_b as u128 == BPS_DENOMINATOR, AmmError::InvalidCurveParameters);
+        Ok(())
+    }
+}
+
+impl Pool {
+    /// 按 curve_type 选择当前曲线，加权曲线的权重按 now 插值
+    pub fn curve(&self, now: i64) -> Box<dyn CurveCalculator> {
+        match self.curve_type {
+            CurveType::ConstantProduct => Box::new(ConstantProduct),
+            CurveType::StableSwap => Box::new(StableSwap { amp: self.amp }),
+            CurveType::Weighted => {
+                let (weight_a, weight_b) = self.lbp_weights(now);
+                Box::new(Weighted { weight_a, weight_b })
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum AmmError {
+    #[msg("Authority account does not match the pool authority")]
+    InvalidAuthority,
+    #[msg("Authority account must sign the transaction")]
+    AuthorityNotSigner,
+    #[msg("Swap price impact exceeds the pool limit")]
+    PriceImpactTooHigh,
+    #[msg("Pool mints must be different")]
+    IdenticalMints,
+    #[msg("An LP mint of this program cannot be used as a pool side")]
+    LpMintAsPoolSide,
+    #[msg("Pool mints must not have a freeze authority")]
+    MintHasFreezeAuthority,
+    #[msg("Pool mints must have a non-zero supply")]
+    ZeroSupplyMint,
+    #[msg("Basis points must be between 1 and 10000")]
+    InvalidBps,
+    #[msg("Fee exceeds the maximum allowed fee")]
+    InvalidFee,
+    #[msg("Fee is not one of the configured fee tiers")]
+    FeeTierNotAllowed,
+    #[msg("Too many fee tiers")]
+    TooManyFeeTiers,
+    #[msg("This pool does not accept swaps via CPI")]
+    CpiNotAllowed,
+    #[msg("Invalid LBP weight schedule")]
+    InvalidLbpSchedule,
+    #[msg("LBP can only be configured before liquidity is added")]
+    PoolNotEmpty,
+    #[msg("Pool is not running an LBP")]
+    LbpNotEnabled,
+    #[msg("Pool has no liquidity")]
+    EmptyPool,
+    #[msg("Pool is locked by an in-progress flash swap")]
+    PoolLocked,
+    #[msg("Flash swap was not repaid with the required amount")]
+    FlashSwapNotRepaid,
+    #[msg("Trader state account is required for rate limited pools")]
+    TraderStateRequired,
+    #[msg("Swap exceeds the per-wallet limit for the current window")]
+    RateLimitExceeded,
+    #[msg("Pool is not active")]
+    PoolNotActive,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("DCA order is not due yet")]
+    DcaNotDue,
+    #[msg("Invalid DCA order parameters")]
+    InvalidDcaOrder,
+    #[msg("Spot price deviates too far from the TWAP")]
+    CircuitBreakerTripped,
+    #[msg("mint_a does not match the pool's mint_a")]
+    MintAMismatch,
+    #[msg("mint_b does not match the pool's mint_b")]
+    MintBMismatch,
+    #[msg("Mint is not part of this pool")]
+    MintNotInPool,
+    #[msg("Creator fee exceeds the configured maximum")]
+    CreatorFeeTooHigh,
+    #[msg("Migration target must be a different active pool of the same pair")]
+    InvalidMigrationTarget,
+    #[msg("Pool account is not in a known layout")]
+    InvalidPoolVersion,
+    #[msg("Deposit would exceed the pool's reserve cap")]
+    DepositCapExceeded,
+    #[msg("Signer is not the approved delegate of the token account")]
+    DelegateNotApproved,
+    #[msg("Invalid curve parameters")]
+    InvalidCurveParameters,
+    #[msg("Operation is not supported by the pool's curve")]
+    UnsupportedCurve,
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Trade amount is below the pool's minimum")]
+    TradeTooSmall,
+    #[msg("Creator and protocol fee shares exceed the swap fee")]
+    ProtocolFeeTooHigh,
+    #[msg("Protocol token buyback is not configured")]
+    BuybackNotConfigured,
+    #[msg("Snapshot merkle root has already been published")]
+    SnapshotRootAlreadySet,
+    #[msg("Snapshot merkle root has not been published")]
+    SnapshotRootNotSet,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Checkpointed voting power exceeds the snapshot's LP supply")]
+    SnapshotSupplyExceeded,
+    #[msg("Invalid limit order parameters")]
+    InvalidLimitOrder,
+    #[msg("Pool price is worse than the order's limit price")]
+    LimitPriceNotMet,
+    #[msg("Pool mints protocol fees as LP; treasury_lp account is required")]
+    TreasuryLpRequired,
+    #[msg("Instruction is not supported while protocol fees are minted as LP")]
+    UnsupportedProtocolFeeMode,
+    #[msg("Pool failed its health check; see the PoolHealth event")]
+    PoolUnhealthy,
+    #[msg("Invalid OTC offer parameters")]
+    InvalidOtcOffer,
+    #[msg("OTC offer has expired")]
+    OtcOfferExpired,
+    #[msg("Pool TWAP is not available yet")]
+    TwapUnavailable,
+    #[msg("Multi-asset pool needs 3 to 8 distinct mints sorted by address")]
+    InvalidMultiPoolTokens,
+    #[msg("All mints of a multi-asset pool must have the same decimals")]
+    MultiPoolDecimalsMismatch,
+    #[msg("Remaining accounts do not match the multi-asset pool")]
+    InvalidMultiPoolAccounts,
+    #[msg("Fee rebate tiers must be sorted by balance with rebates of at most 10000 bps")]
+    InvalidFeeRebateTiers,
+    #[msg("Invalid fee rebate accounts")]
+    InvalidFeeRebateAccounts,
+    #[msg("Initial price numerator and denominator must both be zero or both be non-zero")]
+    InvalidInitialPrice,
+    #[msg("First deposit does not match the pool's declared initial price")]
+    InitialPriceMismatch,
+    #[msg("Pool stats account does not belong to this pool")]
+    InvalidPoolStats,
+    #[msg("Quote does not match this pool, taker or market maker")]
+    InvalidQuote,
+    #[msg("Quote is not signed by the market maker's signing key")]
+    InvalidQuoteSignature,
+    #[msg("Invalid market maker accounts")]
+    InvalidQuoteAccounts,
+    #[msg("Trading window end must be after its start and in the future")]
+    InvalidTradingWindow,
+    #[msg("Trading has not started yet")]
+    TradingNotStarted,
+    #[msg("Trading window has ended")]
+    TradingEnded,
+    #[msg("Split swap needs two distinct pools and an allocation of at most 10000 bps")]
+    InvalidAllocation,
+    #[msg("Mint needs a token badge to create pools in this deployment")]
+    TokenBadgeRequired,
+    #[msg("Emergency withdraw is not enabled for this pool")]
+    EmergencyWithdrawDisabled,
+    #[msg("Swapping TokenA for TokenB is disabled on this pool")]
+    SwapAToBDisabled,
+    #[msg("Swapping TokenB for TokenA is disabled on this pool")]
+    SwapBToADisabled,
+    #[msg("New pool account size must be a larger known Pool layout size")]
+    InvalidPoolSpace,
+    #[msg("Farm reward mint must differ from the LP mint")]
+    InvalidFarm,
+    #[msg("Pool invariant decreased after swap")]
+    InvariantViolated,
+    #[msg("This pool requires a LiquidityGuard account for deposits and withdrawals")]
+    LiquidityGuardRequired,
+    #[msg("Cannot withdraw in the same slot as a deposit")]
+    SameSlotWithdraw,
+    #[msg("Fee tier already exists")]
+    DuplicateFeeTier,
+    #[msg("Relayer fee exceeds the swap output")]
+    RelayerFeeTooHigh,
+    #[msg("relayer_ata account is required when relayer_fee is set")]
+    RelayerAccountRequired,
+    #[msg("This pool has a swap hook; hook_program account is required")]
+    SwapHookRequired,
+    #[msg("Not enough remaining accounts for the swap hook")]
+    SwapHookAccountsMissing,
+    #[msg("Swap hook exceeded its compute budget")]
+    SwapHookComputeExceeded,
+    #[msg("Too many swap hook accounts")]
+    TooManySwapHookAccounts,
+    #[msg("Swap hook cannot be the AMM program itself")]
+    InvalidSwapHook,
+    #[msg("Pair marker has no room for another pool")]
+    PairMarkerFull,
+    #[msg("LP of this pool is soulbound; use deposit_position")]
+    LpSoulbound,
+    #[msg("Pool does not use soulbound LP positions")]
+    PoolNotSoulbound,
+    #[msg("Position does not hold enough liquidity")]
+    InsufficientPositionLiquidity,
+    #[msg("Cannot transfer a position to itself")]
+    InvalidPositionTransfer,
+    #[msg("The same account was passed for more than one mutable token account")]
+    DuplicateMutableAccount,
+    #[msg("Price gap between the pools does not cover the fees")]
+    RebalanceNotProfitable,
+    #[msg("Rebalance amount pushes the prices past each other")]
+    RebalanceOvershoot,
+    #[msg("This pool is oracle-guarded; the external oracle account is required")]
+    ExternalOracleRequired,
+    #[msg("Invalid external oracle account")]
+    InvalidExternalOracle,
+    #[msg("External oracle price is stale")]
+    ExternalOracleStale,
+    #[msg("Pool price is outside the external oracle band")]
+    ExternalOraclePriceDeviation,
+    #[msg("Invalid external oracle configuration")]
+    InvalidExternalOracleConfig,
+    #[msg("Fee destination override requires a permissioned deployment")]
+    FeeDestinationNotAllowed,
+    #[msg("Token account is frozen by the mint's freeze authority")]
+    AccountFrozen,
+    #[msg("Unwrap requires a wrapped SOL output")]
+    UnwrapRequiresWrappedSol,
+    #[msg("Invalid protocol fee batch accounts")]
+    InvalidProtocolFeeBatch,
+    #[msg("Emergency mode is active, only withdrawals are allowed")]
+    EmergencyModeActive,
+    #[msg("Emergency mode is not active")]
+    EmergencyModeNotActive,
+    #[msg("Emergency exit delay has not elapsed")]
+    EmergencyExitNotReady,
+    #[msg("Pool vault has a delegate or close authority")]
+    PoolVaultCompromised,
+    #[msg("Pool requires holding an NFT from its collection to trade")]
+    TradeGateRequired,
+    #[msg("Invalid trade gate NFT accounts")]
+    InvalidTradeGatePass,
+    #[msg("Invalid scheduled withdraw")]
+    InvalidScheduledWithdraw,
+    #[msg("Position whitelist merkle root has not been set")]
+    PositionWhitelistRootNotSet,
+    #[msg("Fee recipients must be distinct with positive weights summing to 10000 bps")]
+    InvalidFeeRecipients,
+    #[msg("Swap output exceeds the maximum share of the output reserve")]
+    ReserveDrainTooHigh,
+    #[msg("Max drain must be at most 10000 bps")]
+    InvalidMaxDrain,
+    #[msg("Portfolio already tracks the maximum number of pools")]
+    PortfolioFull,
+    #[msg("Fee exemptions must have distinct keys, at most 10000 bps discount, and must not name this program")]
+    InvalidFeeExemptions,
+    #[msg("Routing protocol fees to the farm is not enabled for this pool")]
+    ProtocolFeesToFarmDisabled,
+    #[msg("Farm reward mint is not one of the pool's tokens")]
+    FarmRewardNotPoolToken,
+    #[msg("Account is not in a known legacy layout")]
+    InvalidAccountVersion,
+    #[msg("Buyback already ran within the current interval")]
+    BuybackTooSoon,
+}
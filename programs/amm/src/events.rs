@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+
+use crate::{context::swap::SwapResult, state::Pool};
+
+// ========================================
+// 事件
+// ========================================
+//
+// 每个事件都带有所属池子的 event_seq，同一个池子的事件序号从 1 开始逐个递增。
+// 索引器通过 RPC websocket 订阅日志时可能漏掉或乱序收到事件，
+// 发现序号不连续就可以确定缺了哪几个，再按签名回填，而不用重新扫描整个池子的历史。
+//
+// 发出事件前必须先调用 Pool::next_event_seq，序号和事件在同一笔交易中，失败时一起回滚。
+//
+// 任何改变池子储备量或 LP 供应量的指令都必须发出至少一个带 next_event_seq() 的事件，
+// 否则索引器看到的序号是连续的，却漏掉了一次余额变化。新增这类指令时一并添加事件。
+// 只动手续费账户（claim_creator_fees、collect_protocol_fees_batch、fund_farm_from_fees）的指令不算。
+// 多资产池（multi_pool.rs）是独立的账户类型，没有 event_seq，不在此列。
+
+/// 限价单每次成交的事件，average_price 是订单至今所有成交的均价
+///
+/// 价格 = 输出 / 输入 × 10^9（与 PriceFeed 的 expo 相同），按最小单位计算，没有按小数位数换算
+#[event]
+pub struct LimitOrderFilled {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub order: Pubkey,
+    pub amount_in: u64,               // 本次付出的输入代币（含手续费）
+    pub amount_out: u64,              // 本次获得的输出代币
+    pub filled_amount: u64,           // 累计成交的输入代币
+    pub received_amount: u64,         // 累计获得的输出代币
+    pub average_price: u64,
+}
+
+/// OTC 报价成交的事件，twap 是成交时使用的 TokenA 价格（Q64.64，以 TokenB 计价）
+#[event]
+pub struct OtcOfferAccepted {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,                  // taker 获得的 maker 托管代币
+    pub payment: u64,                 // taker 付给 maker 的代币
+    pub twap: u128,
+}
+
+/// verify_pool 的检查结果，issues 为 0 表示全部通过，各个位的含义见 context/verify_pool.rs
+#[event]
+pub struct PoolHealth {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+    pub k: u128,                      // reserve_a * reserve_b
+    pub k_last: u128,
+    pub protocol_fees_a: u64,
+    pub protocol_fees_b: u64,
+    pub issues: u32,                  // 没有通过的检查项，HEALTH_* 按位或
+}
+
+/// swap_with_quote 按做市商报价成交，退回曲线成交时不发出
+#[event]
+pub struct QuoteFilled {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub market_maker: Pubkey,
+    pub taker: Pubkey,
+    pub is_a: bool,                   // true 表示 taker 买入 TokenA
+    pub amount_in: u64,               // taker 付给做市商的代币
+    pub amount_out: u64,              // taker 从做市商库存获得的代币
+    pub nonce: u64,
+}
+
+/// emergency_withdraw 绕过曲线取出流动性，被冻结的一侧数量为 0
+#[event]
+pub struct EmergencyWithdrawn {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub owner: Pubkey,
+    pub amount_lp: u64,               // 销毁的 LP
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// swap / swap_with_relayer_fee / swap_with_quote 每笔成交后发出，登记了 swap hook 的池子还会把同样的内容作为指令数据 CPI 给 hook 程序，见 swap_hook.rs
+///
+/// 不经过 hook 的成交路径（flash_swap、split_swap、execute_dca、rebalance_pair、buyback_and_burn）也发出 SwapEvent，
+/// 其中 split_swap 和 rebalance_pair 涉及的每个池子各发一条；execute_dca 的 trader 是订单所有者
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub trader: Pubkey,
+    pub is_a: bool,                   // true 表示 trader 买入 TokenA
+    pub amount_in: u64,               // trader 付出的输入代币（含手续费）
+    pub amount_out: u64,              // trader 获得的输出代币，已扣除 relayer_fee
+    pub fee_paid: u64,                // 其中的手续费，做市商报价成交时为 0
+    pub relayer_fee: u64,             // swap_with_relayer_fee 从输出中转给中继者的补偿，其他路径为 0
+    pub tag: Option<[u8; 16]>,        // 调用者传入的标记，原样回显
+}
+
+/// deposit / deposit_by_amounts / deposit_imbalanced / deposit_lite / deposit_with_delegate 每次存入后发出，
+/// migrate_liquidity 在新池子上发出；deposit_position、seed_pool_from_treasury、compound 同样发出，
+/// owner 是 LP 的接收者（deposit_with_delegate 为 owner，seed_pool_from_treasury 为 config，compound 为金库）
+///
+/// tag 是调用者自定义的 16 字节标记（例如交易所前端的内部订单号），链上不解释，原样回显，
+/// 索引器不用额外的 memo 指令就能把成交对应回自己的订单；没有传入时为 None
+#[event]
+pub struct LiquidityDeposited {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub owner: Pubkey,
+    pub amount_lp: u64,               // 铸造的 LP
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub tag: Option<[u8; 16]>,
+}
+
+/// withdraw / withdraw_position / claim_scheduled_withdraw 每次取出后发出，migrate_liquidity 在旧池子上发出，
+/// tag 的含义同 LiquidityDeposited
+#[event]
+pub struct LiquidityWithdrawn {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub owner: Pubkey,
+    pub amount_lp: u64,               // 销毁的 LP
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub tag: Option<[u8; 16]>,
+}
+
+/// donate 每次捐赠后发出，储备量增加但不铸造 LP
+#[event]
+pub struct Donated {
+    pub pool: Pubkey,
+    pub event_seq: u64,
+    pub donor: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// 全局紧急模式开启、申请退出或者退出时发出
+///
+/// 与具体池子无关，没有 event_seq；exit_ts 是申请退出后可以执行的时间戳，开启和退出时为 0
+#[event]
+pub struct EmergencyModeChanged {
+    pub authority: Pubkey,
+    pub emergency_mode: bool,
+    pub exit_ts: i64,
+}
+
+impl SwapEvent {
+    /// 按成交结果构造 SwapEvent，同时为池子分配下一个 event_seq
+    pub fn new(pool: &mut Account<Pool>, trader: Pubkey, is_a: bool, result: &SwapResult, relayer_fee: u64, tag: Option<[u8; 16]>) -> Result<Self> {
+        Ok(Self {
+            pool: pool.key(),
+            event_seq: pool.next_event_seq()?,
+            trader,
+            is_a,
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            fee_paid: result.fee_paid,
+            relayer_fee,
+            tag,
+        })
+    }
+}
+
+impl Pool {
+    /// 递增并返回下一个事件序号
+    pub fn next_event_seq(&mut self) -> Result<u64> {
+        self.event_seq = self.event_seq.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(self.event_seq)
+    }
+}
@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, math::BPS_DENOMINATOR, state::Pool};
+
+// ========================================
+// 外部预言机价格带（oracle-guarded 池子）
+// ========================================
+//
+// 池子管理员用 set_external_oracle 登记一个外部价格账户后，deposit / deposit_imbalanced /
+// withdraw / withdraw_percent / swap 都会检查：操作之后池子的现价与外部价格的偏离不超过
+// external_oracle_band_bps，否则整笔交易回滚。依赖池子价格的集成方（借贷、清算）
+// 因此不会在池子被操纵或长时间没有套利、价格已经过时的时候与它交互。
+//
+// - 预言机账户通过 remaining_accounts 的 **第一个** 账户传入，地址必须等于 pool.external_oracle；
+//   swap 的其余 remaining_accounts（手续费折扣、swap hook）顺延
+// - 支持 Pyth 拉取式预言机的 PriceUpdateV2 账户，要求 Full 验证级别，
+//   publish_time 距今不超过 external_oracle_max_age 秒
+// - 外部价格的含义必须是「每枚 TokenA 值多少枚 TokenB」，与 PriceFeed 相同；
+//   现价按两种代币的小数位数换算后再比较，见 Pool::normalize_price_a
+// - 按操作 **之后** 的储备量检查：价格已经偏离的池子仍然可以被套利拉回价格带内
+//
+// 其他修改储备量的指令（flash_swap、split_swap、rebalance_pair、execute_dca、fill_limit_order、
+// buyback_and_burn、compound、donate、deposit_with_delegate、migrate_liquidity、deposit_position / withdraw_position）
+// 没有传入预言机账户的位置，对 oracle-guarded 池子直接拒绝（ExternalOracleRequired），
+// 否则可以先用这些路径把价格推出价格带，绕过上面的检查。
+// emergency_withdraw 是例外：它只在池子管理员开启后可用，用于外部价格失效时让 LP 取回资金。
+
+/// Pyth 拉取式预言机接收程序，PriceUpdateV2 账户的 owner
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = Pubkey::from_str_const("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// PriceUpdateV2 的 Anchor discriminator：sha256("account:PriceUpdateV2")[..8]
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// verification_level 在数据中的位置：discriminator(8) | write_authority(32)
+const VERIFICATION_LEVEL_OFFSET: usize = 40;
+
+/// VerificationLevel::Full 的枚举序号，Full 没有附加字段
+const VERIFICATION_LEVEL_FULL: u8 = 1;
+
+/// Full 验证级别下 price_message 的位置：verification_level(1) 之后是 feed_id(32)
+const PRICE_OFFSET: usize = VERIFICATION_LEVEL_OFFSET + 1 + 32;
+
+/// 从 PriceUpdateV2 读到的价格：实际价格 = price * 10^expo
+struct ExternalPrice {
+    price: i64,
+    expo: i32,
+    publish_time: i64,
+}
+
+impl Pool {
+    pub fn is_oracle_guarded(&self) -> bool {
+        self.external_oracle != Pubkey::default()
+    }
+
+    /// 把 remaining_accounts 拆成（预言机账户, 其余账户），没有登记外部预言机时前者为 None
+    pub fn split_external_oracle<'a, 'info>(&self, accounts: &'a [AccountInfo<'info>]) -> Result<(Option<&'a AccountInfo<'info>>, &'a [AccountInfo<'info>])> {
+        if !self.is_oracle_guarded() {
+            return Ok((None, accounts));
+        }
+        let (oracle, rest) = accounts.split_first().ok_or(AmmError::ExternalOracleRequired)?;
+        require_keys_eq!(oracle.key(), self.external_oracle, AmmError::InvalidExternalOracle);
+        Ok((Some(oracle), rest))
+    }
+
+    /// 检查操作之后的现价在外部价格的价格带内，没有登记外部预言机或者池子被取空时不检查
+    pub fn check_external_oracle(&self, oracle: Option<&AccountInfo>, reserve_a: u64, reserve_b: u64, now: i64) -> Result<()> {
+        if !self.is_oracle_guarded() || reserve_a == 0 {
+            return Ok(());
+        }
+        let oracle = oracle.ok_or(AmmError::ExternalOracleRequired)?;
+        let external = read_pyth_price(oracle)?;
+        require!(
+            now.saturating_sub(external.publish_time) <= self.external_oracle_max_age as i64,
+            AmmError::ExternalOracleStale
+        );
+
+        let reference = external_price_q64(&external)?;
+        let spot = self.normalize_price_a(
+            ((reserve_b as u128) << 64).checked_div(reserve_a as u128).ok_or(ProgramError::ArithmeticOverflow)?,
+        )?;
+
+        // 偏离大到乘法溢出时一定超过价格带
+        let deviation_bps = spot.abs_diff(reference)
+            .checked_mul(BPS_DENOMINATOR)
+            .map_or(u128::MAX, |deviation| deviation / reference);
+        require!(deviation_bps <= self.external_oracle_band_bps as u128, AmmError::ExternalOraclePriceDeviation);
+        Ok(())
+    }
+}
+
+/// 读取 Pyth PriceUpdateV2 账户中的价格，只接受 Full 验证级别
+fn read_pyth_price(account: &AccountInfo) -> Result<ExternalPrice> {
+    require_keys_eq!(*account.owner, PYTH_RECEIVER_PROGRAM_ID, AmmError::InvalidExternalOracle);
+    let data = account.try_borrow_data()?;
+    require!(data.get(..8) == Some(&PRICE_UPDATE_V2_DISCRIMINATOR[..]), AmmError::InvalidExternalOracle);
+    require!(data.get(VERIFICATION_LEVEL_OFFSET) == Some(&VERIFICATION_LEVEL_FULL), AmmError::InvalidExternalOracle);
+
+    // price_message: feed_id(32) | price(i64) | conf(u64) | exponent(i32) | publish_time(i64) | ...
+    let field = |offset: usize, len: usize| data.get(PRICE_OFFSET + offset..PRICE_OFFSET + offset + len).ok_or(AmmError::InvalidExternalOracle);
+    let price = i64::from_le_bytes(field(0, 8)?.try_into().unwrap());
+    let expo = i32::from_le_bytes(field(16, 4)?.try_into().unwrap());
+    let publish_time = i64::from_le_bytes(field(20, 8)?.try_into().unwrap());
+    require_gt!(price, 0, AmmError::InvalidExternalOracle);
+
+    Ok(ExternalPrice { price, expo, publish_time })
+}
+
+/// price * 10^expo 转换成 Q64.64
+fn external_price_q64(external: &ExternalPrice) -> Result<u128> {
+    let value = (external.price as u128) << 64;
+    let scale = 10u128.checked_pow(external.expo.unsigned_abs()).ok_or(AmmError::InvalidExternalOracle)?;
+    let price = if external.expo < 0 {
+        value / scale
+    } else {
+        value.checked_mul(scale).ok_or(ProgramError::ArithmeticOverflow)?
+    };
+    require_gt!(price, 0, AmmError::InvalidExternalOracle);
+    Ok(price)
+}
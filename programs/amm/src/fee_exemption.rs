@@ -0,0 +1,62 @@
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions::get_instruction_relative};
+
+use crate::{
+    error::AmmError,
+    math::BPS_DENOMINATOR,
+    state::{AmmConfig, FeeExemption, MAX_FEE_EXEMPTIONS},
+};
+
+// ========================================
+// 集成方手续费豁免
+// ========================================
+//
+// 协议可以和聚合器等集成方合作，让经由它们成交的 swap 少付或者不付手续费，而不改变其他人的费率。
+// 协议管理员在 AmmConfig.fee_exemptions 中登记集成方的 key 和减免比例，key 可以是：
+// - 程序 ID：通过 Instructions sysvar 读取当前顶层指令，顶层指令属于这个程序时（即 swap 是它 CPI 进来的）享受减免
+// - 钱包地址：swap 的 signer 是这个地址时享受减免
+//
+// 只有 swap / dry_run_swap 检查豁免。与持有者折扣（见 fee_rebate.rs）不叠加，取两者中较低的手续费；
+// 创建者和协议分成按减免后的手续费计算，三方按原来的比例一起让利。
+// 顶层程序只能说明交易的入口是谁，集成方自己需要决定哪些用户可以经由它调用。
+
+impl AmmConfig {
+    /// signer 是 trader、顶层程序是 caller_program 时的减免比例（基点），多个条目同时满足时取最高的
+    pub fn fee_exemption_bps(&self, trader: &Pubkey, caller_program: Option<&Pubkey>) -> u16 {
+        self.fee_exemptions
+            .iter()
+            .filter(|exemption| exemption.key == *trader || Some(&exemption.key) == caller_program)
+            .map(|exemption| exemption.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// 检查豁免列表：最多 MAX_FEE_EXEMPTIONS 个，key 互不相同且不是本程序，减免不超过 10000
+///
+/// 本程序的 ID 永远不会是 CPI 的调用方，登记它只会让直接调用的交易被误认为外部程序调用
+pub fn validate_fee_exemptions(exemptions: &[FeeExemption]) -> Result<()> {
+    require!(exemptions.len() <= MAX_FEE_EXEMPTIONS, AmmError::InvalidFeeExemptions);
+    for (i, exemption) in exemptions.iter().enumerate() {
+        require!(exemption.discount_bps as u128 <= BPS_DENOMINATOR, AmmError::InvalidFeeExemptions);
+        require_keys_neq!(exemption.key, crate::ID, AmmError::InvalidFeeExemptions);
+        require!(exemptions[i + 1..].iter().all(|other| other.key != exemption.key), AmmError::InvalidFeeExemptions);
+    }
+    Ok(())
+}
+
+/// trader 按 fee 成交时减免后的手续费（基点），减免部分向下取整，舍入归池子
+///
+/// 豁免列表为空时直接返回 fee，不读取 Instructions sysvar
+pub fn exempted_fee(config: &AmmConfig, fee: u16, trader: &Pubkey, instructions: &AccountInfo) -> Result<u16> {
+    if config.fee_exemptions.is_empty() {
+        return Ok(fee);
+    }
+
+    // 顶层指令属于本程序说明是用户直接调用，没有 CPI 调用方，见 guard::require_top_level
+    let top_level = get_instruction_relative(0, instructions)?.program_id;
+    let caller_program = (top_level != crate::ID).then_some(&top_level);
+
+    let discount_bps = config.fee_exemption_bps(trader, caller_program);
+    let discount = fee as u128 * discount_bps as u128 / BPS_DENOMINATOR;
+    Ok(fee - discount as u16)
+}
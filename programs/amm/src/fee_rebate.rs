@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::AmmError,
+    math::BPS_DENOMINATOR,
+    state::{AmmConfig, FeeRebateTier, Pool, MAX_FEE_REBATE_TIERS},
+    token_account::{read_token_amount, read_token_mint_and_owner},
+};
+
+// ========================================
+// 持有者手续费折扣
+// ========================================
+//
+// 协议管理员在 AmmConfig 中配置会员代币和折扣档位，持有足够会员代币的钱包 swap 时手续费打折。
+// 会员代币默认是所交易池子的 LP 代币，也可以指定一个全局的会员 mint。
+//
+// swap / swap_by_mint / dry_run_swap 的 remaining_accounts 可选地传入 [config, 持有者代币账户]：
+// - 不传时按池子原手续费成交，已有的集成方不需要任何改动
+// - 传入时代币账户的 owner 必须是 signer、mint 必须是会员代币，否则整笔交易失败
+//
+// 折扣按交易时的余额计算，同一笔交易里借入会员代币也能拿到折扣，档位应当按这一点来定。
+// 创建者和协议分成按打折后的手续费计算，三方按原来的比例一起让利。
+
+impl AmmConfig {
+    /// 持有 balance 个会员代币时的折扣（基点），取满足条件的最高档
+    pub fn fee_rebate_bps(&self, balance: u64) -> u16 {
+        self.fee_rebate_tiers
+            .iter()
+            .filter(|tier| balance >= tier.min_balance)
+            .map(|tier| tier.rebate_bps)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// 检查折扣档位：最多 MAX_FEE_REBATE_TIERS 档，min_balance 严格升序，折扣不超过 10000
+pub fn validate_fee_rebate_tiers(tiers: &[FeeRebateTier]) -> Result<()> {
+    require!(tiers.len() <= MAX_FEE_REBATE_TIERS, AmmError::InvalidFeeRebateTiers);
+    require!(tiers.windows(2).all(|pair| pair[0].min_balance < pair[1].min_balance), AmmError::InvalidFeeRebateTiers);
+    require!(tiers.iter().all(|tier| tier.rebate_bps as u128 <= BPS_DENOMINATOR), AmmError::InvalidFeeRebateTiers);
+    Ok(())
+}
+
+/// trader 在 pool 上 swap 时实际使用的手续费（基点），减免部分向下取整，舍入归池子
+///
+/// accounts 为空时返回池子原手续费，否则必须是 [config, trader 的会员代币账户]
+pub fn rebated_fee(pool: &Account<Pool>, trader: &Pubkey, accounts: &[AccountInfo]) -> Result<u16> {
+    let (config_info, holder_info) = match accounts {
+        [] => return Ok(pool.fee),
+        [config_info, holder_info] => (config_info, holder_info),
+        _ => return err!(AmmError::InvalidFeeRebateAccounts),
+    };
+
+    // swap 刻意避免多余的反序列化，这里只在传入折扣账户时才读取全局配置
+    require_keys_eq!(*config_info.owner, crate::ID, AmmError::InvalidFeeRebateAccounts);
+    let config = AmmConfig::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+    let config_address = Pubkey::create_program_address(&[b"config", &[config.bump]], &crate::ID)
+        .map_err(|_| AmmError::InvalidFeeRebateAccounts)?;
+    require_keys_eq!(config_info.key(), config_address, AmmError::InvalidFeeRebateAccounts);
+
+    let membership_mint = if config.membership_mint == Pubkey::default() {
+        Pubkey::create_program_address(&[b"lp", pool.key().as_ref(), &[pool.lp_bump]], &crate::ID)
+            .map_err(|_| AmmError::InvalidFeeRebateAccounts)?
+    } else {
+        config.membership_mint
+    };
+
+    let (mint, owner) = read_token_mint_and_owner(holder_info)?;
+    require_keys_eq!(mint, membership_mint, AmmError::InvalidFeeRebateAccounts);
+    require_keys_eq!(owner, *trader, AmmError::InvalidFeeRebateAccounts);
+
+    let rebate_bps = config.fee_rebate_bps(read_token_amount(holder_info)?);
+    let rebate = pool.fee as u128 * rebate_bps as u128 / BPS_DENOMINATOR;
+    Ok(pool.fee - rebate as u16)
+}
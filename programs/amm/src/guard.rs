@@ -0,0 +1,69 @@
+use anchor_lang::{prelude::*, solana_program::{ed25519_program, sysvar::instructions::get_instruction_relative}};
+
+use crate::error::AmmError;
+
+// ========================================
+// 指令级别的保护：指令自省（Instructions sysvar）和可写账户别名检查
+// ========================================
+
+/// 要求当前指令是交易中的顶层指令，而不是其他程序 CPI 进来的内部指令
+///
+/// get_instruction_relative(0) 返回的是「当前正在执行的顶层指令」：
+/// - 用户直接调用本程序：顶层指令的 program_id 就是本程序
+/// - 其他程序通过 CPI 调用本程序：顶层指令属于那个外部程序
+pub fn require_top_level(instructions: &AccountInfo) -> Result<()> {
+    let current = get_instruction_relative(0, instructions)?;
+    require_keys_eq!(current.program_id, crate::ID, AmmError::CpiNotAllowed);
+    Ok(())
+}
+
+/// 检查一组可写账户的地址互不相同
+///
+/// 同一个代币账户同时作为 signer_ata_a 和 pool_ata_a（或其他组合）传入时，
+/// 指令读到的余额是转账之前的快照，转账会互相抵消，算出来的储备量和实际不符。
+/// 各指令的 Accounts 结构在第一个可写代币账户上用它做约束，失败时返回 DuplicateMutableAccount。
+pub fn has_distinct_keys(keys: &[Pubkey]) -> bool {
+    keys.iter().enumerate().all(|(i, key)| !keys[i + 1..].contains(key))
+}
+
+/// ed25519 程序指令数据中第一组签名偏移量的起始位置：num_signatures(1) | padding(1)
+const ED25519_OFFSETS_START: usize = 2;
+
+/// 要求交易中紧挨在当前指令之前的是 ed25519 程序的验签指令，并且签名者和消息与期望一致
+///
+/// 运行时在执行 ed25519 指令时已经验证过签名，这里只需要确认它验证的是哪把公钥、哪段消息。
+/// 公钥、签名和消息都必须放在 ed25519 指令自己的数据里（instruction_index 为 u16::MAX），
+/// 否则验签指令可能引用了其他指令中的数据，与这里读到的不是同一份。
+pub fn require_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let ix = get_instruction_relative(-1, instructions).map_err(|_| AmmError::InvalidQuoteSignature)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, AmmError::InvalidQuoteSignature);
+
+    let data = &ix.data;
+    require!(data.first() == Some(&1), AmmError::InvalidQuoteSignature);
+
+    let read_u16 = |index: usize| -> Result<usize> {
+        let offset = ED25519_OFFSETS_START + index * 2;
+        let bytes = data.get(offset..offset + 2).ok_or(AmmError::InvalidQuoteSignature)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+
+    // Ed25519SignatureOffsets：signature_offset, signature_instruction_index,
+    // public_key_offset, public_key_instruction_index,
+    // message_data_offset, message_data_size, message_instruction_index
+    let this_instruction = u16::MAX as usize;
+    require!(
+        read_u16(1)? == this_instruction && read_u16(3)? == this_instruction && read_u16(6)? == this_instruction,
+        AmmError::InvalidQuoteSignature
+    );
+
+    let public_key_offset = read_u16(2)?;
+    let message_offset = read_u16(4)?;
+    let message_size = read_u16(5)?;
+
+    let public_key = data.get(public_key_offset..public_key_offset + 32).ok_or(AmmError::InvalidQuoteSignature)?;
+    let signed_message = data.get(message_offset..message_offset + message_size).ok_or(AmmError::InvalidQuoteSignature)?;
+
+    require!(public_key == signer.as_ref(), AmmError::InvalidQuoteSignature);
+    require!(signed_message == message, AmmError::InvalidQuoteSignature);
+    Ok(())
+}
@@ -2,9 +2,25 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Transfer, MintTo, Burn, Token, TokenAccount, Mint};
 
 pub mod state;
+pub mod error;
+pub mod oracle;
+pub mod events;
+pub mod protocol_fee;
+pub mod fee_rebate;
+pub mod fee_exemption;
+pub mod math;
+pub mod curve;
+pub mod guard;
+pub mod token_account;
+pub mod seeds;
+pub mod swap_hook;
+pub mod external_oracle;
+pub mod trade_gate;
 pub mod context;
+#[cfg(feature = "client")]
+pub mod client;  // 链下构造指令的辅助函数
+#[cfg(feature = "course-examples")]
 pub mod cpi_examples;  // CPI 调用示例模块
-pub mod signer_seeds_examples;  // Signer Seeds 三重引用详解模块
 
 pub use context::*;
 
@@ -34,6 +50,102 @@ declare_id!("7yyTbdSb8hMNoutaAHpvUeQALW8Hcd4oPcm9WXMR9mpb");
 pub mod amm {
     use super::*;
 
+    /// 创建全局配置，只有程序升级权限持有者可以调用
+    /// admin: 协议管理员，可以是多签 PDA
+    /// fee_tiers: 允许创建池子的手续费档位（基点），每个档位不超过 MAX_FEE_BPS
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey, fee_tiers: Vec<u16>) -> Result<()> {
+        ctx.accounts.initialize_config(admin, fee_tiers, ctx.bumps.config)
+    }
+
+    /// 协议管理员指令：替换允许的手续费档位
+    pub fn set_fee_tiers(ctx: Context<ConfigAdmin>, fee_tiers: Vec<state::FeeTier>) -> Result<()> {
+        ctx.accounts.set_fee_tiers(fee_tiers)
+    }
+
+    /// 协议管理员指令：新增手续费档位，protocol_fee_bps 是该档位新池子的协议分成
+    pub fn add_fee_tier(ctx: Context<ConfigAdmin>, fee: u16, protocol_fee_bps: u16) -> Result<()> {
+        ctx.accounts.add_fee_tier(fee, protocol_fee_bps)
+    }
+
+    /// 协议管理员指令：下线手续费档位，已经存在的池子不受影响
+    pub fn remove_fee_tier(ctx: Context<ConfigAdmin>, fee: u16) -> Result<()> {
+        ctx.accounts.remove_fee_tier(fee)
+    }
+
+    /// 协议管理员指令：设置创建者手续费分成的上限（基点，占 swap 手续费的比例）
+    pub fn set_max_creator_fee(ctx: Context<ConfigAdmin>, max_creator_fee_bps: u16) -> Result<()> {
+        ctx.accounts.set_max_creator_fee(max_creator_fee_bps)
+    }
+
+    /// 协议管理员指令：设置回购销毁的协议代币，以及每次回购卖出的上限和允许的最大价格冲击（基点），
+    /// max_buyback_impact_bps 同时限制回购前现价偏离 TWAP 的幅度
+    pub fn set_buyback_config(ctx: Context<ConfigAdmin>, protocol_token_mint: Pubkey, max_buyback_amount_in: u64, max_buyback_impact_bps: u16) -> Result<()> {
+        ctx.accounts.set_buyback_config(protocol_token_mint, max_buyback_amount_in, max_buyback_impact_bps)
+    }
+
+    /// 协议管理员指令：是否允许用有 freeze authority 的 mint（如 USDC）创建池子
+    /// 这样的池子 has_freezable_mint = true，发行方冻结池子的代币账户后池子会无法使用
+    pub fn set_allow_freezable_mints(ctx: Context<ConfigAdmin>, allow_freezable_mints: bool) -> Result<()> {
+        ctx.accounts.set_allow_freezable_mints(allow_freezable_mints)
+    }
+
+    /// 协议管理员指令：设置是否只允许持有 TokenBadge 的 mint 创建池子
+    pub fn set_require_token_badges(ctx: Context<ConfigAdmin>, require_token_badges: bool) -> Result<()> {
+        ctx.accounts.set_require_token_badges(require_token_badges)
+    }
+
+    /// 协议管理员指令：设置可以开启全局紧急模式的守护者，默认值表示只有 admin 可以开启
+    pub fn set_guardian(ctx: Context<ConfigAdmin>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.set_guardian(guardian)
+    }
+
+    /// guardian 或协议管理员：开启全局紧急模式，所有池子立即只允许取出，见 emergency_mode.rs
+    pub fn enable_emergency_mode(ctx: Context<EnableEmergencyMode>) -> Result<()> {
+        ctx.accounts.enable_emergency_mode()
+    }
+
+    /// 协议管理员指令：申请退出紧急模式，EMERGENCY_EXIT_DELAY 秒之后才能执行
+    pub fn request_emergency_exit(ctx: Context<EmergencyExit>) -> Result<()> {
+        ctx.accounts.request_emergency_exit()
+    }
+
+    /// 协议管理员指令：等待期满后退出紧急模式
+    pub fn disable_emergency_mode(ctx: Context<EmergencyExit>) -> Result<()> {
+        ctx.accounts.disable_emergency_mode()
+    }
+
+    /// 协议管理员指令：设置持有者手续费折扣的会员代币和档位
+    /// membership_mint 为默认值时按所交易池子的 LP 代币计算，tiers 为空表示关闭
+    pub fn set_fee_rebate_tiers(ctx: Context<ConfigAdmin>, membership_mint: Pubkey, tiers: Vec<state::FeeRebateTier>) -> Result<()> {
+        ctx.accounts.set_fee_rebate_tiers(membership_mint, tiers)
+    }
+
+    /// 协议管理员指令：设置协议手续费的接收方和权重（最多 4 个，权重之和为 10000），空列表表示只有一个金库
+    pub fn set_fee_recipients(ctx: Context<ConfigAdmin>, recipients: Vec<state::FeeRecipient>) -> Result<()> {
+        ctx.accounts.set_fee_recipients(recipients)
+    }
+
+    /// 协议管理员指令：替换集成方手续费豁免列表（程序 ID 或钱包地址，最多 8 个），空列表表示关闭
+    pub fn set_fee_exemptions(ctx: Context<ConfigAdmin>, exemptions: Vec<state::FeeExemption>) -> Result<()> {
+        ctx.accounts.set_fee_exemptions(exemptions)
+    }
+
+    /// 协议管理员指令：设置池子 swap 手续费中归协议的比例（基点），与创建者分成之和不超过 10000
+    pub fn set_protocol_fee(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        ctx.accounts.set_protocol_fee(protocol_fee_bps)
+    }
+
+    /// 协议管理员指令：切换协议分成的收取方式，Lp 模式下在存取流动性时把协议分成以 LP 铸造到 treasury_lp
+    pub fn set_protocol_fee_mode(ctx: Context<SetProtocolFee>, protocol_fee_mode: state::ProtocolFeeMode, treasury_lp: Pubkey) -> Result<()> {
+        ctx.accounts.set_protocol_fee_mode(protocol_fee_mode, treasury_lp)
+    }
+
+    /// 协议管理员指令：允许任何人通过 fund_farm_from_fees 把池子奖励代币一侧的协议分成注入池子的 Farm
+    #[cfg(feature = "farming")]
+    pub fn set_protocol_fees_to_farm(ctx: Context<SetProtocolFee>, enabled: bool) -> Result<()> {
+        ctx.accounts.set_protocol_fees_to_farm(enabled)
+    }
+
     /// 初始化 AMM 流动性池
     /// 
     /// bump 在账户验证阶段自动生成，然后在 initialize 函数中显性获取并存储。
@@ -51,34 +163,598 @@ pub mod amm {
     /// 3. **确定性保证**：确保使用正确的 canonical bump，防止恶意攻击者提供错误的 bump
     /// 4. **代码透明性**：明确显示哪些 PDA 被使用，提高代码可读性和可审计性
     /// 5. **Gas 效率**：减少指令执行时间，降低交易成本
-    pub fn initialize(ctx: Context<Initialize>, fee: u16) -> Result<()> {
+    ///
+    /// creator_fee_bps: 创建者从每笔 swap 手续费中分走的比例（基点），不能超过 AmmConfig 的上限
+    /// initial_price_numerator / initial_price_denominator: 声明的初始价格（TokenB / TokenA，最小单位），
+    /// 空池子存入的比例必须与之相符；都传 0 表示不声明
+    /// start_ts / end_ts: 允许 swap 的时间窗口，start_ts 之前只能存入，end_ts 之后只能取出；0 表示不限制
+    /// soulbound_lp: 为 true 时 LP 记在不可自由转让的 Position 中，创建后不能再修改，见 context/position.rs
+    /// fee_destination_mode: 手续费中 LP 那部分的去向，Creator 只能在许可部署中选择，创建后不能再修改
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(ctx: Context<Initialize>, fee: u16, creator_fee_bps: u16, initial_price_numerator: u64, initial_price_denominator: u64, start_ts: i64, end_ts: i64, soulbound_lp: bool, fee_destination_mode: state::FeeDestinationMode) -> Result<()> {
         // 显性获取并传递 bumps：
         // - ctx.bumps.pool: 从 Context 中获取 pool PDA 的 canonical bump
         // - ctx.bumps.mint_lp: 从 Context 中获取 LP token mint PDA 的 canonical bump
         // 这些 bump 值由 Anchor 框架在账户验证阶段自动计算并存储在 ctx.bumps 中
         // 然后传入 initialize 实现函数，最终存储到 Pool 账户数据中
-        ctx.accounts.initialize(fee, ctx.bumps.pool, ctx.bumps.mint_lp)
+        ctx.accounts.initialize(fee, creator_fee_bps, initial_price_numerator, initial_price_denominator, start_ts, end_ts, soulbound_lp, fee_destination_mode, ctx.bumps.pool, ctx.bumps.mint_lp, ctx.bumps.registry, ctx.bumps.registry_page, ctx.bumps.pair_marker)
     }
 
     /// 向流动性池存入代币，获得 LP 代币
     /// amount: 期望的 LP 代币数量
-    /// max_token_a/max_token_b: 愿意支付的最大代币数量（滑点保护）
-    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, max_token_a, max_token_b)
+    /// max_token_a/max_token_b: 愿意支付的最大代币数量（滑点保护），传 0 表示不限制，见 math::NO_SLIPPAGE_LIMIT
+    /// tag: 可选的 16 字节自定义标记（例如内部订单号），在 LiquidityDeposited 事件中原样回显
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_token_a: u64, max_token_b: u64, tag: Option<[u8; 16]>) -> Result<()> {
+        ctx.accounts.deposit(amount, max_token_a, max_token_b, tag, ctx.remaining_accounts)
+    }
+
+    /// 按愿意存入的代币数量按比例存入，由程序计算能铸造的 LP，多余的一侧不会被转走
+    /// amount_a_desired/amount_b_desired: 最多愿意存入的代币数量
+    /// min_lp: 期望获得的最少 LP 代币数量（滑点保护）
+    /// tag: 与 deposit 相同，在 LiquidityDeposited 事件中原样回显
+    pub fn deposit_by_amounts(ctx: Context<Deposit>, amount_a_desired: u64, amount_b_desired: u64, min_lp: u64, tag: Option<[u8; 16]>) -> Result<()> {
+        ctx.accounts.deposit_by_amounts(amount_a_desired, amount_b_desired, min_lp, tag, ctx.remaining_accounts)
+    }
+
+    /// 按任意比例存入代币，获得 LP 代币
+    /// amount_a/amount_b: 实际存入的代币数量
+    /// min_lp: 期望获得的最少 LP 代币数量（滑点保护）
+    /// 偏离池子比例的那部分视为一次隐式 swap，需要收取 swap 手续费
+    /// tag: 与 deposit 相同，在 LiquidityDeposited 事件中原样回显
+    pub fn deposit_imbalanced(ctx: Context<Deposit>, amount_a: u64, amount_b: u64, min_lp: u64, tag: Option<[u8; 16]>) -> Result<()> {
+        ctx.accounts.deposit_imbalanced(amount_a, amount_b, min_lp, tag, ctx.remaining_accounts)
+    }
+
+    /// 由 SPL Token 委托的 delegate 代 owner 按比例存入，owner 不需要签名，LP 铸造给 owner
+    /// 参数含义与 deposit 相同
+    pub fn deposit_with_delegate(ctx: Context<DelegatedDeposit>, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
+        ctx.accounts.deposit_with_delegate(amount, max_token_a, max_token_b)
+    }
+
+    /// 给机器人用的精简按比例存入，代币账户不要求是关联代币账户，LP 代币账户必须已经存在
+    /// 参数含义与 deposit 相同，只支持 Skim 模式的协议手续费、没有开启 jit_protection 的池子
+    #[cfg(feature = "lite-deposit")]
+    pub fn deposit_lite(ctx: Context<DepositLite>, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
+        ctx.accounts.deposit_lite(amount, max_token_a, max_token_b, ctx.remaining_accounts)
     }
 
     /// 从流动性池提取代币，销毁 LP 代币
     /// amount: 要销毁的 LP 代币数量
-    /// min_token_a/min_token_b: 期望获得的最小代币数量（滑点保护）
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
-        ctx.accounts.withdraw(amount, min_token_a, min_token_b)
+    /// min_token_a/min_token_b: 期望获得的最小代币数量（滑点保护），传 0 表示不限制
+    /// tag: 可选的 16 字节自定义标记，在 LiquidityWithdrawn 事件中原样回显
+    /// unwrap: 池子有一侧是 WSOL 时传 true，取出之后关闭 WSOL 账户，SOL 直接退回 signer
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64, min_token_a: u64, min_token_b: u64, tag: Option<[u8; 16]>, unwrap: bool) -> Result<()> {
+        ctx.accounts.withdraw(amount, min_token_a, min_token_b, tag, unwrap, ctx.remaining_accounts)
+    }
+
+    /// 按当前 LP 余额的百分比提取流动性
+    /// bps: 提取比例（基点），10000 表示全部取出
+    /// min_token_a/min_token_b: 期望获得的最小代币数量（滑点保护），传 0 表示不限制
+    pub fn withdraw_percent(ctx: Context<Withdraw>, bps: u16, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        ctx.accounts.withdraw_percent(bps, min_token_a, min_token_b, ctx.remaining_accounts)
+    }
+
+    /// 把 amount_lp 个 LP 托管起来，在 duration 秒内线性释放，创建后不能取消
+    /// id: 同一个用户在同一个池子里区分多个计划
+    pub fn schedule_withdraw(ctx: Context<ScheduleWithdraw>, id: u64, amount_lp: u64, duration: i64) -> Result<()> {
+        ctx.accounts.schedule_withdraw(id, amount_lp, duration, ctx.bumps.scheduled_withdraw)
+    }
+
+    /// 取出分期计划中已经释放的 LP 对应的代币，全部取完后关闭计划
+    /// min_token_a/min_token_b: 本次期望获得的最小代币数量（滑点保护），传 0 表示不限制
+    pub fn claim_scheduled_withdraw(ctx: Context<ClaimScheduledWithdraw>, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        ctx.accounts.claim_scheduled_withdraw(min_token_a, min_token_b, ctx.remaining_accounts)
+    }
+
+    /// 把 WithdrawOnly 旧池子的流动性迁移到同一交易对的新池子
+    /// amount: 要销毁的旧 LP 数量
+    /// min_new_lp: 期望获得的最少新 LP 数量（滑点保护）
+    pub fn migrate_liquidity(ctx: Context<MigrateLiquidity>, amount: u64, min_new_lp: u64) -> Result<()> {
+        ctx.accounts.migrate_liquidity(amount, min_new_lp)
+    }
+
+    /// 无需权限：把 v1 / v2 布局的池子账户扩容并升级到当前布局，payer 补足租金
+    pub fn migrate_pool_v2(ctx: Context<MigratePoolV2>, fee: u16) -> Result<()> {
+        ctx.accounts.migrate_pool_v2(fee)
+    }
+
+    /// 无需权限：把 v1 布局的全局配置扩容并升级到当前布局，payer 补足租金
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        ctx.accounts.migrate_config()
+    }
+
+    /// 无需权限：Pool 末尾新增字段后，把已有的 v3 池子账户扩容到 new_space（POOL_V3_SPACES 中的长度），payer 补足租金，新增字段为默认值
+    pub fn extend_pool_account(ctx: Context<ExtendPoolAccount>, new_space: u32) -> Result<()> {
+        ctx.accounts.extend_pool_account(new_space)
     }
 
     /// 在流动性池中交换代币
     /// amount: 期望获得的输出代币数量
-    /// max_amount_in: 愿意支付的最大输入代币数量（滑点保护）
+    /// max_amount_in: 愿意支付的最大输入代币数量（滑点保护），传 0 表示不限制，见 math::NO_SLIPPAGE_LIMIT
     /// is_a: true 表示用 token_a 换 token_b，false 表示用 token_b 换 token_a
-    pub fn swap(ctx: Context<Swap>, amount: u64, max_amount_in: u64, is_a: bool) -> Result<()> {
-        ctx.accounts.swap(amount, max_amount_in, is_a)
+    /// tag: 可选的 16 字节自定义标记，在 SwapEvent 中原样回显
+    /// unwrap: 输出代币是 WSOL 时传 true，成交之后关闭输出代币账户，SOL 直接退回 signer
+    /// remaining_accounts: 可选传入 [config, 会员代币账户] 享受持有者手续费折扣，见 fee_rebate.rs
+    /// relayer_ata / hook_program: 账户列表末尾的两个可选账户，不用时传占位（程序 ID）；之后不再传任何账户时可以从末尾省略
+    pub fn swap<'info>(ctx: Context<'_, '_, '_, 'info, Swap<'info>>, amount: u64, max_amount_in: u64, is_a: bool, tag: Option<[u8; 16]>, unwrap: bool) -> Result<()> {
+        ctx.accounts.swap(amount, max_amount_in, is_a, tag, unwrap, ctx.remaining_accounts)
+    }
+
+    /// 池子创建者领取累积的创建者手续费
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+        ctx.accounts.claim_creator_fees()
+    }
+
+    /// 无需权限：把池子累积的协议手续费换成协议代币并销毁，每次卖出数量和价格冲击受 AmmConfig 限制，
+    /// 现价偏离 TWAP 时拒绝，每个池子每 BUYBACK_INTERVAL 秒最多执行一次
+    pub fn buyback_and_burn(ctx: Context<BuybackAndBurn>) -> Result<()> {
+        ctx.accounts.buyback_and_burn()
+    }
+
+    /// 协议管理员指令：一次领取多个池子累积的协议手续费到金库
+    /// remaining_accounts 每个池子一组 [pool, creator_fee_vault_a, creator_fee_vault_b, 每个接收方的 (destination_a, destination_b)]
+    pub fn collect_protocol_fees_batch<'info>(ctx: Context<'_, '_, 'info, 'info, CollectProtocolFeesBatch<'info>>) -> Result<()> {
+        ctx.accounts.collect_protocol_fees_batch(ctx.remaining_accounts)
+    }
+
+    /// 无需权限：检查池子账户的一致性并发出 PoolHealth 事件，有异常时返回 PoolUnhealthy
+    pub fn verify_pool(ctx: Context<VerifyPool>) -> Result<()> {
+        ctx.accounts.verify_pool()
+    }
+
+    /// 只预览不成交：执行与 swap 相同的全部检查，把 SwapResult 写入 return data，不改变任何状态
+    /// 账户和参数与 swap 完全相同，可以直接替换指令名后模拟交易
+    pub fn dry_run_swap(ctx: Context<Swap>, amount: u64, max_amount_in: u64, is_a: bool) -> Result<()> {
+        ctx.accounts.dry_run_swap(amount, max_amount_in, is_a, ctx.remaining_accounts)
+    }
+
+    /// 只读：返回池子的 mint、LP mint 和各个代币账户地址，客户端模拟这条指令即可拼出 swap 的账户列表
+    pub fn get_pool_vaults(ctx: Context<GetPoolVaults>) -> Result<PoolVaults> {
+        ctx.accounts.get_pool_vaults()
+    }
+
+    /// 按输出代币的 mint 指定方向的 swap，不需要传 is_a
+    /// output_mint: 期望获得的代币 mint，必须是池子的 mint_a 或 mint_b
+    pub fn swap_by_mint<'info>(ctx: Context<'_, '_, '_, 'info, Swap<'info>>, output_mint: Pubkey, amount: u64, max_amount_in: u64) -> Result<()> {
+        ctx.accounts.swap_by_mint(output_mint, amount, max_amount_in, ctx.remaining_accounts)
+    }
+
+    /// 中继者代付交易费的 swap：成交后从用户获得的输出代币中扣除 relayer_fee 转给 relayer_ata
+    /// 其他参数与 swap 相同，relayer_fee 不能超过 amount；return data 和 SwapEvent 中的 amount_out 是扣除之后的净数量
+    pub fn swap_with_relayer_fee<'info>(ctx: Context<'_, '_, '_, 'info, Swap<'info>>, amount: u64, max_amount_in: u64, is_a: bool, relayer_fee: u64) -> Result<()> {
+        ctx.accounts.swap_with_relayer_fee(amount, max_amount_in, is_a, relayer_fee, ctx.remaining_accounts)
+    }
+
+    /// 在同一交易对的两个费率池子之间拆分一笔精确输出的 swap
+    /// amount: 合计输出数量；allocation_bps: 由 pool_1 成交的比例（基点），其余由 pool_2 成交
+    /// max_amount_in: 两个池子合计的最大输入（含手续费）
+    pub fn split_swap(ctx: Context<SplitSwap>, amount: u64, allocation_bps: u16, max_amount_in: u64, is_a: bool) -> Result<()> {
+        ctx.accounts.split_swap(amount, allocation_bps, max_amount_in, is_a)
+    }
+
+    /// 无需权限：在同一交易对的两个费率池子之间套利，把价格拉近，调用者分得一部分利润（TokenB）
+    /// amount_a: 从便宜的池子搬到贵的池子的 TokenA 数量，由调用者在链下计算
+    pub fn rebalance_pair(ctx: Context<RebalancePair>, amount_a: u64) -> Result<()> {
+        ctx.accounts.rebalance_pair(amount_a)
+    }
+
+    /// 按登记做市商的 ed25519 签名报价成交，前一条指令必须是对应的 ed25519 验签指令
+    /// 报价过期、nonce 已使用或者不如曲线价格时退回普通 swap
+    /// remaining_accounts: [market_maker, 做市商库存代币账户, 做市商收款代币账户]
+    pub fn swap_with_quote<'info>(ctx: Context<'_, '_, 'info, 'info, Swap<'info>>, quote: SignedQuote, max_amount_in: u64) -> Result<()> {
+        ctx.accounts.swap_with_quote(quote, max_amount_in, ctx.remaining_accounts)
+    }
+
+    /// 向池子捐赠代币，不铸造 LP，价值按比例归现有全体 LP
+    /// amount_a/amount_b: 捐赠的代币数量，可以只捐赠一侧
+    pub fn donate(ctx: Context<Donate>, amount_a: u64, amount_b: u64) -> Result<()> {
+        ctx.accounts.donate(amount_a, amount_b)
+    }
+
+    /// 管理员指令：通过 Metaplex Token Metadata 为 LP mint 创建名称、符号和 URI
+    /// uri: 指向链下 JSON 元数据的地址
+    pub fn create_lp_metadata(ctx: Context<CreateLpMetadata>, uri: String) -> Result<()> {
+        ctx.accounts.create_lp_metadata(uri)
+    }
+
+    /// 无需权限：把池子的现价和 TWAP 写入 PriceFeed PDA，供只读取简单 feed 的协议使用
+    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>) -> Result<()> {
+        ctx.accounts.update_price_feed(ctx.bumps.price_feed)
+    }
+
+    /// 管理员指令：设置单笔 swap 允许的最大价格冲击（基点），0 表示不限制
+    pub fn set_max_price_impact(ctx: Context<PoolAdmin>, max_price_impact_bps: u16) -> Result<()> {
+        ctx.accounts.set_max_price_impact(max_price_impact_bps)
+    }
+
+    /// 管理员指令：设置单笔 swap 最多取走输出侧储备量的比例（基点，例如 3000 = 30%），0 表示不限制
+    pub fn set_max_drain(ctx: Context<PoolAdmin>, max_drain_bps: u16) -> Result<()> {
+        ctx.accounts.set_max_drain(max_drain_bps)
+    }
+
+    /// 管理员指令：设置单笔 swap 的最小输出数量，拒绝刷舍入误差的粉尘交易，0 表示不限制
+    pub fn set_min_trade_amount(ctx: Context<PoolAdmin>, min_trade_amount: u64) -> Result<()> {
+        ctx.accounts.set_min_trade_amount(min_trade_amount)
+    }
+
+    /// 管理员指令：提名新的池子创建者（例如 DAO 或多签），传入默认值表示取消提名
+    pub fn transfer_pool_creator(ctx: Context<PoolAdmin>, new_creator: Pubkey) -> Result<()> {
+        ctx.accounts.transfer_pool_creator(new_creator)
+    }
+
+    /// 被提名的新创建者接受转交，池子管理员和创建者手续费的领取人一起变更
+    pub fn accept_pool_creator(ctx: Context<AcceptPoolCreator>) -> Result<()> {
+        ctx.accounts.accept_pool_creator()
+    }
+
+    /// 管理员指令：禁用某个方向的 swap（A→B 表示卖出 TokenA），用于只卖 / 只买的发射阶段
+    pub fn set_trade_directions(ctx: Context<PoolAdmin>, disable_swap_a_to_b: bool, disable_swap_b_to_a: bool) -> Result<()> {
+        ctx.accounts.set_trade_directions(disable_swap_a_to_b, disable_swap_b_to_a)
+    }
+
+    /// 管理员指令：开启 / 关闭紧急取出，见 emergency_withdraw
+    pub fn set_emergency_withdraw(ctx: Context<PoolAdmin>, enabled: bool) -> Result<()> {
+        ctx.accounts.set_emergency_withdraw(enabled)
+    }
+
+    /// 池子开启紧急取出后，按 LP 占比从池子代币账户的实际余额中取出两种代币，不经过曲线和协议手续费结算
+    /// 被冻结的一侧跳过，LP 放弃这一侧的份额
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        ctx.accounts.emergency_withdraw(amount, min_token_a, min_token_b)
+    }
+
+    /// 管理员指令：开启后拒绝与存入同一 slot 的取出，见 liquidity_guard
+    pub fn set_jit_protection(ctx: Context<PoolAdmin>, jit_protection: bool) -> Result<()> {
+        ctx.accounts.set_jit_protection(jit_protection)
+    }
+
+    /// 管理员指令：登记 swap 之后 CPI 调用的 hook 程序，见 swap_hook
+    /// hook_accounts: hook 需要的额外账户数量，swap 时放在 remaining_accounts 的最后
+    pub fn set_swap_hook(ctx: Context<PoolAdmin>, hook_program: Pubkey, hook_accounts: u8) -> Result<()> {
+        ctx.accounts.set_swap_hook(hook_program, hook_accounts)
+    }
+
+    /// 管理员指令：登记外部预言机（Pyth PriceUpdateV2 账户），存取流动性和 swap 之后的现价必须在其价格带内
+    /// band_bps: 允许的最大偏离（基点）；max_age: 外部价格的最长有效期（秒）；oracle 传默认值表示取消
+    pub fn set_external_oracle(ctx: Context<PoolAdmin>, oracle: Pubkey, band_bps: u16, max_age: u32) -> Result<()> {
+        ctx.accounts.set_external_oracle(oracle, band_bps, max_age)
+    }
+
+    /// 管理员指令：登记 Metaplex 集合，之后只有持有该集合中 NFT 的钱包可以 swap，见 trade_gate
+    /// collection 传默认值表示取消
+    pub fn set_trade_gate(ctx: Context<PoolAdmin>, collection: Pubkey) -> Result<()> {
+        ctx.accounts.set_trade_gate(collection)
+    }
+
+    /// 管理员指令：开启后只允许用户直接调用 swap，拒绝其他程序的 CPI 调用
+    pub fn set_cpi_restricted(ctx: Context<PoolAdmin>, cpi_restricted: bool) -> Result<()> {
+        ctx.accounts.set_cpi_restricted(cpi_restricted)
+    }
+
+    /// 管理员指令：把空池子配置成 LBP，TokenA 权重在 [start_ts, end_ts] 内从 start_weight_a 线性变化到 end_weight_a
+    pub fn configure_lbp(ctx: Context<ConfigureLbp>, start_weight_a: u16, end_weight_a: u16, start_ts: i64, end_ts: i64) -> Result<()> {
+        ctx.accounts.configure_lbp(start_weight_a, end_weight_a, start_ts, end_ts)
+    }
+
+    /// 管理员指令：把空池子切换为 StableSwap 曲线
+    /// amp: 放大系数 A，范围 [MIN_AMP, MAX_AMP]
+    pub fn configure_stable_swap(ctx: Context<ConfigureStableSwap>, amp: u64) -> Result<()> {
+        ctx.accounts.configure_stable_swap(amp)
+    }
+
+    /// 管理员指令：结束 LBP，回到普通恒定乘积曲线
+    pub fn end_lbp(ctx: Context<PoolAdmin>) -> Result<()> {
+        ctx.accounts.end_lbp()
+    }
+
+    /// 闪电兑换：先转出 amount 个输出代币，再 CPI 调用 callback_program（remaining_accounts 原样传递），
+    /// 回调结束后池子输入侧必须收到含手续费的输入数量
+    /// data: 传给回调程序的指令数据
+    pub fn flash_swap<'info>(ctx: Context<'_, '_, '_, 'info, FlashSwap<'info>>, amount: u64, is_a: bool, data: Vec<u8>) -> Result<()> {
+        ctx.accounts.flash_swap(amount, is_a, data, ctx.remaining_accounts)
+    }
+
+    /// 管理员指令：设置价格带熔断阈值（基点），swap 后现价偏离 TWAP 超过阈值时交易回滚，0 表示关闭
+    pub fn set_circuit_breaker(ctx: Context<PoolAdmin>, circuit_breaker_bps: u16) -> Result<()> {
+        ctx.accounts.set_circuit_breaker(circuit_breaker_bps)
+    }
+
+    /// 管理员指令：设置池子状态，WithdrawOnly 时 swap 和 deposit 失败但 withdraw 仍然可用
+    pub fn set_pool_status(ctx: Context<PoolAdmin>, status: state::PoolStatus) -> Result<()> {
+        ctx.accounts.set_pool_status(status)
+    }
+
+    /// 为调用者在某个池子上创建 TraderState，开启钱包限额的池子 swap 前需要先创建
+    pub fn init_trader_state(ctx: Context<InitTraderState>) -> Result<()> {
+        ctx.accounts.init_trader_state(ctx.bumps.trader_state)
+    }
+
+    /// 无需权限：把往返附加费之前创建的 v1 TraderState 扩容到当前布局，payer 补足租金
+    pub fn migrate_trader_state(ctx: Context<MigrateTraderState>) -> Result<()> {
+        ctx.accounts.migrate_trader_state()
+    }
+
+    /// 为调用者在某个池子上创建 LiquidityGuard，开启 JIT 保护的池子存取流动性前需要先创建
+    pub fn init_liquidity_guard(ctx: Context<InitLiquidityGuard>) -> Result<()> {
+        ctx.accounts.init_liquidity_guard(ctx.bumps.liquidity_guard)
+    }
+
+    /// 为调用者创建 UserPortfolio，之后 deposit / withdraw 传入时记录提供过流动性的池子，见 portfolio.rs
+    pub fn init_portfolio(ctx: Context<InitPortfolio>) -> Result<()> {
+        ctx.accounts.init_portfolio(ctx.bumps.portfolio)
+    }
+
+    /// 为调用者在 soulbound LP 池子上创建 Position，存入流动性或接收转让前需要先创建
+    pub fn init_position(ctx: Context<InitPosition>) -> Result<()> {
+        ctx.accounts.init_position(ctx.bumps.position)
+    }
+
+    /// 向 soulbound LP 池子存入代币，LP 由池子托管并记入调用者的 Position
+    /// amount: 期望的 LP 数量
+    /// max_token_a/max_token_b: 愿意支付的最大代币数量（滑点保护），传 0 表示不限制，见 math::NO_SLIPPAGE_LIMIT
+    pub fn deposit_position(ctx: Context<PositionDeposit>, amount: u64, max_token_a: u64, max_token_b: u64) -> Result<()> {
+        ctx.accounts.deposit_position(amount, max_token_a, max_token_b)
+    }
+
+    /// 从调用者的 Position 中取出 amount 个 LP 对应的代币
+    /// min_token_a/min_token_b: 最少获得的代币数量（滑点保护）
+    pub fn withdraw_position(ctx: Context<PositionWithdraw>, amount: u64, min_token_a: u64, min_token_b: u64) -> Result<()> {
+        ctx.accounts.withdraw_position(amount, min_token_a, min_token_b)
+    }
+
+    /// 把调用者仓位中的 amount 个 LP 转给白名单中的钱包
+    pub fn transfer_position(ctx: Context<TransferPosition>, amount: u64) -> Result<()> {
+        ctx.accounts.transfer_position(amount)
+    }
+
+    /// 把调用者仓位中的 amount 个 LP 转给默克尔根白名单中的钱包，proof 为接收方的默克尔证明
+    pub fn transfer_position_with_proof(ctx: Context<TransferPositionWithProof>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.transfer_position_with_proof(amount, proof)
+    }
+
+    /// 管理员指令：发布默克尔根白名单的默克尔根（叶子为 sha256(0x00 || wallet)），全 0 表示取消
+    pub fn set_position_whitelist_root(ctx: Context<PoolAdmin>, root: [u8; 32]) -> Result<()> {
+        ctx.accounts.set_position_whitelist_root(root)
+    }
+
+    /// 池子管理员指令：允许 wallet 接收 soulbound LP 仓位的转让
+    pub fn add_position_whitelist(ctx: Context<AddPositionWhitelist>) -> Result<()> {
+        ctx.accounts.add_position_whitelist(ctx.bumps.whitelist)
+    }
+
+    /// 池子管理员指令：把钱包移出仓位转让白名单，租金退还给管理员
+    pub fn remove_position_whitelist(ctx: Context<RemovePositionWhitelist>) -> Result<()> {
+        ctx.accounts.remove_position_whitelist()
+    }
+
+    /// 池子管理员指令：登记报价做市商，signing_key 签名的报价可以通过 swap_with_quote 成交
+    /// 账户中的 owner 是做市商库存代币账户的 owner，必须一起签名；库存账户需要把池子 PDA 设为 delegate
+    pub fn register_market_maker(ctx: Context<RegisterMarketMaker>, signing_key: Pubkey) -> Result<()> {
+        ctx.accounts.register_market_maker(signing_key, ctx.bumps.market_maker)
+    }
+
+    /// 池子管理员指令：注销报价做市商，已经签出的报价随之失效
+    pub fn deregister_market_maker(ctx: Context<DeregisterMarketMaker>) -> Result<()> {
+        ctx.accounts.deregister_market_maker()
+    }
+
+    /// 协议管理员指令：为 mint 签发 TokenBadge，策展部署模式下只有持有徽章的 mint 可以创建池子
+    pub fn issue_token_badge(ctx: Context<IssueTokenBadge>) -> Result<()> {
+        ctx.accounts.issue_token_badge(ctx.bumps.token_badge)
+    }
+
+    /// 协议管理员指令：撤销 TokenBadge，不影响已经创建的池子
+    pub fn revoke_token_badge(ctx: Context<RevokeTokenBadge>) -> Result<()> {
+        ctx.accounts.revoke_token_badge()
+    }
+
+    /// 协议管理员指令：把 config 名下金库中的代币按池子比例存入，铸造的 LP 留在 config 名下，见 protocol_liquidity.rs
+    pub fn seed_pool_from_treasury(ctx: Context<SeedPoolFromTreasury>, amount_a: u64, amount_b: u64, min_lp: u64) -> Result<()> {
+        ctx.accounts.seed_pool_from_treasury(amount_a, amount_b, min_lp, ctx.remaining_accounts)
+    }
+
+    /// 为池子创建 PoolStats（成交量和手续费的 24 小时滚动窗口），任何人都可以调用并支付租金
+    pub fn init_pool_stats(ctx: Context<InitPoolStats>) -> Result<()> {
+        ctx.accounts.init_pool_stats(ctx.bumps.pool_stats)
+    }
+
+    /// 管理员指令：设置每个钱包每个 slot 窗口的最大买入数量，window_slots 为 0 表示关闭
+    pub fn set_rate_limit(ctx: Context<PoolAdmin>, window_slots: u64, max_out_a: u64, max_out_b: u64) -> Result<()> {
+        ctx.accounts.set_rate_limit(window_slots, max_out_a, max_out_b)
+    }
+
+    /// 管理员指令：同一钱包在 window_slots 个 slot 内反方向 swap 时加收 fee_bps 的附加费，归 LP 所有，
+    /// window_slots 为 0 表示关闭；开启后 swap 需要传入 trader_state
+    pub fn set_round_trip_fee(ctx: Context<PoolAdmin>, window_slots: u64, fee_bps: u16) -> Result<()> {
+        ctx.accounts.set_round_trip_fee(window_slots, fee_bps)
+    }
+
+    /// 管理员指令：设置存款后储备量的上限，0 表示不限制
+    pub fn set_deposit_cap(ctx: Context<PoolAdmin>, max_reserve_a: u64, max_reserve_b: u64) -> Result<()> {
+        ctx.accounts.set_deposit_cap(max_reserve_a, max_reserve_b)
+    }
+
+    /// 为池子创建自动复投金库、金库份额 mint 和金库的代币账户
+    pub fn initialize_auto_vault(ctx: Context<InitializeAutoVault>) -> Result<()> {
+        ctx.accounts.initialize_auto_vault(ctx.bumps.auto_vault)
+    }
+
+    /// 向自动复投金库存入 LP，获得金库份额
+    /// min_shares: 期望获得的最小份额（滑点保护）
+    pub fn auto_vault_deposit(ctx: Context<AutoVaultShares>, amount_lp: u64, min_shares: u64) -> Result<()> {
+        ctx.accounts.auto_vault_deposit(amount_lp, min_shares)
+    }
+
+    /// 销毁金库份额，取回 LP
+    /// min_lp: 期望取回的最小 LP 数量（滑点保护）
+    pub fn auto_vault_withdraw(ctx: Context<AutoVaultShares>, shares: u64, min_lp: u64) -> Result<()> {
+        ctx.accounts.auto_vault_withdraw(shares, min_lp)
+    }
+
+    /// 无需权限：把金库收到的 TokenA / TokenB 存入池子，换来的 LP 留在金库，提高每份额的 LP
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        ctx.accounts.compound()
+    }
+
+    /// 创建定投订单，把 total_amount 个输入代币存入订单托管
+    /// amount_per_interval: 每次执行付出的输入代币（含手续费）
+    /// min_amount_out: 每次执行至少获得的输出代币（滑点保护）
+    /// interval: 两次执行之间的最小间隔（秒）
+    /// keeper_fee: 每次执行支付给 keeper 的输入代币
+    /// is_a: 与 swap 相同，true 表示买入 TokenA
+    #[cfg(feature = "orders")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dca(
+        ctx: Context<CreateDca>,
+        id: u64,
+        total_amount: u64,
+        amount_per_interval: u64,
+        min_amount_out: u64,
+        interval: i64,
+        keeper_fee: u64,
+        is_a: bool,
+    ) -> Result<()> {
+        ctx.accounts.create_dca(id, total_amount, amount_per_interval, min_amount_out, interval, keeper_fee, is_a, ctx.bumps.dca_order)
+    }
+
+    /// 无需权限：执行一次到期的定投订单，keeper 获得 keeper_fee
+    #[cfg(feature = "orders")]
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        ctx.accounts.execute_dca()
+    }
+
+    /// 取消定投订单，退回剩余的托管代币
+    #[cfg(feature = "orders")]
+    pub fn cancel_dca(ctx: Context<CancelDca>) -> Result<()> {
+        ctx.accounts.cancel_dca()
+    }
+
+    /// 池子管理员指令：为池子创建 LP 质押挖矿，reward_rate 为每秒释放的奖励代币
+    /// accrual_mode: 奖励按当前权重分配（PerShare），还是按累计的 LP 秒数分配（LpSeconds），创建后不能修改
+    #[cfg(feature = "farming")]
+    pub fn create_farm(ctx: Context<CreateFarm>, reward_rate: u64, accrual_mode: state::FarmAccrualMode) -> Result<()> {
+        ctx.accounts.create_farm(reward_rate, accrual_mode, ctx.bumps.farm)
+    }
+
+    /// 池子管理员指令：修改每秒释放的奖励，之前的时间段按旧速率结算
+    #[cfg(feature = "farming")]
+    pub fn set_farm_reward_rate(ctx: Context<FarmAdmin>, reward_rate: u64) -> Result<()> {
+        ctx.accounts.set_farm_reward_rate(reward_rate)
+    }
+
+    /// 任何人都可以向挖矿注入奖励代币
+    #[cfg(feature = "farming")]
+    pub fn fund_farm(ctx: Context<FundFarm>, amount: u64) -> Result<()> {
+        ctx.accounts.fund_farm(amount)
+    }
+
+    /// 无需权限：池子开启 protocol_fees_to_farm 后，把奖励代币一侧累积的协议分成全部注入 Farm
+    #[cfg(feature = "farming")]
+    pub fn fund_farm_from_fees(ctx: Context<FundFarmFromFees>) -> Result<()> {
+        ctx.accounts.fund_farm_from_fees()
+    }
+
+    /// 质押 LP，lock 越长奖励权重越高，解锁前退出会被罚没一部分 LP
+    #[cfg(feature = "farming")]
+    pub fn stake(ctx: Context<Stake>, id: u64, amount: u64, lock: state::LockDuration) -> Result<()> {
+        ctx.accounts.stake(id, amount, lock, ctx.bumps.stake_position)
+    }
+
+    /// 领取质押奖励和分到的罚没 LP
+    #[cfg(feature = "farming")]
+    pub fn claim_stake_rewards(ctx: Context<ManageStake>) -> Result<()> {
+        ctx.accounts.claim_stake_rewards()
+    }
+
+    /// 退出质押并关闭仓位，解锁前退出的罚没部分分给剩下的质押者
+    #[cfg(feature = "farming")]
+    pub fn unstake(ctx: Context<ManageStake>) -> Result<()> {
+        ctx.accounts.unstake()
+    }
+
+    /// 创建限价单，把 amount_in 个输入代币存入订单托管，限价 = min_amount_out / amount_in
+    /// is_a: 与 swap 相同，true 表示买入 TokenA
+    #[cfg(feature = "orders")]
+    pub fn create_limit_order(ctx: Context<CreateLimitOrder>, id: u64, amount_in: u64, min_amount_out: u64, is_a: bool) -> Result<()> {
+        ctx.accounts.create_limit_order(id, amount_in, min_amount_out, is_a, ctx.bumps.limit_order)
+    }
+
+    /// 无需权限：池子价格优于限价时成交订单的一部分，可以多次调用直到全部成交
+    /// amount: 本次最多成交的输入代币（含手续费）
+    #[cfg(feature = "orders")]
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>, amount: u64) -> Result<()> {
+        ctx.accounts.fill_limit_order(amount)
+    }
+
+    /// 取消限价单，退回没有成交的托管代币
+    #[cfg(feature = "orders")]
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        ctx.accounts.cancel_limit_order()
+    }
+
+    /// 创建 OTC 报价，把 amount 个卖出代币存入托管，成交价 = 池子 TWAP × (10000 + spread_bps) / 10000
+    /// is_a: 与 swap 相同，true 表示 maker 买入 TokenA；taker 为默认值时任何人都可以成交，expires_at 为 0 时不过期
+    #[cfg(feature = "orders")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_otc_offer(ctx: Context<CreateOtcOffer>, id: u64, amount: u64, spread_bps: i16, min_amount_out: u64, taker: Pubkey, expires_at: i64, is_a: bool) -> Result<()> {
+        ctx.accounts.create_otc_offer(id, amount, spread_bps, min_amount_out, taker, expires_at, is_a, ctx.bumps.otc_offer)
+    }
+
+    /// 按当前 TWAP 整单成交 OTC 报价
+    /// max_payment: taker 最多付给 maker 的代币
+    #[cfg(feature = "orders")]
+    pub fn accept_otc_offer(ctx: Context<AcceptOtcOffer>, max_payment: u64) -> Result<()> {
+        ctx.accounts.accept_otc_offer(max_payment)
+    }
+
+    /// 取消 OTC 报价，退回托管代币
+    #[cfg(feature = "orders")]
+    pub fn cancel_otc_offer(ctx: Context<CancelOtcOffer>) -> Result<()> {
+        ctx.accounts.cancel_otc_offer()
+    }
+
+    /// 创建 3~8 种代币的多资产池（StableSwap N 币种不变量）
+    /// mints: 按地址严格升序；remaining_accounts 先传 N 个 mint，再传池子在每个 mint 上的关联代币账户
+    pub fn initialize_multi_pool<'info>(ctx: Context<'_, '_, 'info, 'info, InitializeMultiPool<'info>>, fee: u16, amp: u64, mints: Vec<Pubkey>) -> Result<()> {
+        ctx.accounts.initialize_multi_pool(fee, amp, mints, ctx.remaining_accounts, ctx.bumps.multi_pool, ctx.bumps.mint_lp)
+    }
+
+    /// 按比例向多资产池存入流动性，空池子首次存入时按 max_amounts 全额存入
+    /// remaining_accounts 先传用户的 N 个代币账户，再传池子的 N 个代币账户
+    pub fn multi_deposit<'info>(ctx: Context<'_, '_, '_, 'info, MultiDeposit<'info>>, amount_lp: u64, max_amounts: Vec<u64>) -> Result<()> {
+        ctx.accounts.multi_deposit(amount_lp, max_amounts, ctx.remaining_accounts)
+    }
+
+    /// 按比例从多资产池取出流动性，账户顺序与 multi_deposit 相同
+    pub fn multi_withdraw<'info>(ctx: Context<'_, '_, '_, 'info, MultiWithdraw<'info>>, amount_lp: u64, min_amounts: Vec<u64>) -> Result<()> {
+        ctx.accounts.multi_withdraw(amount_lp, min_amounts, ctx.remaining_accounts)
+    }
+
+    /// 多资产池精确输出 swap：从下标 index_out 的代币中拿走 amount_out，付出下标 index_in 的代币
+    /// remaining_accounts 传池子的 N 个代币账户
+    pub fn multi_swap<'info>(ctx: Context<'_, '_, '_, 'info, MultiSwap<'info>>, index_in: u8, index_out: u8, amount_out: u64, max_amount_in: u64) -> Result<()> {
+        ctx.accounts.multi_swap(index_in, index_out, amount_out, max_amount_in, ctx.remaining_accounts)
+    }
+
+    /// 管理员指令：在当前 slot 创建 LP 快照，记录 LP 总供应量和储备量，供治理计算投票权
+    /// id: 快照编号，同一个池子内唯一
+    pub fn snapshot_lp_balances(ctx: Context<SnapshotLpBalances>, id: u64) -> Result<()> {
+        ctx.accounts.snapshot_lp_balances(id, ctx.bumps.snapshot)
+    }
+
+    /// 管理员指令：发布快照 slot 时各持有者 LP 余额的默克尔根，只能发布一次
+    pub fn set_snapshot_root(ctx: Context<SetSnapshotRoot>, merkle_root: [u8; 32]) -> Result<()> {
+        ctx.accounts.set_snapshot_root(merkle_root)
+    }
+
+    /// 无需权限：提交默克尔证明，为 owner 创建该快照中的投票权记录
+    /// amount: owner 在快照 slot 持有的 LP 数量
+    pub fn checkpoint_voting_power(ctx: Context<CheckpointVotingPower>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.checkpoint_voting_power(amount, proof, ctx.bumps.voting_power)
+    }
+
+    /// 无需权限的 keeper 指令：在没有交易的时段推进价格累加器和观测环形缓冲区
+    pub fn crank(ctx: Context<Crank>) -> Result<()> {
+        ctx.accounts.crank()
     }
 }
\ No newline at end of file
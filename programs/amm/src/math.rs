@@ -0,0 +1,821 @@
+use anchor_lang::prelude::*;
+
+// ========================================
+// AMM 数学（纯函数）
+// ========================================
+//
+// 所有和账户无关的计算都放在这里，指令只负责读取余额、调用这些函数、执行 CPI。
+// 这样可以脱离链上环境直接对数学做属性测试（见 tests/math_invariants.rs）。
+//
+// 舍入方向统一遵循「对池子有利」：
+// - 用户付给池子的数量向上取整
+// - 池子付给用户的数量（包括 LP）向下取整
+
+/// 基点分母：手续费、价格冲击等比例参数统一以基点表示，fee = 30 表示 0.30%
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// 池子手续费上限（10%），超过这个值的池子基本不可能有正常交易
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// 滑点参数的「不限制」哨兵值
+///
+/// - 上限类参数（max_amount_in、max_token_a / max_token_b）：传 0 或 u64::MAX 都表示不限制，
+///   校验前统一经过 max_amount_limit 换算成 u64::MAX
+/// - 下限类参数（min_token_a / min_token_b、min_lp、min_new_lp）：传 0 本身就是不限制，不需要换算
+///
+/// 首次存入时 max_token_a / max_token_b 是实际存入的数量而不是上限，不适用这个哨兵值。
+/// 协议内部的 CPI 不想设置滑点时可以直接传 NO_SLIPPAGE_LIMIT。
+pub const NO_SLIPPAGE_LIMIT: u64 = 0;
+
+/// 把上限类滑点参数换算成实际上限：NO_SLIPPAGE_LIMIT 换算成 u64::MAX，其他值原样返回
+pub fn max_amount_limit(limit: u64) -> u64 {
+    if limit == NO_SLIPPAGE_LIMIT { u64::MAX } else { limit }
+}
+
+/// 向下取整的整数平方根（牛顿迭代）
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// 向上取整的整数平方根
+pub fn isqrt_ceil(n: u128) -> u128 {
+    let root = isqrt(n);
+    if root * root < n { root + 1 } else { root }
+}
+
+/// 向上取整的除法
+pub fn ceil_div(numerator: u128, denominator: u128) -> Option<u128> {
+    numerator
+        .checked_add(denominator.checked_sub(1)?)?
+        .checked_div(denominator)
+}
+
+/// 128 x 128 位乘法，返回 256 位结果的 (高 128 位, 低 128 位)
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // 中间一段三个加数都小于 2^64，相加不会溢出
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (middle << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+/// 256 位 (hi, lo) 除以 128 位，返回 (商, 余数)；商超出 u128 或除数为 0 时返回 None
+fn div_wide(hi: u128, lo: u128, denominator: u128) -> Option<(u128, u128)> {
+    if denominator == 0 || hi >= denominator {
+        return None;
+    }
+    if hi == 0 {
+        return Some((lo / denominator, lo % denominator));
+    }
+
+    // 逐位长除法，remainder 始终小于 denominator，左移时最高位单独记录
+    let mut quotient = 0u128;
+    let mut remainder = hi;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+/// a * b / denominator，向下取整；中间乘积按 256 位计算，不会因为乘法溢出而截断精度
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    let (hi, lo) = widening_mul(a, b);
+    div_wide(hi, lo, denominator).map(|(quotient, _)| quotient)
+}
+
+/// a * b / denominator，向上取整
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    let (hi, lo) = widening_mul(a, b);
+    let (quotient, remainder) = div_wide(hi, lo, denominator)?;
+    if remainder > 0 { quotient.checked_add(1) } else { Some(quotient) }
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+    Ok(value.try_into().map_err(|_| ProgramError::ArithmeticOverflow)?)
+}
+
+/// 精确输出 swap：想从池子拿走 amount_out，需要付出多少输入代币
+///
+/// 返回 (不含手续费的输入, 含手续费的输入)：
+/// - 不含手续费的输入向下取整，只用于统计
+/// - 含手续费的输入（用户实际付出的数量）向上取整
+///
+/// 手续费按用户实际付出的数量收取，扣掉手续费之后的部分参与恒定乘积：
+/// amount_in_with_fees * (10000 - fee) / 10000 = amount_in
+///
+/// k = reserve_in * reserve_out
+/// amount_in = k / (reserve_out - amount_out) - reserve_in
+///           = reserve_in * amount_out / (reserve_out - amount_out)
+/// amount_in_with_fees = reserve_in * amount_out * 10000 / ((reserve_out - amount_out) * (10000 - fee))
+///
+/// 只在最后一步向上取整，保证 swap 之后 k 不会减少。
+/// 手续费 amount_in_with_fees - amount_in 也转入池子（创建者和协议分成除外），所以 k 随手续费增长。
+pub fn swap_amount_in(reserve_in: u64, reserve_out: u64, amount_out: u64, fee: u16) -> Result<(u64, u64)> {
+    let remaining_out = reserve_out.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)? as u128;
+
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let amount_in = numerator.checked_div(remaining_out).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let amount_in_with_fees = ceil_div(
+        numerator.checked_mul(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?,
+        remaining_out.checked_mul(fee_complement(fee)?).ok_or(ProgramError::ArithmeticOverflow)?,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok((to_u64(amount_in)?, to_u64(amount_in_with_fees)?))
+}
+
+/// 精确输入 swap：付出 amount_in_with_fees 个输入代币最多可以拿走多少输出代币，向下取整
+///
+/// amount_in = amount_in_with_fees * (10000 - fee) / 10000
+/// amount_out = reserve_out * amount_in / (reserve_in + amount_in)
+///
+/// 两步都向下取整，再用 swap_amount_in 报价时需要的输入不会超过 amount_in_with_fees。
+pub fn swap_amount_out(reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, fee: u16) -> Result<u64> {
+    let amount_in = excess_after_fee(amount_in_with_fees as u128, fee)?;
+
+    let amount_out = (reserve_out as u128)
+        .checked_mul(amount_in).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div((reserve_in as u128).checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64(amount_out)
+}
+
+/// 扣除手续费之后参与曲线计算的比例（基点）：10000 - fee
+fn fee_complement(fee: u16) -> Result<u128> {
+    let complement = BPS_DENOMINATOR.checked_sub(fee as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    require_gt!(complement, 0);
+    Ok(complement)
+}
+
+/// 曲线需要 amount_in 时用户应付的含手续费输入，向上取整：amount_in * 10000 / (10000 - fee)
+///
+/// 按 excess_after_fee 扣除手续费之后不少于 amount_in，StableSwap / 加权池 / N 币种池共用
+pub fn amount_with_fee(amount_in: u128, fee: u16) -> Result<u128> {
+    Ok(ceil_div(
+        amount_in.checked_mul(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?,
+        fee_complement(fee)?,
+    ).ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+/// 创建者从 swap 手续费中分走的数量，向下取整
+///
+/// creator_fee = amount_in_with_fees * fee / 10000 * creator_fee_bps / 10000
+///
+/// 以用户实际付出的输入为基数，与曲线扣除手续费的方式一致：creator_fee_bps 不超过 10000 时，
+/// 池子实际收到的 amount_in_with_fees - creator_fee 仍然不少于 amount_in_with_fees * (10000 - fee) / 10000，
+/// 也就是曲线要求的输入，k 不会减少。
+pub fn creator_fee_amount(amount_in_with_fees: u64, fee: u16, creator_fee_bps: u16) -> Result<u64> {
+    let creator_fee = (amount_in_with_fees as u128)
+        .checked_mul(fee as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_mul(creator_fee_bps as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(BPS_DENOMINATOR * BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64(creator_fee)
+}
+
+/// 按权重（基点，之和为 10000）拆分 amount，前面的份额向下取整，余数全部归最后一份，总和恰好等于 amount
+pub fn split_by_weights(amount: u64, weights: &[u16]) -> Result<Vec<u64>> {
+    let mut remaining = amount;
+    let mut shares = Vec::with_capacity(weights.len());
+    for (i, &weight) in weights.iter().enumerate() {
+        let share = if i + 1 == weights.len() {
+            remaining
+        } else {
+            to_u64(mul_div_floor(amount as u128, weight as u128, BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?)?
+        };
+        remaining = remaining.checked_sub(share).ok_or(ProgramError::ArithmeticOverflow)?;
+        shares.push(share);
+    }
+    Ok(shares)
+}
+
+/// 成交均价相对现价的偏离（基点）：amount_out / (reserve_out - amount_out)
+///
+/// 这是两个价格的比值，小数位数在分子分母中相互抵消，不需要按 decimals 换算
+pub fn price_impact_bps(reserve_out: u64, amount_out: u64) -> Result<u128> {
+    let remaining_out = reserve_out.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok((amount_out as u128)
+        .checked_mul(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(remaining_out as u128).ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+/// 空池子的首次存入：LP 数量 = amount_a * amount_b
+pub fn initial_lp(amount_a: u64, amount_b: u64) -> Result<u64> {
+    Ok(amount_a.checked_mul(amount_b).ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+/// LP 模式的协议手续费：上次结算以来 sqrt(k) 的增长中，按 protocol_fee_bps 铸造给协议的 LP，向下取整
+///
+/// liquidity = lp_supply * p * (rk - rk_last) / ((10000 - p) * rk + p * rk_last)
+///
+/// 铸造之后协议持有的份额正好是 sqrt(k) 增长部分的 p / 10000，p = 1667 时与 Uniswap V2 的 1/6 相同。
+/// k_last 为 0（还没有开始记录）或者 k 没有增长时不铸造。
+pub fn protocol_fee_lp(reserve_a: u64, reserve_b: u64, lp_supply: u64, k_last: u128, protocol_fee_bps: u16) -> Result<u64> {
+    if k_last == 0 || lp_supply == 0 || protocol_fee_bps == 0 {
+        return Ok(0);
+    }
+
+    let root_k = isqrt(reserve_a as u128 * reserve_b as u128);
+    let root_k_last = isqrt(k_last);
+    if root_k <= root_k_last {
+        return Ok(0);
+    }
+
+    let p = protocol_fee_bps as u128;
+    let denominator = (BPS_DENOMINATOR - p)
+        .checked_mul(root_k).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_add(p.checked_mul(root_k_last).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let liquidity = mul_div_floor(lp_supply as u128 * p, root_k - root_k_last, denominator).ok_or(ProgramError::ArithmeticOverflow)?;
+    to_u64(liquidity)
+}
+
+/// 按比例存入：铸造 amount_lp 个 LP 需要的 (amount_a, amount_b)，向上取整
+///
+/// amount_x = reserve_x * amount_lp / lp_supply
+///
+/// 以 LP 总供应量而不是 k 作为分母：swap 手续费会让 k 增长得比 LP 供应量快，
+/// 用 k 计算会让新 LP 以低于净值的价格入场，稀释老 LP。
+pub fn deposit_amounts(reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_lp: u64) -> Result<(u64, u64)> {
+    require_gt!(lp_supply, 0);
+
+    let amount_a = mul_div_ceil(reserve_a as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    let amount_b = mul_div_ceil(reserve_b as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok((to_u64(amount_a)?, to_u64(amount_b)?))
+}
+
+/// 手上有 (amount_a, amount_b) 时最多可以按比例铸造的 LP 数量，向下取整
+///
+/// lp = min(amount_a * lp_supply / reserve_a, amount_b * lp_supply / reserve_b)
+/// 再用 deposit_amounts(lp) 向上取整得到的存入数量不会超过 amount_a / amount_b
+pub fn max_proportional_lp(reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_a: u64, amount_b: u64) -> Result<u64> {
+    let lp_a = mul_div_floor(amount_a as u128, lp_supply as u128, reserve_a as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    let lp_b = mul_div_floor(amount_b as u128, lp_supply as u128, reserve_b as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64(lp_a.min(lp_b))
+}
+
+/// 按比例取出：销毁 amount_lp 个 LP 可以拿回的 (amount_a, amount_b)，向下取整
+///
+/// amount_x = reserve_x * amount_lp / lp_supply
+///
+/// 不再先算一个 1e6 精度的比例再相乘：比例本身的截断会让小额取出直接变成 0，
+/// 大额取出也会少拿最多 reserve_x / 1e6 个代币
+pub fn withdraw_amounts(reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_lp: u64) -> Result<(u64, u64)> {
+    // 防止除零错误
+    require_gt!(lp_supply, 0);
+    require_gt!(amount_lp, 0);
+    require_gte!(lp_supply, amount_lp);
+
+    let amount_a = mul_div_floor(reserve_a as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    let amount_b = mul_div_floor(reserve_b as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok((to_u64(amount_a)?, to_u64(amount_b)?))
+}
+
+/// 自动复投金库：存入 amount_lp 个 LP 可以获得的份额，向下取整
+///
+/// 金库还没有份额时按 1:1 发放，之后按「金库持有的 LP / 份额总量」的比例发放
+pub fn vault_shares_for_lp(vault_lp: u64, share_supply: u64, amount_lp: u64) -> Result<u64> {
+    if share_supply == 0 {
+        return Ok(amount_lp);
+    }
+
+    let shares = mul_div_floor(amount_lp as u128, share_supply as u128, vault_lp as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64(shares)
+}
+
+/// 自动复投金库：赎回 shares 个份额可以取回的 LP 数量，向下取整
+pub fn vault_lp_for_shares(vault_lp: u64, share_supply: u64, shares: u64) -> Result<u64> {
+    require_gt!(share_supply, 0);
+    require_gte!(share_supply, shares);
+
+    let amount_lp = mul_div_floor(shares as u128, vault_lp as u128, share_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64(amount_lp)
+}
+
+/// 多出来的部分扣除 swap 手续费后的有效数量
+/// 与 swap 相同，手续费从付出的数量中扣除：excess * (10000 - fee) / 10000，向下取整
+pub fn excess_after_fee(excess: u128, fee: u16) -> Result<u128> {
+    Ok(excess
+        .checked_mul(fee_complement(fee)?).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(BPS_DENOMINATOR).ok_or(ProgramError::ArithmeticOverflow)?)
+}
+
+/// 任意比例存入可以铸造的 LP 数量
+///
+/// 1. 拆分成「按比例部分」和「多出来的部分」：
+///    in_a / reserve_a > in_b / reserve_b 说明 A 给多了，反之 B 给多了。
+///    多出来的部分相当于先 swap 再按比例存入，因此按 swap 的费率扣掉手续费后
+///    才计入不变量，避免用不平衡存入 + 取出来绕过 swap 手续费。
+/// 2. 按不变量 sqrt(k) 的增长比例铸造 LP：
+///    lp = supply * (sqrt(k1) - sqrt(k0)) / sqrt(k0)
+///    sqrt(k0) 向上取整、sqrt(k1) 向下取整，舍入误差始终对池子有利
+pub fn imbalanced_deposit_lp(reserve_a: u64, reserve_b: u64, lp_supply: u64, amount_a: u64, amount_b: u64, fee: u16) -> Result<u64> {
+    // 空池子没有价格可以参考，首次存入请使用 deposit
+    require_gt!(reserve_a, 0);
+    require_gt!(reserve_b, 0);
+    require_gt!(lp_supply, 0);
+
+    let reserve_a = reserve_a as u128;
+    let reserve_b = reserve_b as u128;
+    let in_a = amount_a as u128;
+    let in_b = amount_b as u128;
+
+    let a_times_rb = in_a.checked_mul(reserve_b).ok_or(ProgramError::ArithmeticOverflow)?;
+    let b_times_ra = in_b.checked_mul(reserve_a).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let (effective_a, effective_b) = if a_times_rb > b_times_ra {
+        let balanced_a = b_times_ra.checked_div(reserve_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        let excess_a = in_a.checked_sub(balanced_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        (balanced_a.checked_add(excess_after_fee(excess_a, fee)?).ok_or(ProgramError::ArithmeticOverflow)?, in_b)
+    } else {
+        let balanced_b = a_times_rb.checked_div(reserve_a).ok_or(ProgramError::ArithmeticOverflow)?;
+        let excess_b = in_b.checked_sub(balanced_b).ok_or(ProgramError::ArithmeticOverflow)?;
+        (in_a, balanced_b.checked_add(excess_after_fee(excess_b, fee)?).ok_or(ProgramError::ArithmeticOverflow)?)
+    };
+
+    let k0 = reserve_a.checked_mul(reserve_b).ok_or(ProgramError::ArithmeticOverflow)?;
+    let k1 = reserve_a.checked_add(effective_a).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_mul(reserve_b.checked_add(effective_b).ok_or(ProgramError::ArithmeticOverflow)?)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let root_k0 = isqrt_ceil(k0);
+    let root_k1 = isqrt(k1);
+
+    to_u64(
+        (lp_supply as u128)
+            .checked_mul(root_k1.saturating_sub(root_k0)).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(root_k0).ok_or(ProgramError::ArithmeticOverflow)?
+    )
+}
+
+// ========================================
+// 加权恒定乘积（LBP 使用）
+// ========================================
+//
+// 不变量：reserve_a^w_a * reserve_b^w_b = 常数
+// 精确输出时需要的输入：
+//   amount_in = reserve_in * ((reserve_out / (reserve_out - amount_out))^(w_out / w_in) - 1)
+//
+// 指数不是整数，用 Q2.62 定点数计算 log2 / exp2：
+//   base^e = 2^(e * log2(base))
+// 定点近似会有约 2^-50 的相对误差，结果再放大 2^-40 的安全余量并向上取整，
+// 保证误差永远由交易者承担，而不是池子。
+
+/// Q.62 定点数的 1
+const Q62_ONE: u128 = 1 << 62;
+
+/// ln(2) 的 Q.62 表示
+const LN2_Q62: u128 = 3_196_577_161_300_663_915;
+
+/// log2(x)，x 为 Q.62 且 x >= 1，结果为 Q.62
+fn log2_q62(x: u128) -> u128 {
+    // 整数部分：最高位的位置
+    let msb = 127 - x.leading_zeros();
+    let integer = (msb - 62) as u128;
+    let mut y = x >> integer;
+    let mut result = integer << 62;
+
+    // 小数部分：逐位平方，y >= 2 时该位为 1
+    for bit in (0..62).rev() {
+        y = (y * y) >> 62;
+        if y >= 2 * Q62_ONE {
+            y >>= 1;
+            result += 1u128 << bit;
+        }
+    }
+    result
+}
+
+/// 2^z，z 为 Q.62，结果为 Q.62；整数部分过大时返回 None
+fn exp2_q62(z: u128) -> Option<u128> {
+    let integer = z >> 62;
+    if integer >= 63 {
+        return None;
+    }
+    let fraction = z & (Q62_ONE - 1);
+
+    // 2^f = e^(f * ln2)，f * ln2 < 0.7，泰勒级数很快收敛
+    let t = (fraction * LN2_Q62) >> 62;
+    let mut sum = Q62_ONE;
+    let mut term = Q62_ONE;
+    for k in 1..=24u128 {
+        term = ((term * t) >> 62) / k;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    sum.checked_shl(integer as u32)
+}
+
+/// 加权池的精确输出 swap，返回值与 swap_amount_in 相同：(不含手续费的输入, 含手续费的输入)
+///
+/// weight_in / weight_out 以基点表示，两者之和为 BPS_DENOMINATOR
+pub fn weighted_swap_amount_in(reserve_in: u64, reserve_out: u64, amount_out: u64, weight_in: u16, weight_out: u16, fee: u16) -> Result<(u64, u64)> {
+    require_gt!(weight_in, 0);
+    require_gt!(weight_out, 0);
+    let remaining_out = reserve_out.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+    require_gt!(remaining_out, 0);
+
+    // base = reserve_out / remaining_out，向上取整
+    let base = ceil_div((reserve_out as u128) << 62, remaining_out as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // exponent * log2(base)
+    let z = log2_q62(base)
+        .checked_mul(weight_out as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(weight_in as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let power = exp2_q62(z).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // 安全余量：覆盖 log2 / exp2 的截断误差
+    let power = power
+        .checked_add(power >> 40).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // amount_in = reserve_in * (power - 1)，拆成整数部分和小数部分避免溢出
+    let growth = power.saturating_sub(Q62_ONE);
+    let whole = (reserve_in as u128)
+        .checked_mul(growth >> 62).ok_or(ProgramError::ArithmeticOverflow)?;
+    let fraction = ceil_div(
+        (reserve_in as u128).checked_mul(growth & (Q62_ONE - 1)).ok_or(ProgramError::ArithmeticOverflow)?,
+        Q62_ONE,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
+    let amount_in = whole.checked_add(fraction).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let amount_in_with_fees = amount_with_fee(amount_in, fee)?;
+
+    Ok((to_u64(amount_in)?, to_u64(amount_in_with_fees)?))
+}
+
+/// 加权池不变量 reserve_a^w_a * reserve_b^w_b 的对数：w_a * log2(reserve_a) + w_b * log2(reserve_b)，Q.62，向下取整
+///
+/// 权重以基点表示，结果是对数的 BPS_DENOMINATOR 倍；储备量不能为 0
+pub fn weighted_invariant_log2(reserve_a: u64, reserve_b: u64, weight_a: u16, weight_b: u16) -> Result<u128> {
+    require_gt!(reserve_a, 0);
+    require_gt!(reserve_b, 0);
+
+    // log2 最大为 64 * 2^62，乘以基点权重不会超出 u128
+    let log_a = log2_q62((reserve_a as u128) << 62) * weight_a as u128;
+    let log_b = log2_q62((reserve_b as u128) << 62) * weight_b as u128;
+    Ok(log_a + log_b)
+}
+
+/// 按时间线性插值 LBP 的 TokenA 权重（基点）
+pub fn interpolate_weight(start_weight: u16, end_weight: u16, start_ts: i64, end_ts: i64, now: i64) -> u16 {
+    if now <= start_ts || end_ts <= start_ts {
+        return if now >= end_ts { end_weight } else { start_weight };
+    }
+    if now >= end_ts {
+        return end_weight;
+    }
+
+    let elapsed = (now - start_ts) as i128;
+    let duration = (end_ts - start_ts) as i128;
+    let delta = end_weight as i128 - start_weight as i128;
+    (start_weight as i128 + delta * elapsed / duration) as u16
+}
+
+/// 加权池的精确输入 swap：付出 amount_in_with_fees 个输入代币最多可以拿走多少输出代币，向下取整
+///
+/// amount_out = reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(w_in / w_out))
+///            = reserve_out - reserve_out / ((reserve_in + amount_in) / reserve_in)^(w_in / w_out)
+///
+/// 与 weighted_swap_amount_in 相反，这里的底数和幂都向下修正，输出偏少，
+/// 保证拿这个输出再用 weighted_swap_amount_in 报价，需要的输入不会超过 amount_in_with_fees
+pub fn weighted_swap_amount_out(reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, weight_in: u16, weight_out: u16, fee: u16) -> Result<u64> {
+    require_gt!(weight_in, 0);
+    require_gt!(weight_out, 0);
+    require_gt!(reserve_in, 0);
+
+    // 少算 1 个输入，抵消精确输出报价最后一步的向上取整
+    let amount_in = excess_after_fee(amount_in_with_fees as u128, fee)?.saturating_sub(1);
+
+    // base = (reserve_in + amount_in) / reserve_in，向下取整
+    // 两者之和小于 2^65，左移 62 位不会超出 u128
+    let base = (((reserve_in as u128) + amount_in) << 62) / reserve_in as u128;
+
+    // 安全余量放在底数上：指数 w_in / w_out 可能远小于 1，放在幂上会被指数缩小，
+    // 不足以抵消 weighted_swap_amount_in 在幂上加的余量
+    let base = base.saturating_sub(base >> 38).max(Q62_ONE);
+
+    let z = log2_q62(base)
+        .checked_mul(weight_in as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(weight_out as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // 指数太大时 power 溢出，说明几乎可以买空池子，按 power 无穷大处理
+    let Some(power) = exp2_q62(z) else {
+        return to_u64((reserve_out as u128).saturating_sub(1));
+    };
+
+    // 安全余量：与精确输出相反，幂往小的方向修正
+    let power = power.saturating_sub((power >> 40) + 1).max(Q62_ONE);
+
+    // remaining_out = reserve_out / power，向上取整
+    let remaining_out = ceil_div((reserve_out as u128) << 62, power).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    to_u64((reserve_out as u128).saturating_sub(remaining_out))
+}
+
+// ========================================
+// StableSwap（Curve 两币种不变量）
+// ========================================
+//
+// 不变量：A * n^n * (x + y) + D = A * D * n^n + D^(n+1) / (n^n * x * y)，n = 2
+// A 越大，价格在 1:1 附近越平坦，适合稳定币、LST 等价格锚定的交易对。
+// D 和 y 都没有闭式解，用牛顿迭代求解，收敛到相邻两次差值不超过 1。
+// 整数除法的截断可能让迭代在两个相邻值之间来回跳，这时同样视为收敛。
+//
+// 舍入方向：D 和 y 都取较大的值。D 越大，精确输出需要的输入越多、精确输入得到的输出越少，
+// 两个方向都对池子有利。
+//
+// 中间结果 D^2 需要放进 u128，储备量超过约 2^62 时返回溢出错误。
+
+/// 牛顿迭代的最大次数，正常参数下十几次以内就会收敛
+const STABLE_SWAP_MAX_ITERATIONS: usize = 255;
+
+/// 求不变量 D，向上取整
+pub fn stable_swap_d(amp: u64, x: u64, y: u64) -> Result<u128> {
+    let sum = (x as u128).checked_add(y as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    if sum == 0 {
+        return Ok(0);
+    }
+    require_gt!(x, 0);
+    require_gt!(y, 0);
+
+    let ann = (amp as u128).checked_mul(4).ok_or(ProgramError::ArithmeticOverflow)?;
+    let mut d = sum;
+    let mut before_previous = None;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        // d_p = D^3 / (4xy)
+        let d_p = d
+            .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(x as u128 * 2).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div(y as u128 * 2).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let previous = d;
+        // D = (Ann * S + 2 * d_p) * D / ((Ann - 1) * D + 3 * d_p)
+        let numerator = ann
+            .checked_mul(sum).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(d_p.checked_mul(2).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(d_p.checked_mul(3).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+        d = numerator.checked_div(denominator).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if d.abs_diff(previous) <= 1 || before_previous == Some(d) {
+            return Ok(d.max(previous));
+        }
+        before_previous = Some(previous);
+    }
+    Err(ProgramError::ArithmeticOverflow.into())
+}
+
+/// 已知一侧储备量 x 和不变量 D，求另一侧储备量 y，向上取整
+pub fn stable_swap_y(amp: u64, x: u64, d: u128) -> Result<u128> {
+    require_gt!(x, 0);
+
+    let ann = (amp as u128).checked_mul(4).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // y^2 + (b - D) * y = c
+    // c = D^3 / (4 * x * Ann)，b = x + D / Ann
+    let c = d
+        .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(x as u128 * 2).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(ann.checked_mul(2).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+    let b = (x as u128).checked_add(d / ann).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let mut y = d;
+    let mut before_previous = None;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let previous = y;
+        // y = (y^2 + c) / (2y + b - D)
+        let numerator = y
+            .checked_mul(y).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(c).ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = y
+            .checked_mul(2).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(b).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_sub(d).ok_or(ProgramError::ArithmeticOverflow)?;
+        y = ceil_div(numerator, denominator).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if y.abs_diff(previous) <= 1 || before_previous == Some(y) {
+            return Ok(y.max(previous));
+        }
+        before_previous = Some(previous);
+    }
+    Err(ProgramError::ArithmeticOverflow.into())
+}
+
+/// StableSwap 精确输出，返回值与 swap_amount_in 相同：(不含手续费的输入, 含手续费的输入)
+///
+/// 需要的输入 = y(reserve_out - amount_out) - reserve_in，再加 1 覆盖牛顿迭代的舍入误差
+pub fn stable_swap_amount_in(amp: u64, reserve_in: u64, reserve_out: u64, amount_out: u64, fee: u16) -> Result<(u64, u64)> {
+    let remaining_out = reserve_out.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+    require_gt!(remaining_out, 0);
+
+    let d = stable_swap_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = stable_swap_y(amp, remaining_out, d)?;
+
+    let amount_in = new_reserve_in
+        .checked_sub(reserve_in as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let amount_in_with_fees = amount_with_fee(amount_in, fee)?;
+
+    Ok((to_u64(amount_in)?, to_u64(amount_in_with_fees)?))
+}
+
+/// StableSwap 精确输入：输出 = reserve_out - y(reserve_in + amount_in) - 1，向下取整
+///
+/// 输入少算 3：stable_swap_amount_in 最多多算 3（y 向上取整 1、迭代收敛误差 1、安全余量 1），
+/// 保证这个输出再按精确输出报价不会超出预算
+pub fn stable_swap_amount_out(amp: u64, reserve_in: u64, reserve_out: u64, amount_in_with_fees: u64, fee: u16) -> Result<u64> {
+    let amount_in = excess_after_fee(amount_in_with_fees as u128, fee)?.saturating_sub(3);
+    let new_reserve_in = to_u64((reserve_in as u128).checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?)?;
+
+    let d = stable_swap_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_out = stable_swap_y(amp, new_reserve_in, d)?;
+
+    to_u64((reserve_out as u128).saturating_sub(new_reserve_out).saturating_sub(1))
+}
+
+// ========================================
+// StableSwap N 币种（多资产池）
+// ========================================
+//
+// 不变量与两币种相同，把 n 推广到 2..=8：
+//   A * n^n * Σx + D = A * D * n^n + D^(n+1) / (n^n * Πx)
+// n = 2 时与 stable_swap_d / stable_swap_y 的结果完全相同。
+// 储备量按最小单位直接参与计算，不做小数位数换算，所以多资产池要求所有代币的小数位数相同。
+// 舍入方向和迭代收敛条件与两币种相同；D^(n+1) / Πx 逐项用 256 位中间乘积计算，
+// 但 y 的迭代里仍有 y^2，储备量之和超过约 2^62 时返回溢出错误。
+
+/// 求 N 币种不变量 D，向上取整
+pub fn stable_swap_n_d(amp: u64, balances: &[u64]) -> Result<u128> {
+    let n = balances.len() as u128;
+    let sum = balances.iter().try_fold(0u128, |sum, &x| sum.checked_add(x as u128)).ok_or(ProgramError::ArithmeticOverflow)?;
+    if sum == 0 {
+        return Ok(0);
+    }
+    for &x in balances {
+        require_gt!(x, 0);
+    }
+
+    let ann = (amp as u128).checked_mul(n.pow(n as u32)).ok_or(ProgramError::ArithmeticOverflow)?;
+    let mut d = sum;
+    let mut before_previous = None;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        // d_p = D^(n+1) / (n^n * Πx)
+        let mut d_p = d;
+        for &x in balances {
+            d_p = mul_div_floor(d_p, d, x as u128 * n).ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+
+        let previous = d;
+        // D = (Ann * S + n * d_p) * D / ((Ann - 1) * D + (n + 1) * d_p)
+        let numerator = ann
+            .checked_mul(sum).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(d_p.checked_mul(n).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(d_p.checked_mul(n + 1).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+        d = mul_div_floor(numerator, d, denominator).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if d.abs_diff(previous) <= 1 || before_previous == Some(d) {
+            return Ok(d.max(previous));
+        }
+        before_previous = Some(previous);
+    }
+    Err(ProgramError::ArithmeticOverflow.into())
+}
+
+/// 已知其他币种的储备量和不变量 D，求 balances[index]，向上取整；balances[index] 本身不参与计算
+pub fn stable_swap_n_y(amp: u64, balances: &[u64], index: usize, d: u128) -> Result<u128> {
+    let n = balances.len() as u128;
+    let ann = (amp as u128).checked_mul(n.pow(n as u32)).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // y^2 + (b - D) * y = c
+    // c = D^(n+1) / (n^n * Πx' * Ann)，b = Σx' + D / Ann，x' 为除 index 以外的储备量
+    let mut c = d;
+    let mut sum = 0u128;
+    for (k, &x) in balances.iter().enumerate() {
+        if k == index {
+            continue;
+        }
+        require_gt!(x, 0);
+        sum = sum.checked_add(x as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+        c = mul_div_floor(c, d, x as u128 * n).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    c = mul_div_floor(c, d, ann.checked_mul(n).ok_or(ProgramError::ArithmeticOverflow)?).ok_or(ProgramError::ArithmeticOverflow)?;
+    let b = sum.checked_add(d / ann).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let mut y = d;
+    let mut before_previous = None;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let previous = y;
+        // y = (y^2 + c) / (2y + b - D)
+        let numerator = y
+            .checked_mul(y).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(c).ok_or(ProgramError::ArithmeticOverflow)?;
+        let denominator = y
+            .checked_mul(2).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_add(b).ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_sub(d).ok_or(ProgramError::ArithmeticOverflow)?;
+        y = ceil_div(numerator, denominator).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if y.abs_diff(previous) <= 1 || before_previous == Some(y) {
+            return Ok(y.max(previous));
+        }
+        before_previous = Some(previous);
+    }
+    Err(ProgramError::ArithmeticOverflow.into())
+}
+
+/// N 币种精确输出，返回值与 swap_amount_in 相同：(不含手续费的输入, 含手续费的输入)
+pub fn stable_swap_n_amount_in(amp: u64, balances: &[u64], index_in: usize, index_out: usize, amount_out: u64, fee: u16) -> Result<(u64, u64)> {
+    let mut balances_after = balances.to_vec();
+    balances_after[index_out] = balances[index_out].checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+    require_gt!(balances_after[index_out], 0);
+
+    let d = stable_swap_n_d(amp, balances)?;
+    let new_reserve_in = stable_swap_n_y(amp, &balances_after, index_in, d)?;
+
+    let amount_in = new_reserve_in
+        .checked_sub(balances[index_in] as u128).ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let amount_in_with_fees = amount_with_fee(amount_in, fee)?;
+
+    Ok((to_u64(amount_in)?, to_u64(amount_in_with_fees)?))
+}
+
+/// N 币种按比例存入：铸造 amount_lp 个 LP 需要的各币种数量，向上取整
+pub fn deposit_amounts_n(reserves: &[u64], lp_supply: u64, amount_lp: u64) -> Result<Vec<u64>> {
+    require_gt!(lp_supply, 0);
+
+    reserves
+        .iter()
+        .map(|&reserve| to_u64(mul_div_ceil(reserve as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?))
+        .collect()
+}
+
+/// N 币种按比例取出：销毁 amount_lp 个 LP 可以拿回的各币种数量，向下取整
+pub fn withdraw_amounts_n(reserves: &[u64], lp_supply: u64, amount_lp: u64) -> Result<Vec<u64>> {
+    require_gt!(lp_supply, 0);
+    require_gt!(amount_lp, 0);
+    require_gte!(lp_supply, amount_lp);
+
+    reserves
+        .iter()
+        .map(|&reserve| to_u64(mul_div_floor(reserve as u128, amount_lp as u128, lp_supply as u128).ok_or(ProgramError::ArithmeticOverflow)?))
+        .collect()
+}
+
+/// 线性释放：[start_ts, end_ts] 内按时间比例释放 total，到期后全部释放，向下取整
+pub fn linear_vested_amount(total: u64, start_ts: i64, end_ts: i64, now: i64) -> Result<u64> {
+    if now >= end_ts {
+        return Ok(total);
+    }
+    if now <= start_ts {
+        return Ok(0);
+    }
+
+    let elapsed = now.abs_diff(start_ts) as u128;
+    let duration = end_ts.abs_diff(start_ts) as u128;
+    to_u64(mul_div_floor(total as u128, elapsed, duration).ok_or(ProgramError::ArithmeticOverflow)?)
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, math::BPS_DENOMINATOR, state::{Observation, Pool, OBSERVATION_CAPACITY, OBSERVATION_INTERVAL}};
+
+// ========================================
+// 价格预言机（Uniswap V2 风格累加器）
+// ========================================
+//
+// price_a = reserve_b / reserve_a，使用 Q64.64 定点数表示
+// price_a_cumulative += price_a * elapsed（允许溢出回绕，只关心差值）
+//
+// 任意两个观测之间的 TWAP：
+//   twap_a = (cumulative_a_2 - cumulative_a_1) / (t2 - t1)
+//
+// 累加器必须在储备量变化 **之前** 更新，这样记录的是上一段时间内的价格。
+
+impl Pool {
+    /// 使用当前储备量推进价格累加器，并在间隔足够时写入新的观测
+    pub fn update_oracle(&mut self, reserve_a: u64, reserve_b: u64, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_update_ts);
+
+        if elapsed > 0 && reserve_a > 0 && reserve_b > 0 {
+            // u64 左移 64 位后刚好放进 u128，不会溢出
+            let price_a = ((reserve_b as u128) << 64)
+                .checked_div(reserve_a as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+            let price_b = ((reserve_a as u128) << 64)
+                .checked_div(reserve_b as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+            self.price_a_cumulative = self.price_a_cumulative.wrapping_add(price_a.wrapping_mul(elapsed as u128));
+            self.price_b_cumulative = self.price_b_cumulative.wrapping_add(price_b.wrapping_mul(elapsed as u128));
+        }
+
+        if elapsed > 0 {
+            self.last_update_ts = now;
+        }
+
+        self.write_observation(now);
+        Ok(())
+    }
+
+    /// 距离上一次观测超过 OBSERVATION_INTERVAL 时，推进环形缓冲区
+    fn write_observation(&mut self, now: i64) {
+        let last = self.observations[self.observation_index as usize];
+        if last.timestamp != 0 && now.saturating_sub(last.timestamp) < OBSERVATION_INTERVAL {
+            return;
+        }
+
+        let next = if last.timestamp == 0 {
+            self.observation_index as usize
+        } else {
+            (self.observation_index as usize + 1) % OBSERVATION_CAPACITY
+        };
+
+        self.observations[next] = Observation {
+            timestamp: now,
+            price_a_cumulative: self.price_a_cumulative,
+            price_b_cumulative: self.price_b_cumulative,
+        };
+        self.observation_index = next as u8;
+    }
+
+    /// 把以最小单位计算的价格（reserve_b / reserve_a）换算成以「整枚代币」计价的价格
+    ///
+    /// 储备量都是最小单位，6 位小数和 9 位小数的代币直接相除会差 1000 倍：
+    ///   实际价格 = (reserve_b / 10^decimals_b) / (reserve_a / 10^decimals_a)
+    ///            = raw_price * 10^(decimals_a - decimals_b)
+    /// 累加器里存的是原始比值（常数倍数不影响 TWAP 的差分），对外输出价格时再统一换算。
+    pub fn normalize_price_a(&self, raw_price: u128) -> Result<u128> {
+        if self.decimals_a >= self.decimals_b {
+            let scale = 10u128.pow((self.decimals_a - self.decimals_b) as u32);
+            Ok(raw_price.checked_mul(scale).ok_or(ProgramError::ArithmeticOverflow)?)
+        } else {
+            let scale = 10u128.pow((self.decimals_b - self.decimals_a) as u32);
+            Ok(raw_price.checked_div(scale).ok_or(ProgramError::ArithmeticOverflow)?)
+        }
+    }
+
+    /// 环形缓冲区中最早的一条有效观测
+    pub fn oldest_observation(&self) -> Observation {
+        let next = (self.observation_index as usize + 1) % OBSERVATION_CAPACITY;
+        if self.observations[next].timestamp != 0 {
+            self.observations[next]
+        } else {
+            // 缓冲区还没写满，最早的观测在下标 0
+            self.observations[0]
+        }
+    }
+
+    /// 从最早的观测到最近一次 update_oracle 之间 TokenA 的 TWAP（Q64.64，以 TokenB 计价）
+    /// 时间跨度为 0 时返回 None
+    pub fn twap_a(&self) -> Option<u128> {
+        let oldest = self.oldest_observation();
+        let elapsed = self.last_update_ts.checked_sub(oldest.timestamp)?;
+        if elapsed <= 0 || oldest.timestamp == 0 {
+            return None;
+        }
+        self.price_a_cumulative
+            .wrapping_sub(oldest.price_a_cumulative)
+            .checked_div(elapsed as u128)
+    }
+
+    /// 价格带熔断：交易后的现价偏离 TWAP 超过 circuit_breaker_bps 时拒绝这笔交易
+    ///
+    /// reserve_a / reserve_b 是交易 **之后** 的储备量。操纵者必须在一笔交易里把价格推离 TWAP，
+    /// 超过阈值的那一笔会整体回滚，本 slot 内之后的每一笔同样要重新通过检查，
+    /// 依赖现价的集成方就不会在同一个 slot 里读到被操纵的价格。
+    /// 熔断关闭或者观测不足、还没有 TWAP 时不检查。
+    pub fn check_circuit_breaker(&self, reserve_a: u64, reserve_b: u64) -> Result<()> {
+        if self.circuit_breaker_bps == 0 || reserve_a == 0 {
+            return Ok(());
+        }
+
+        let Some(deviation_bps) = self.twap_deviation_bps(reserve_a, reserve_b)? else {
+            return Ok(());
+        };
+
+        require!(deviation_bps <= self.circuit_breaker_bps as u128, AmmError::CircuitBreakerTripped);
+        Ok(())
+    }
+
+    /// 按 reserve_a / reserve_b 计算的现价偏离 TWAP 多少基点，还没有 TWAP 时返回 None
+    pub fn twap_deviation_bps(&self, reserve_a: u64, reserve_b: u64) -> Result<Option<u128>> {
+        let twap = match self.twap_a() {
+            Some(twap) if twap > 0 => twap,
+            _ => return Ok(None),
+        };
+
+        let spot = ((reserve_b as u128) << 64)
+            .checked_div(reserve_a as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // 偏离大到乘法溢出时一定超过阈值
+        let deviation_bps = spot.abs_diff(twap)
+            .checked_mul(BPS_DENOMINATOR)
+            .map_or(u128::MAX, |deviation| deviation / twap);
+
+        Ok(Some(deviation_bps))
+    }
+}
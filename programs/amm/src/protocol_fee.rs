@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{mint_to, Mint, MintTo, Token, TokenAccount};
+
+use crate::{
+    error::AmmError,
+    math::protocol_fee_lp,
+    state::{CurveType, Pool, ProtocolFeeMode},
+};
+
+// ========================================
+// LP 模式的协议手续费（Uniswap V2 kLast）
+// ========================================
+//
+// Skim 模式下每笔 swap 都要把协议分成单独转入手续费账户，多一次 CPI。
+// LP 模式下 swap 不做任何额外处理，手续费全部留在储备量中让 k 增长，
+// 等到有人存取流动性时再一次性结算：
+// 1. 流动性变化之前，按 k_last 以来 sqrt(k) 的增长铸造 LP 给 treasury_lp（settle_protocol_fee）
+// 2. 流动性变化之后，把新的 k 记为 k_last（update_k_last），存取本身不会被当成手续费
+//
+// 存取流动性的指令都必须走这两步，否则下一次结算会把存入的流动性也算成手续费。
+// 目前 deposit / deposit_imbalanced / withdraw / withdraw_percent 支持，
+// 其他改变流动性的指令（委托存入、复投、迁移）在 LP 模式下直接拒绝，见 require_skim_protocol_fee。
+//
+// sqrt(k) 只对恒定乘积曲线有意义，其他曲线不铸造。donate 让 k 增长但不改变 LP 供应量，会被当作手续费分给协议。
+
+impl Pool {
+    /// LP 模式下 swap 不扣协议分成
+    pub fn skims_protocol_fee(&self) -> bool {
+        self.protocol_fee_mode == ProtocolFeeMode::Skim
+    }
+
+    /// 无法结算 LP 模式协议手续费的指令调用
+    pub fn require_skim_protocol_fee(&self) -> Result<()> {
+        require!(self.skims_protocol_fee(), AmmError::UnsupportedProtocolFeeMode);
+        Ok(())
+    }
+
+    /// 流动性变化之后记录新的 k，Skim 模式不记录
+    pub fn update_k_last(&mut self, reserve_a: u64, reserve_b: u64) {
+        if !self.skims_protocol_fee() {
+            self.k_last = reserve_a as u128 * reserve_b as u128;
+        }
+    }
+}
+
+/// 流动性变化之前调用：LP 模式下按 k 的增长铸造 LP 给 treasury_lp，并刷新 mint_lp 的供应量
+///
+/// 铸造之后调用方再按 mint_lp.supply 计算存取数量，新 LP 不会分到结算前的协议手续费
+pub fn settle_protocol_fee<'info>(
+    pool: &Account<'info, Pool>,
+    mint_lp: &mut Account<'info, Mint>,
+    treasury_lp: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    reserve_a: u64,
+    reserve_b: u64,
+) -> Result<()> {
+    if pool.skims_protocol_fee() {
+        return Ok(());
+    }
+
+    let treasury_lp = treasury_lp.ok_or(AmmError::TreasuryLpRequired)?;
+
+    if pool.curve_type != CurveType::ConstantProduct {
+        return Ok(());
+    }
+
+    let amount = protocol_fee_lp(reserve_a, reserve_b, mint_lp.supply, pool.k_last, pool.protocol_fee_bps)?;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let accounts = MintTo {
+        mint: mint_lp.to_account_info(),
+        to: treasury_lp.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+
+    let pool_seeds = pool.signer_seeds();
+
+    let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+
+    let ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        accounts,
+        &signer_seeds
+    );
+
+    mint_to(ctx, amount)?;
+
+    mint_lp.reload()
+}
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{AmmConfig, AutoVault, DcaOrder, Farm, LimitOrder, MultiPool, OtcOffer, Pool, ScheduledWithdraw};
+
+// ========================================
+// PDA 的签名种子
+// ========================================
+//
+// 池子代币账户和 LP mint 的 authority 都是 pool PDA，几乎每个指令都要用 pool 的种子签名 CPI。
+// 种子里的 fee 和 bump 需要先变成字节数组，字节数组必须活得比 CpiContext 长，
+// 所以这里返回一个持有这些字节的结构体，调用方先绑定到局部变量再取切片：
+//
+//     let pool_seeds = self.pool.signer_seeds();
+//     let signer_seeds: [&[&[u8]];1] = [&pool_seeds.as_seeds()];
+//
+// 三层引用分别是：&[u8] 单个种子、&[&[u8]] 一个 PDA 的全部种子、&[&[&[u8]]] 一次 CPI 的全部签名者。
+// as_seeds() 借用结构体本身，返回值的生命周期不会超过 pool_seeds，忘记绑定局部变量时编译器直接报错。
+//
+// 种子的顺序与各自的 seeds 约束一致：["pool", mint_a, mint_b, fee, bump]、["lp", pool, lp_bump]、["farm", pool, bump]、
+// ["auto_vault", pool, bump]、["multi_pool", mints_hash, fee, bump]、["config", bump]，
+// 以及限价单、定投、OTC 报价、分期取出共用的 [前缀, pool, owner, id, bump]（OTC 报价的 owner 是 maker）。
+// 外部程序 CPI 调用时可以用 new 直接构造，不需要先反序列化账户。
+
+/// pool PDA 的签名种子
+pub struct PoolSeeds {
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    fee: [u8; 2],
+    bump: [u8; 1],
+}
+
+impl PoolSeeds {
+    pub fn new(mint_a: Pubkey, mint_b: Pubkey, fee: u16, bump: u8) -> Self {
+        Self {
+            mint_a,
+            mint_b,
+            fee: fee.to_le_bytes(),
+            bump: [bump],
+        }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 5] {
+        [b"pool", self.mint_a.as_ref(), self.mint_b.as_ref(), &self.fee, &self.bump]
+    }
+}
+
+/// LP mint PDA 的签名种子
+pub struct LpMintSeeds {
+    pool: Pubkey,
+    bump: [u8; 1],
+}
+
+impl LpMintSeeds {
+    pub fn new(pool: Pubkey, bump: u8) -> Self {
+        Self { pool, bump: [bump] }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 3] {
+        [b"lp", self.pool.as_ref(), &self.bump]
+    }
+}
+
+/// farm PDA 的签名种子，farm 的奖励和 LP 代币账户都由它签名转出
+pub struct FarmSeeds {
+    pool: Pubkey,
+    bump: [u8; 1],
+}
+
+impl FarmSeeds {
+    pub fn new(pool: Pubkey, bump: u8) -> Self {
+        Self { pool, bump: [bump] }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 3] {
+        [b"farm", self.pool.as_ref(), &self.bump]
+    }
+}
+
+/// 用户订单类 PDA 的签名种子：限价单、定投、OTC 报价、分期取出，托管 ATA 都由它签名转出
+pub struct OrderSeeds {
+    prefix: &'static [u8],
+    pool: Pubkey,
+    owner: Pubkey,
+    id: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl OrderSeeds {
+    pub fn new(prefix: &'static [u8], pool: Pubkey, owner: Pubkey, id: u64, bump: u8) -> Self {
+        Self {
+            prefix,
+            pool,
+            owner,
+            id: id.to_le_bytes(),
+            bump: [bump],
+        }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 5] {
+        [self.prefix, self.pool.as_ref(), self.owner.as_ref(), &self.id, &self.bump]
+    }
+}
+
+/// 自动复投金库 PDA 的签名种子
+pub struct AutoVaultSeeds {
+    pool: Pubkey,
+    bump: [u8; 1],
+}
+
+impl AutoVaultSeeds {
+    pub fn new(pool: Pubkey, bump: u8) -> Self {
+        Self { pool, bump: [bump] }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 3] {
+        [b"auto_vault", self.pool.as_ref(), &self.bump]
+    }
+}
+
+/// 多币种池子 PDA 的签名种子
+pub struct MultiPoolSeeds {
+    mints_hash: [u8; 32],
+    fee: [u8; 2],
+    bump: [u8; 1],
+}
+
+impl MultiPoolSeeds {
+    pub fn new(mints_hash: [u8; 32], fee: u16, bump: u8) -> Self {
+        Self {
+            mints_hash,
+            fee: fee.to_le_bytes(),
+            bump: [bump],
+        }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 4] {
+        [b"multi_pool", &self.mints_hash, &self.fee, &self.bump]
+    }
+}
+
+/// 全局配置 PDA 的签名种子，协议金库（config 名下的关联代币账户）由它签名转出
+pub struct ConfigSeeds {
+    bump: [u8; 1],
+}
+
+impl ConfigSeeds {
+    pub fn new(bump: u8) -> Self {
+        Self { bump: [bump] }
+    }
+
+    pub fn as_seeds(&self) -> [&[u8]; 2] {
+        [b"config", &self.bump]
+    }
+}
+
+impl Pool {
+    /// pool PDA 的签名种子，mint_a / mint_b 取自池子账户本身
+    pub fn signer_seeds(&self) -> PoolSeeds {
+        PoolSeeds::new(self.mint_a, self.mint_b, self.fee, self.bump)
+    }
+
+    /// LP mint PDA 的签名种子，pool 是池子账户的地址
+    pub fn lp_mint_signer_seeds(&self, pool: &Pubkey) -> LpMintSeeds {
+        LpMintSeeds::new(*pool, self.lp_bump)
+    }
+}
+
+impl Farm {
+    /// farm PDA 的签名种子
+    pub fn signer_seeds(&self) -> FarmSeeds {
+        FarmSeeds::new(self.pool, self.bump)
+    }
+}
+
+impl LimitOrder {
+    /// 限价单 PDA 的签名种子
+    pub fn signer_seeds(&self) -> OrderSeeds {
+        OrderSeeds::new(b"limit_order", self.pool, self.owner, self.id, self.bump)
+    }
+}
+
+impl DcaOrder {
+    /// 定投订单 PDA 的签名种子
+    pub fn signer_seeds(&self) -> OrderSeeds {
+        OrderSeeds::new(b"dca", self.pool, self.owner, self.id, self.bump)
+    }
+}
+
+impl OtcOffer {
+    /// OTC 报价 PDA 的签名种子，owner 位置是 maker
+    pub fn signer_seeds(&self) -> OrderSeeds {
+        OrderSeeds::new(b"otc_offer", self.pool, self.maker, self.id, self.bump)
+    }
+}
+
+impl ScheduledWithdraw {
+    /// 分期取出计划 PDA 的签名种子
+    pub fn signer_seeds(&self) -> OrderSeeds {
+        OrderSeeds::new(b"scheduled_withdraw", self.pool, self.owner, self.id, self.bump)
+    }
+}
+
+impl AutoVault {
+    /// 自动复投金库 PDA 的签名种子
+    pub fn signer_seeds(&self) -> AutoVaultSeeds {
+        AutoVaultSeeds::new(self.pool, self.bump)
+    }
+}
+
+impl MultiPool {
+    /// 多币种池子 PDA 的签名种子
+    pub fn signer_seeds(&self) -> MultiPoolSeeds {
+        MultiPoolSeeds::new(self.mints_hash, self.fee, self.bump)
+    }
+}
+
+impl AmmConfig {
+    /// 全局配置 PDA 的签名种子
+    pub fn signer_seeds(&self) -> ConfigSeeds {
+        ConfigSeeds::new(self.bump)
+    }
+}
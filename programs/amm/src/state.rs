@@ -1,11 +1,721 @@
 use anchor_lang::prelude::*;
 
+/// LBP 单侧权重的下限和上限（基点），限制加权 swap 中指数的范围
+pub const MIN_LBP_WEIGHT_BPS: u16 = 100;
+pub const MAX_LBP_WEIGHT_BPS: u16 = 9_900;
+
+/// 当前 Pool 账户的布局版本，旧布局由 migrate_pool_v2 升级
+///
+/// - v1：最早只有 mint_a / mint_b / fee / bump / lp_bump 的布局，没有 version 字段，见 PoolV1
+/// - v2：第一个带 version 字段的布局，见 PoolV2
+/// - v3：当前布局。此后只允许在 Pool 末尾追加字段，追加后把新的长度加到 POOL_V3_SPACES，
+///   已有的池子用 extend_pool_account 扩容；在中间插入、删除或者修改字段类型必须递增版本，
+///   并在 migrate_pool_v2 中加上对应的旧布局
+pub const POOL_VERSION: u8 = 3;
+
+/// v3 布局先后出现过的账户长度（含 discriminator），按时间顺序，最后一个是当前 Pool 的长度
+pub const POOL_V3_SPACES: [usize; 2] = [1012, 1020];
+
+const _: () = assert!(POOL_V3_SPACES[POOL_V3_SPACES.len() - 1] == 8 + Pool::INIT_SPACE);
+
+/// 其他账户的布局版本，布局变化时递增并提供对应的迁移指令
+///
+/// - v1：初始布局
+/// - v2：AmmConfig 的 fee_tiers 改为 FeeTier 并增加回购、紧急模式等字段，旧账户见 AmmConfigV1 和 migrate_config
+/// - v3：TraderState 末尾追加往返附加费的 last_swap_slot / last_swap_is_a，旧账户见 TraderStateV1 和 migrate_trader_state
+pub const ACCOUNT_VERSION: u8 = 3;
+
+/// 观测环形缓冲区的容量
+pub const OBSERVATION_CAPACITY: usize = 8;
+
+/// 两次观测之间的最小间隔（秒），避免同一区块内反复覆盖环形缓冲区
+pub const OBSERVATION_INTERVAL: i64 = 60;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Pool {
+    pub version: u8,                  // 布局版本，紧跟在 discriminator 之后，索引器只读一个字节就能判断如何解析
+    pub authority: Pubkey,            // 池子管理员，可以是普通钱包，也可以是多签 PDA
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,              // 池子的 TokenA 关联代币账户，swap 只按地址校验
+    pub vault_b: Pubkey,              // 池子的 TokenB 关联代币账户
+    pub decimals_a: u8,               // TokenA 的小数位数，价格换算时使用
+    pub decimals_b: u8,               // TokenB 的小数位数
+    pub has_freezable_mint: bool,     // mint_a 或 mint_b 有 freeze authority，发行方冻结池子账户后池子无法再转出该代币
+    pub fee: u16,
+    pub initial_price_numerator: u64, // 创建时声明的初始价格 numerator / denominator（TokenB / TokenA，最小单位），
+    pub initial_price_denominator: u64, // 空池子存入的比例必须与之相符，防止抢先创建池子后按偏离的价格注入流动性；0 表示不限制
+    pub start_ts: i64,                // 允许 swap 的起始时间戳，之前只能存入流动性；0 表示不限制
+    pub end_ts: i64,                  // swap 和存入的截止时间戳，之后只能取出；0 表示不限制
+    pub creator: Pubkey,              // 池子创建者，领取创建者手续费
+    pub pending_creator: Pubkey,      // 两步转交中等待接受的新创建者，默认值表示没有进行中的转交
+    pub creator_fee_bps: u16,         // 创建者从每笔 swap 手续费中分走的比例（基点），0 表示不分成
+    pub creator_fee_vault_a: Pubkey,  // 累积创建者和协议手续费的 TokenA 账户，authority 是 pool
+    pub creator_fee_vault_b: Pubkey,  // 累积创建者和协议手续费的 TokenB 账户
+    pub protocol_fee_bps: u16,        // 协议从每笔 swap 手续费中分走的比例（基点），由协议管理员设置，0 表示不分成
+    pub protocol_fees_a: u64,         // creator_fee_vault_a 中属于协议、等待 buyback_and_burn 的部分
+    pub protocol_fees_b: u64,         // creator_fee_vault_b 中属于协议、等待 buyback_and_burn 的部分
+    pub protocol_fee_mode: ProtocolFeeMode, // 协议分成的收取方式，见 protocol_fee.rs
+    pub k_last: u128,                 // LP 模式下上次结算后的 reserve_a * reserve_b，0 表示还没有开始记录
+    pub treasury_lp: Pubkey,          // LP 模式下接收协议 LP 的代币账户
+    pub bump: u8,
+    pub lp_bump: u8,
+    pub max_price_impact_bps: u16,    // 单笔 swap 允许的最大价格冲击（基点），0 表示不限制
+    pub min_trade_amount: u64,        // 单笔 swap 的最小输出数量，0 表示不限制
+    pub circuit_breaker_bps: u16,     // swap 后现价偏离 TWAP 的上限（基点），0 表示关闭熔断
+    pub cpi_restricted: bool,         // 为 true 时拒绝其他程序通过 CPI 调用 swap
+    pub disable_swap_a_to_b: bool,    // 为 true 时拒绝卖出 TokenA 换 TokenB（只能买入 TokenA）
+    pub disable_swap_b_to_a: bool,    // 为 true 时拒绝卖出 TokenB 换 TokenA（只能卖出 TokenA）
+    pub locked: bool,                 // flash_swap 回调期间为 true，拒绝所有读取储备量的指令（防重入）
+    pub status: PoolStatus,           // 池子的生命周期状态，见 PoolStatus
+    pub emergency_withdraw_enabled: bool, // 为 true 时允许 emergency_withdraw 绕过曲线按实际余额取出，见 emergency_withdraw.rs
+    pub event_seq: u64,               // 最近一个事件的序号，每发出一个事件加 1，见 events.rs
+    // ========== 存款上限（试运行期限制 TVL） ==========
+    pub max_reserve_a: u64,           // 存款后 TokenA 储备量的上限，0 表示不限制
+    pub max_reserve_b: u64,           // 存款后 TokenB 储备量的上限，0 表示不限制
+    // ========== 每个钱包的 swap 限额（发射保护） ==========
+    pub rate_limit_window_slots: u64, // 限额窗口长度（slot），0 表示不限制
+    pub max_out_a_per_window: u64,    // 每个钱包每个窗口最多买走的 TokenA，0 表示不限制
+    pub max_out_b_per_window: u64,    // 每个钱包每个窗口最多买走的 TokenB，0 表示不限制
+    // ========== 曲线 ==========
+    pub curve_type: CurveType,        // swap 使用的不变量，见 curve.rs
+    pub amp: u64,                     // StableSwap 的放大系数 A，其他曲线为 0
+    // ========== LBP（流动性引导池）权重计划，curve_type = Weighted 时使用 ==========
+    pub lbp_start_weight_a: u16,      // 开始时 TokenA 的权重（基点），TokenB 权重 = 10000 - weight_a
+    pub lbp_end_weight_a: u16,        // 结束时 TokenA 的权重（基点）
+    pub lbp_start_ts: i64,
+    pub lbp_end_ts: i64,
+    // ========== 价格预言机（TWAP）状态 ==========
+    pub last_update_ts: i64,          // 上一次更新累加器的时间戳
+    pub price_a_cumulative: u128,     // TokenA 以 TokenB 计价的价格累加器（Q64.64 × 秒）
+    pub price_b_cumulative: u128,     // TokenB 以 TokenA 计价的价格累加器（Q64.64 × 秒）
+    pub observation_index: u8,        // 最近一次写入的观测下标
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+    // ========== JIT 保护（同一 slot 内存入后取出） ==========
+    pub jit_protection: bool,         // 为 true 时存取流动性需要传入 LiquidityGuard，拒绝与存入同一 slot 的取出
+    // ========== swap 之后的下游回调，见 swap_hook.rs ==========
+    pub swap_hook_program: Pubkey,    // 每笔 swap 之后 CPI 调用的程序，默认值表示不回调
+    pub swap_hook_accounts: u8,       // hook 需要的额外账户数量，取 remaining_accounts 的最后几个
+    // ========== 不可转让的 LP（合规池子），见 context/position.rs ==========
+    pub soulbound_lp: bool,           // initialize 时选择，为 true 时 LP 记在 Position 中，只能转给白名单钱包
+    // ========== 外部预言机价格带，见 external_oracle.rs ==========
+    pub external_oracle: Pubkey,      // Pyth PriceUpdateV2 账户，默认值表示不检查
+    pub external_oracle_band_bps: u16, // 现价与外部价格允许的最大偏离（基点）
+    pub external_oracle_max_age: u32, // 外部价格的最长有效期（秒）
+    // ========== 手续费去向（许可部署中的协议自有流动性池） ==========
+    pub fee_destination_mode: FeeDestinationMode, // initialize 时选择，之后不能修改
+    // ========== 会员制池子，见 trade_gate.rs ==========
+    pub trade_gate_collection: Pubkey, // swap 需要持有该 Metaplex 集合中的 NFT，默认值表示不限制
+    // ========== 往返 swap 附加费（防夹子），见 context/trader_state.rs ==========
+    pub round_trip_window_slots: u64, // 同一钱包反方向 swap 距上一笔不超过这么多 slot 时加收附加费，0 表示关闭
+    pub round_trip_fee_bps: u16,      // 附加费（基点），全部留在储备量中归 LP
+    // ========== 仓位转让的默克尔根白名单（静态，不是 spl-account-compression 的并发默克尔树），见 context/position.rs ==========
+    pub position_whitelist_root: [u8; 32], // 白名单钱包的默克尔根，全 0 表示没有发布
+    // ========== 单笔 swap 取走输出储备量的上限 ==========
+    pub max_drain_bps: u16,           // 单笔 swap 的输出占输出侧储备量的最大比例（基点），0 表示不限制
+    // ========== 协议手续费注入挖矿，见 context/farm.rs ==========
+    pub protocol_fees_to_farm: bool,  // 为 true 时任何人都可以把奖励代币一侧的协议分成注入池子的 Farm
+    // ========== 协议手续费回购销毁，见 context/buyback.rs ==========
+    pub last_buyback_ts: i64,         // 上一次 buyback_and_burn 的时间戳，0 表示还没有执行过
+}
+
+/// v1 池子的账户布局，只用于 migrate_pool_v2 读取旧数据
+///
+/// v1 没有 version 字段，discriminator 与 Pool 相同，只能通过数据长度区分
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PoolV1 {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee: u16,
+    pub bump: u8,
+    pub lp_bump: u8,
+}
+
+/// v2 池子的账户布局，只用于 migrate_pool_v2 读取旧数据
+///
+/// 与 v3 的区别：还没有 has_freezable_mint、初始价格、协议分成、曲线类型等字段，
+/// LBP 用 lbp_enabled 开关而不是 curve_type
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PoolV2 {
+    pub version: u8,
+    pub authority: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub decimals_a: u8,
+    pub decimals_b: u8,
     pub fee: u16,
+    pub creator: Pubkey,
+    pub creator_fee_bps: u16,
+    pub creator_fee_vault_a: Pubkey,
+    pub creator_fee_vault_b: Pubkey,
     pub bump: u8,
     pub lp_bump: u8,
-}
\ No newline at end of file
+    pub max_price_impact_bps: u16,
+    pub circuit_breaker_bps: u16,
+    pub cpi_restricted: bool,
+    pub locked: bool,
+    pub status: PoolStatus,
+    pub rate_limit_window_slots: u64,
+    pub max_out_a_per_window: u64,
+    pub max_out_b_per_window: u64,
+    pub lbp_enabled: bool,
+    pub lbp_start_weight_a: u16,
+    pub lbp_end_weight_a: u16,
+    pub lbp_start_ts: i64,
+    pub lbp_end_ts: i64,
+    pub last_update_ts: i64,
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    pub observation_index: u8,
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+}
+
+/// 池子的生命周期状态
+///
+/// 换手续费档位时，管理员先把旧池子标记为 WithdrawOnly，
+/// LP 仍然可以取回流动性，再存入新池子
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum PoolStatus {
+    Active,                           // 正常：swap / deposit / withdraw 都可用
+    WithdrawOnly,                     // 已弃用：只允许 withdraw
+    Paused,                           // 暂停：所有读取储备量的用户指令都拒绝
+}
+
+/// 池子使用的不变量，对应 curve.rs 中的 CurveCalculator 实现
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,                  // x * y = k
+    StableSwap,                       // Curve 两币种不变量，适合价格锚定的交易对
+    Weighted,                         // 加权恒定乘积，权重按 LBP 计划随时间变化
+}
+
+/// 协议分成的收取方式
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ProtocolFeeMode {
+    Skim,                             // 每笔 swap 从手续费中扣出，转入手续费账户，见 buyback.rs
+    Lp,                               // swap 不扣，存取流动性时按 k 的增长铸造 LP 给协议（Uniswap V2 kLast）
+}
+
+/// swap 手续费中 LP 那部分的去向
+///
+/// Creator 模式下扣除协议分成之后的手续费全部转入 creator_fee_vault，由 creator 领取，
+/// LP 只承担价格风险、不分手续费。适合 creator 是 DAO 金库、流动性全部由协议自己提供的池子，
+/// 只有开启了 require_token_badges 的许可部署可以创建
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum FeeDestinationMode {
+    Lp,                               // 默认：扣除创建者和协议分成之后的手续费留在储备量中，归 LP
+    Creator,                          // 扣除协议分成之后的手续费全部归 creator
+}
+
+/// 某一时刻的累加器快照，两个快照之差除以时间差即为该区间的 TWAP
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+}
+
+/// 每个注册表分页最多记录的池子数量，写满后滚动到下一页
+pub const REGISTRY_PAGE_CAPACITY: usize = 32;
+
+/// 全局注册表头，记录当前正在写入的分页
+#[account]
+#[derive(InitSpace)]
+pub struct PoolRegistry {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub current_page: u32,
+    pub total_pools: u64,
+    pub bump: u8,
+}
+
+/// 注册表分页，客户端按 page 顺序读取即可枚举所有池子
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub page: u32,
+    pub bump: u8,
+    #[max_len(REGISTRY_PAGE_CAPACITY)]
+    pub entries: Vec<RegistryEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RegistryEntry {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee: u16,
+}
+
+/// 每个交易对最多记录的池子数量（两种 mint 顺序、所有手续费档位合计）
+pub const MAX_PAIR_POOLS: usize = 16;
+
+/// 交易对反查 PDA：["pair", 较小的 mint, 较大的 mint]，记录这个交易对已经创建的池子，
+/// 客户端只需读取一个推导出的地址，就能找到同一交易对的所有池子，不用扫描注册表
+#[account]
+#[derive(InitSpace)]
+pub struct PairMarker {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub mint_0: Pubkey,               // 按字节序较小的 mint
+    pub mint_1: Pubkey,               // 按字节序较大的 mint
+    pub bump: u8,
+    #[max_len(MAX_PAIR_POOLS)]
+    pub pools: Vec<PairPool>,         // 按创建顺序
+}
+
+/// 交易对下的一个池子，池子地址 = ["pool", mint_a, mint_b, fee]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct PairPool {
+    pub fee: u16,
+    pub reversed: bool,               // false 表示 (mint_a, mint_b) = (mint_0, mint_1)，true 表示反过来
+}
+
+impl PairMarker {
+    /// 把两个 mint 排成 (mint_0, mint_1)，与种子的顺序一致
+    pub fn sorted_mints(mint_a: Pubkey, mint_b: Pubkey) -> (Pubkey, Pubkey) {
+        if mint_a <= mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) }
+    }
+}
+
+/// 全局配置最多允许的手续费档位数量
+pub const MAX_FEE_TIERS: usize = 8;
+
+/// 手续费档位：允许创建 fee 档位的池子，新池子的协议分成默认为 protocol_fee_bps
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct FeeTier {
+    pub fee: u16,                     // 池子手续费（基点）
+    pub protocol_fee_bps: u16,        // 新池子的 protocol_fee_bps，之后协议管理员仍可按池子调整
+}
+
+/// 全局配置最多允许的手续费折扣档位数量
+pub const MAX_FEE_REBATE_TIERS: usize = 4;
+
+/// 手续费折扣档位：持有至少 min_balance 个会员代币的钱包，swap 手续费减免 rebate_bps
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct FeeRebateTier {
+    pub min_balance: u64,
+    pub rebate_bps: u16,              // 减免的比例（基点，占池子手续费的比例），10000 表示免手续费
+}
+
+/// 全局配置最多允许的协议手续费接收方数量
+pub const MAX_FEE_RECIPIENTS: usize = 4;
+
+/// 协议手续费接收方：collect_protocol_fees_batch 按 weight_bps 把协议分成转给 owner 的代币账户
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct FeeRecipient {
+    pub owner: Pubkey,                // 接收方钱包（或多签 PDA），目标代币账户的 owner 必须是它
+    pub weight_bps: u16,              // 分得的比例（基点），所有接收方之和为 10000
+}
+
+/// 全局配置最多允许的手续费豁免数量
+pub const MAX_FEE_EXEMPTIONS: usize = 8;
+
+/// 手续费豁免：swap 的 signer 是 key，或者 swap 是从 key 这个程序 CPI 进来的，手续费减免 discount_bps
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct FeeExemption {
+    pub key: Pubkey,                  // 集成方的程序 ID 或者钱包地址
+    pub discount_bps: u16,            // 减免手续费的比例（基点），10000 表示免手续费
+}
+
+/// 全局配置：协议管理员和允许创建的手续费档位
+#[account]
+#[derive(InitSpace)]
+pub struct AmmConfig {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub admin: Pubkey,                // 协议管理员，可以是普通钱包，也可以是多签 PDA
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<FeeTier>,      // 允许的池子手续费档位，fee 互不相同
+    pub max_creator_fee_bps: u16,     // 创建者手续费分成的上限（基点，占 swap 手续费的比例）
+    // ========== 协议手续费回购销毁，见 buyback.rs ==========
+    pub protocol_token_mint: Pubkey,  // 回购并销毁的协议代币，默认值表示未开启
+    pub max_buyback_amount_in: u64,   // 每次 buyback_and_burn 最多卖给池子的手续费代币数量（含手续费）
+    pub max_buyback_impact_bps: u16,  // 每次回购允许的最大价格冲击（基点），也是回购前现价偏离 TWAP 的上限
+    pub allow_freezable_mints: bool,  // 为 true 时允许用有 freeze authority 的 mint（如 USDC）创建池子
+    pub require_token_badges: bool,   // 为 true 时只有持有 TokenBadge 的 mint 可以创建池子，见 token_badge.rs
+    // ========== 持有者手续费折扣，见 fee_rebate.rs ==========
+    pub membership_mint: Pubkey,      // 会员代币，默认值表示按所交易池子的 LP 代币计算
+    #[max_len(MAX_FEE_REBATE_TIERS)]
+    pub fee_rebate_tiers: Vec<FeeRebateTier>, // 按 min_balance 严格升序，为空表示不打折
+    // ========== 全局紧急模式，见 emergency_mode.rs ==========
+    pub guardian: Pubkey,             // 可以开启紧急模式的守护者，与 admin 分开保管，默认值表示只有 admin 可以开启
+    pub emergency_mode: bool,         // 为 true 时所有池子只允许取出
+    pub emergency_exit_ts: i64,       // admin 申请退出紧急模式后可以执行的时间戳，0 表示没有进行中的申请
+    // ========== 协议手续费分配，见 collect_protocol_fees.rs ==========
+    #[max_len(MAX_FEE_RECIPIENTS)]
+    pub fee_recipients: Vec<FeeRecipient>, // 为空表示全部转给调用方指定的一个金库
+    // ========== 集成方手续费豁免，见 fee_exemption.rs ==========
+    #[max_len(MAX_FEE_EXEMPTIONS)]
+    pub fee_exemptions: Vec<FeeExemption>, // key 互不相同，为空表示不豁免
+    pub bump: u8,
+}
+
+/// v1 全局配置的账户布局，只用于 migrate_config 读取旧数据
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct AmmConfigV1 {
+    pub version: u8,
+    pub admin: Pubkey,
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<u16>,
+    pub max_creator_fee_bps: u16,
+    pub bump: u8,
+}
+
+/// 价格 feed 使用的十进制指数：price = 实际价格 * 10^9
+pub const PRICE_FEED_EXPONENT: i32 = -9;
+
+/// 简单价格 feed 账户，字段含义与 Pyth 的 price / conf / expo / publish_time 相同
+/// 借贷协议等只读取简单 feed 的程序可以直接消费 AMM 的价格
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub price: i64,                   // 每枚 TokenA 值多少枚 TokenB（已按小数位数换算），实际价格 = price * 10^expo
+    pub conf: u64,                    // 置信区间：现价与 TWAP 的偏差
+    pub expo: i32,
+    pub publish_time: i64,
+    pub twap: i64,                    // 观测窗口内的 TWAP，精度同 price
+    pub bump: u8,
+}
+
+/// 单个钱包在某个池子上的 swap 记录，用于每个窗口的限额
+#[account]
+#[derive(InitSpace)]
+pub struct TraderState {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    pub window_start_slot: u64,       // 当前窗口开始的 slot
+    pub out_a: u64,                   // 当前窗口内已经买走的 TokenA
+    pub out_b: u64,                   // 当前窗口内已经买走的 TokenB
+    pub bump: u8,
+    pub last_swap_slot: u64,          // 最近一笔 swap 的 slot，用于往返附加费
+    pub last_swap_is_a: Option<bool>, // 最近一笔 swap 的方向（与 swap 的 is_a 相同），还没有 swap 过时为 None
+}
+
+/// v1 TraderState 的账户布局，只用于 migrate_trader_state 读取旧数据
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct TraderStateV1 {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    pub window_start_slot: u64,
+    pub out_a: u64,
+    pub out_b: u64,
+    pub bump: u8,
+}
+
+/// 每个钱包在某个池子上最近一次存入流动性的 slot，开启 jit_protection 的池子使用
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidityGuard {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub last_deposit_slot: u64,       // 最近一次存入的 slot，同一 slot 内不能取出
+    pub bump: u8,
+}
+
+/// soulbound_lp 池子中的 LP 仓位：["position", pool, owner]
+///
+/// 对应的 LP 代币由池子托管在 position_vault（pool 在 LP mint 上的关联代币账户）中，
+/// 所有仓位的 liquidity 之和等于 position_vault 的余额
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u64,               // 持有的 LP 数量
+    pub bump: u8,
+}
+
+/// 可以通过 transfer_position 接收仓位的钱包：["position_whitelist", pool, wallet]，由池子管理员签发
+#[account]
+#[derive(InitSpace)]
+pub struct PositionWhitelist {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
+
+/// 自动复投金库：用户存入 LP 换取金库份额，compound 把金库收到的代币重新存入池子
+#[account]
+#[derive(InitSpace)]
+pub struct AutoVault {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub mint_lp: Pubkey,
+    pub share_mint: Pubkey,           // 金库份额代币，mint authority 是金库 PDA
+    pub last_compound_ts: i64,        // 上一次 compound 的时间戳
+    pub bump: u8,
+}
+
+/// 定投（DCA）订单：输入代币托管在订单 PDA 的 ATA 中，keeper 每隔 interval 秒执行一次
+#[account]
+#[derive(InitSpace)]
+pub struct DcaOrder {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub id: u64,                      // 同一个用户在同一个池子里可以有多个订单
+    pub is_a: bool,                   // 与 swap 相同：true 表示买入 TokenA，付出 TokenB
+    pub escrow: Pubkey,               // 托管输入代币的 ATA，authority 是订单 PDA
+    pub destination: Pubkey,          // 接收输出代币的代币账户
+    pub amount_per_interval: u64,     // 每次执行付出的输入代币（含手续费）
+    pub min_amount_out: u64,          // 每次执行至少获得的输出代币，防止 keeper 夹子
+    pub interval: i64,                // 两次执行之间的最小间隔（秒）
+    pub next_execution_ts: i64,
+    pub keeper_fee: u64,              // 每次执行从托管中支付给 keeper 的输入代币
+    pub bump: u8,
+}
+
+/// 限价单：输入代币托管在订单 PDA 的 ATA 中，池子价格达到限价时任何人都可以分多次成交
+///
+/// 限价 = min_amount_out / amount_in，每次成交的价格都不能低于限价
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub id: u64,                      // 同一个用户在同一个池子里可以有多个订单
+    pub is_a: bool,                   // 与 swap 相同：true 表示买入 TokenA，付出 TokenB
+    pub escrow: Pubkey,               // 托管输入代币的 ATA，authority 是订单 PDA
+    pub destination: Pubkey,          // 接收输出代币的代币账户
+    pub amount_in: u64,               // 订单总共卖出的输入代币（含手续费）
+    pub min_amount_out: u64,          // 全部成交时至少获得的输出代币，与 amount_in 一起确定限价
+    pub filled_amount: u64,           // 已经成交的输入代币
+    pub received_amount: u64,         // 已经获得的输出代币
+    pub bump: u8,
+}
+
+/// LP 快照：记录某个 slot 的 LP 总供应量和储备量，治理合约按快照计算 LP 加权投票权
+///
+/// 每个持有者在该 slot 的 LP 余额由池子管理员以默克尔根的形式发布，
+/// 持有者用默克尔证明创建自己的 VotingPower，已证明的总量不能超过快照时的 LP 总供应量
+#[account]
+#[derive(InitSpace)]
+pub struct LpSnapshot {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub id: u64,                      // 同一个池子可以有多个快照
+    pub slot: u64,                    // 快照所在的 slot
+    pub lp_supply: u64,               // 快照时的 LP 总供应量
+    pub reserve_a: u64,               // 快照时的 TokenA 储备量，用于把 LP 换算成底层代币
+    pub reserve_b: u64,               // 快照时的 TokenB 储备量
+    pub merkle_root: [u8; 32],        // (owner, LP 余额) 的默克尔根，全零表示还没有发布
+    pub total_checkpointed: u64,      // 已经通过证明创建 VotingPower 的 LP 总量
+    pub checkpoint_count: u64,        // 已经创建的 VotingPower 数量
+    pub bump: u8,
+}
+
+/// 单个持有者在某个快照中的投票权（LP 余额）
+#[account]
+#[derive(InitSpace)]
+pub struct VotingPower {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub snapshot: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,                  // 快照 slot 时持有的 LP 数量
+    pub bump: u8,
+}
+
+/// OTC 报价：maker 托管卖出的代币，成交价 = 池子 TWAP 加减 spread_bps，整单成交
+///
+/// 成交时不经过曲线、不改变储备量，池子只提供参考价格
+#[account]
+#[derive(InitSpace)]
+pub struct OtcOffer {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub maker: Pubkey,
+    pub pool: Pubkey,                 // 提供 TWAP 的池子
+    pub id: u64,                      // 同一个 maker 在同一个池子里可以有多个报价
+    pub is_a: bool,                   // 与 swap 相同：true 表示 maker 买入 TokenA，托管 TokenB
+    pub escrow: Pubkey,               // 托管卖出代币的 ATA，authority 是报价 PDA
+    pub destination: Pubkey,          // maker 接收付款的代币账户
+    pub amount: u64,                  // 托管的卖出代币数量
+    pub spread_bps: i16,              // 相对 TWAP 的溢价（正）或折价（负），基点
+    pub min_amount_out: u64,          // maker 至少收到的付款，防止 TWAP 偏离过大时成交
+    pub taker: Pubkey,                // 只允许该地址成交，默认值表示任何人
+    pub expires_at: i64,              // 过期时间戳，0 表示不过期
+    pub bump: u8,
+}
+
+/// 多资产池的代币数量范围
+pub const MIN_MULTI_POOL_TOKENS: usize = 3;
+pub const MAX_MULTI_POOL_TOKENS: usize = 8;
+
+/// 多资产池（3~8 种代币，StableSwap N 币种不变量），见 context/multi_pool.rs
+///
+/// 账户数量随代币数量变化，代币账户都通过 remaining_accounts 按 mints 的顺序传入
+#[account]
+#[derive(InitSpace)]
+pub struct MultiPool {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub authority: Pubkey,            // 池子管理员，可以是普通钱包，也可以是多签 PDA
+    pub mints_hash: [u8; 32],         // sha256(mints 依次拼接)，与 fee 一起作为池子 PDA 的种子
+    pub num_tokens: u8,
+    pub mints: [Pubkey; MAX_MULTI_POOL_TOKENS],  // 前 num_tokens 个有效，按地址严格升序
+    pub vaults: [Pubkey; MAX_MULTI_POOL_TOKENS], // 池子在每种代币上的关联代币账户，顺序与 mints 相同
+    pub decimals: u8,                 // 所有代币的小数位数必须相同
+    pub fee: u16,
+    pub amp: u64,                     // StableSwap 的放大系数 A
+    pub status: PoolStatus,
+    pub bump: u8,
+    pub lp_bump: u8,
+}
+
+/// 协议管理员为 mint 签发的徽章，策展部署模式下创建池子需要，见 context/token_badge.rs
+#[account]
+#[derive(InitSpace)]
+pub struct TokenBadge {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+/// 在池子上登记的报价做市商，见 context/rfq.rs
+#[account]
+#[derive(InitSpace)]
+pub struct MarketMaker {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub signing_key: Pubkey,          // 链下签名报价使用的 ed25519 公钥
+    pub owner: Pubkey,                // 库存代币账户的 owner，成交时只从它的账户转出
+    pub last_nonce: u64,              // 最近一次成交的报价 nonce，新报价必须更大，防止重放
+    pub bump: u8,
+}
+
+/// PoolStats 保留最近多少个小时桶，24 个桶正好覆盖 24 小时
+pub const STATS_BUCKET_COUNT: usize = 24;
+
+/// 每个统计桶覆盖的时间（秒）
+pub const STATS_BUCKET_SECONDS: i64 = 3600;
+
+/// 一个小时内的成交量和手续费，见 PoolStats
+#[zero_copy]
+#[derive(Default)]
+pub struct VolumeBucket {
+    pub hour: i64,                    // 桶对应的小时编号（unix_timestamp / STATS_BUCKET_SECONDS）
+    pub volume_a: u64,                // 这一小时内流入和流出池子的 TokenA
+    pub volume_b: u64,                // 这一小时内流入和流出池子的 TokenB
+    pub fees_a: u64,                  // 以 TokenA 支付的手续费（含创建者和协议分成）
+    pub fees_b: u64,                  // 以 TokenB 支付的手续费（含创建者和协议分成）
+}
+
+/// 池子的成交统计：累计值加上按小时滚动的环形缓冲区，见 context/pool_stats.rs
+///
+/// zero-copy 账户，swap 时只写入用到的几个字段，不做整体反序列化
+#[account(zero_copy)]
+pub struct PoolStats {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub bump: u8,
+    pub _padding: [u8; 6],            // zero-copy 需要 8 字节对齐
+    pub pool: Pubkey,
+    pub swap_count: u64,              // 累计 swap 笔数
+    pub total_volume_a: u64,          // 累计成交量，口径与 VolumeBucket 相同
+    pub total_volume_b: u64,
+    pub total_fees_a: u64,
+    pub total_fees_b: u64,
+    pub buckets: [VolumeBucket; STATS_BUCKET_COUNT], // 下标为 hour % STATS_BUCKET_COUNT
+}
+
+/// 锁仓一个月按 30 天计
+pub const LOCK_MONTH_SECONDS: i64 = 30 * 86_400;
+
+/// 锁仓刚开始就退出时罚没的 LP 比例（基点），之后随剩余锁仓时间线性减少
+pub const MAX_EARLY_EXIT_PENALTY_BPS: u16 = 2_000;
+
+/// 质押 LP 的锁仓时长，锁得越久奖励权重越高，见 context/farm.rs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum LockDuration {
+    None,                             // 不锁仓，权重 1x，随时退出
+    OneMonth,                         // 权重 1.25x
+    ThreeMonths,                      // 权重 1.5x
+    SixMonths,                        // 权重 2x
+}
+
+/// Farm 的奖励分配方式，创建时选定，见 context/farm.rs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum FarmAccrualMode {
+    PerShare,                         // 每秒的奖励按当前权重分配
+    LpSeconds,                        // 每秒的奖励按仓位累计的 权重 × 秒 分配，持有越久分得越多
+}
+
+/// 池子的 LP 质押挖矿，奖励按 reward_rate 每秒释放，按质押权重分配，见 context/farm.rs
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub pool: Pubkey,
+    pub mint_lp: Pubkey,
+    pub reward_mint: Pubkey,          // 奖励代币，托管在 farm PDA 的 ATA 中
+    pub reward_rate: u64,             // 每秒释放的奖励代币
+    pub reward_remaining: u64,        // 已经注入但还没有释放的奖励
+    pub total_staked: u64,            // 质押中的 LP 总量
+    pub total_weight: u128,           // 所有仓位的奖励权重之和
+    pub acc_reward_per_weight: u128,  // 每单位权重累计的奖励（Q64.64）
+    pub acc_penalty_per_weight: u128, // 每单位权重累计的提前退出罚没 LP（Q64.64）
+    pub last_update_ts: i64,          // 上一次释放奖励的时间戳
+    pub bump: u8,
+    pub accrual_mode: FarmAccrualMode,
+    pub origin_ts: i64,               // 创建时间，LpSeconds 模式下时间都相对它计算
+    pub total_weight_start: u128,     // LpSeconds 模式：所有仓位 权重 × 质押时间（相对 origin_ts）之和
+    pub acc_reward_per_lp_second: u128,      // LpSeconds 模式：Σ 释放量 / 总 LP 秒数（Q64.64）
+    pub acc_reward_time_per_lp_second: u128, // LpSeconds 模式：Σ 释放量 × 释放时间 / 总 LP 秒数（Q64.64）
+}
+
+/// 一笔 LP 质押仓位，同一个用户可以用不同的 id 持有多个不同锁仓时长的仓位
+#[account]
+#[derive(InitSpace)]
+pub struct StakePosition {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+    pub amount: u64,                  // 质押的 LP
+    pub lock: LockDuration,
+    pub weight: u128,                 // amount × 锁仓加成
+    pub unlock_ts: i64,               // 此后退出不再罚没
+    pub reward_debt: u128,            // 上次结算时已经计入的奖励：weight × acc_reward_per_weight >> 64
+    pub penalty_debt: u128,           // 上次结算时已经计入的罚没 LP：weight × acc_penalty_per_weight >> 64
+    pub bump: u8,
+    pub lp_seconds_debt: u128,        // LpSeconds 模式：weight × 质押时间 × acc_reward_per_lp_second >> 64，
+                                      // 此时 reward_debt 记的是 weight × acc_reward_time_per_lp_second >> 64
+}
+
+/// 分期取出流动性：LP 托管在 escrow 中，[start_ts, end_ts] 内线性释放，
+/// owner 随时可以 claim 已经释放的部分，按 claim 时的储备量换成 TokenA / TokenB
+#[account]
+#[derive(InitSpace)]
+pub struct ScheduledWithdraw {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub id: u64,                      // 同一个用户在同一个池子里可以有多个计划
+    pub escrow: Pubkey,               // 托管 LP 的 ATA，authority 是计划 PDA
+    pub total_lp: u64,                // 计划取出的 LP 总量
+    pub claimed_lp: u64,              // 已经取出（销毁）的 LP
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub bump: u8,
+}
+
+/// 一个钱包的 UserPortfolio 最多记录的池子数量
+pub const MAX_PORTFOLIO_POOLS: usize = 32;
+
+/// 钱包提供过流动性的池子列表，钱包读取这一个账户就能展示全部 LP 仓位，不需要扫描所有 LP mint
+#[account]
+#[derive(InitSpace)]
+pub struct UserPortfolio {
+    pub version: u8,                  // 布局版本，见 ACCOUNT_VERSION
+    pub owner: Pubkey,
+    #[max_len(MAX_PORTFOLIO_POOLS)]
+    pub pools: Vec<Pubkey>,           // 首次存入时追加，全部取出时移除
+    pub bump: u8,
+}
@@ -0,0 +1,92 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{compute_units::sol_remaining_compute_units, instruction::{AccountMeta, Instruction}, program::invoke},
+};
+
+use crate::{context::swap::SwapResult, error::AmmError, events::SwapEvent, state::Pool};
+
+// ========================================
+// swap 之后的下游回调（swap hook）
+// ========================================
+//
+// 池子管理员用 set_swap_hook 登记一个 hook 程序，每笔成交的 swap 结束前都会 CPI 调用它，
+// 奖励、记账、合规检查等逻辑可以直接挂在池子上，而不用 fork AMM：
+// - 指令数据：SwapEvent 的 discriminator + Borsh 序列化内容，与事件日志的格式相同
+// - 账户：[pool（只读）, trader（只读）, 额外账户...]，额外账户是 remaining_accounts 的最后 swap_hook_accounts 个
+// - 所有账户都不以签名者身份传入，hook 拿不到转移用户或池子资产的权限
+// - 回调期间 pool.locked = true 并写回账户数据，hook 无法重入池子（与 flash_swap 相同）
+// - hook 消耗的计算量超过 MAX_SWAP_HOOK_COMPUTE_UNITS，或者 hook 自己报错，整笔 swap 失败
+//
+// 登记了 hook 的池子，swap 必须传入 hook_program 账户，hook 不能被调用者绕过。
+// 其他会与池子成交的路径（flash_swap、split_swap、rebalance、限价单、定投、deposit_imbalanced、
+// 自动复投、buyback_and_burn）不调用 hook，直接拒绝登记了 hook 的池子。
+
+/// hook 最多可以要求的额外账户数量
+pub const MAX_SWAP_HOOK_ACCOUNTS: u8 = 8;
+
+/// 单次 hook 调用允许消耗的计算量
+pub const MAX_SWAP_HOOK_COMPUTE_UNITS: u64 = 50_000;
+
+impl Pool {
+    pub fn has_swap_hook(&self) -> bool {
+        self.swap_hook_program != Pubkey::default()
+    }
+
+    /// 把 remaining_accounts 拆成（其他用途的账户, hook 的额外账户），没有登记 hook 时后者为空
+    pub fn split_swap_hook_accounts<'a, 'info>(&self, accounts: &'a [AccountInfo<'info>]) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+        let hook_len = if self.has_swap_hook() { self.swap_hook_accounts as usize } else { 0 };
+        require_gte!(accounts.len(), hook_len, AmmError::SwapHookAccountsMissing);
+        Ok(accounts.split_at(accounts.len() - hook_len))
+    }
+}
+
+/// 成交之后调用：发出 SwapEvent 并 CPI 调用 hook 程序
+///
+/// 每笔成交都发出 SwapEvent，没有登记 hook 时不做 CPI。hook_program 的地址已经由 Swap 的 address 约束校验
+#[allow(clippy::too_many_arguments)]
+pub fn invoke_swap_hook<'info>(
+    pool: &mut Account<'info, Pool>,
+    hook_program: Option<&UncheckedAccount<'info>>,
+    trader: &AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    is_a: bool,
+    result: &SwapResult,
+    relayer_fee: u64,
+    tag: Option<[u8; 16]>,
+) -> Result<()> {
+    let event = SwapEvent::new(pool, trader.key(), is_a, result, relayer_fee, tag)?;
+    if !pool.has_swap_hook() {
+        emit!(event);
+        return Ok(());
+    }
+    let hook_program = hook_program.ok_or(AmmError::SwapHookRequired)?;
+    let data = anchor_lang::Event::data(&event);
+    emit!(event);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(pool.key(), false),
+        AccountMeta::new_readonly(trader.key(), false),
+    ];
+    // 额外账户保留可写标记，但去掉签名者标记
+    accounts.extend(hook_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: false,
+        is_writable: account.is_writable,
+    }));
+
+    let mut account_infos = vec![pool.to_account_info(), trader.clone()];
+    account_infos.extend_from_slice(hook_accounts);
+    account_infos.push(hook_program.to_account_info());
+
+    // Anchor 默认在指令结束时才写回账户数据，这里必须手动 exit，hook 里重入的指令才能读到 locked = true
+    pool.locked = true;
+    pool.exit(&crate::ID)?;
+
+    let before = sol_remaining_compute_units();
+    invoke(&Instruction { program_id: hook_program.key(), accounts, data }, &account_infos)?;
+    let used = before.saturating_sub(sol_remaining_compute_units());
+    require_gte!(MAX_SWAP_HOOK_COMPUTE_UNITS, used, AmmError::SwapHookComputeExceeded);
+
+    pool.locked = false;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, close_account, spl_token::native_mint, CloseAccount, Token};
+
+use crate::error::AmmError;
+
+// ========================================
+// 轻量读取 SPL Token 账户
+// ========================================
+//
+// SPL Token 账户布局：mint(32) | owner(32) | amount(8) | ...
+// 热路径上只需要 amount 时，直接读 64..72 字节，省掉完整反序列化的计算量。
+// state 在第 108 字节：0 = Uninitialized，1 = Initialized，2 = Frozen。
+
+const MINT_OFFSET: usize = 0;
+const OWNER_OFFSET: usize = 32;
+const AMOUNT_OFFSET: usize = 64;
+const STATE_OFFSET: usize = 108;
+const STATE_FROZEN: u8 = 2;
+
+/// 读取 token 账户余额，要求账户属于 SPL Token 程序
+pub fn read_token_amount(account: &AccountInfo) -> Result<u64> {
+    require_keys_eq!(*account.owner, token::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+    let data = account.try_borrow_data()?;
+    let bytes: [u8; 8] = data
+        .get(AMOUNT_OFFSET..AMOUNT_OFFSET + 8)
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?
+        .try_into()
+        .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// 读取 token 账户的 (mint, owner)，要求账户属于 SPL Token 程序
+pub fn read_token_mint_and_owner(account: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+    require_keys_eq!(*account.owner, token::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+    let data = account.try_borrow_data()?;
+    let read_pubkey = |offset: usize| -> Result<Pubkey> {
+        let bytes: [u8; 32] = data
+            .get(offset..offset + 32)
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?
+            .try_into()
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+        Ok(Pubkey::new_from_array(bytes))
+    };
+
+    Ok((read_pubkey(MINT_OFFSET)?, read_pubkey(OWNER_OFFSET)?))
+}
+
+/// token 账户是否被 mint 的 freeze authority 冻结，要求账户属于 SPL Token 程序
+pub fn is_token_account_frozen(account: &AccountInfo) -> Result<bool> {
+    require_keys_eq!(*account.owner, token::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+    let data = account.try_borrow_data()?;
+    let state = data.get(STATE_OFFSET).ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok(*state == STATE_FROZEN)
+}
+
+/// 转账之前检查代币账户都没有被冻结，有任何一个被冻结时返回 AccountFrozen
+///
+/// 否则 SPL Token 的 transfer 会在几次 CPI 之后才失败，集成方只能看到 token 程序的错误码
+pub fn require_not_frozen(accounts: &[&AccountInfo]) -> Result<()> {
+    for account in accounts {
+        require!(!is_token_account_frozen(account)?, AmmError::AccountFrozen);
+    }
+    Ok(())
+}
+
+/// 输出代币是 WSOL 时关闭用户的 WSOL 账户，包装的 SOL 连同租金一起退回 owner，
+/// 省掉用户单独发一笔 closeAccount 交易（swap / withdraw 的 unwrap 选项）
+pub fn unwrap_wsol<'info>(token_program: &Program<'info, Token>, mint: &Pubkey, account: AccountInfo<'info>, owner: AccountInfo<'info>) -> Result<()> {
+    require_keys_eq!(*mint, native_mint::ID, AmmError::UnwrapRequiresWrappedSol);
+
+    let accounts = CloseAccount {
+        account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+
+    let ctx = CpiContext::new(
+        token_program.to_account_info(),
+        accounts
+    );
+
+    close_account(ctx)
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::mpl_token_metadata::{self, accounts::Metadata};
+
+use crate::{error::AmmError, state::Pool, token_account::{read_token_amount, read_token_mint_and_owner}};
+
+// ========================================
+// 持有 NFT 才能交易（会员制池子）
+// ========================================
+//
+// 池子管理员用 set_trade_gate 登记一个 Metaplex 集合（collection mint）后，
+// swap / swap_by_mint / swap_with_relayer_fee / swap_with_quote / dry_run_swap 要求 signer 持有该集合中的 NFT：
+//
+// - remaining_accounts 在外部预言机账户（如果有）之后依次传入 [NFT 代币账户, NFT 的 Metadata 账户]，
+//   手续费折扣、swap hook 等其余账户顺延
+// - 代币账户的 owner 必须是 signer、余额至少为 1
+// - Metadata 账户必须属于 Token Metadata 程序，mint 与代币账户一致，collection 等于登记的集合并且已经验证。
+//   同一个 mint 只可能有一个 Metadata 账户，所以只检查账户 owner 和其中的 mint 字段，不在链上推导 PDA
+//
+// 余额按交易时计算，同一笔交易里借入 NFT 也能通过检查。
+// flash_swap / split_swap / rebalance_pair / 限价单 / 定投等不经过 swap 的成交路径无法传入这两个账户，
+// 对开启了会员制的池子直接拒绝，与钱包限额的处理方式相同。
+// deposit_imbalanced 和自动复投金库的 compound 包含一笔隐含的 swap，同样直接拒绝，只能按比例存入。
+
+/// 开启会员制的池子在 remaining_accounts 中额外占用的账户数量
+pub const TRADE_GATE_ACCOUNTS: usize = 2;
+
+impl Pool {
+    pub fn is_trade_gated(&self) -> bool {
+        self.trade_gate_collection != Pubkey::default()
+    }
+
+    /// 把 remaining_accounts 拆成（会员凭证账户, 其余账户），没有开启会员制时前者为空
+    pub fn split_trade_gate<'a, 'info>(&self, accounts: &'a [AccountInfo<'info>]) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+        if !self.is_trade_gated() {
+            return Ok((&[], accounts));
+        }
+        require_gte!(accounts.len(), TRADE_GATE_ACCOUNTS, AmmError::TradeGateRequired);
+        Ok(accounts.split_at(TRADE_GATE_ACCOUNTS))
+    }
+
+    /// 检查 trader 持有登记集合中的 NFT，没有开启会员制时不检查
+    pub fn check_trade_gate(&self, trader: &Pubkey, accounts: &[AccountInfo]) -> Result<()> {
+        if !self.is_trade_gated() {
+            return Ok(());
+        }
+        let [holder_info, metadata_info] = accounts else {
+            return err!(AmmError::TradeGateRequired);
+        };
+
+        let (mint, owner) = read_token_mint_and_owner(holder_info)?;
+        require_keys_eq!(owner, *trader, AmmError::InvalidTradeGatePass);
+        require_gte!(read_token_amount(holder_info)?, 1, AmmError::InvalidTradeGatePass);
+
+        require_keys_eq!(*metadata_info.owner, mpl_token_metadata::ID, AmmError::InvalidTradeGatePass);
+        let metadata = Metadata::safe_deserialize(&metadata_info.try_borrow_data()?)
+            .map_err(|_| AmmError::InvalidTradeGatePass)?;
+        require_keys_eq!(metadata.mint, mint, AmmError::InvalidTradeGatePass);
+
+        let in_collection = metadata.collection
+            .is_some_and(|collection| collection.verified && collection.key == self.trade_gate_collection);
+        require!(in_collection, AmmError::InvalidTradeGatePass);
+        Ok(())
+    }
+}
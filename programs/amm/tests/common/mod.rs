@@ -0,0 +1,421 @@
+#![allow(dead_code)]
+
+use amm::{
+    client::{build_deposit_ix, build_initialize_ix, build_swap_ix, build_withdraw_ix, config_address, lp_mint_address, pool_address},
+    error::AmmError,
+    state::{AmmConfig, FeeDestinationMode, FeeTier, ACCOUNT_VERSION},
+};
+use anchor_lang::{
+    solana_program::{program_option::COption, program_pack::Pack},
+    AccountSerialize, Discriminator, InstructionData, Space, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, spl_associated_token_account::instruction::create_associated_token_account_idempotent},
+    token::spl_token,
+};
+use solana_program_test::{read_file, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::path::{Path, PathBuf};
+
+// ========================================
+// BanksClient 集成测试的公共夹具
+// ========================================
+//
+// PoolFixture 负责搭好一个可以直接交易的池子：两种 mint、写入测试环境的全局配置、
+// 若干个持有两种代币的钱包，以及 initialize（可选首次存入）。
+// 测试只需要描述场景本身：
+//
+//   let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(1_000_000, 2_000_000).build().await;
+//   let trader = fixture.wallet(1);
+//   fixture.swap(&trader, 1_000, true).await;
+//
+// 加载 anchor build 生成的 amm.so，需要先 anchor build：
+//   anchor build && cargo test -p amm --features client --test <name>
+//
+// 每个测试文件用 `mod common;` 引入，没有用到的辅助函数不报 dead_code。
+
+/// 加载 anchor build 生成的 amm.so：设置了 SBF_OUT_DIR 时从那里读取，否则读取工作区的 target/deploy
+///
+/// 不用 ProgramTest::new 加载：它在 prefer_bpf 生效之前就查找程序，而且只在当前目录（programs/amm）下找
+pub fn amm_program_test() -> ProgramTest {
+    let dir = std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"));
+    let data = read_file(dir.join("amm.so"));
+
+    let mut program_test = ProgramTest::default();
+    // amm.so 在下面手动加载，之后 add_program 加入的测试程序（swap hook 等）总是使用 processor! 的原生实现
+    program_test.prefer_bpf(false);
+    program_test.add_account(amm::ID, Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: bpf_loader::id(),
+        executable: true,
+        rent_epoch: 0,
+    });
+    program_test
+}
+
+/// 默认的池子手续费档位
+pub const DEFAULT_FEE: u16 = 30;
+
+/// 每个钱包默认持有的两种代币
+pub const DEFAULT_BALANCE: u64 = 1_000_000_000_000;
+
+pub struct PoolFixtureBuilder {
+    fee: u16,
+    wallets: usize,
+    initial_balance: u64,
+    fee_destination_mode: FeeDestinationMode,
+    seed_liquidity: Option<(u64, u64)>,
+    soulbound_lp: bool,
+    setup: Vec<Setup>,
+}
+
+/// 启动之前对测试环境的额外修改，见 PoolFixtureBuilder::setup
+type Setup = Box<dyn FnOnce(&mut ProgramTest)>;
+
+impl PoolFixtureBuilder {
+    pub fn fee(mut self, fee: u16) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// 持有两种代币的钱包数量，通过 PoolFixture::wallet 按下标取出
+    pub fn wallets(mut self, count: usize) -> Self {
+        self.wallets = count;
+        self
+    }
+
+    pub fn initial_balance(mut self, amount: u64) -> Self {
+        self.initial_balance = amount;
+        self
+    }
+
+    pub fn fee_destination_mode(mut self, mode: FeeDestinationMode) -> Self {
+        self.fee_destination_mode = mode;
+        self
+    }
+
+    /// 创建 LP 不可自由转让的池子，只能通过 Position 存取；这类池子不能用 seed_liquidity
+    pub fn soulbound_lp(mut self, soulbound_lp: bool) -> Self {
+        self.soulbound_lp = soulbound_lp;
+        self
+    }
+
+    /// 启动之前额外修改测试环境，比如用 add_program 加载 swap hook 之类被 CPI 调用的原生程序
+    pub fn setup(mut self, setup: impl FnOnce(&mut ProgramTest) + 'static) -> Self {
+        self.setup.push(Box::new(setup));
+        self
+    }
+
+    /// initialize 之后由第一个钱包首次存入 (amount_a, amount_b)，至少需要一个钱包
+    pub fn seed_liquidity(mut self, amount_a: u64, amount_b: u64) -> Self {
+        self.seed_liquidity = Some((amount_a, amount_b));
+        self
+    }
+
+    pub async fn build(self) -> PoolFixture {
+        let admin = Keypair::new();
+        let mint_authority = Keypair::new();
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        let wallets: Vec<Keypair> = (0..self.wallets).map(|_| Keypair::new()).collect();
+
+        let mut program_test = amm_program_test();
+
+        for actor in std::iter::once(&admin).chain(&wallets) {
+            program_test.add_account(actor.pubkey(), Account {
+                lamports: 10_000_000_000,
+                ..Account::default()
+            });
+        }
+
+        let supply = self.initial_balance.saturating_mul(self.wallets as u64);
+        for mint in [mint_a, mint_b] {
+            add_mint(&mut program_test, mint, &mint_authority.pubkey(), supply);
+            for wallet in &wallets {
+                add_token_account(&mut program_test, get_associated_token_address(&wallet.pubkey(), &mint), mint, wallet.pubkey(), self.initial_balance);
+            }
+        }
+
+        add_config(&mut program_test, admin.pubkey(), self.fee);
+        for setup in self.setup {
+            setup(&mut program_test);
+        }
+
+        let ctx = program_test.start_with_context().await;
+        let pool = pool_address(&mint_a, &mint_b, self.fee);
+        let mut fixture = PoolFixture {
+            ctx,
+            admin,
+            wallets,
+            mint_a,
+            mint_b,
+            fee: self.fee,
+            pool,
+            mint_lp: lp_mint_address(&pool),
+        };
+
+        let admin = fixture.admin.insecure_clone();
+        fixture.send(&admin, vec![
+            build_initialize_ix(&admin.pubkey(), &admin.pubkey(), &mint_a, &mint_b, self.fee, 0, None, None, 0, false, self.soulbound_lp, self.fee_destination_mode),
+        ]).await;
+
+        if let Some((amount_a, amount_b)) = self.seed_liquidity {
+            let provider = fixture.wallet(0);
+            fixture.deposit(&provider, 0, amount_a, amount_b).await;
+        }
+
+        fixture
+    }
+}
+
+pub struct PoolFixture {
+    pub ctx: ProgramTestContext,
+    pub admin: Keypair,               // 协议管理员兼池子管理员，也是 initialize 的付款人
+    pub wallets: Vec<Keypair>,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee: u16,
+    pub pool: Pubkey,
+    pub mint_lp: Pubkey,
+}
+
+impl PoolFixture {
+    pub fn builder() -> PoolFixtureBuilder {
+        PoolFixtureBuilder {
+            fee: DEFAULT_FEE,
+            wallets: 1,
+            initial_balance: DEFAULT_BALANCE,
+            fee_destination_mode: FeeDestinationMode::Lp,
+            seed_liquidity: None,
+            soulbound_lp: false,
+            setup: Vec::new(),
+        }
+    }
+
+    /// 第 index 个钱包，Keypair 不能 Clone，这里返回一份拷贝，避免和 &mut self 的借用冲突
+    pub fn wallet(&self, index: usize) -> Keypair {
+        self.wallets[index].insecure_clone()
+    }
+
+    // ========== 场景操作 ==========
+
+    /// 按 LP 数量存入，amount_lp 为 0 表示首次存入；按需创建 LP 代币账户
+    pub async fn deposit(&mut self, owner: &Keypair, amount_lp: u64, max_token_a: u64, max_token_b: u64) {
+        self.send(owner, vec![
+            create_associated_token_account_idempotent(&owner.pubkey(), &owner.pubkey(), &self.mint_lp, &spl_token::ID),
+            build_deposit_ix(&owner.pubkey(), &self.mint_a, &self.mint_b, self.fee, amount_lp, max_token_a, max_token_b),
+        ]).await;
+    }
+
+    pub async fn withdraw(&mut self, owner: &Keypair, amount_lp: u64) {
+        self.send(owner, vec![
+            build_withdraw_ix(&owner.pubkey(), &self.mint_a, &self.mint_b, self.fee, amount_lp, 0, 0),
+        ]).await;
+    }
+
+    /// 精确输出 swap，不限制最大输入
+    pub async fn swap(&mut self, trader: &Keypair, amount_out: u64, is_a: bool) {
+        self.send(trader, vec![
+            build_swap_ix(&trader.pubkey(), &self.mint_a, &self.mint_b, self.fee, amount_out, u64::MAX, is_a, false),
+        ]).await;
+    }
+
+    /// 以 admin 身份调用 PoolAdmin 类的管理员指令
+    pub async fn pool_admin<T: InstructionData>(&mut self, data: T) {
+        let accounts = amm::accounts::PoolAdmin {
+            authority: self.admin.pubkey(),
+            pool: self.pool,
+        };
+        let mut accounts = accounts.to_account_metas(None);
+        // authority 是 UncheckedAccount，生成的 AccountMeta 不带签名标记
+        accounts[0].is_signer = true;
+
+        let admin = self.admin.insecure_clone();
+        self.send(&admin, vec![Instruction { program_id: amm::ID, accounts, data: data.data() }]).await;
+    }
+
+    /// 以 admin 身份调用 ConfigAdmin 类的协议管理员指令
+    pub async fn config_admin<T: InstructionData>(&mut self, data: T) {
+        let accounts = amm::accounts::ConfigAdmin {
+            authority: self.admin.pubkey(),
+            config: config_address(),
+        };
+        let mut accounts = accounts.to_account_metas(None);
+        accounts[0].is_signer = true;
+
+        let admin = self.admin.insecure_clone();
+        self.send(&admin, vec![Instruction { program_id: amm::ID, accounts, data: data.data() }]).await;
+    }
+
+    /// 以 admin 身份开启全局紧急模式，见 emergency_mode.rs
+    pub async fn enable_emergency_mode(&mut self) {
+        let accounts = amm::accounts::EnableEmergencyMode {
+            authority: self.admin.pubkey(),
+            config: config_address(),
+        };
+        let mut accounts = accounts.to_account_metas(None);
+        accounts[0].is_signer = true;
+
+        let admin = self.admin.insecure_clone();
+        self.send(&admin, vec![Instruction { program_id: amm::ID, accounts, data: amm::instruction::EnableEmergencyMode {}.data() }]).await;
+    }
+
+    // ========== 读取状态 ==========
+
+    pub async fn reserves(&mut self) -> (u64, u64) {
+        let reserve_a = self.token_balance(get_associated_token_address(&self.pool, &self.mint_a)).await;
+        let reserve_b = self.token_balance(get_associated_token_address(&self.pool, &self.mint_b)).await;
+        (reserve_a, reserve_b)
+    }
+
+    pub async fn lp_supply(&mut self) -> u64 {
+        match self.ctx.banks_client.get_account(self.mint_lp).await.unwrap() {
+            Some(account) => spl_token::state::Mint::unpack(&account.data).unwrap().supply,
+            None => 0,
+        }
+    }
+
+    /// owner 在 (mint_a, mint_b, mint_lp) 上关联代币账户的余额
+    pub async fn balances(&mut self, owner: &Pubkey) -> (u64, u64, u64) {
+        (
+            self.token_balance(get_associated_token_address(owner, &self.mint_a)).await,
+            self.token_balance(get_associated_token_address(owner, &self.mint_b)).await,
+            self.token_balance(get_associated_token_address(owner, &self.mint_lp)).await,
+        )
+    }
+
+    pub async fn token_balance(&mut self, address: Pubkey) -> u64 {
+        match self.ctx.banks_client.get_account(address).await.unwrap() {
+            Some(account) => spl_token::state::Account::unpack(&account.data).unwrap().amount,
+            None => 0,
+        }
+    }
+
+    /// 跳到指定 slot，测试 slot 窗口类的逻辑（钱包限额、往返附加费、JIT 保护）
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        self.ctx.warp_to_slot(slot).unwrap();
+    }
+
+    /// 把链上时间往后拨 seconds 秒，测试按 unix_timestamp 计算的逻辑（线性释放、时间窗口）
+    pub async fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // ========== 发送交易 ==========
+
+    /// 发送交易，失败时直接 panic 并打印日志
+    pub async fn send(&mut self, signer: &Keypair, instructions: Vec<Instruction>) {
+        if let Err(logs) = self.try_send(signer, instructions).await {
+            panic!("transaction failed: {logs}");
+        }
+    }
+
+    /// 发送交易并要求以 error 失败
+    pub async fn send_expect_error(&mut self, signer: &Keypair, instructions: Vec<Instruction>, error: AmmError) {
+        let Err(logs) = self.try_send(signer, instructions).await else {
+            panic!("transaction should fail with {}", error.name());
+        };
+        let expected = format!("Error Code: {}.", error.name());
+        assert!(logs.contains(&expected), "expected {expected}\n{logs}");
+    }
+
+    /// 发送交易，失败时返回错误和程序日志
+    pub async fn try_send(&mut self, signer: &Keypair, instructions: Vec<Instruction>) -> Result<(), String> {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&instructions, Some(&signer.pubkey()), &[signer], blockhash);
+
+        let result = self.ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+        match result.result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                let logs = result.metadata.map(|metadata| metadata.log_messages.join("\n")).unwrap_or_default();
+                Err(format!("{err:?}\n{logs}"))
+            }
+        }
+    }
+}
+
+fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, authority: &Pubkey, supply: u64) {
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }, &mut data).unwrap();
+
+    program_test.add_account(mint, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::ID,
+        ..Account::default()
+    });
+}
+
+fn add_token_account(program_test: &mut ProgramTest, address: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }, &mut data).unwrap();
+
+    program_test.add_account(address, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::ID,
+        ..Account::default()
+    });
+}
+
+/// 全局配置需要程序的升级权限才能创建，这里直接写进测试环境
+fn add_config(program_test: &mut ProgramTest, admin: Pubkey, fee: u16) {
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &amm::ID);
+    assert_eq!(config, config_address());
+
+    let mut data = Vec::with_capacity(AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE);
+    AmmConfig {
+        version: ACCOUNT_VERSION,
+        admin,
+        fee_tiers: vec![FeeTier { fee, protocol_fee_bps: 0 }],
+        max_creator_fee_bps: 0,
+        protocol_token_mint: Pubkey::default(),
+        max_buyback_amount_in: 0,
+        max_buyback_impact_bps: 0,
+        allow_freezable_mints: false,
+        require_token_badges: false,
+        membership_mint: Pubkey::default(),
+        fee_rebate_tiers: Vec::new(),
+        guardian: Pubkey::default(),
+        emergency_mode: false,
+        emergency_exit_ts: 0,
+        fee_recipients: Vec::new(),
+        fee_exemptions: Vec::new(),
+        bump,
+    }.try_serialize(&mut data).unwrap();
+    data.resize(AmmConfig::DISCRIMINATOR.len() + AmmConfig::INIT_SPACE, 0);
+
+    program_test.add_account(config, Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: amm::ID,
+        ..Account::default()
+    });
+}
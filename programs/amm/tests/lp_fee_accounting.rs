@@ -0,0 +1,208 @@
+mod common;
+
+use amm::client::{build_deposit_ix, build_swap_ix, build_withdraw_ix};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, spl_associated_token_account::instruction::create_associated_token_account_idempotent},
+    token::spl_token,
+};
+use common::PoolFixture;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::{Keypair, Signer}};
+
+// ========================================
+// LP 手续费记账的多角色集成测试
+// ========================================
+//
+// swap 手续费直接留在储备量里（不需要 harvest），LP 取出时按份额一起拿走。
+// 这里用多个 LP 在 swap 前后进出池子，检查每个 LP 拿回的代币：
+// - 每次取出都严格等于 floor(储备量 × LP / 供应量)
+// - 以 sqrt(a × b) 计的价值 = 存入价值 × 持有期间每份 LP 价值的增长，误差只来自取整
+// - 同时进出的 LP 增长相同，后进入的 LP 拿不到进入之前的手续费
+// - 所有代币守恒：存入 + 交易者净流入 = 取出 + 池子剩余
+//
+// 池子和各角色的搭建见 tests/common，需要先 anchor build：
+//   anchor build && cargo test -p amm --features client --test lp_fee_accounting
+
+/// 每个角色初始持有的两种代币
+const INITIAL_BALANCE: u64 = common::DEFAULT_BALANCE;
+
+/// 价值比较的相对误差上限，存入向上取整、取出向下取整各最多差 1 个最小单位
+const RELATIVE_TOLERANCE: f64 = 1e-6;
+
+struct Harness {
+    fixture: PoolFixture,
+}
+
+/// 一个 LP 的存入记录
+struct Position {
+    owner: Keypair,
+    amount_lp: u64,
+    deposited_a: u64,
+    deposited_b: u64,
+    entry_value_per_lp: f64,          // 存入时每份 LP 对应的 sqrt(a × b)
+}
+
+/// 一个 LP 全部取出之后的结果
+struct Exit {
+    withdrawn_a: u64,
+    withdrawn_b: u64,
+    growth: f64,                      // 持有期间每份 LP 价值的增长倍数
+}
+
+#[tokio::test]
+async fn lp_withdrawals_match_deposits_plus_pro_rata_fees() {
+    let fixture = PoolFixture::builder().wallets(4).initial_balance(INITIAL_BALANCE).build().await;
+    let (lp1, lp2, lp3, trader) = (fixture.wallet(0), fixture.wallet(1), fixture.wallet(2), fixture.wallet(3));
+    let (mint_a, mint_b) = (fixture.mint_a, fixture.mint_b);
+    let mut harness = Harness { fixture };
+
+    // lp1 首次存入确定价格，lp2 紧接着按比例存入一半的供应量，两者之间没有 swap
+    let lp1 = harness.deposit(lp1, 0, 100_000_000, 200_000_000).await;
+    let lp2_amount = harness.lp_supply().await / 2;
+    let lp2 = harness.deposit(lp2, lp2_amount, u64::MAX, u64::MAX).await;
+
+    harness.swap_round_trips(&trader, 10, 5_000_000).await;
+
+    // lp3 在第一轮 swap 之后进入，不应该分到这一轮的手续费
+    let lp3_amount = harness.lp_supply().await / 4;
+    let lp3 = harness.deposit(lp3, lp3_amount, u64::MAX, u64::MAX).await;
+
+    harness.swap_round_trips(&trader, 10, 3_000_000).await;
+
+    let exit2 = harness.withdraw_all(&lp2).await;
+
+    harness.swap_round_trips(&trader, 10, 4_000_000).await;
+
+    let exit1 = harness.withdraw_all(&lp1).await;
+    let exit3 = harness.withdraw_all(&lp3).await;
+
+    // 所有 LP 都退出之后池子应该被取空
+    let (reserve_a, reserve_b) = harness.reserves().await;
+    assert_eq!(harness.lp_supply().await, 0);
+    assert_eq!((reserve_a, reserve_b), (0, 0));
+
+    // swap 只会让 k 增长，持有期间经历过 swap 的 LP 一定赚到手续费
+    for exit in [&exit1, &exit2, &exit3] {
+        assert!(exit.growth > 1.0, "growth {} should include swap fees", exit.growth);
+    }
+
+    // lp1 和 lp2 同时进入：lp1 多经历了第三轮 swap，增长更多
+    assert!(exit1.growth > exit2.growth);
+    // lp1 和 lp3 同时退出：lp3 错过了第一轮 swap，增长更少
+    assert!(exit3.growth < exit1.growth);
+
+    // 代币守恒：没有创建者和协议分成时，交易者付出的全部手续费都分给了 LP
+    let trader_a = harness.token_balance(get_associated_token_address(&trader.pubkey(), &mint_a)).await;
+    let trader_b = harness.token_balance(get_associated_token_address(&trader.pubkey(), &mint_b)).await;
+    let positions = [&lp1, &lp2, &lp3];
+    let exits = [&exit1, &exit2, &exit3];
+
+    let deposited_a: i128 = positions.iter().map(|position| position.deposited_a as i128).sum();
+    let deposited_b: i128 = positions.iter().map(|position| position.deposited_b as i128).sum();
+    let withdrawn_a: i128 = exits.iter().map(|exit| exit.withdrawn_a as i128).sum();
+    let withdrawn_b: i128 = exits.iter().map(|exit| exit.withdrawn_b as i128).sum();
+
+    assert_eq!(deposited_a + (INITIAL_BALANCE as i128 - trader_a as i128), withdrawn_a);
+    assert_eq!(deposited_b + (INITIAL_BALANCE as i128 - trader_b as i128), withdrawn_b);
+}
+
+impl Harness {
+    /// 存入并记录存入时每份 LP 的价值，amount_lp 为 0 表示首次存入
+    async fn deposit(&mut self, owner: Keypair, amount_lp: u64, max_token_a: u64, max_token_b: u64) -> Position {
+        let signer_ata_a = get_associated_token_address(&owner.pubkey(), &self.fixture.mint_a);
+        let signer_ata_b = get_associated_token_address(&owner.pubkey(), &self.fixture.mint_b);
+        let signer_ata_lp = get_associated_token_address(&owner.pubkey(), &self.fixture.mint_lp);
+
+        // 首次存入之前池子是空的，按存入之后的状态计价
+        let value_before = self.value_per_lp().await;
+        let before_a = self.token_balance(signer_ata_a).await;
+        let before_b = self.token_balance(signer_ata_b).await;
+
+        self.send(&owner, vec![
+            create_associated_token_account_idempotent(&owner.pubkey(), &owner.pubkey(), &self.fixture.mint_lp, &spl_token::ID),
+            build_deposit_ix(&owner.pubkey(), &self.fixture.mint_a, &self.fixture.mint_b, self.fixture.fee, amount_lp, max_token_a, max_token_b),
+        ]).await;
+
+        let entry_value_per_lp = match value_before {
+            Some(value) => value,
+            None => self.value_per_lp().await.unwrap(),
+        };
+
+        Position {
+            amount_lp: self.token_balance(signer_ata_lp).await,
+            deposited_a: before_a - self.token_balance(signer_ata_a).await,
+            deposited_b: before_b - self.token_balance(signer_ata_b).await,
+            entry_value_per_lp,
+            owner,
+        }
+    }
+
+    /// 取出全部 LP，检查取出数量和价值增长
+    async fn withdraw_all(&mut self, position: &Position) -> Exit {
+        let owner = &position.owner;
+        let signer_ata_a = get_associated_token_address(&owner.pubkey(), &self.fixture.mint_a);
+        let signer_ata_b = get_associated_token_address(&owner.pubkey(), &self.fixture.mint_b);
+
+        let (reserve_a, reserve_b) = self.reserves().await;
+        let lp_supply = self.lp_supply().await;
+        let exit_value_per_lp = self.value_per_lp().await.unwrap();
+        let before_a = self.token_balance(signer_ata_a).await;
+        let before_b = self.token_balance(signer_ata_b).await;
+
+        self.send(owner, vec![
+            build_withdraw_ix(&owner.pubkey(), &self.fixture.mint_a, &self.fixture.mint_b, self.fixture.fee, position.amount_lp, 0, 0),
+        ]).await;
+
+        let withdrawn_a = self.token_balance(signer_ata_a).await - before_a;
+        let withdrawn_b = self.token_balance(signer_ata_b).await - before_b;
+
+        // 取出按份额向下取整，不多也不少
+        assert_eq!(withdrawn_a as u128, reserve_a as u128 * position.amount_lp as u128 / lp_supply as u128);
+        assert_eq!(withdrawn_b as u128, reserve_b as u128 * position.amount_lp as u128 / lp_supply as u128);
+
+        // 按比例存取时 sqrt(a × b) 不受价格变化影响，只随手续费增长
+        let growth = exit_value_per_lp / position.entry_value_per_lp;
+        let deposited_value = ((position.deposited_a as f64) * (position.deposited_b as f64)).sqrt();
+        let withdrawn_value = ((withdrawn_a as f64) * (withdrawn_b as f64)).sqrt();
+        let expected_value = deposited_value * growth;
+        assert!(
+            (withdrawn_value - expected_value).abs() <= expected_value * RELATIVE_TOLERANCE,
+            "withdrawn value {withdrawn_value} differs from deposit {deposited_value} × growth {growth}"
+        );
+
+        Exit { withdrawn_a, withdrawn_b, growth }
+    }
+
+    /// 交易者来回 swap：每轮先买 A 再买 B，数量逐次加 1，避免出现重复交易
+    async fn swap_round_trips(&mut self, trader: &Keypair, rounds: u64, amount: u64) {
+        for round in 0..rounds {
+            for is_a in [true, false] {
+                self.send(trader, vec![
+                    build_swap_ix(&trader.pubkey(), &self.fixture.mint_a, &self.fixture.mint_b, self.fixture.fee, amount + round, u64::MAX, is_a, false),
+                ]).await;
+            }
+        }
+    }
+
+    /// 每份 LP 对应的 sqrt(reserve_a × reserve_b)，池子为空时返回 None
+    async fn value_per_lp(&mut self) -> Option<f64> {
+        let (reserve_a, reserve_b) = self.reserves().await;
+        let lp_supply = self.lp_supply().await;
+        (lp_supply > 0).then(|| ((reserve_a as f64) * (reserve_b as f64)).sqrt() / lp_supply as f64)
+    }
+
+    async fn reserves(&mut self) -> (u64, u64) {
+        self.fixture.reserves().await
+    }
+
+    async fn lp_supply(&mut self) -> u64 {
+        self.fixture.lp_supply().await
+    }
+
+    async fn token_balance(&mut self, address: Pubkey) -> u64 {
+        self.fixture.token_balance(address).await
+    }
+
+    async fn send(&mut self, signer: &Keypair, instructions: Vec<Instruction>) {
+        self.fixture.send(signer, instructions).await
+    }
+}
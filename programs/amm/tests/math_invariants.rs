@@ -0,0 +1,535 @@
+use amm::context::{merkle_parent, verify_merkle_proof, voting_power_leaf};
+use amm::curve::{CurveCalculator, StableSwap, Weighted};
+use amm::math::{
+    creator_fee_amount, deposit_amounts, imbalanced_deposit_lp, isqrt, linear_vested_amount, max_amount_limit, max_proportional_lp, mul_div_ceil, mul_div_floor, protocol_fee_lp, split_by_weights, stable_swap_amount_in, stable_swap_amount_out, stable_swap_d, stable_swap_n_amount_in, stable_swap_n_d, swap_amount_in,
+    weighted_swap_amount_in, weighted_swap_amount_out, withdraw_amounts, NO_SLIPPAGE_LIMIT,
+};
+#[cfg(feature = "farming")]
+use amm::state::{Farm, FarmAccrualMode, LockDuration, StakePosition};
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+
+// ========================================
+// AMM 数学不变量的属性测试
+// ========================================
+//
+// 储备量和 LP 供应量限制在 1e12 以内，保证中间乘积不会超出 u128，
+// 专注于验证舍入方向而不是溢出路径。
+
+const MAX: u64 = 1_000_000_000_000;
+
+/// StableSwap 不变量的验证式里有 A·x·y·(x + y)，储备量再缩小到 1e9 才能放进 u128
+const STABLE_MAX: u64 = 1_000_000_000;
+
+/// 三币种验证式里有 A·27·xyz·(x + y + z)，储备量缩小到 1e6
+const STABLE_N_MAX: u64 = 1_000_000;
+
+proptest! {
+    /// swap 之后 k 不会减少（包括 fee = 0 的情况）
+    #[test]
+    fn swap_never_decreases_k(
+        reserve_in in 1..MAX,
+        reserve_out in 2..MAX,
+        out_seed in any::<u64>(),
+        fee in 0u16..=1_000,
+    ) {
+        let amount_out = 1 + out_seed % (reserve_out - 1);
+        let Ok((amount_in, amount_in_with_fees)) = swap_amount_in(reserve_in, reserve_out, amount_out, fee) else {
+            return Err(TestCaseError::reject("overflow"));
+        };
+
+        prop_assert!(amount_in_with_fees >= amount_in);
+
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        let k_after = (reserve_in as u128 + amount_in_with_fees as u128) * (reserve_out - amount_out) as u128;
+        prop_assert!(k_after >= k_before);
+    }
+
+    /// 手续费按实际付出的输入收取：曲线只用到扣除手续费之后的部分，
+    /// 创建者和协议分走各自的份额之后，其余手续费留在池子里让 k 增长
+    #[test]
+    fn swap_fee_accrues_to_pool(
+        reserve_in in 1..MAX,
+        reserve_out in 2..MAX,
+        out_seed in any::<u64>(),
+        fee in 0u16..=1_000,
+        creator_fee_bps in 0u16..=10_000,
+        protocol_seed in any::<u16>(),
+    ) {
+        let protocol_fee_bps = protocol_seed % (10_001 - creator_fee_bps);
+        let amount_out = 1 + out_seed % (reserve_out - 1);
+        let Ok((amount_in, amount_in_with_fees)) = swap_amount_in(reserve_in, reserve_out, amount_out, fee) else {
+            return Err(TestCaseError::reject("overflow"));
+        };
+
+        // 收取的手续费不少于 amount_in_with_fees * fee / 10000
+        let lp_fee = amount_in_with_fees as u128 * fee as u128 / 10_000;
+        prop_assert!((amount_in_with_fees - amount_in) as u128 >= lp_fee);
+
+        let creator_fee = creator_fee_amount(amount_in_with_fees, fee, creator_fee_bps).unwrap();
+        let protocol_fee = creator_fee_amount(amount_in_with_fees, fee, protocol_fee_bps).unwrap();
+        prop_assert!((creator_fee + protocol_fee) as u128 <= lp_fee);
+
+        let amount_to_pool = amount_in_with_fees - creator_fee - protocol_fee;
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        let k_after = (reserve_in as u128 + amount_to_pool as u128) * (reserve_out - amount_out) as u128;
+        prop_assert!(k_after >= k_before);
+
+        // 手续费完全留在池子里时，k 的增长不少于按扣费后输入成交再加上手续费
+        if creator_fee_bps == 0 && protocol_fee_bps == 0 && fee > 0 {
+            let k_without_fee = (reserve_in as u128 + amount_in as u128) * (reserve_out - amount_out) as u128;
+            prop_assert!(k_after >= k_without_fee + lp_fee * (reserve_out - amount_out) as u128);
+        }
+    }
+
+    /// 协议手续费按权重拆分：总和恰好等于 amount，除最后一份外都不超过按比例计算的数量
+    #[test]
+    fn split_by_weights_conserves_amount(
+        amount in any::<u64>(),
+        cuts in prop::collection::vec(1u16..10_000, 0..3),
+    ) {
+        let mut cuts = cuts;
+        cuts.sort_unstable();
+        cuts.dedup();
+        let bounds: Vec<u16> = std::iter::once(0).chain(cuts).chain(std::iter::once(10_000)).collect();
+        let weights: Vec<u16> = bounds.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+        let shares = split_by_weights(amount, &weights).unwrap();
+        prop_assert_eq!(shares.iter().map(|&share| share as u128).sum::<u128>(), amount as u128);
+        for (share, weight) in shares.iter().zip(&weights).take(weights.len() - 1) {
+            prop_assert!(*share as u128 * 10_000 <= amount as u128 * *weight as u128);
+        }
+    }
+
+    /// 按比例存入、取出都不会降低每份 LP 对应的储备量
+    #[test]
+    fn lp_value_per_share_never_decreases(
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        lp_supply in 1..MAX,
+        lp_seed in any::<u64>(),
+    ) {
+        // 单次存入的 LP 不超过现有供应量，保证存入数量仍在 u64 范围内
+        let amount_lp = 1 + lp_seed % lp_supply;
+        let (amount_a, amount_b) = deposit_amounts(reserve_a, reserve_b, lp_supply, amount_lp).unwrap();
+        let new_a = reserve_a as u128 + amount_a as u128;
+        let new_b = reserve_b as u128 + amount_b as u128;
+        let new_supply = lp_supply as u128 + amount_lp as u128;
+
+        // new_a / new_supply >= reserve_a / lp_supply
+        prop_assert!(new_a * lp_supply as u128 >= reserve_a as u128 * new_supply);
+        prop_assert!(new_b * lp_supply as u128 >= reserve_b as u128 * new_supply);
+
+        let (out_a, out_b) = withdraw_amounts(reserve_a, reserve_b, lp_supply, amount_lp).unwrap();
+        let left_supply = (lp_supply - amount_lp) as u128;
+
+        prop_assert!((reserve_a - out_a) as u128 * lp_supply as u128 >= reserve_a as u128 * left_supply);
+        prop_assert!((reserve_b - out_b) as u128 * lp_supply as u128 >= reserve_b as u128 * left_supply);
+    }
+
+    /// 按代币数量存入：实际存入不超过愿意存入的数量，每份 LP 对应的储备量不会降低
+    #[test]
+    fn deposit_by_amounts_fits_budget_and_never_dilutes(
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        lp_supply in 1..MAX,
+        desired_a in 0..MAX,
+        desired_b in 0..MAX,
+    ) {
+        let Ok(amount_lp) = max_proportional_lp(reserve_a, reserve_b, lp_supply, desired_a, desired_b) else {
+            return Err(TestCaseError::reject("lp overflow"));
+        };
+        if amount_lp == 0 {
+            return Ok(());
+        }
+        let (amount_a, amount_b) = deposit_amounts(reserve_a, reserve_b, lp_supply, amount_lp).unwrap();
+        prop_assert!(amount_a <= desired_a);
+        prop_assert!(amount_b <= desired_b);
+
+        // (reserve_x + amount_x) / (lp_supply + amount_lp) >= reserve_x / lp_supply
+        let new_supply = lp_supply as u128 + amount_lp as u128;
+        prop_assert!((reserve_a as u128 + amount_a as u128) * lp_supply as u128 >= reserve_a as u128 * new_supply);
+        prop_assert!((reserve_b as u128 + amount_b as u128) * lp_supply as u128 >= reserve_b as u128 * new_supply);
+    }
+
+    /// 存入后立刻取出，拿回的代币不会多于存入的代币
+    #[test]
+    fn deposit_then_withdraw_creates_nothing(
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        lp_supply in 1..MAX,
+        lp_seed in any::<u64>(),
+    ) {
+        // 单次存入的 LP 不超过现有供应量，保证存入数量仍在 u64 范围内
+        let amount_lp = 1 + lp_seed % lp_supply;
+        let (amount_a, amount_b) = deposit_amounts(reserve_a, reserve_b, lp_supply, amount_lp).unwrap();
+        let (out_a, out_b) = withdraw_amounts(
+            reserve_a + amount_a,
+            reserve_b + amount_b,
+            lp_supply + amount_lp,
+            amount_lp,
+        ).unwrap();
+
+        prop_assert!(out_a <= amount_a);
+        prop_assert!(out_b <= amount_b);
+    }
+
+    /// 任意比例存入不会降低每份 LP 对应的 sqrt(k)
+    #[test]
+    fn imbalanced_deposit_never_dilutes(
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        lp_supply in 1..MAX,
+        amount_a in 0..MAX,
+        amount_b in 0..MAX,
+        fee in 0u16..=1_000,
+    ) {
+        let Ok(lp) = imbalanced_deposit_lp(reserve_a, reserve_b, lp_supply, amount_a, amount_b, fee) else {
+            return Err(TestCaseError::reject("lp overflow"));
+        };
+
+        let root_before = isqrt(reserve_a as u128 * reserve_b as u128);
+        let root_after = isqrt((reserve_a + amount_a) as u128 * (reserve_b + amount_b) as u128);
+
+        // root_after / (lp_supply + lp) >= root_before / lp_supply
+        prop_assert!(root_after * lp_supply as u128 >= root_before * (lp_supply as u128 + lp as u128));
+    }
+
+    /// 一连串 swap 之后全部取出，池子不会凭空多付代币
+    #[test]
+    fn swaps_then_full_withdraw_conserves_tokens(
+        reserve_a in 1_000..MAX,
+        reserve_b in 1_000..MAX,
+        fee in 0u16..=1_000,
+        trades in prop::collection::vec((any::<bool>(), 1u64..1_000), 1..16),
+    ) {
+        let (mut a, mut b) = (reserve_a, reserve_b);
+        let lp_supply = 1_000_000u64;
+
+        for (is_a, out) in trades {
+            let (reserve_in, reserve_out) = if is_a { (b, a) } else { (a, b) };
+            if out >= reserve_out {
+                continue;
+            }
+            let Ok((_, paid)) = swap_amount_in(reserve_in, reserve_out, out, fee) else { continue };
+            if is_a { a -= out; b += paid; } else { b -= out; a += paid; }
+        }
+
+        let (out_a, out_b) = withdraw_amounts(a, b, lp_supply, lp_supply).unwrap();
+        prop_assert!(out_a <= a);
+        prop_assert!(out_b <= b);
+    }
+}
+
+/// 储备量 (x, y) 对应的不变量是否不小于 d：
+/// 16A·xy(x + y) + 4xy·D >= D^3 + 16A·xy·D，左右同乘 4xy 避免除法
+fn stable_swap_invariant_holds(amp: u64, x: u64, y: u64, d: u128) -> bool {
+    let (a, x, y) = (amp as u128, x as u128, y as u128);
+    let xy = x * y;
+    16 * a * xy * (x + y) + 4 * xy * d >= d * d * d + 16 * a * xy * d
+}
+
+proptest! {
+    /// StableSwap 精确输出之后不变量 D 不会减少
+    #[test]
+    fn stable_swap_never_decreases_d(
+        amp in 1u64..=10_000,
+        reserve_in in 1..STABLE_MAX,
+        reserve_out in 2..STABLE_MAX,
+        out_seed in any::<u64>(),
+        fee in 0u16..=1_000,
+    ) {
+        let amount_out = 1 + out_seed % (reserve_out - 1);
+        let Ok((amount_in, amount_in_with_fees)) = stable_swap_amount_in(amp, reserve_in, reserve_out, amount_out, fee) else {
+            return Err(TestCaseError::reject("overflow"));
+        };
+
+        prop_assert!(amount_in_with_fees >= amount_in);
+
+        // D 向上取整，直接比较两次计算的 D 会受舍入影响，
+        // 改为检查新储备量代入不变量后不小于 swap 之前的 D
+        let d_before = stable_swap_d(amp, reserve_in, reserve_out).unwrap();
+        prop_assert!(stable_swap_invariant_holds(amp, reserve_in + amount_in, reserve_out - amount_out, d_before));
+    }
+
+    /// swap 转账之后的 check_invariant 接受 StableSwap 和加权曲线的精确输出报价（包括 fee = 0 的情况）
+    #[test]
+    fn curve_quotes_pass_check_invariant(
+        amp in 1u64..=10_000,
+        weight_a in 100u16..=9_900,
+        is_a in any::<bool>(),
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        out_seed in any::<u64>(),
+        fee in 0u16..=1_000,
+    ) {
+        let curves: [Box<dyn CurveCalculator>; 2] = [
+            Box::new(StableSwap { amp }),
+            Box::new(Weighted { weight_a, weight_b: 10_000 - weight_a }),
+        ];
+        let (reserve_in, reserve_out) = if is_a { (reserve_b, reserve_a) } else { (reserve_a, reserve_b) };
+        prop_assume!(reserve_out >= 2);
+        let amount_out = 1 + out_seed % (reserve_out - 1);
+
+        for curve in curves {
+            let Ok((_, amount_in_with_fees)) = curve.swap_in_given_out(reserve_in, reserve_out, amount_out, is_a, fee) else {
+                continue;
+            };
+            let new_in = reserve_in + amount_in_with_fees;
+            let after = if is_a { (reserve_out - amount_out, new_in) } else { (new_in, reserve_out - amount_out) };
+            prop_assert!(curve.check_invariant((reserve_a, reserve_b), after).is_ok());
+        }
+    }
+
+    /// 精确输入得到的输出再按精确输出报价，需要的输入不会超过原来的预算
+    #[test]
+    fn exact_in_output_fits_budget(
+        amp in 1u64..=10_000,
+        weight_in in 100u16..=9_900,
+        reserve_in in 1..MAX,
+        reserve_out in 2..MAX,
+        budget in 1..MAX,
+        fee in 0u16..=1_000,
+    ) {
+        let weight_out = 10_000 - weight_in;
+
+        if let Ok(out) = stable_swap_amount_out(amp, reserve_in, reserve_out, budget, fee) {
+            if out > 0 {
+                let (_, paid) = stable_swap_amount_in(amp, reserve_in, reserve_out, out, fee).unwrap();
+                prop_assert!(paid <= budget);
+            }
+        }
+
+        if let Ok(out) = weighted_swap_amount_out(reserve_in, reserve_out, budget, weight_in, weight_out, fee) {
+            if out > 0 {
+                if let Ok((_, paid)) = weighted_swap_amount_in(reserve_in, reserve_out, out, weight_in, weight_out, fee) {
+                    prop_assert!(paid <= budget);
+                }
+            }
+        }
+    }
+}
+
+proptest! {
+    /// 中间乘积能放进 u128 时，mul_div 与直接计算一致
+    #[test]
+    fn mul_div_matches_native(a in any::<u64>(), b in any::<u64>(), denominator in 1..u128::MAX) {
+        let product = a as u128 * b as u128;
+        prop_assert_eq!(mul_div_floor(a as u128, b as u128, denominator), Some(product / denominator));
+        prop_assert_eq!(mul_div_ceil(a as u128, b as u128, denominator), Some(product.div_ceil(denominator)));
+    }
+
+    /// 中间乘积超出 u128 时，a * b / b 仍然精确还原 a
+    #[test]
+    fn mul_div_handles_wide_products(a in any::<u128>(), b in 1..u128::MAX) {
+        prop_assert_eq!(mul_div_floor(a, b, b), Some(a));
+        prop_assert_eq!(mul_div_ceil(a, b, b), Some(a));
+        prop_assert_eq!(mul_div_floor(a, b, 1), a.checked_mul(b));
+    }
+}
+
+proptest! {
+    /// 线性释放的数量随时间单调不减，不超过总量，到期后等于总量
+    #[test]
+    fn linear_vesting_is_monotonic_and_bounded(
+        total in any::<u64>(),
+        start_ts in -1_000_000_000i64..1_000_000_000,
+        duration in 1i64..1_000_000_000,
+        t1 in -1_000_000_000i64..3_000_000_000,
+        t2 in -1_000_000_000i64..3_000_000_000,
+    ) {
+        let end_ts = start_ts + duration;
+        let (earlier, later) = (t1.min(t2), t1.max(t2));
+        let vested_earlier = linear_vested_amount(total, start_ts, end_ts, earlier).unwrap();
+        let vested_later = linear_vested_amount(total, start_ts, end_ts, later).unwrap();
+        prop_assert!(vested_earlier <= vested_later);
+        prop_assert!(vested_later <= total);
+        prop_assert_eq!(linear_vested_amount(total, start_ts, end_ts, end_ts).unwrap(), total);
+    }
+}
+
+proptest! {
+    /// 滑点上限传 0（NO_SLIPPAGE_LIMIT）或 u64::MAX 时任何数量都能通过，其他值只放行不超过它的数量
+    #[test]
+    fn max_amount_limit_sentinel(limit in any::<u64>(), amount in any::<u64>()) {
+        prop_assert!(max_amount_limit(NO_SLIPPAGE_LIMIT) >= amount);
+        prop_assert!(max_amount_limit(u64::MAX) >= amount);
+        if limit != NO_SLIPPAGE_LIMIT {
+            prop_assert_eq!(max_amount_limit(limit) >= amount, limit >= amount);
+        }
+    }
+}
+
+proptest! {
+    /// LP 模式铸造给协议的份额不超过 sqrt(k) 增长部分的 protocol_fee_bps / 10000
+    #[test]
+    fn protocol_fee_lp_never_exceeds_share(
+        reserve_a in 1..MAX,
+        reserve_b in 1..MAX,
+        lp_supply in 1..MAX,
+        growth_bps in 0u128..=10_000,
+        protocol_fee_bps in 0u16..=10_000,
+    ) {
+        let k = reserve_a as u128 * reserve_b as u128;
+        let k_last = (k * (10_000 - growth_bps) / 10_000).max(1);
+        let liquidity = protocol_fee_lp(reserve_a, reserve_b, lp_supply, k_last, protocol_fee_bps).unwrap();
+
+        let root_k = isqrt(k);
+        let root_k_last = isqrt(k_last);
+        let owned = liquidity as u128 * 10_000 * root_k;
+        let share = protocol_fee_bps as u128 * root_k.saturating_sub(root_k_last) * (lp_supply as u128 + liquidity as u128);
+        prop_assert!(owned <= share);
+    }
+}
+
+/// 按 snapshot.rs 的约定逐层构建默克尔树，返回 (根, 每个叶子的证明)，奇数个节点时最后一个直接上移
+fn merkle_tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut positions: Vec<usize> = (0..leaves.len()).collect();
+    let mut level = leaves.to_vec();
+
+    while level.len() > 1 {
+        for (leaf, position) in positions.iter_mut().enumerate() {
+            let sibling = *position ^ 1;
+            if sibling < level.len() {
+                proofs[leaf].push(level[sibling]);
+            }
+            *position /= 2;
+        }
+        level = level.chunks(2).map(|pair| if pair.len() == 2 { merkle_parent(pair[0], pair[1]) } else { pair[0] }).collect();
+    }
+
+    (level[0], proofs)
+}
+
+proptest! {
+    /// 每个持有者的证明都能通过，改动数量后不能通过
+    #[test]
+    fn merkle_proofs_verify_only_exact_balances(
+        balances in prop::collection::vec((any::<[u8; 32]>(), 1..MAX), 1..20),
+    ) {
+        let leaves: Vec<[u8; 32]> = balances.iter().map(|(owner, amount)| voting_power_leaf(&Pubkey::new_from_array(*owner), *amount)).collect();
+        let (root, proofs) = merkle_tree(&leaves);
+
+        for ((owner, amount), proof) in balances.iter().zip(&proofs) {
+            let owner = Pubkey::new_from_array(*owner);
+            prop_assert!(verify_merkle_proof(proof, root, voting_power_leaf(&owner, *amount)));
+            prop_assert!(!verify_merkle_proof(proof, root, voting_power_leaf(&owner, amount + 1)));
+        }
+    }
+}
+
+/// 三币种储备量对应的不变量是否不小于 d：
+/// (Ann·S + D)·27xyz >= D^4 + Ann·D·27xyz，左右同乘 27xyz 避免除法
+fn stable_swap_3_invariant_holds(amp: u64, balances: [u64; 3], d: u128) -> bool {
+    let ann = amp as u128 * 27;
+    let sum: u128 = balances.iter().map(|&x| x as u128).sum();
+    let product = 27 * balances.iter().map(|&x| x as u128).product::<u128>();
+    (ann * sum + d) * product >= d * d * d * d + ann * d * product
+}
+
+proptest! {
+    /// n = 2 时 N 币种的 D 与两币种完全相同
+    #[test]
+    fn stable_swap_n_d_matches_two_coin(
+        amp in 1u64..=10_000,
+        x in 1..STABLE_MAX,
+        y in 1..STABLE_MAX,
+    ) {
+        prop_assert_eq!(stable_swap_n_d(amp, &[x, y]).unwrap(), stable_swap_d(amp, x, y).unwrap());
+    }
+
+    /// 三币种精确输出之后不变量 D 不会减少
+    #[test]
+    fn stable_swap_n_never_decreases_d(
+        amp in 1u64..=10_000,
+        balances in [1..STABLE_N_MAX, 1..STABLE_N_MAX, 2..STABLE_N_MAX],
+        out_seed in any::<u64>(),
+        fee in 0u16..=1_000,
+    ) {
+        let amount_out = 1 + out_seed % (balances[2] - 1);
+        let Ok((amount_in, amount_in_with_fees)) = stable_swap_n_amount_in(amp, &balances, 0, 2, amount_out, fee) else {
+            return Err(TestCaseError::reject("overflow"));
+        };
+
+        prop_assert!(amount_in_with_fees >= amount_in);
+
+        let d_before = stable_swap_n_d(amp, &balances).unwrap();
+        let after = [balances[0] + amount_in, balances[1], balances[2] - amount_out];
+        prop_assert!(stable_swap_3_invariant_holds(amp, after, d_before));
+    }
+}
+
+#[cfg(feature = "farming")]
+fn lp_seconds_stake(farm: &mut Farm, weight: u128, staked_at: i64) -> StakePosition {
+    farm.update(staked_at).unwrap();
+    let mut position = StakePosition {
+        version: 0,
+        farm: Pubkey::default(),
+        owner: Pubkey::default(),
+        id: 0,
+        amount: weight as u64,
+        lock: LockDuration::None,
+        weight,
+        unlock_ts: staked_at,
+        reward_debt: 0,
+        penalty_debt: 0,
+        bump: 0,
+        lp_seconds_debt: 0,
+    };
+    farm.total_weight += weight;
+    farm.total_weight_start += farm.weight_start(&position).unwrap();
+    farm.settle(&mut position).unwrap();
+    position
+}
+
+#[cfg(feature = "farming")]
+proptest! {
+    /// LpSeconds 模式：两个仓位分到的奖励不超过释放量，按 权重 × 已质押秒数 的比例分配
+    #[test]
+    fn lp_seconds_rewards_follow_holding_time(
+        weight_a in 1u128..MAX as u128,
+        weight_b in 1u128..MAX as u128,
+        join_b in 1i64..1_000_000,
+        hold_b in 1i64..1_000_000,
+        reward_rate in 1u64..1_000_000,
+    ) {
+        let end = join_b + hold_b;
+        let mut farm = Farm {
+            version: 0,
+            pool: Pubkey::default(),
+            mint_lp: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            reward_rate,
+            reward_remaining: u64::MAX,
+            total_staked: 0,
+            total_weight: 0,
+            acc_reward_per_weight: 0,
+            acc_penalty_per_weight: 0,
+            last_update_ts: 0,
+            bump: 0,
+            accrual_mode: FarmAccrualMode::LpSeconds,
+            origin_ts: 0,
+            total_weight_start: 0,
+            acc_reward_per_lp_second: 0,
+            acc_reward_time_per_lp_second: 0,
+        };
+
+        let mut position_a = lp_seconds_stake(&mut farm, weight_a, 0);
+        let mut position_b = lp_seconds_stake(&mut farm, weight_b, join_b);
+        farm.update(end).unwrap();
+        let (reward_a, _) = farm.settle(&mut position_a).unwrap();
+        let (reward_b, _) = farm.settle(&mut position_b).unwrap();
+
+        // join_b 之前 A 独占奖励，之后按 LP 秒数分配
+        let emitted = reward_rate as u128 * end as u128;
+        prop_assert!(reward_a as u128 + reward_b as u128 <= emitted);
+
+        // 最后一段里 B 的 LP 秒数占比：w_b × hold_b / (w_a × end + w_b × hold_b)
+        let lp_seconds_a = weight_a * end as u128;
+        let lp_seconds_b = weight_b * hold_b as u128;
+        let expected_b = reward_rate as u128 * hold_b as u128 * lp_seconds_b / (lp_seconds_a + lp_seconds_b);
+        prop_assert!(reward_b as u128 <= expected_b + 1);
+        prop_assert!(reward_b as u128 + 2 >= expected_b);
+    }
+}
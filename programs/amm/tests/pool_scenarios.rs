@@ -0,0 +1,700 @@
+mod common;
+
+use amm::{
+    client::{build_add_position_whitelist_ix, build_buyback_and_burn_ix, build_claim_scheduled_withdraw_ix, build_deposit_by_amounts_ix, build_deposit_position_ix, build_deposit_with_portfolio_ix, build_fund_farm_from_fees_ix, build_init_portfolio_ix, build_init_position_ix, build_init_trader_state_ix, build_register_market_maker_ix, build_schedule_withdraw_ix, build_seed_pool_from_treasury_ix, build_swap_ix, build_swap_with_hook_ix, build_swap_with_quote_ix, build_transfer_position_ix, build_transfer_position_with_proof_ix, build_withdraw_position_ix, build_withdraw_with_portfolio_ix, config_address, creator_fee_vault_address, farm_address, lp_mint_address, market_maker_address, portfolio_address, position_address, scheduled_withdraw_address},
+    context::{merkle_parent, position_whitelist_leaf, SignedQuote, BUYBACK_INTERVAL},
+    error::AmmError,
+    math::swap_amount_in,
+    state::{Farm, FarmAccrualMode, FeeExemption, MarketMaker, Pool, Position, UserPortfolio},
+    swap_hook::MAX_SWAP_HOOK_COMPUTE_UNITS,
+};
+use anchor_lang::{
+    solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::invoke, program_error::ProgramError, sysvar::Sysvar},
+    AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, spl_associated_token_account::instruction::create_associated_token_account_idempotent},
+    token::spl_token,
+};
+use common::PoolFixture;
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+use solana_program_test::processor;
+use solana_sdk::{instruction::{AccountMeta, Instruction}, program_pack::Pack, pubkey::Pubkey, signature::{Keypair, Signer}};
+
+// ========================================
+// 单个指令的场景测试（BanksClient）
+// ========================================
+//
+// 每个场景用 PoolFixture 搭一个已经有流动性的池子，执行一两笔指令，再按链上余额检查结果。
+//   anchor build && cargo test -p amm --features client --test pool_scenarios
+
+const SEED_A: u64 = 1_000_000_000;
+const SEED_B: u64 = 2_000_000_000;
+
+/// 交易者付出的数量与 swap_amount_in 的报价一致，没有创建者和协议分成时全部进入储备量
+#[tokio::test]
+async fn swap_fee_stays_in_pool() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let trader = fixture.wallet(1);
+    let amount_out = 10_000_000;
+
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let (_, before_b, _) = fixture.balances(&trader.pubkey()).await;
+
+    // 买入 TokenA，付出 TokenB
+    fixture.swap(&trader, amount_out, true).await;
+
+    let (_, after_b, _) = fixture.balances(&trader.pubkey()).await;
+    let paid = before_b - after_b;
+    let (amount_in, amount_in_with_fees) = swap_amount_in(reserve_b, reserve_a, amount_out, fixture.fee).unwrap();
+    assert_eq!(paid, amount_in_with_fees);
+    assert!(paid > amount_in);
+
+    let (reserve_a_after, reserve_b_after) = fixture.reserves().await;
+    assert_eq!(reserve_a_after, reserve_a - amount_out);
+    assert_eq!(reserve_b_after, reserve_b + paid);
+    assert!(reserve_a_after as u128 * reserve_b_after as u128 > reserve_a as u128 * reserve_b as u128);
+}
+
+/// 按代币数量存入：实际存入不超过愿意存入的数量，每份 LP 对应的储备量不会降低
+#[tokio::test]
+async fn deposit_by_amounts_fits_desired_amounts() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let provider = fixture.wallet(1);
+    let (desired_a, desired_b) = (50_000_000, 300_000_000);
+
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let lp_supply = fixture.lp_supply().await;
+    let (before_a, before_b, _) = fixture.balances(&provider.pubkey()).await;
+
+    fixture.send(&provider, vec![
+        create_associated_token_account_idempotent(&provider.pubkey(), &provider.pubkey(), &fixture.mint_lp, &spl_token::ID),
+        build_deposit_by_amounts_ix(&provider.pubkey(), &fixture.mint_a, &fixture.mint_b, fixture.fee, desired_a, desired_b, 1),
+    ]).await;
+
+    let (after_a, after_b, amount_lp) = fixture.balances(&provider.pubkey()).await;
+    let (spent_a, spent_b) = (before_a - after_a, before_b - after_b);
+    assert!(amount_lp > 0);
+    assert!(spent_a <= desired_a && spent_b <= desired_b);
+
+    let (new_a, new_b) = fixture.reserves().await;
+    let new_supply = fixture.lp_supply().await;
+    assert_eq!(new_supply, lp_supply + amount_lp);
+    assert!(new_a as u128 * lp_supply as u128 >= reserve_a as u128 * new_supply as u128);
+    assert!(new_b as u128 * lp_supply as u128 >= reserve_b as u128 * new_supply as u128);
+}
+
+/// 开启往返附加费后 swap 需要 trader_state；窗口内反方向的第二笔按 fee + 附加费报价
+#[tokio::test]
+async fn round_trip_swap_pays_surcharge() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let trader = fixture.wallet(1);
+    let surcharge_bps = 100;
+    let amount_out = 5_000_000;
+
+    fixture.pool_admin(amm::instruction::SetRoundTripFee { window_slots: 100, fee_bps: surcharge_bps }).await;
+
+    let (mint_a, mint_b, fee) = (fixture.mint_a, fixture.mint_b, fixture.fee);
+    fixture.send_expect_error(&trader, vec![
+        build_swap_ix(&trader.pubkey(), &mint_a, &mint_b, fee, amount_out, u64::MAX, true, false),
+    ], AmmError::TraderStateRequired).await;
+
+    fixture.send(&trader, vec![build_init_trader_state_ix(&trader.pubkey(), &fixture.pool)]).await;
+
+    // 第一笔买入 TokenA，按池子手续费报价
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let (_, before_b, _) = fixture.balances(&trader.pubkey()).await;
+    fixture.send(&trader, vec![
+        build_swap_ix(&trader.pubkey(), &mint_a, &mint_b, fee, amount_out, u64::MAX, true, true),
+    ]).await;
+    let (before_a, after_b, _) = fixture.balances(&trader.pubkey()).await;
+    assert_eq!(before_b - after_b, swap_amount_in(reserve_b, reserve_a, amount_out, fee).unwrap().1);
+
+    // 紧接着买回 TokenB，多付的附加费留在储备量中
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    fixture.send(&trader, vec![
+        build_swap_ix(&trader.pubkey(), &mint_a, &mint_b, fee, amount_out, u64::MAX, false, true),
+    ]).await;
+    let (after_a, _, _) = fixture.balances(&trader.pubkey()).await;
+    let paid = before_a - after_a;
+    assert_eq!(paid, swap_amount_in(reserve_a, reserve_b, amount_out, fee + surcharge_bps).unwrap().1);
+    assert!(paid > swap_amount_in(reserve_a, reserve_b, amount_out, fee).unwrap().1);
+    assert_eq!(fixture.reserves().await.0, reserve_a + paid);
+}
+
+/// 设置 max_drain_bps 之后，一笔取走超过该比例输出储备量的 swap 被拒绝，恰好等于上限时可以成交
+#[tokio::test]
+async fn max_drain_rejects_oversized_swap() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let trader = fixture.wallet(1);
+
+    fixture.pool_admin(amm::instruction::SetMaxDrain { max_drain_bps: 3_000 }).await;
+
+    let (reserve_a, _) = fixture.reserves().await;
+    let limit = reserve_a / 10_000 * 3_000;
+    let (mint_a, mint_b, fee) = (fixture.mint_a, fixture.mint_b, fixture.fee);
+    fixture.send_expect_error(&trader, vec![
+        build_swap_ix(&trader.pubkey(), &mint_a, &mint_b, fee, limit + 1, u64::MAX, true, false),
+    ], AmmError::ReserveDrainTooHigh).await;
+
+    fixture.swap(&trader, limit, true).await;
+    assert_eq!(fixture.reserves().await.0, reserve_a - limit);
+}
+
+/// 传入 portfolio 的存入把池子加入仓位列表，重复存入不会重复记录，部分取出保留，全部取出之后移除
+#[tokio::test]
+async fn portfolio_tracks_pool_until_full_exit() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let provider = fixture.wallet(1);
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+    let owner = provider.pubkey();
+
+    fixture.send(&provider, vec![build_init_portfolio_ix(&owner)]).await;
+    for _ in 0..2 {
+        fixture.send(&provider, vec![
+            create_associated_token_account_idempotent(&owner, &owner, &fixture.mint_lp, &spl_token::ID),
+            build_deposit_with_portfolio_ix(&owner, &mint_a, &mint_b, fee, 1_000_000, u64::MAX, u64::MAX),
+        ]).await;
+    }
+
+    let portfolio_pools = |data: Vec<u8>| UserPortfolio::try_deserialize(&mut data.as_slice()).unwrap().pools;
+    let data = fixture.ctx.banks_client.get_account(portfolio_address(&owner)).await.unwrap().unwrap().data;
+    assert_eq!(portfolio_pools(data), vec![pool]);
+
+    let (_, _, amount_lp) = fixture.balances(&owner).await;
+    fixture.send(&provider, vec![build_withdraw_with_portfolio_ix(&owner, &mint_a, &mint_b, fee, amount_lp / 2, 0, 0)]).await;
+    let data = fixture.ctx.banks_client.get_account(portfolio_address(&owner)).await.unwrap().unwrap().data;
+    assert_eq!(portfolio_pools(data), vec![pool]);
+
+    let (_, _, amount_lp) = fixture.balances(&owner).await;
+    fixture.send(&provider, vec![build_withdraw_with_portfolio_ix(&owner, &mint_a, &mint_b, fee, amount_lp, 0, 0)]).await;
+    let data = fixture.ctx.banks_client.get_account(portfolio_address(&owner)).await.unwrap().unwrap().data;
+    assert!(portfolio_pools(data).is_empty());
+}
+
+/// 金库（config 名下的 ATA）中的代币按池子比例存入，LP 铸造到 config 名下，只有协议管理员可以执行
+#[tokio::test]
+async fn seed_pool_from_treasury_mints_protocol_owned_lp() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let funder = fixture.wallet(1);
+    let admin = fixture.admin.insecure_clone();
+    let (mint_a, mint_b, fee) = (fixture.mint_a, fixture.mint_b, fixture.fee);
+    let config = config_address();
+    let (amount_a, amount_b) = (10_000_000, 20_000_000);
+
+    // 任何人都可以把代币转进金库
+    let mut ixs = Vec::new();
+    for (mint, amount) in [(mint_a, amount_a), (mint_b, amount_b)] {
+        let treasury_ata = get_associated_token_address(&config, &mint);
+        ixs.push(create_associated_token_account_idempotent(&funder.pubkey(), &config, &mint, &spl_token::ID));
+        ixs.push(spl_token::instruction::transfer(&spl_token::ID, &get_associated_token_address(&funder.pubkey(), &mint), &treasury_ata, &funder.pubkey(), &[], amount).unwrap());
+    }
+    fixture.send(&funder, ixs).await;
+
+    fixture.send_expect_error(&funder, vec![
+        build_seed_pool_from_treasury_ix(&funder.pubkey(), &funder.pubkey(), &mint_a, &mint_b, fee, amount_a, amount_b, 1),
+    ], AmmError::InvalidAuthority).await;
+
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let lp_supply = fixture.lp_supply().await;
+    fixture.send(&admin, vec![
+        build_seed_pool_from_treasury_ix(&admin.pubkey(), &admin.pubkey(), &mint_a, &mint_b, fee, amount_a, amount_b, 1),
+    ]).await;
+
+    let (treasury_a, treasury_b, treasury_lp) = fixture.balances(&config).await;
+    assert_eq!(treasury_lp, fixture.lp_supply().await - lp_supply);
+    assert_eq!(treasury_lp, lp_supply / 100);
+    let (new_a, new_b) = fixture.reserves().await;
+    assert_eq!((new_a, new_b), (reserve_a + amount_a - treasury_a, reserve_b + amount_b - treasury_b));
+}
+
+/// 登记为豁免的钱包按减免后的手续费成交，其他钱包仍然按池子手续费成交
+#[tokio::test]
+async fn fee_exempt_wallet_swaps_without_fee() {
+    let mut fixture = PoolFixture::builder().wallets(3).seed_liquidity(SEED_A, SEED_B).build().await;
+    let (partner, trader) = (fixture.wallet(1), fixture.wallet(2));
+    let amount_out = 10_000_000;
+
+    fixture.config_admin(amm::instruction::SetFeeExemptions {
+        exemptions: vec![FeeExemption { key: partner.pubkey(), discount_bps: 10_000 }],
+    }).await;
+
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let (_, before_b, _) = fixture.balances(&partner.pubkey()).await;
+    fixture.swap(&partner, amount_out, true).await;
+    let (_, after_b, _) = fixture.balances(&partner.pubkey()).await;
+    assert_eq!(before_b - after_b, swap_amount_in(reserve_b, reserve_a, amount_out, 0).unwrap().1);
+
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let (_, before_b, _) = fixture.balances(&trader.pubkey()).await;
+    fixture.swap(&trader, amount_out, true).await;
+    let (_, after_b, _) = fixture.balances(&trader.pubkey()).await;
+    assert_eq!(before_b - after_b, swap_amount_in(reserve_b, reserve_a, amount_out, fixture.fee).unwrap().1);
+}
+
+/// 开启 protocol_fees_to_farm 之后，奖励代币一侧（TokenA）累积的协议分成可以由任何人注入 Farm
+#[tokio::test]
+async fn fund_farm_from_fees_moves_protocol_fees() {
+    let mut fixture = PoolFixture::builder().wallets(3).seed_liquidity(SEED_A, SEED_B).build().await;
+    let (trader, keeper) = (fixture.wallet(1), fixture.wallet(2));
+    let admin = fixture.admin.insecure_clone();
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+    let farm = farm_address(&pool);
+
+    // 协议管理员指令和池子管理员指令的 authority 都是 UncheckedAccount，需要手动加上签名标记
+    let admin_ix = |accounts: Vec<AccountMeta>, data: Vec<u8>| {
+        let mut accounts = accounts;
+        accounts[0].is_signer = true;
+        Instruction { program_id: amm::ID, accounts, data }
+    };
+    let protocol_fee_accounts = amm::accounts::SetProtocolFee { authority: admin.pubkey(), config: config_address(), pool }.to_account_metas(None);
+    let mut create_farm_accounts = amm::accounts::CreateFarm {
+        payer: admin.pubkey(),
+        authority: admin.pubkey(),
+        pool,
+        mint_lp: fixture.mint_lp,
+        reward_mint: mint_a,
+        farm,
+        farm_ata_lp: get_associated_token_address(&farm, &fixture.mint_lp),
+        farm_ata_reward: get_associated_token_address(&farm, &mint_a),
+        token_program: spl_token::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: solana_sdk::system_program::ID,
+    }.to_account_metas(None);
+    create_farm_accounts[1].is_signer = true;
+    fixture.send(&admin, vec![
+        admin_ix(protocol_fee_accounts.clone(), amm::instruction::SetProtocolFee { protocol_fee_bps: 5_000 }.data()),
+        Instruction { program_id: amm::ID, accounts: create_farm_accounts, data: amm::instruction::CreateFarm { reward_rate: 1, accrual_mode: FarmAccrualMode::PerShare }.data() },
+    ]).await;
+
+    // 卖出 TokenA，手续费（含协议分成）以 TokenA 支付
+    fixture.swap(&trader, 10_000_000, false).await;
+    let read_pool = |data: Vec<u8>| Pool::try_deserialize(&mut data.as_slice()).unwrap();
+    let protocol_fees_a = read_pool(fixture.ctx.banks_client.get_account(pool).await.unwrap().unwrap().data).protocol_fees_a;
+    assert!(protocol_fees_a > 0);
+
+    fixture.send_expect_error(&keeper, vec![
+        build_fund_farm_from_fees_ix(&keeper.pubkey(), &mint_a, &mint_b, fee, &mint_a),
+    ], AmmError::ProtocolFeesToFarmDisabled).await;
+
+    fixture.send(&admin, vec![
+        admin_ix(protocol_fee_accounts, amm::instruction::SetProtocolFeesToFarm { enabled: true }.data()),
+    ]).await;
+    fixture.send(&keeper, vec![build_fund_farm_from_fees_ix(&keeper.pubkey(), &mint_a, &mint_b, fee, &mint_a)]).await;
+
+    assert_eq!(fixture.token_balance(get_associated_token_address(&farm, &mint_a)).await, protocol_fees_a);
+    let farm_data = fixture.ctx.banks_client.get_account(farm).await.unwrap().unwrap().data;
+    assert_eq!(Farm::try_deserialize(&mut farm_data.as_slice()).unwrap().reward_remaining, protocol_fees_a);
+    assert_eq!(read_pool(fixture.ctx.banks_client.get_account(pool).await.unwrap().unwrap().data).protocol_fees_a, 0);
+}
+
+/// 首次存入之前往池子代币账户里转入的代币不会让池子无法存入，和首次存入一起计入储备，LP 归首个存入者
+#[tokio::test]
+async fn donation_before_first_deposit_goes_to_first_depositor() {
+    let mut fixture = PoolFixture::builder().wallets(2).build().await;
+    let (provider, donor) = (fixture.wallet(0), fixture.wallet(1));
+    let (mint_a, mint_b) = (fixture.mint_a, fixture.mint_b);
+    let dust = 1;
+
+    // 池子代币账户的地址可以提前算出，任何人都能在首次存入之前转入
+    let mut ixs = Vec::new();
+    for mint in [mint_a, mint_b] {
+        let vault = get_associated_token_address(&fixture.pool, &mint);
+        ixs.push(spl_token::instruction::transfer(&spl_token::ID, &get_associated_token_address(&donor.pubkey(), &mint), &vault, &donor.pubkey(), &[], dust).unwrap());
+    }
+    fixture.send(&donor, ixs).await;
+    assert_eq!(fixture.reserves().await, (dust, dust));
+    assert_eq!(fixture.lp_supply().await, 0);
+
+    fixture.deposit(&provider, 0, SEED_A, SEED_B).await;
+
+    assert_eq!(fixture.reserves().await, (SEED_A + dust, SEED_B + dust));
+    let (_, _, provider_lp) = fixture.balances(&provider.pubkey()).await;
+    assert_eq!(provider_lp, (SEED_A + dust) * (SEED_B + dust));
+    assert_eq!(fixture.lp_supply().await, provider_lp);
+}
+
+/// 其他人往分期取出的托管 ATA 转入 LP 之后，最后一次 claim 仍然能关闭计划，多出来的 LP 被销毁
+#[tokio::test]
+async fn scheduled_withdraw_closes_with_extra_escrow_lp() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let (owner, griefer) = (fixture.wallet(0), fixture.wallet(1));
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+    let mint_lp = lp_mint_address(&pool);
+    let scheduled_withdraw = scheduled_withdraw_address(&pool, &owner.pubkey(), 0);
+    let escrow = get_associated_token_address(&scheduled_withdraw, &mint_lp);
+    let (amount_lp, extra) = (1_000_000, 1);
+
+    fixture.send(&owner, vec![build_schedule_withdraw_ix(&owner.pubkey(), &pool, 0, amount_lp, 10)]).await;
+
+    fixture.deposit(&griefer, 1_000, u64::MAX, u64::MAX).await;
+    fixture.send(&griefer, vec![
+        spl_token::instruction::transfer(&spl_token::ID, &get_associated_token_address(&griefer.pubkey(), &mint_lp), &escrow, &griefer.pubkey(), &[], extra).unwrap(),
+    ]).await;
+    assert_eq!(fixture.token_balance(escrow).await, amount_lp + extra);
+
+    fixture.warp_seconds(10).await;
+    let lp_supply = fixture.lp_supply().await;
+    fixture.send(&owner, vec![build_claim_scheduled_withdraw_ix(&owner.pubkey(), &mint_a, &mint_b, fee, 0, 1, 1)]).await;
+
+    assert_eq!(fixture.lp_supply().await, lp_supply - amount_lp - extra);
+    assert!(fixture.ctx.banks_client.get_account(escrow).await.unwrap().is_none());
+    assert!(fixture.ctx.banks_client.get_account(scheduled_withdraw).await.unwrap().is_none());
+}
+
+/// 登记做市商时库存账户的 owner 必须签名，池子管理员不能把别人的钱包登记成 owner
+#[tokio::test]
+async fn register_market_maker_requires_owner_signature() {
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B).build().await;
+    let victim = fixture.wallet(1);
+    let admin = fixture.admin.insecure_clone();
+    let pool = fixture.pool;
+    let signing_key = Pubkey::new_unique();
+
+    // 交易只带管理员的签名，owner 的 AccountMeta 去掉签名标记
+    let mut ix = build_register_market_maker_ix(&admin.pubkey(), &admin.pubkey(), &victim.pubkey(), &pool, &signing_key);
+    ix.accounts[2].is_signer = false;
+    let logs = fixture.try_send(&admin, vec![ix]).await.unwrap_err();
+    assert!(logs.contains("AccountNotSigner"), "{logs}");
+    assert!(fixture.ctx.banks_client.get_account(market_maker_address(&pool, &signing_key)).await.unwrap().is_none());
+
+    // 管理员登记自己的库存账户
+    fixture.send(&admin, vec![
+        build_register_market_maker_ix(&admin.pubkey(), &admin.pubkey(), &admin.pubkey(), &pool, &signing_key),
+    ]).await;
+    let data = fixture.ctx.banks_client.get_account(market_maker_address(&pool, &signing_key)).await.unwrap().unwrap().data;
+    assert_eq!(MarketMaker::try_deserialize(&mut data.as_slice()).unwrap().owner, admin.pubkey());
+}
+
+/// 回购每个池子每 BUYBACK_INTERVAL 秒最多执行一次，现价被推离 TWAP 时拒绝回购
+#[tokio::test]
+async fn buyback_respects_interval_and_twap() {
+    let mut fixture = PoolFixture::builder().wallets(4).seed_liquidity(SEED_A, SEED_B).build().await;
+    let (trader, keeper_1, keeper_2) = (fixture.wallet(1), fixture.wallet(2), fixture.wallet(3));
+    let admin = fixture.admin.insecure_clone();
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+
+    // 协议代币是 TokenB，卖出 TokenA 的 swap 累积 TokenA 一侧的协议分成
+    fixture.config_admin(amm::instruction::SetBuybackConfig {
+        protocol_token_mint: mint_b,
+        max_buyback_amount_in: u64::MAX,
+        max_buyback_impact_bps: 500,
+    }).await;
+    let mut protocol_fee_accounts = amm::accounts::SetProtocolFee { authority: admin.pubkey(), config: config_address(), pool }.to_account_metas(None);
+    protocol_fee_accounts[0].is_signer = true;
+    fixture.send(&admin, vec![
+        Instruction { program_id: amm::ID, accounts: protocol_fee_accounts, data: amm::instruction::SetProtocolFee { protocol_fee_bps: 5_000 }.data() },
+    ]).await;
+
+    // 隔一个观测间隔再 swap，累加器里才有 TWAP
+    fixture.warp_seconds(60).await;
+    fixture.swap(&trader, 10_000_000, false).await;
+    let read_pool = |data: Vec<u8>| Pool::try_deserialize(&mut data.as_slice()).unwrap();
+    let protocol_fees_a = read_pool(fixture.ctx.banks_client.get_account(pool).await.unwrap().unwrap().data).protocol_fees_a;
+    assert!(protocol_fees_a > 0);
+
+    let supply_b = |data: Vec<u8>| spl_token::state::Mint::unpack(&data).unwrap().supply;
+    let before = supply_b(fixture.ctx.banks_client.get_account(mint_b).await.unwrap().unwrap().data);
+    fixture.send(&keeper_1, vec![build_buyback_and_burn_ix(&keeper_1.pubkey(), &mint_a, &mint_b, fee)]).await;
+    assert!(supply_b(fixture.ctx.banks_client.get_account(mint_b).await.unwrap().unwrap().data) < before);
+    let state = read_pool(fixture.ctx.banks_client.get_account(pool).await.unwrap().unwrap().data);
+    assert!(state.protocol_fees_a < protocol_fees_a);
+    assert_ne!(state.last_buyback_ts, 0);
+
+    // 同一个间隔内第二次回购被拒绝
+    fixture.swap(&trader, 10_000_000, false).await;
+    fixture.send_expect_error(&keeper_2, vec![
+        build_buyback_and_burn_ix(&keeper_2.pubkey(), &mint_a, &mint_b, fee),
+    ], AmmError::BuybackTooSoon).await;
+
+    // 间隔过后，先把协议代币的价格推离 TWAP 再回购同样被拒绝
+    fixture.warp_seconds(BUYBACK_INTERVAL).await;
+    fixture.swap(&trader, 400_000_000, false).await;
+    fixture.send_expect_error(&keeper_2, vec![
+        build_buyback_and_burn_ix(&keeper_2.pubkey(), &mint_a, &mint_b, fee),
+    ], AmmError::CircuitBreakerTripped).await;
+}
+
+// ========== swap hook（见 swap_hook.rs）==========
+//
+// hook 程序用 processor! 包装成原生程序加载，和 amm.so 一样通过 CPI 调用
+
+/// 什么都不做的 hook
+fn noop_hook(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    Ok(())
+}
+
+/// 反复读取 Clock：原生程序每次读取 sysvar 按 sysvar_base_cost 扣计算量，累计超过 MAX_SWAP_HOOK_COMPUTE_UNITS
+fn expensive_hook(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    for _ in 0..MAX_SWAP_HOOK_COMPUTE_UNITS / 100 {
+        Clock::get()?;
+    }
+    Ok(())
+}
+
+/// 在回调里重入池子，调用读取储备量的 verify_pool
+///
+/// 额外账户为 [mint_lp, pool_ata_a, pool_ata_b, creator_fee_vault_a, creator_fee_vault_b, pool（可写）, AMM 程序]。
+/// 回调固定传入的 pool 是只读的，交易里再把 pool 作为可写的额外账户传一次，hook 才拿得到可写权限
+fn reentrant_hook(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    let [_, _, mint_lp, pool_ata_a, pool_ata_b, creator_fee_vault_a, creator_fee_vault_b, pool, _] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let verify_pool = amm::accounts::VerifyPool {
+        mint_lp: *mint_lp.key,
+        pool_ata_a: *pool_ata_a.key,
+        pool_ata_b: *pool_ata_b.key,
+        creator_fee_vault_a: *creator_fee_vault_a.key,
+        creator_fee_vault_b: *creator_fee_vault_b.key,
+        pool: *pool.key,
+    };
+    invoke(&Instruction { program_id: amm::ID, accounts: verify_pool.to_account_metas(None), data: amm::instruction::VerifyPool {}.data() }, accounts)
+}
+
+/// 登记了 hook 的池子，不传 hook_program 的 swap 被拒绝，传入之后正常成交
+#[tokio::test]
+async fn hooked_swap_requires_hook_program() {
+    let hook = Pubkey::new_unique();
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B)
+        .setup(move |program_test| program_test.add_program("noop_hook", hook, processor!(noop_hook)))
+        .build().await;
+    let trader = fixture.wallet(1);
+    let (mint_a, mint_b, fee) = (fixture.mint_a, fixture.mint_b, fixture.fee);
+    fixture.pool_admin(amm::instruction::SetSwapHook { hook_program: hook, hook_accounts: 0 }).await;
+
+    fixture.send_expect_error(&trader, vec![
+        build_swap_ix(&trader.pubkey(), &mint_a, &mint_b, fee, 1_000_000, u64::MAX, true, false),
+    ], AmmError::SwapHookRequired).await;
+
+    let (before_a, _, _) = fixture.balances(&trader.pubkey()).await;
+    fixture.send(&trader, vec![build_swap_with_hook_ix(&trader.pubkey(), &mint_a, &mint_b, fee, 1_000_000, u64::MAX, true, &hook, &[])]).await;
+    let (after_a, _, _) = fixture.balances(&trader.pubkey()).await;
+    assert_eq!(after_a - before_a, 1_000_000);
+}
+
+/// 回调期间 pool.locked = true，hook 重入池子的指令以 PoolLocked 失败，整笔 swap 回滚
+#[tokio::test]
+async fn reentrant_hook_fails_with_pool_locked() {
+    let hook = Pubkey::new_unique();
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B)
+        .setup(move |program_test| program_test.add_program("reentrant_hook", hook, processor!(reentrant_hook)))
+        .build().await;
+    let trader = fixture.wallet(1);
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+    fixture.pool_admin(amm::instruction::SetSwapHook { hook_program: hook, hook_accounts: 7 }).await;
+
+    let hook_accounts = [
+        AccountMeta::new_readonly(fixture.mint_lp, false),
+        AccountMeta::new_readonly(get_associated_token_address(&pool, &mint_a), false),
+        AccountMeta::new_readonly(get_associated_token_address(&pool, &mint_b), false),
+        AccountMeta::new_readonly(creator_fee_vault_address(&pool, &mint_a), false),
+        AccountMeta::new_readonly(creator_fee_vault_address(&pool, &mint_b), false),
+        AccountMeta::new(pool, false),
+        AccountMeta::new_readonly(amm::ID, false),
+    ];
+    let reserves = fixture.reserves().await;
+    fixture.send_expect_error(&trader, vec![
+        build_swap_with_hook_ix(&trader.pubkey(), &mint_a, &mint_b, fee, 1_000_000, u64::MAX, true, &hook, &hook_accounts),
+    ], AmmError::PoolLocked).await;
+    assert_eq!(fixture.reserves().await, reserves);
+}
+
+/// hook 消耗的计算量超过 MAX_SWAP_HOOK_COMPUTE_UNITS 时整笔 swap 失败
+#[tokio::test]
+async fn expensive_hook_fails_swap() {
+    let hook = Pubkey::new_unique();
+    let mut fixture = PoolFixture::builder().wallets(2).seed_liquidity(SEED_A, SEED_B)
+        .setup(move |program_test| program_test.add_program("expensive_hook", hook, processor!(expensive_hook)))
+        .build().await;
+    let trader = fixture.wallet(1);
+    let (mint_a, mint_b, fee) = (fixture.mint_a, fixture.mint_b, fixture.fee);
+    fixture.pool_admin(amm::instruction::SetSwapHook { hook_program: hook, hook_accounts: 0 }).await;
+
+    let reserves = fixture.reserves().await;
+    fixture.send_expect_error(&trader, vec![
+        build_swap_with_hook_ix(&trader.pubkey(), &mint_a, &mint_b, fee, 1_000_000, u64::MAX, true, &hook, &[]),
+    ], AmmError::SwapHookComputeExceeded).await;
+    assert_eq!(fixture.reserves().await, reserves);
+}
+
+// ========== 签名报价（见 context/rfq.rs）==========
+
+/// 用 signer 签名报价，返回 ed25519 验签指令
+fn sign_quote(signer: &Keypair, quote: &SignedQuote) -> Instruction {
+    let message = quote.message().unwrap();
+    let signature = <[u8; 64]>::from(signer.sign_message(&message));
+    new_ed25519_instruction_with_signature(&message, &signature, &signer.pubkey().to_bytes())
+}
+
+/// 同一个 nonce 的报价只能成交一次，重放时退回曲线成交，不再动用做市商库存；
+/// 未登记的公钥签出的报价直接报错
+#[tokio::test]
+async fn swap_with_quote_rejects_replay_and_unregistered_signer() {
+    let mut fixture = PoolFixture::builder().wallets(3).seed_liquidity(SEED_A, SEED_B).build().await;
+    let (funder, taker) = (fixture.wallet(1), fixture.wallet(2));
+    let admin = fixture.admin.insecure_clone();
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+    let quote_signer = Keypair::new();
+    let (inventory_amount, amount_out, amount_in) = (10_000_000, 1_000_000, 1_990_000);
+
+    // 管理员自己做市：登记做市商，库存账户持有 TokenA，把池子 PDA 设为 delegate
+    let inventory = get_associated_token_address(&admin.pubkey(), &mint_a);
+    let destination = get_associated_token_address(&admin.pubkey(), &mint_b);
+    fixture.send(&funder, vec![
+        create_associated_token_account_idempotent(&funder.pubkey(), &admin.pubkey(), &mint_a, &spl_token::ID),
+        create_associated_token_account_idempotent(&funder.pubkey(), &admin.pubkey(), &mint_b, &spl_token::ID),
+        spl_token::instruction::transfer(&spl_token::ID, &get_associated_token_address(&funder.pubkey(), &mint_a), &inventory, &funder.pubkey(), &[], inventory_amount).unwrap(),
+    ]).await;
+    fixture.send(&admin, vec![
+        build_register_market_maker_ix(&admin.pubkey(), &admin.pubkey(), &admin.pubkey(), &pool, &quote_signer.pubkey()),
+        spl_token::instruction::approve(&spl_token::ID, &inventory, &pool, &admin.pubkey(), &[], inventory_amount).unwrap(),
+    ]).await;
+
+    // 报价比曲线价格好，从做市商库存成交
+    let quote = SignedQuote { pool, taker: taker.pubkey(), is_a: true, amount_out, amount_in, nonce: 1, expires_at: i64::MAX };
+    fixture.send(&taker, vec![
+        sign_quote(&quote_signer, &quote),
+        build_swap_with_quote_ix(&taker.pubkey(), &mint_a, &mint_b, fee, &quote_signer.pubkey(), quote.clone(), u64::MAX, &inventory, &destination, false),
+    ]).await;
+    assert_eq!(fixture.token_balance(inventory).await, inventory_amount - amount_out);
+    assert_eq!(fixture.token_balance(destination).await, amount_in);
+
+    // 重放同一份报价：nonce 已经用过，按曲线成交，库存和收款都不变（max_amount_in 不同，避免与上一笔交易重复）
+    let reserves = fixture.reserves().await;
+    fixture.send(&taker, vec![
+        sign_quote(&quote_signer, &quote),
+        build_swap_with_quote_ix(&taker.pubkey(), &mint_a, &mint_b, fee, &quote_signer.pubkey(), quote.clone(), u64::MAX - 1, &inventory, &destination, false),
+    ]).await;
+    assert_eq!(fixture.token_balance(inventory).await, inventory_amount - amount_out);
+    assert_eq!(fixture.token_balance(destination).await, amount_in);
+    assert_eq!(fixture.reserves().await.0, reserves.0 - amount_out);
+
+    // 未登记的公钥签出的报价，即使指向已登记的做市商也无法通过验签
+    let forger = Keypair::new();
+    let quote = SignedQuote { nonce: 2, ..quote };
+    fixture.send_expect_error(&taker, vec![
+        sign_quote(&forger, &quote),
+        build_swap_with_quote_ix(&taker.pubkey(), &mint_a, &mint_b, fee, &quote_signer.pubkey(), quote.clone(), u64::MAX, &inventory, &destination, false),
+    ], AmmError::InvalidQuoteSignature).await;
+
+    // 未登记的公钥没有做市商账户
+    let logs = fixture.try_send(&taker, vec![
+        sign_quote(&forger, &quote),
+        build_swap_with_quote_ix(&taker.pubkey(), &mint_a, &mint_b, fee, &forger.pubkey(), quote, u64::MAX, &inventory, &destination, false),
+    ]).await.unwrap_err();
+    assert!(logs.contains("AccountNotInitialized"), "{logs}");
+    assert_eq!(fixture.token_balance(inventory).await, inventory_amount - amount_out);
+}
+
+// ========== soulbound LP 仓位（见 context/position.rs）==========
+
+/// 仓位只能转给白名单中的钱包；全局紧急模式下不能再存入，但仓位照常可以取出
+#[tokio::test]
+async fn soulbound_position_transfer_and_emergency_withdraw() {
+    let mut fixture = PoolFixture::builder().soulbound_lp(true).wallets(3).build().await;
+    let (owner, member, outsider) = (fixture.wallet(0), fixture.wallet(1), fixture.wallet(2));
+    let admin = fixture.admin.insecure_clone();
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+
+    for wallet in [&owner, &member, &outsider] {
+        fixture.send(wallet, vec![build_init_position_ix(&wallet.pubkey(), &pool)]).await;
+    }
+    fixture.send(&owner, vec![
+        build_deposit_position_ix(&owner.pubkey(), &mint_a, &mint_b, fee, 0, SEED_A, SEED_B),
+    ]).await;
+    fixture.send(&admin, vec![
+        build_add_position_whitelist_ix(&admin.pubkey(), &admin.pubkey(), &pool, &member.pubkey()),
+    ]).await;
+
+    // LP 全部托管在 position_vault，钱包里没有 LP 代币
+    let liquidity = fixture.lp_supply().await;
+    assert!(liquidity > 0);
+    assert_eq!(fixture.balances(&owner.pubkey()).await.2, 0);
+    assert_eq!(fixture.token_balance(get_associated_token_address(&pool, &fixture.mint_lp)).await, liquidity);
+
+    let position_liquidity = |data: Vec<u8>| Position::try_deserialize(&mut data.as_slice()).unwrap().liquidity;
+    let owner_position = position_address(&pool, &owner.pubkey());
+    let member_position = position_address(&pool, &member.pubkey());
+    assert_eq!(position_liquidity(fixture.ctx.banks_client.get_account(owner_position).await.unwrap().unwrap().data), liquidity);
+
+    // 转给白名单中的钱包
+    let amount = liquidity / 2;
+    fixture.send(&owner, vec![build_transfer_position_ix(&owner.pubkey(), &pool, &member.pubkey(), amount)]).await;
+    assert_eq!(position_liquidity(fixture.ctx.banks_client.get_account(owner_position).await.unwrap().unwrap().data), liquidity - amount);
+    assert_eq!(position_liquidity(fixture.ctx.banks_client.get_account(member_position).await.unwrap().unwrap().data), amount);
+
+    // 不在白名单中的钱包没有 PositionWhitelist 账户
+    let logs = fixture.try_send(&owner, vec![
+        build_transfer_position_ix(&owner.pubkey(), &pool, &outsider.pubkey(), amount),
+    ]).await.unwrap_err();
+    assert!(logs.contains("AccountNotInitialized"), "{logs}");
+    assert_eq!(position_liquidity(fixture.ctx.banks_client.get_account(owner_position).await.unwrap().unwrap().data), liquidity - amount);
+
+    fixture.enable_emergency_mode().await;
+
+    fixture.send_expect_error(&member, vec![
+        build_deposit_position_ix(&member.pubkey(), &mint_a, &mint_b, fee, 1_000, u64::MAX, u64::MAX),
+    ], AmmError::EmergencyModeActive).await;
+
+    // 紧急模式下仓位照常取出
+    let (reserve_a, reserve_b) = fixture.reserves().await;
+    let (before_a, before_b, _) = fixture.balances(&member.pubkey()).await;
+    fixture.send(&member, vec![
+        build_withdraw_position_ix(&member.pubkey(), &mint_a, &mint_b, fee, amount, 1, 1),
+    ]).await;
+
+    let (after_a, after_b, _) = fixture.balances(&member.pubkey()).await;
+    let (new_a, new_b) = fixture.reserves().await;
+    assert_eq!(after_a - before_a, reserve_a - new_a);
+    assert_eq!(after_b - before_b, reserve_b - new_b);
+    assert!(after_a > before_a && after_b > before_b);
+    assert_eq!(position_liquidity(fixture.ctx.banks_client.get_account(member_position).await.unwrap().unwrap().data), 0);
+    assert_eq!(fixture.lp_supply().await, liquidity - amount);
+}
+
+/// 默克尔根白名单：接收方附上有效证明才能接收仓位，证明与接收方不符或被篡改时拒绝
+#[tokio::test]
+async fn position_transfer_with_proof_checks_whitelist_root() {
+    let mut fixture = PoolFixture::builder().soulbound_lp(true).wallets(3).build().await;
+    let (owner, member, outsider) = (fixture.wallet(0), fixture.wallet(1), fixture.wallet(2));
+    let (mint_a, mint_b, fee, pool) = (fixture.mint_a, fixture.mint_b, fixture.fee, fixture.pool);
+
+    for wallet in [&owner, &member, &outsider] {
+        fixture.send(wallet, vec![build_init_position_ix(&wallet.pubkey(), &pool)]).await;
+    }
+    fixture.send(&owner, vec![
+        build_deposit_position_ix(&owner.pubkey(), &mint_a, &mint_b, fee, 0, SEED_A, SEED_B),
+    ]).await;
+    let amount = fixture.lp_supply().await / 4;
+
+    // 四个成员的树，member 是第一个叶子，证明为 [兄弟叶子, 右子树]
+    let leaves = [member.pubkey(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()].map(|wallet| position_whitelist_leaf(&wallet));
+    let right = merkle_parent(leaves[2], leaves[3]);
+    let root = merkle_parent(merkle_parent(leaves[0], leaves[1]), right);
+    let proof = vec![leaves[1], right];
+
+    // 还没有发布根（amount 不同，避免和后面成功的交易重复）
+    fixture.send_expect_error(&owner, vec![
+        build_transfer_position_with_proof_ix(&owner.pubkey(), &pool, &member.pubkey(), amount - 1, proof.clone()),
+    ], AmmError::PositionWhitelistRootNotSet).await;
+
+    fixture.pool_admin(amm::instruction::SetPositionWhitelistRoot { root }).await;
+
+    // 不在树中的钱包拿别人的证明
+    fixture.send_expect_error(&owner, vec![
+        build_transfer_position_with_proof_ix(&owner.pubkey(), &pool, &outsider.pubkey(), amount, proof.clone()),
+    ], AmmError::InvalidMerkleProof).await;
+
+    // 篡改过的证明
+    fixture.send_expect_error(&owner, vec![
+        build_transfer_position_with_proof_ix(&owner.pubkey(), &pool, &member.pubkey(), amount, vec![leaves[2], right]),
+    ], AmmError::InvalidMerkleProof).await;
+
+    fixture.send(&owner, vec![
+        build_transfer_position_with_proof_ix(&owner.pubkey(), &pool, &member.pubkey(), amount, proof),
+    ]).await;
+    let data = fixture.ctx.banks_client.get_account(position_address(&pool, &member.pubkey())).await.unwrap().unwrap().data;
+    assert_eq!(Position::try_deserialize(&mut data.as_slice()).unwrap().liquidity, amount);
+    let data = fixture.ctx.banks_client.get_account(position_address(&pool, &outsider.pubkey())).await.unwrap().unwrap().data;
+    assert_eq!(Position::try_deserialize(&mut data.as_slice()).unwrap().liquidity, 0);
+}
@@ -1,8 +1,9 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Amm } from "../target/types/amm";
-import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction } from "@solana/web3.js";
 import { BN, min } from "bn.js";
+import { assert } from "chai";
 import { ASSOCIATED_PROGRAM_ID, TOKEN_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
 import { createAssociatedTokenAccountIdempotentInstruction, createInitializeMint2Instruction, createMintToInstruction, getAssociatedTokenAddressSync, getMinimumBalanceForRentExemptMint, MINT_SIZE } from "@solana/spl-token";
 
@@ -188,8 +189,51 @@ describe("amm", () => {
   ],
   program.programId)[0];
 
+  const config = PublicKey.findProgramAddressSync([
+    Buffer.from("config")
+  ],
+  program.programId)[0];
+  // 程序的 ProgramData 账户，记录升级权限，用于校验谁可以创建全局配置
+  const programData = PublicKey.findProgramAddressSync([
+    program.programId.toBuffer()
+  ],
+  new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111"))[0];
+
+  const registry = PublicKey.findProgramAddressSync([
+    Buffer.from("registry")
+  ],
+  program.programId)[0];
+  const registryPage = PublicKey.findProgramAddressSync([
+    Buffer.from("registry_page"),
+    new BN(0).toArrayLike(Buffer, "le", 4)
+  ],
+  program.programId)[0];
+
+  // 交易对反查记录：种子中的两个 mint 按字节序排列，与 mintA / mintB 的顺序无关
+  const [mint0, mint1] = [mintA.publicKey, mintB.publicKey].sort((x, y) => Buffer.compare(x.toBuffer(), y.toBuffer()));
+  const pairMarker = PublicKey.findProgramAddressSync([
+    Buffer.from("pair"),
+    mint0.toBuffer(),
+    mint1.toBuffer()
+  ],
+  program.programId)[0];
+
   const tokenProgram = TOKEN_PROGRAM_ID;
 
+  // 创建者手续费账户，authority 是 pool
+  const creatorFeeVaultA = PublicKey.findProgramAddressSync([
+    Buffer.from("creator_fee"),
+    pool.toBuffer(),
+    mintA.publicKey.toBuffer()
+  ],
+  program.programId)[0];
+  const creatorFeeVaultB = PublicKey.findProgramAddressSync([
+    Buffer.from("creator_fee"),
+    pool.toBuffer(),
+    mintB.publicKey.toBuffer()
+  ],
+  program.programId)[0];
+
   const poolAtaA = getAssociatedTokenAddressSync(
     mintA.publicKey,
     pool,
@@ -236,6 +280,16 @@ describe("amm", () => {
     signerAtaLp,
     poolAtaA,
     poolAtaB,
+    creatorFeeVaultA,
+    creatorFeeVaultB,
+    config,             // 全局配置，紧急模式下 swap / deposit 被拒绝
+    traderState: null,  // 池子没有开启钱包限额，可选账户传 null
+    poolStats: null,    // 不记录成交统计
+    liquidityGuard: null, // 池子没有开启 JIT 保护
+    portfolio: null,    // 不更新仓位列表
+    relayerAta: null,   // 不使用中继者代付
+    hookProgram: null,  // 池子没有登记 swap hook
+    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
     systemProgram: SystemProgram.programId,
     tokenProgram,
     associatedTokenProgram: ASSOCIATED_PROGRAM_ID
@@ -304,6 +358,24 @@ describe("amm", () => {
     await logBalances("空投和创建代币后");
   });
 
+  it("Initialize config", async () => {
+    // anchor test 部署时 provider 钱包就是程序的升级权限持有者
+    const tx = await program.methods.initializeConfig(
+      provider.publicKey,
+      [30, fee.toNumber()]  // 允许 0.30% 和 5% 两个手续费档位
+    )
+    .accountsStrict({
+      signer: provider.publicKey,
+      config,
+      program: program.programId,
+      programData,
+      systemProgram: SystemProgram.programId
+    })
+    .rpc()
+    .then(confirm)
+    .then(log);
+  });
+
   it("Initialize a pool", async () => {
     // ========================================
     // Promise 链式调用示例
@@ -327,10 +399,24 @@ describe("amm", () => {
      * const result = await promise3;
      */
     const tx = await program.methods.initialize(
-      fee.toNumber()    // 手续费参数 (500 = 5%)
+      fee.toNumber(),   // 手续费参数 (500 = 5%)
+      0,                // 创建者手续费分成 (AmmConfig 默认上限为 0)
+      new BN(0),        // 不声明初始价格，首次存入可以按任意比例
+      new BN(0),
+      new BN(0),        // 不限制交易时间窗口
+      new BN(0),
+      false,            // LP 是普通的 SPL 代币，可以自由转让
+      { lp: {} }        // 手续费中 LP 的那部分留在储备量中
     )
     .accountsStrict({   // 严格账户验证，必须提供所有必需账户
-      ...accounts       // 展开所有预定义账户
+      ...accounts,      // 展开所有预定义账户
+      payer: signer.publicKey,  // 租金支付者，这里由创建者自己支付
+      config,           // 全局配置，校验 fee 是否在允许的档位中
+      registry,         // 全局池子注册表
+      registryPage,     // 注册表当前分页（首个池子写入第 0 页）
+      pairMarker,       // 交易对反查记录
+      tokenBadgeA: null, // 未开启 require_token_badges，不需要代币徽章
+      tokenBadgeB: null
     })
     .signers([          // 交易签名者数组
       signer            // 池子创建者
@@ -349,9 +435,149 @@ describe("amm", () => {
     await logBalances("初始化池子后");
   });
 
+  it("Initialize rejects identical mints", async () => {
+    const samePool = PublicKey.findProgramAddressSync([
+      Buffer.from("pool"),
+      mintA.publicKey.toBuffer(),
+      mintA.publicKey.toBuffer(),
+      fee.toArrayLike(Buffer, "le", 2)
+    ],
+    program.programId)[0];
+    const samePoolAta = getAssociatedTokenAddressSync(mintA.publicKey, samePool, true, tokenProgram);
+
+    try {
+      await program.methods.initialize(fee.toNumber(), 0, new BN(0), new BN(0), new BN(0), new BN(0), false, { lp: {} })
+      .accountsStrict({
+        signer: signer.publicKey,
+        payer: signer.publicKey,
+        mintA: mintA.publicKey,
+        mintB: mintA.publicKey,
+        mintLp: PublicKey.findProgramAddressSync([Buffer.from("lp"), samePool.toBuffer()], program.programId)[0],
+        poolAtaA: samePoolAta,
+        poolAtaB: samePoolAta,
+        creatorFeeVaultA: PublicKey.findProgramAddressSync([Buffer.from("creator_fee"), samePool.toBuffer(), mintA.publicKey.toBuffer()], program.programId)[0],
+        creatorFeeVaultB: PublicKey.findProgramAddressSync([Buffer.from("creator_fee"), samePool.toBuffer(), mintA.publicKey.toBuffer()], program.programId)[0],
+        pool: samePool,
+        config,
+        registry,
+        registryPage,
+        pairMarker: PublicKey.findProgramAddressSync([Buffer.from("pair"), mintA.publicKey.toBuffer(), mintA.publicKey.toBuffer()], program.programId)[0],
+        tokenBadgeA: null,
+        tokenBadgeB: null,
+        systemProgram: SystemProgram.programId,
+        tokenProgram,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .signers([
+        signer
+      ])
+      .rpc();
+      assert.fail("相同的 mint 不应该能创建池子");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "IdenticalMints");
+    }
+  });
+
+  // ========================================
+  // initialize 对两侧 mint 的检查
+  // ========================================
+
+  /**
+   * 创建一个测试用的 mint，supply > 0 时铸造到 provider 的关联代币账户
+   * freezeAuthority 传 null 表示没有 freeze authority
+   */
+  const createTestMint = async (freezeAuthority: PublicKey | null, supply: number): Promise<PublicKey> => {
+    const mint = Keypair.generate();
+    const providerAta = getAssociatedTokenAddressSync(mint.publicKey, provider.publicKey, false, tokenProgram);
+    const tx = new Transaction();
+    tx.instructions = [
+      SystemProgram.createAccount({
+        fromPubkey: provider.publicKey,
+        newAccountPubkey: mint.publicKey,
+        lamports: await getMinimumBalanceForRentExemptMint(connection),
+        space: MINT_SIZE,
+        programId: tokenProgram,
+      }),
+      createInitializeMint2Instruction(mint.publicKey, 6, provider.publicKey!, freezeAuthority, tokenProgram),
+      ...(supply > 0 ? [
+        createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, providerAta, provider.publicKey, mint.publicKey, tokenProgram),
+        createMintToInstruction(mint.publicKey, providerAta, provider.publicKey!, supply, undefined, tokenProgram),
+      ] : []),
+    ];
+    await provider.sendAndConfirm(tx, [mint]);
+    return mint.publicKey;
+  };
+
+  /**
+   * 用 (sideA, sideB) 创建池子，要求以 code 失败
+   * 账户按 initialize 的种子推导，与上面的 accounts 相同，只是换成了这两个 mint
+   */
+  const expectInitializeError = async (sideA: PublicKey, sideB: PublicKey, code: string): Promise<void> => {
+    const sidePool = PublicKey.findProgramAddressSync([
+      Buffer.from("pool"),
+      sideA.toBuffer(),
+      sideB.toBuffer(),
+      fee.toArrayLike(Buffer, "le", 2)
+    ],
+    program.programId)[0];
+    const [side0, side1] = [sideA, sideB].sort((x, y) => Buffer.compare(x.toBuffer(), y.toBuffer()));
+
+    try {
+      await program.methods.initialize(fee.toNumber(), 0, new BN(0), new BN(0), new BN(0), new BN(0), false, { lp: {} })
+      .accountsStrict({
+        signer: signer.publicKey,
+        payer: signer.publicKey,
+        mintA: sideA,
+        mintB: sideB,
+        mintLp: PublicKey.findProgramAddressSync([Buffer.from("lp"), sidePool.toBuffer()], program.programId)[0],
+        poolAtaA: getAssociatedTokenAddressSync(sideA, sidePool, true, tokenProgram),
+        poolAtaB: getAssociatedTokenAddressSync(sideB, sidePool, true, tokenProgram),
+        creatorFeeVaultA: PublicKey.findProgramAddressSync([Buffer.from("creator_fee"), sidePool.toBuffer(), sideA.toBuffer()], program.programId)[0],
+        creatorFeeVaultB: PublicKey.findProgramAddressSync([Buffer.from("creator_fee"), sidePool.toBuffer(), sideB.toBuffer()], program.programId)[0],
+        pool: sidePool,
+        config,
+        registry,
+        registryPage,
+        pairMarker: PublicKey.findProgramAddressSync([Buffer.from("pair"), side0.toBuffer(), side1.toBuffer()], program.programId)[0],
+        tokenBadgeA: null,
+        tokenBadgeB: null,
+        systemProgram: SystemProgram.programId,
+        tokenProgram,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .signers([
+        signer
+      ])
+      .rpc();
+      assert.fail(`应该以 ${code} 失败`);
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, code);
+    }
+  };
+
+  it("Initialize rejects another pool's LP mint as a pool side", async () => {
+    // LP 代币不能作为另一个池子的一侧，两侧分别检查
+    await expectInitializeError(mintA.publicKey, mintLp, "LpMintAsPoolSide");
+    await expectInitializeError(mintLp, mintB.publicKey, "LpMintAsPoolSide");
+  });
+
+  it("Initialize rejects a mint with zero supply", async () => {
+    // 还没有铸造过的 mint 无法存入首笔流动性，创建的池子只会占用交易对
+    const emptyMint = await createTestMint(null, 0);
+    await expectInitializeError(mintA.publicKey, emptyMint, "ZeroSupplyMint");
+    await expectInitializeError(emptyMint, mintB.publicKey, "ZeroSupplyMint");
+  });
+
+  it("Initialize rejects a mint with a freeze authority", async () => {
+    // 全局配置默认不允许 freeze authority 不为空的 mint，发行方可以冻结池子的代币账户
+    const freezableMint = await createTestMint(provider.publicKey, 1e9);
+    await expectInitializeError(mintA.publicKey, freezableMint, "MintHasFreezeAuthority");
+    await expectInitializeError(freezableMint, mintB.publicKey, "MintHasFreezeAuthority");
+  });
+
   it("Deposit", async () => {
     const tx = await program.methods.deposit(
-      new BN(625), new BN(25), new BN(25)
+      new BN(625), new BN(25), new BN(25), null
     )
     .preInstructions([
       createAssociatedTokenAccountIdempotentInstruction(
@@ -376,9 +602,20 @@ describe("amm", () => {
     await logBalances("存入流动性后");
   });
 
+  it("Get pool vaults", async () => {
+    // 只传 pool，通过模拟读取 return data，得到 swap 需要的池子相关地址
+    const vaults = await program.methods.getPoolVaults()
+    .accountsStrict({ pool })
+    .view();
+
+    assert.ok(vaults.poolAtaA.equals(poolAtaA));
+    assert.ok(vaults.poolAtaB.equals(poolAtaB));
+    assert.ok(vaults.mintLp.equals(mintLp));
+  });
+
   it("Swap", async () => {
     const tx = await program.methods.swap(
-      new BN(4), new BN(6), true  // 增加滑点容忍度到6，确保能容纳手续费
+      new BN(4), new BN(6), true, null, false  // 增加滑点容忍度到6，确保能容纳手续费
     )
     .accountsStrict({
       ...accounts
@@ -397,9 +634,66 @@ describe("amm", () => {
     await analyzeSwapFees();
   });
 
+  it("Swap with max_amount_in = 0 has no slippage limit", async () => {
+    // 0 是文档化的「不限制」哨兵值，而不是「最多支付 0」
+    await program.methods.swap(new BN(1), new BN(0), true, null, false)
+    .accountsStrict({
+      ...accounts
+    })
+    .signers([
+      signer
+    ])
+    .rpc()
+    .then(confirm)
+    .then(log);
+  });
+
+  it("Swap with a tag echoes it in SwapEvent", async () => {
+    // 交易所前端用 tag 把链上成交对应到内部订单号，不需要额外的 memo 指令
+    // 与上一笔方向相反，把池子里的 TokenA 补回来，后面 Withdraw 的最小取出数量才能满足
+    const tag = Array.from({ length: 16 }, (_, i) => i + 1);
+    const signature = await program.methods.swap(new BN(1), new BN(0), false, tag, false)
+    .accountsStrict({
+      ...accounts
+    })
+    .signers([
+      signer
+    ])
+    .rpc()
+    .then(confirm);
+
+    const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    const swapEvent = [...parser.parseLogs(tx.meta.logMessages)].find((event) => event.name === "swapEvent");
+    assert.deepEqual(Array.from(swapEvent.data.tag), tag);
+  });
+
+  it("Crank", async () => {
+    const tx = await program.methods.crank()
+    .accountsStrict({
+      signer: signer.publicKey,
+      mintA: mintA.publicKey,
+      mintB: mintB.publicKey,
+      poolAtaA,
+      poolAtaB,
+      pool,
+      tokenProgram,
+      associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+    })
+    .signers([
+      signer
+    ])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    const poolAccount = await program.account.pool.fetch(pool);
+    console.log(`价格累加器: A=${poolAccount.priceACumulative.toString()}, B=${poolAccount.priceBCumulative.toString()}`);
+  });
+
   it("Withdraw", async () => {
     const tx = await program.methods.withdraw(
-      new BN(625), new BN(21), new BN(29)
+      new BN(625), new BN(21), new BN(29), null, false
     )
     .accountsStrict({
       ...accounts
@@ -415,6 +709,54 @@ describe("amm", () => {
     await logBalances("提取流动性后");
   });
 
+  it("Deposit and withdraw reject a mint_lp that is not the pool's LP PDA", async () => {
+    // mint_lp 按 pool.lp_bump 校验种子，传入其他 mint 必须在账户校验阶段失败
+    const wrongLp = { ...accounts, mintLp: mintA.publicKey, signerAtaLp: signerAtaA };
+
+    try {
+      await program.methods.deposit(new BN(625), new BN(25), new BN(25), null)
+      .accountsStrict(wrongLp)
+      .signers([signer])
+      .rpc();
+      assert.fail("错误的 LP mint 不应该能存入");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "ConstraintSeeds");
+    }
+
+    try {
+      await program.methods.withdraw(new BN(1), new BN(0), new BN(0), null, false)
+      .accountsStrict(wrongLp)
+      .signers([signer])
+      .rpc();
+      assert.fail("错误的 LP mint 不应该能取出");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "ConstraintSeeds");
+    }
+  });
+
+  it("Deposit and swap reject a signer ATA aliased to a pool vault", async () => {
+    // 同一个代币账户不能同时作为用户账户和池子账户传入
+    try {
+      await program.methods.deposit(new BN(1), new BN(0), new BN(0), null)
+      .accountsStrict({ ...accounts, signerAtaA: poolAtaA })
+      .signers([signer])
+      .rpc();
+      assert.fail("别名账户不应该能存入");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "DuplicateMutableAccount");
+    }
+
+    try {
+      await program.methods.swap(new BN(1), new BN(0), true, null, false)
+      .accountsStrict({ ...accounts, signerAtaB: poolAtaB })
+      .signers([signer])
+      .rpc();
+      assert.fail("别名账户不应该能 swap");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "DuplicateMutableAccount");
+    }
+  });
+
   /**
    * 手续费分析函数
    * 详细分析swap交易中的手续费计算
@@ -463,16 +805,18 @@ describe("amm", () => {
     console.log(`  步骤3: amount_in_exact = ${Number(numerator)} ÷ ${a2} = ${amount_in_exact}`);
     
     // 步骤3: 计算含手续费的金额 (向上取整)
+    // 手续费从实际付出的数量中扣除：amount_in_with_fees * (10000 - fee) / 10000 = amount_in
     const feeRate = 500; // 5%
-    const fee_multiplier = 10000 + feeRate; // 10500
-    const amount_with_fees_exact_bigint = amount_in_exact_bigint * BigInt(fee_multiplier); // 4 * 10500 = 42000
+    const fee_complement = 10000 - feeRate; // 9500
+    const amount_with_fees_numerator = numerator * BigInt(10000); // 100 * 10000 = 1000000
+    const amount_with_fees_denominator = a2_bigint * BigInt(fee_complement); // 21 * 9500 = 199500
     
-    // 向上取整: ceiling(42000 / 10000) = ceiling(4.2) = 5
-    const amount_in_with_fees_bigint = (amount_with_fees_exact_bigint + BigInt(10000 - 1)) / BigInt(10000);
+    // 向上取整: ceiling(1000000 / 199500) = ceiling(5.01) = 6
+    const amount_in_with_fees_bigint = (amount_with_fees_numerator + amount_with_fees_denominator - BigInt(1)) / amount_with_fees_denominator;
     const amount_in_with_fees = Number(amount_in_with_fees_bigint);
     
-    console.log(`  步骤4: 含手续费金额 = ${amount_in_exact} × ${fee_multiplier} = ${Number(amount_with_fees_exact_bigint)}`);
-    console.log(`  步骤5: 向上取整 = ceiling(${Number(amount_with_fees_exact_bigint)} ÷ 10000) = ${amount_in_with_fees}`);
+    console.log(`  步骤4: 含手续费金额 = ${Number(numerator)} × 10000 ÷ (${a2} × ${fee_complement})`);
+    console.log(`  步骤5: 向上取整 = ceiling(${Number(amount_with_fees_numerator)} ÷ ${Number(amount_with_fees_denominator)}) = ${amount_in_with_fees}`);
     
     console.log("\n🎯 **验证实际结果：**");
     const actualBPaid = poolB - originalB; // 实际付出的TokenB